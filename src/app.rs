@@ -5,36 +5,53 @@
 //! preview pane and parent pane requests.
 
 pub(crate) mod actions;
+pub(crate) mod details;
+pub(crate) mod dir_sizes;
 pub(crate) mod handlers;
+pub(crate) mod help;
+pub(crate) mod history;
 pub(crate) mod keymap;
+pub(crate) mod layout;
+pub(crate) mod macros;
 pub(crate) mod metadata;
+pub(crate) mod mouse;
 pub(crate) mod nav;
 mod parent;
 pub(crate) mod preview;
+pub(crate) mod session;
 mod state;
+pub(crate) mod status;
 pub(crate) mod tab;
+pub(crate) mod theme_picker;
+pub(crate) mod tree;
 
 pub(crate) use nav::NavState;
 pub(crate) use parent::ParentState;
 pub(crate) use preview::{PreviewData, PreviewState};
 pub(crate) use state::{AppState, KeypressResult, LayoutMetrics};
-pub(crate) use tab::{handle_sort_action, handle_tab_action};
+pub(crate) use tab::{handle_pane_action, handle_sort_action, handle_tab_action};
 
 use crossterm::{
     cursor::Hide,
+    event::KeyEvent,
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 
-use crate::config::Config;
+use crate::config::{Config, display::ColorDepth};
 use crate::utils::timings::{Throttler, Timings};
 use crate::{
+    app::history::PromptHistory,
+    app::keymap::TabAction,
+    app::macros::{MacroCommand, MacroState, RegisterResult},
     app::tab::TabManager,
+    core::status::StatusRequest,
     core::workers::{WorkerResponse, Workers},
 };
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 /// The main container enum to hold either the TabManager or a single boxed AppState to then match
@@ -65,29 +82,258 @@ impl Clipboard {
 }
 
 /// The main struct of runa
-/// Contains the AppContainer, the shared clipboard and the worker pool
+/// Contains the AppContainer, the shared clipboard, the prompt history, the macro registers
+/// and the worker pool
 pub(crate) struct RunaRoot {
     pub(crate) container: AppContainer,
     pub(crate) clipboard: Clipboard,
+    pub(crate) history: PromptHistory,
     pub(crate) workers: Workers,
+    macros: MacroState,
     ui_reload_throttler: Throttler,
     config_reload_throttler: Throttler,
     last_watch_dir: Option<PathBuf>,
+    last_status: Option<StatusRequest>,
 }
 
 impl RunaRoot {
     #[inline]
     pub(crate) fn new(container: AppContainer, workers: Workers) -> Self {
-        Self {
+        let history_size = match &container {
+            AppContainer::Single(app) => app.config().general().history_size(),
+            AppContainer::Tabs(tabs) => tabs.tabs[tabs.current].config().general().history_size(),
+        };
+        let mut root = Self {
             container,
             clipboard: Clipboard::default(),
+            history: PromptHistory::new(history_size),
             workers,
+            macros: MacroState::default(),
             config_reload_throttler: Throttler::default(),
             ui_reload_throttler: Throttler::default(),
             last_watch_dir: None,
+            last_status: None,
+        };
+        root.sync_dual_panes();
+        root.current_mut().push_key_issues();
+        root
+    }
+
+    /// Opens a second pane if the dual pane layout is enabled and only one tab is open.
+    /// The first pane keeps the focus.
+    fn sync_dual_panes(&mut self) {
+        let AppContainer::Single(app) = &self.container else {
+            return;
+        };
+        if !app.config().display().is_dual() {
+            return;
+        }
+
+        handle_tab_action(&self.workers, &mut self.container, TabAction::New);
+        if let AppContainer::Tabs(tabs) = &mut self.container {
+            tabs.set_active(0);
         }
     }
 
+    /// Ticks the current tab and, in the dual pane layout, the opposite pane.
+    /// Returns true if a redraw is needed.
+    pub(crate) fn tick(&mut self) -> bool {
+        match &mut self.container {
+            AppContainer::Single(app) => app.tick(&self.workers),
+            AppContainer::Tabs(tabs) => match tabs.dual_panes_mut() {
+                Some((current, opposite)) => {
+                    let changed = current.tick(&self.workers);
+                    opposite.tick(&self.workers) || changed
+                }
+                None => tabs.current_tab_mut().tick(&self.workers),
+            },
+        }
+    }
+
+    /// Returns true if mouse support is enabled in the config of the current tab.
+    pub(crate) fn mouse_enabled(&self) -> bool {
+        let app = match &self.container {
+            AppContainer::Single(app) => app.as_ref(),
+            AppContainer::Tabs(tabs) => &tabs.tabs[tabs.current],
+        };
+        app.config().display().mouse()
+    }
+
+    /// Returns the color depth the frames are drawn with.
+    pub(crate) fn color_depth(&self) -> ColorDepth {
+        self.current().config().display().colors().resolve()
+    }
+
+    fn current(&self) -> &AppState {
+        match &self.container {
+            AppContainer::Single(app) => app.as_ref(),
+            AppContainer::Tabs(tabs) => &tabs.tabs[tabs.current],
+        }
+    }
+
+    fn current_mut(&mut self) -> &mut AppState {
+        match &mut self.container {
+            AppContainer::Single(app) => app.as_mut(),
+            AppContainer::Tabs(tabs) => tabs.current_tab_mut(),
+        }
+    }
+
+    /// Handles a key typed by the user.
+    ///
+    /// A typed key stops a running macro playback and is consumed by it.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> KeypressResult {
+        if self.macros.is_playing() {
+            self.macros.cancel_playback();
+            self.current_mut().push_overlay_message(
+                "Macro stopped".into(),
+                Duration::from_secs(2),
+                None,
+            );
+            return KeypressResult::Consumed;
+        }
+        self.dispatch_key(key, false)
+    }
+
+    /// Handles text pasted into the terminal, which is inserted into an open prompt.
+    pub(crate) fn handle_paste(&mut self, text: &str) -> KeypressResult {
+        match &mut self.container {
+            AppContainer::Single(app) => app.handle_paste(&self.workers, text),
+            AppContainer::Tabs(tabs) => tabs.current_tab_mut().handle_paste(&self.workers, text),
+        }
+    }
+
+    /// Returns the next key of the running macro playback.
+    ///
+    /// Returns None while the app waits for worker responses, so every replayed key
+    /// sees the results of the keys before it.
+    pub(crate) fn next_macro_key(&mut self) -> Option<KeyEvent> {
+        let busy = self.is_busy();
+        self.macros.next_key(busy, Timings::MACRO_WAIT_MS)
+    }
+
+    /// Handles a key of the running macro playback.
+    pub(crate) fn replay_key(&mut self, key: KeyEvent) -> KeypressResult {
+        self.dispatch_key(key, true)
+    }
+
+    /// Ends a key sequence of the current tab which timed out and runs the action bound to
+    /// the keys typed so far. Returns None if no sequence timed out.
+    pub(crate) fn resolve_expired_keys(&mut self) -> Option<KeypressResult> {
+        let result = match &mut self.container {
+            AppContainer::Single(app) => {
+                app.resolve_expired_keys(&self.workers, &mut self.clipboard)
+            }
+            AppContainer::Tabs(tabs) => tabs
+                .current_tab_mut()
+                .resolve_expired_keys(&self.workers, &mut self.clipboard),
+        }?;
+        let result = self.apply_history(result);
+
+        let KeypressResult::Macro(cmd) = result else {
+            return Some(result);
+        };
+        if let Err(msg) = self.macros.command(cmd) {
+            self.current_mut()
+                .push_overlay_message(msg, Duration::from_secs(2), None);
+        }
+        Some(KeypressResult::Consumed)
+    }
+
+    /// Returns true if a macro playback is running.
+    pub(crate) fn macro_playing(&self) -> bool {
+        self.macros.is_playing()
+    }
+
+    /// Shows the register which is recorded into in the current tab.
+    /// Returns true if the status changed.
+    pub(crate) fn sync_macro_status(&mut self) -> bool {
+        let recording = self.macros.recording();
+        let app = self.current_mut();
+        if app.macro_recording() == recording {
+            return false;
+        }
+        app.set_macro_recording(recording);
+        true
+    }
+
+    /// Passes a key to the current tab, handling macro registers and recording around it.
+    fn dispatch_key(&mut self, key: KeyEvent, replayed: bool) -> KeypressResult {
+        let record = !replayed && self.macros.recording().is_some();
+
+        let result = match self.macros.feed_register(&key) {
+            RegisterResult::Ignored => match &mut self.container {
+                AppContainer::Single(app) => {
+                    app.handle_keypress(key, &self.workers, &mut self.clipboard)
+                }
+                AppContainer::Tabs(tabs) => {
+                    tabs.current_tab_mut()
+                        .handle_keypress(key, &self.workers, &mut self.clipboard)
+                }
+            },
+            RegisterResult::Consumed => KeypressResult::Consumed,
+            RegisterResult::Failed(msg) => {
+                self.current_mut()
+                    .push_overlay_message(msg, Duration::from_secs(2), None);
+                KeypressResult::Consumed
+            }
+        };
+        let result = self.apply_history(result);
+
+        let KeypressResult::Macro(cmd) = result else {
+            if record {
+                self.macros.record(key);
+            }
+            return result;
+        };
+
+        // The key which stops a recording is not part of the macro.
+        let stops_recording = record && cmd == MacroCommand::Record;
+        if let Err(msg) = self.macros.command(cmd) {
+            self.current_mut()
+                .push_overlay_message(msg, Duration::from_secs(2), None);
+        }
+        if record && !stops_recording {
+            self.macros.record(key);
+        }
+        KeypressResult::Consumed
+    }
+
+    /// Reads the prompt history from the history file.
+    pub(crate) fn load_history(&mut self, path: &Path) {
+        let limit = self.current().config().general().history_size();
+        self.history = PromptHistory::load(path, limit);
+    }
+
+    /// Adds an accepted prompt input to the history and loads the history of a newly
+    /// opened prompt of the current tab.
+    fn apply_history(&mut self, result: KeypressResult) -> KeypressResult {
+        let result = match result {
+            KeypressResult::History(kind, entry) => {
+                self.history.push(kind, &entry);
+                KeypressResult::Consumed
+            }
+            result => result,
+        };
+
+        let app = match &mut self.container {
+            AppContainer::Single(app) => app.as_mut(),
+            AppContainer::Tabs(tabs) => tabs.current_tab_mut(),
+        };
+        if let Some(kind) = app.actions.history().pending() {
+            let entries = self.history.entries(kind).to_vec();
+            app.actions.history_mut().load(entries);
+        }
+        result
+    }
+
+    /// Returns true if the current tab or the file operation worker still has work in flight.
+    fn is_busy(&self) -> bool {
+        // A finished file operation sends its response before the active count drops,
+        // so the response queue is checked after the counters.
+        let ops = self.workers.fileop_tx().len() + self.workers.active().load(Ordering::SeqCst);
+        ops > 0 || !self.workers.response_rx().is_empty() || self.current().is_busy()
+    }
+
     pub(crate) fn sync_watch(&mut self) {
         {
             let app = match &self.container {
@@ -117,6 +363,16 @@ impl RunaRoot {
         self.last_watch_dir = Some(current);
     }
 
+    /// Sends the status segments of the current tab to the status worker, if they changed.
+    pub(crate) fn sync_status(&mut self) {
+        let request = self.current_mut().status_request();
+        if self.last_status.as_ref() == Some(&request) {
+            return;
+        }
+        self.workers.request_status(request.clone());
+        self.last_status = Some(request);
+    }
+
     pub(crate) fn update(&mut self) -> bool {
         let mut changed = false;
 
@@ -132,6 +388,23 @@ impl RunaRoot {
                     app.handle_worker_response(response, &self.workers);
                 }
                 AppContainer::Tabs(tabs) => {
+                    // Both panes of the dual pane layout show the result of a file operation.
+                    if let Some(idx) = tabs.opposite()
+                        && matches!(response, WorkerResponse::OperationComplete { .. })
+                    {
+                        let opposite = &mut tabs.tabs[idx];
+                        opposite.handle_worker_response(
+                            operation_for_pane(opposite, &response),
+                            &self.workers,
+                        );
+                        let current = tabs.current_tab_mut();
+                        current.handle_worker_response(
+                            operation_for_pane(current, &response),
+                            &self.workers,
+                        );
+                        continue;
+                    }
+
                     let target_app = if let Some(id) = response.tab_id() {
                         tabs.tabs.iter_mut().find(|t| t.tab_id == Some(id))
                     } else {
@@ -167,6 +440,27 @@ impl RunaRoot {
         Ok(true)
     }
 
+    /// Shows the named theme in all tabs, or the theme of the config for None.
+    pub(crate) fn preview_theme(&mut self, name: Option<&str>) {
+        match Config::load_with_theme(name) {
+            Ok(config) => {
+                let new_config = Arc::new(config);
+                match &mut self.container {
+                    AppContainer::Single(app) => app.apply_theme_config(new_config),
+                    AppContainer::Tabs(tabs) => {
+                        for tab in &mut tabs.tabs {
+                            tab.apply_theme_config(Arc::clone(&new_config));
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                self.current_mut()
+                    .push_overlay_message(e, Duration::from_secs(5), None);
+            }
+        }
+    }
+
     pub(crate) fn reload_config(&mut self) {
         if !self
             .config_reload_throttler
@@ -179,6 +473,7 @@ impl RunaRoot {
             Ok(config) => {
                 self.config_reload_throttler.touch();
                 let new_config = Arc::new(config);
+                self.history.set_limit(new_config.general().history_size());
 
                 match &mut self.container {
                     AppContainer::Single(app) => {
@@ -201,6 +496,8 @@ impl RunaRoot {
                         );
                     }
                 }
+                self.sync_dual_panes();
+                self.current_mut().push_key_issues();
             }
             Err(e) => {
                 self.config_reload_throttler.touch();
@@ -213,3 +510,31 @@ impl RunaRoot {
         }
     }
 }
+
+/// Adapts a finished file operation to a pane of the dual pane layout.
+///
+/// The focused entry is only kept if it exists in the directory of the pane,
+/// otherwise the pane keeps its selection, e.g. after the entry was moved to the other pane.
+fn operation_for_pane(app: &AppState, response: &WorkerResponse) -> WorkerResponse {
+    let WorkerResponse::OperationComplete {
+        need_reload,
+        focus,
+        modified_dirs,
+    } = response
+    else {
+        return response.clone();
+    };
+
+    let dir = app.nav().current_dir();
+    let focus = focus
+        .as_ref()
+        .filter(|name| dir.join(name).symlink_metadata().is_ok())
+        .cloned()
+        .or_else(|| app.nav().selected_root_name());
+
+    WorkerResponse::OperationComplete {
+        need_reload: *need_reload,
+        focus,
+        modified_dirs: modified_dirs.clone(),
+    }
+}
@@ -6,7 +6,6 @@
 //! copy, paste, rename, create, delete, filter.
 
 use std::cell::Cell;
-use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -14,7 +13,12 @@ use std::time::{Duration, Instant};
 
 use crossbeam_channel::Sender;
 
-use crate::app::{Clipboard, keymap::KeyPrefix, nav::NavState};
+use crate::app::{
+    Clipboard,
+    history::{HistoryCursor, HistoryKind},
+    keymap::KeyPrefix,
+    nav::NavState,
+};
 use crate::core::{
     proc::FindResult,
     workers::{FileOperation, WorkerTask},
@@ -51,8 +55,23 @@ pub(crate) enum InputMode {
     Find,
     MoveFile,
     GoToPath,
+    MarkMatching,
+    UnmarkMatching,
+}
+
+/// A file operation which can be repeated with the repeat action.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RepeatAction {
+    Copy,
+    Paste,
+    Delete { is_trash: bool },
+    Create { name: String, is_dir: bool },
+    Move { dest: PathBuf },
 }
 
+/// Upper bound of a typed count prefix.
+const MAX_COUNT: usize = 99_999;
+
 /// Tracks current user action and input buffer state for file operations and commands.
 ///
 /// Stores the current mode/prompt, input buffer, cursor, and clipboard (for copy/yank) status.
@@ -69,10 +88,14 @@ pub(crate) struct ActionContext {
     mode: ActionMode,
     input_buffer: String,
     input_cursor_pos: usize,
+    kill_buffer: String,
+    history: HistoryCursor,
     autocomplete: AutoCompleteState,
     prefix_recognizer: KeyPrefix,
     find: FindState,
     scroll: ScrollState,
+    count: Option<usize>,
+    last_repeat: Option<RepeatAction>,
 }
 
 impl ActionContext {
@@ -83,16 +106,48 @@ impl ActionContext {
         scroll: &ScrollState,
         prefix_recognizer: &KeyPrefix,
         find: &FindState,
+        history: &HistoryCursor,
+        count: Option<usize>,
+        last_repeat: &Option<RepeatAction>,
     }
 
     pub(crate) fn prefix_recognizer_mut(&mut self) -> &mut KeyPrefix {
         &mut self.prefix_recognizer
     }
 
+    pub(crate) fn history_mut(&mut self) -> &mut HistoryCursor {
+        &mut self.history
+    }
+
     pub(crate) fn autocomplete_mut(&mut self) -> &mut AutoCompleteState {
         &mut self.autocomplete
     }
 
+    // Count and repeat functions
+
+    /// Appends a digit to the pending count.
+    /// Returns false if the digit cannot start a count, which is the case for a leading zero.
+    pub(crate) fn push_count_digit(&mut self, digit: u32) -> bool {
+        if digit == 0 && self.count.is_none() {
+            return false;
+        }
+        let count = self
+            .count
+            .unwrap_or(0)
+            .saturating_mul(10)
+            .saturating_add(digit as usize);
+        self.count = Some(count.min(MAX_COUNT));
+        true
+    }
+
+    pub(crate) fn take_count(&mut self) -> Option<usize> {
+        self.count.take()
+    }
+
+    pub(crate) fn set_last_repeat(&mut self, action: RepeatAction) {
+        self.last_repeat = Some(action);
+    }
+
     // Find functions
 
     pub(crate) fn find_mut(&mut self) -> &mut FindState {
@@ -124,6 +179,11 @@ impl ActionContext {
     }
 
     pub(crate) fn enter_mode(&mut self, mode: ActionMode, initial_value: String) {
+        let kind = match &mode {
+            ActionMode::Input { mode, .. } => HistoryKind::of(mode),
+            ActionMode::Normal => None,
+        };
+        self.history = HistoryCursor::new(kind);
         self.mode = mode;
         self.input_buffer = initial_value;
         self.input_cursor_pos = self.input_buffer.len();
@@ -134,6 +194,7 @@ impl ActionContext {
         self.input_buffer.clear();
         self.find.reset();
         self.autocomplete.reset();
+        self.history = HistoryCursor::default();
     }
 
     // Actions functions
@@ -156,7 +217,7 @@ impl ActionContext {
             op: FileOperation::Delete(targets.into_iter().collect(), move_to_trash),
         });
 
-        nav.clear_markers();
+        nav.finish_action();
     }
 
     /// Copies or cuts the currently marked files or the selected file if no markers exist.
@@ -167,22 +228,15 @@ impl ActionContext {
         clipboard: &mut Clipboard,
         is_cut: bool,
     ) {
-        let mut set = HashSet::new();
-        if !nav.markers().is_empty() {
-            for path in nav.markers() {
-                set.insert(path.clone());
-            }
-            nav.clear_markers();
-        } else if let Some(entry) = nav.selected_entry() {
-            set.insert(nav.current_dir().join(entry.name()));
-        }
+        let set = nav.get_action_targets();
+        nav.finish_action();
         if !set.is_empty() {
             clipboard.entries = Some(set);
             clipboard.is_cut = is_cut;
         }
     }
 
-    /// Pastes the files from the clipboard into the current directory.
+    /// Pastes the files from the clipboard into the directory of the selected entry.
     ///
     /// Sends a copy task to the worker thread via the provided channel.
     pub(crate) fn action_paste(
@@ -201,7 +255,7 @@ impl ActionContext {
             let _ = worker_tx.send(WorkerTask::FileOp {
                 op: FileOperation::Copy {
                     src: source.iter().cloned().collect(),
-                    dest: nav.current_dir().to_path_buf(),
+                    dest: nav.selected_dir().to_path_buf(),
                     cut: clipboard.is_cut,
                     focus: first_file_name,
                 },
@@ -229,8 +283,7 @@ impl ActionContext {
         if self.input_buffer.is_empty() {
             return;
         }
-        if let Some(entry) = nav.selected_entry() {
-            let old_path = nav.current_dir().join(entry.name());
+        if let Some(old_path) = nav.selected_path() {
             let new_path = old_path.with_file_name(&self.input_buffer);
 
             let _ = worker_tx.send(WorkerTask::FileOp {
@@ -259,7 +312,7 @@ impl ActionContext {
             return;
         }
 
-        let path = nav.current_dir().join(&self.input_buffer);
+        let path = nav.selected_dir().join(&self.input_buffer);
         let _ = worker_tx.send(WorkerTask::FileOp {
             op: FileOperation::Create {
                 path,
@@ -288,35 +341,132 @@ impl ActionContext {
                 focus: None,
             },
         });
-        nav.clear_markers();
+        nav.finish_action();
     }
 
     // Cursor actions
 
-    /// Moves the input cursor one position to the left, if possible.
+    /// Moves the input cursor one character to the left, if possible.
     pub(crate) fn action_move_cursor_left(&mut self) {
-        if self.input_cursor_pos > 0 {
-            self.input_cursor_pos -= 1;
+        if let Some((previous, _)) = self.input_buffer[..self.input_cursor_pos]
+            .char_indices()
+            .next_back()
+        {
+            self.input_cursor_pos = previous;
         }
     }
 
-    /// Moves the input cursor one position to the right, if possible.
+    /// Moves the input cursor one character to the right, if possible.
     pub(crate) fn action_move_cursor_right(&mut self) {
-        if self.input_cursor_pos < self.input_buffer.len() {
-            self.input_cursor_pos += 1;
+        if let Some(ch) = self.input_buffer[self.input_cursor_pos..].chars().next() {
+            self.input_cursor_pos += ch.len_utf8();
         }
     }
 
+    /// Moves the input cursor to the start of the current or previous word.
+    pub(crate) fn action_move_word_left(&mut self) {
+        self.input_cursor_pos = word_start_before(&self.input_buffer, self.input_cursor_pos);
+    }
+
+    /// Moves the input cursor to the end of the current or next word.
+    pub(crate) fn action_move_word_right(&mut self) {
+        self.input_cursor_pos = word_end_after(&self.input_buffer, self.input_cursor_pos);
+    }
+
     /// Inserts a character at the current cursor position in the input buffer.
     pub(crate) fn action_insert_at_cursor(&mut self, ch: char) {
         self.input_buffer.insert(self.input_cursor_pos, ch);
         self.input_cursor_pos += ch.len_utf8();
     }
 
+    /// Inserts a string at the current cursor position in the input buffer.
+    pub(crate) fn action_insert_str_at_cursor(&mut self, text: &str) {
+        self.input_buffer.insert_str(self.input_cursor_pos, text);
+        self.input_cursor_pos += text.len();
+    }
+
+    /// Deletes the character under the cursor.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_delete_at_cursor(&mut self) -> bool {
+        if self.input_cursor_pos < self.input_buffer.len() {
+            self.input_buffer.remove(self.input_cursor_pos);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Kills the text from the start of the current or previous word to the cursor.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_kill_word_back(&mut self) -> bool {
+        let start = word_start_before(&self.input_buffer, self.input_cursor_pos);
+        self.kill_range(start, self.input_cursor_pos)
+    }
+
+    /// Kills the text from the cursor to the end of the current or next word.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_kill_word_forward(&mut self) -> bool {
+        let end = word_end_after(&self.input_buffer, self.input_cursor_pos);
+        self.kill_range(self.input_cursor_pos, end)
+    }
+
+    /// Kills the text from the cursor to the end of the input buffer.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_kill_to_end(&mut self) -> bool {
+        self.kill_range(self.input_cursor_pos, self.input_buffer.len())
+    }
+
+    /// Kills the text from the start of the input buffer to the cursor.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_kill_to_start(&mut self) -> bool {
+        self.kill_range(0, self.input_cursor_pos)
+    }
+
+    /// Inserts the last killed text at the cursor.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_yank(&mut self) -> bool {
+        if self.kill_buffer.is_empty() {
+            return false;
+        }
+        let text = self.kill_buffer.clone();
+        self.action_insert_str_at_cursor(&text);
+        true
+    }
+
+    /// Swaps the character before the cursor with the one under it and moves the cursor
+    /// forward. At the end of the input the last two characters are swapped.
+    /// Returns true if the input buffer changed.
+    pub(crate) fn action_transpose_chars(&mut self) -> bool {
+        let buf = &self.input_buffer;
+        let right = match buf[self.input_cursor_pos..].chars().next() {
+            Some(ch) => self.input_cursor_pos + ch.len_utf8(),
+            None => buf.len(),
+        };
+        let mut chars = buf[..right].char_indices().rev();
+        let (Some((_, second)), Some((first, a))) = (chars.next(), chars.next()) else {
+            return false;
+        };
+
+        let swapped = format!("{second}{a}");
+        self.input_buffer.replace_range(first..right, &swapped);
+        self.input_cursor_pos = right;
+        true
+    }
+
+    /// Removes the range of the input buffer into the kill buffer.
+    fn kill_range(&mut self, start: usize, end: usize) -> bool {
+        if start >= end {
+            return false;
+        }
+        self.kill_buffer = self.input_buffer.drain(start..end).collect();
+        self.input_cursor_pos = start;
+        true
+    }
+
     /// Deletes the character before the current cursor position in the input buffer.
     ///
-    /// Moves the cursor back accordingly
-    pub(crate) fn action_backspace_at_cursor(&mut self) {
+    /// Moves the cursor back accordingly. Returns true if the input buffer changed.
+    pub(crate) fn action_backspace_at_cursor(&mut self) -> bool {
         if self.input_cursor_pos > 0
             && let Some((previous, _)) = self.input_buffer[..self.input_cursor_pos]
                 .char_indices()
@@ -324,6 +474,9 @@ impl ActionContext {
         {
             self.input_buffer.remove(previous);
             self.input_cursor_pos = previous;
+            true
+        } else {
+            false
         }
     }
 
@@ -376,10 +529,14 @@ impl Default for ActionContext {
             mode: ActionMode::Normal,
             input_buffer: String::new(),
             input_cursor_pos: 0,
+            kill_buffer: String::new(),
+            history: HistoryCursor::default(),
             autocomplete: AutoCompleteState::default(),
             prefix_recognizer: KeyPrefix::new(Duration::from_secs(4)),
             find: FindState::default(),
             scroll: ScrollState::default(),
+            count: None,
+            last_repeat: None,
         }
     }
 }
@@ -560,3 +717,76 @@ impl ScrollState {
         self.max_offset.set(0);
     }
 }
+
+/// Returns true for the characters of a word in the prompt.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the byte index of the start of the word before `pos`, skipping separators first.
+fn word_start_before(text: &str, pos: usize) -> usize {
+    let mut start = pos;
+    let mut in_word = false;
+    for (i, c) in text[..pos].char_indices().rev() {
+        if is_word_char(c) {
+            in_word = true;
+        } else if in_word {
+            break;
+        }
+        start = i;
+    }
+    start
+}
+
+/// Returns the byte index of the end of the word after `pos`, skipping separators first.
+fn word_end_after(text: &str, pos: usize) -> usize {
+    let mut in_word = false;
+    for (i, c) in text[pos..].char_indices() {
+        if is_word_char(c) {
+            in_word = true;
+        } else if in_word {
+            return pos + i;
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_editing_moves_and_kills_words() {
+        let mut actions = ActionContext::default();
+        actions.set_input_buffer("foo bar-baz".into());
+
+        actions.action_move_word_left();
+        assert_eq!(actions.input_cursor_pos(), 8);
+        assert!(actions.action_kill_word_back());
+        assert_eq!(actions.input_buffer(), "foo baz");
+        assert_eq!(actions.input_cursor_pos(), 4);
+
+        assert!(actions.action_kill_to_end());
+        assert_eq!(actions.input_buffer(), "foo ");
+        assert!(actions.action_yank());
+        assert_eq!(actions.input_buffer(), "foo baz");
+
+        actions.action_cursor_home();
+        actions.action_move_word_right();
+        assert_eq!(actions.input_cursor_pos(), 3);
+        assert!(actions.action_kill_to_start());
+        assert_eq!(actions.input_buffer(), " baz");
+
+        // Transposing at the end swaps the last two characters.
+        actions.action_cursor_end();
+        assert!(actions.action_transpose_chars());
+        assert_eq!(actions.input_buffer(), " bza");
+
+        // The cursor moves over whole characters.
+        actions.set_input_buffer("aé".into());
+        actions.action_move_cursor_left();
+        assert_eq!(actions.input_cursor_pos(), 1);
+        assert!(actions.action_delete_at_cursor());
+        assert_eq!(actions.input_buffer(), "a");
+    }
+}
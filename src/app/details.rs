@@ -0,0 +1,142 @@
+//! Details view state for AppState.
+//!
+//! [DetailsState] holds whether the details view is shown and the detail rows loaded
+//! for the current directory. Rows are requested for the visible entries only and kept
+//! while scrolling, so every row is read once per directory load.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use crate::core::details::DetailRow;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DetailsState {
+    enabled: bool,
+    dir: PathBuf,
+    rows: HashMap<OsString, DetailRow>,
+    requested: HashSet<OsString>,
+    request_id: u64,
+    min_request_id: u64,
+}
+
+impl DetailsState {
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..Self::default()
+        }
+    }
+
+    crate::getters! {
+        enabled: bool,
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+    }
+
+    /// Returns the loaded row of the entry with the key, its path relative to the current dir.
+    pub(crate) fn row(&self, key: &OsStr) -> Option<&DetailRow> {
+        self.rows.get(key)
+    }
+
+    /// Returns all loaded rows.
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &DetailRow> {
+        self.rows.values()
+    }
+
+    /// Marks the loaded rows as outdated, so the visible rows are requested again.
+    /// Outdated rows are still shown until the new ones arrive.
+    pub(crate) fn invalidate(&mut self) {
+        self.requested.clear();
+        self.min_request_id = self.request_id;
+    }
+
+    /// Drops all loaded rows, for example after the columns changed.
+    pub(crate) fn clear(&mut self) {
+        self.rows.clear();
+        self.invalidate();
+    }
+
+    /// Takes the keys of the given entries of `dir` which have not been requested yet.
+    /// Returns None if all of them are loaded or pending.
+    pub(crate) fn take_missing<'a>(
+        &mut self,
+        dir: &Path,
+        keys: impl Iterator<Item = &'a OsStr>,
+    ) -> Option<(u64, Vec<OsString>)> {
+        if self.dir != dir {
+            self.dir = dir.to_path_buf();
+            self.clear();
+        }
+
+        let missing: Vec<OsString> = keys
+            .filter(|key| !self.requested.contains(*key))
+            .map(OsStr::to_os_string)
+            .collect();
+        if missing.is_empty() {
+            return None;
+        }
+
+        self.requested.extend(missing.iter().cloned());
+        let id = self.request_id;
+        self.request_id = self.request_id.wrapping_add(1);
+        Some((id, missing))
+    }
+
+    /// Stores the loaded rows if they belong to the current directory and are not outdated.
+    /// Returns true if the rows were stored.
+    pub(crate) fn apply(
+        &mut self,
+        dir: &Path,
+        rows: Vec<(OsString, DetailRow)>,
+        request_id: u64,
+    ) -> bool {
+        if dir != self.dir || request_id < self.min_request_id {
+            return false;
+        }
+        self.rows.extend(rows);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn details_state_requests_rows_once() -> Result<(), Box<dyn std::error::Error>> {
+        let mut details = DetailsState::new(true);
+        let dir = Path::new("/data");
+        let keys = [OsStr::new("a"), OsStr::new("b")];
+
+        let (first_id, missing) = details
+            .take_missing(dir, keys.into_iter())
+            .ok_or("rows should be missing")?;
+        assert_eq!(missing, ["a", "b"]);
+        assert!(details.take_missing(dir, keys.into_iter()).is_none());
+
+        let row: DetailRow = Box::new(["1 kB".into()]);
+        assert!(details.apply(dir, vec![("a".into(), row.clone())], first_id));
+        assert!(details.row(OsStr::new("a")).is_some());
+
+        // Responses requested before an invalidation are outdated.
+        details.invalidate();
+        assert!(!details.apply(dir, vec![("b".into(), row.clone())], first_id));
+        let (id, missing) = details
+            .take_missing(dir, keys.into_iter())
+            .ok_or("rows should be requested again")?;
+        assert_eq!(missing.len(), 2);
+        assert!(details.apply(dir, vec![("b".into(), row)], id));
+        assert!(details.row(OsStr::new("a")).is_some());
+
+        assert!(
+            details
+                .take_missing(Path::new("/other"), keys.into_iter())
+                .is_some()
+        );
+        assert!(details.row(OsStr::new("a")).is_none());
+        Ok(())
+    }
+}
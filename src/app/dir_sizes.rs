@@ -0,0 +1,80 @@
+//! Directory size state for AppState.
+//!
+//! [DirSizeState] holds the recursive sizes of the subdirectories of the current directory,
+//! formatted for the listing and the status line, and the cancel token of the running
+//! calculation, which is cancelled when the directory is left.
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::core::formatter;
+
+#[derive(Debug, Default)]
+pub(crate) struct DirSizeState {
+    dir: PathBuf,
+    sizes: HashMap<OsString, Arc<str>>,
+    cancel: Option<Arc<AtomicBool>>,
+    request_id: u64,
+}
+
+impl DirSizeState {
+    /// Returns the formatted size of the subdirectory with the name.
+    pub(crate) fn size_of(&self, name: &OsStr) -> Option<&str> {
+        self.sizes.get(name).map(|s| &**s)
+    }
+
+    /// Switches to the directory, dropping the sizes of the previous one.
+    pub(crate) fn set_dir(&mut self, dir: &Path) {
+        if self.dir != dir {
+            self.cancel();
+            self.dir = dir.to_path_buf();
+            self.sizes.clear();
+        }
+    }
+
+    pub(crate) fn set_size(&mut self, name: OsString, size: u64) {
+        let formatted = formatter::format_file_size(Some(size), false);
+        self.sizes.insert(name, Arc::from(formatted));
+    }
+
+    /// Cancels the running calculation and starts a new one.
+    /// Returns the request id and cancel token of the new calculation.
+    pub(crate) fn begin_request(&mut self) -> (u64, Arc<AtomicBool>) {
+        self.cancel();
+        self.request_id = self.request_id.wrapping_add(1);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel = Some(Arc::clone(&cancel));
+        (self.request_id, cancel)
+    }
+
+    /// Marks the running calculation as finished.
+    pub(crate) fn finish(&mut self) {
+        self.cancel = None;
+    }
+
+    /// Returns the width of the widest formatted size.
+    pub(crate) fn max_width(&self) -> u16 {
+        self.sizes
+            .values()
+            .map(|s| UnicodeWidthStr::width(&**s))
+            .max()
+            .unwrap_or(0) as u16
+    }
+
+    /// Cancels the running calculation.
+    pub(crate) fn cancel(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.store(true, Ordering::Release);
+        }
+    }
+
+    /// Returns true if the response belongs to the running calculation.
+    pub(crate) fn is_current(&self, request_id: u64) -> bool {
+        self.cancel.is_some() && request_id == self.request_id
+    }
+}
@@ -5,5 +5,7 @@
 
 mod file_actions;
 mod input_mode;
+mod layout_actions;
+mod mouse_actions;
 mod nav_actions;
 mod overlay_handlers;
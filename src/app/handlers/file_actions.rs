@@ -17,19 +17,24 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crossterm::{
+    event::{DisableBracketedPaste, DisableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 
 use crate::app::{
     Clipboard, Workers,
-    actions::{ActionMode, InputMode},
+    actions::{ActionMode, InputMode, RepeatAction},
     keymap::FileAction,
     state::{AppState, KeypressResult},
 };
 use crate::config::Editor;
+use crate::core::workers::{FileOperation, WorkerTask};
 use crate::utils::path;
 
+/// Upper bound of files created by a single repeated create.
+const MAX_REPEAT_CREATE: usize = 100;
+
 /// AppState file action handlers
 impl AppState {
     /// Handles file actions (open, delete, copy, etc).
@@ -53,12 +58,14 @@ impl AppState {
             FileAction::Copy => {
                 let nav = &mut self.nav;
                 self.actions.action_copy(nav, clipboard, false);
+                self.actions.set_last_repeat(RepeatAction::Copy);
                 self.handle_timed_message(Duration::from_secs(15));
             }
             FileAction::Paste => {
                 let fileop_tx = workers.fileop_tx();
                 self.actions
                     .action_paste(&mut self.nav, clipboard, fileop_tx);
+                self.actions.set_last_repeat(RepeatAction::Paste);
             }
             FileAction::Rename => self.prompt_rename(),
             FileAction::Create => self.prompt_create_file(),
@@ -71,6 +78,103 @@ impl AppState {
                 clipboard.clear();
                 self.request_preview(workers);
             }
+            FileAction::Repeat => return self.handle_repeat(workers, clipboard, 1),
+        }
+        KeypressResult::Continue
+    }
+
+    /// Handles file actions with a count prefix (`3d`, `2y`).
+    ///
+    /// Actions on the selection target the count entries starting at the selection,
+    /// the repeat action is applied count times.
+    pub(in crate::app) fn handle_file_count(
+        &mut self,
+        workers: &Workers,
+        action: FileAction,
+        count: usize,
+        clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        match action {
+            FileAction::Delete
+            | FileAction::AlternateDelete
+            | FileAction::Copy
+            | FileAction::MoveFile => {
+                self.nav.target_range(count);
+                self.handle_file_action(workers, action, clipboard)
+            }
+            FileAction::Repeat => self.handle_repeat(workers, clipboard, count),
+            _ => self.handle_file_action(workers, action, clipboard),
+        }
+    }
+
+    /// Handles the repeat action, which repeats the last file operation.
+    ///
+    /// A repeated create uses the next free incremented name, e.g. `notes_1.txt` after `notes.txt`.
+    /// Deletion still asks for confirmation.
+    fn handle_repeat(
+        &mut self,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
+        count: usize,
+    ) -> KeypressResult {
+        let Some(last) = self.actions.last_repeat().clone() else {
+            self.push_overlay_message(
+                "Nothing to repeat".to_string(),
+                Duration::from_secs(2),
+                None,
+            );
+            return KeypressResult::Continue;
+        };
+
+        match last {
+            RepeatAction::Copy => {
+                if count > 1 {
+                    self.nav.target_range(count);
+                }
+                return self.handle_file_action(workers, FileAction::Copy, clipboard);
+            }
+            RepeatAction::Paste => {
+                return self.handle_file_action(workers, FileAction::Paste, clipboard);
+            }
+            RepeatAction::Delete { is_trash } => {
+                if count > 1 {
+                    self.nav.target_range(count);
+                }
+                self.prompt_delete(is_trash);
+            }
+            RepeatAction::Create { mut name, is_dir } => {
+                let dir = self.nav.selected_dir().to_path_buf();
+                let fileop_tx = workers.fileop_tx();
+                for _ in 0..count.min(MAX_REPEAT_CREATE) {
+                    name = path::next_free_name(&dir, &name, is_dir);
+                    self.actions.set_input_buffer(name.clone());
+                    self.actions.action_create(&mut self.nav, is_dir, fileop_tx);
+                }
+                self.push_overlay_message(
+                    format!("Created '{}'", name),
+                    Duration::from_secs(2),
+                    None,
+                );
+                self.actions
+                    .set_last_repeat(RepeatAction::Create { name, is_dir });
+            }
+            RepeatAction::Move { dest } => {
+                if !dest.is_dir() {
+                    self.push_overlay_message(
+                        "Move failed: not a directory".into(),
+                        Duration::from_secs(3),
+                        None,
+                    );
+                    return KeypressResult::Continue;
+                }
+                let move_msg = format!(
+                    "Files moved to: {}",
+                    path::clean_display_path(&dest.to_string_lossy())
+                );
+                let fileop_tx = workers.fileop_tx();
+                self.actions.actions_move(&mut self.nav, dest, fileop_tx);
+                self.push_overlay_message(move_msg, Duration::from_secs(3), None);
+            }
         }
         KeypressResult::Continue
     }
@@ -80,8 +184,7 @@ impl AppState {
     /// If a file is selected, attempts to open it in the configured editor.
     /// If an error occurs, prints it to stderr.
     fn handle_open_file(&mut self, workers: &Workers) -> KeypressResult {
-        if let Some(entry) = self.nav.selected_entry() {
-            let path = self.nav.current_dir().join(entry.name());
+        if let (Some(entry), Some(path)) = (self.nav.selected_entry(), self.nav.selected_path()) {
             let editor = self.config.editor();
             if !editor.exists(&path) {
                 let cmd = editor.cmd(&path);
@@ -198,6 +301,9 @@ impl AppState {
             path::clean_display_path(&absolute_dest.to_string_lossy())
         );
 
+        self.actions.set_last_repeat(RepeatAction::Move {
+            dest: absolute_dest.clone(),
+        });
         self.actions
             .actions_move(&mut self.nav, absolute_dest, fileop_tx);
 
@@ -205,6 +311,78 @@ impl AppState {
         self.push_overlay_message(move_msg, Duration::from_secs(3), None);
     }
 
+    /// Copies or moves the marked entries, or the selected entry, into the directory
+    /// of the other pane of the dual pane layout.
+    pub(crate) fn transfer_to_pane(&mut self, workers: &Workers, dest: &Path, cut: bool) {
+        let verb = if cut { "Move" } else { "Copy" };
+        let fail = |app: &mut Self, reason: String| {
+            app.push_overlay_message(
+                format!("{} failed: {}", verb, reason),
+                Duration::from_secs(3),
+                None,
+            );
+            app.nav.clear_count_targets();
+        };
+
+        if !dest.is_dir() {
+            fail(self, "not a directory".into());
+            return;
+        }
+        let targets = self.nav.get_action_targets();
+        if targets.is_empty() {
+            return;
+        }
+
+        for src in &targets {
+            if src.parent() == Some(dest) {
+                fail(self, "source and destination are the same".into());
+                return;
+            }
+            if let (Ok(abs_src), Ok(abs_dest)) = (src.canonicalize(), dest.canonicalize())
+                && abs_dest.starts_with(&abs_src)
+            {
+                let normalized = path::format_display_path(&abs_src);
+                fail(
+                    self,
+                    format!(
+                        "cannot {} a directory into itself: {}",
+                        verb.to_lowercase(),
+                        path::clean_display_path(&normalized)
+                    ),
+                );
+                return;
+            }
+        }
+
+        let focus = targets
+            .iter()
+            .min()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_os_string());
+        let msg = format!(
+            "{} {} item(s) to: {}",
+            if cut { "Moved" } else { "Copied" },
+            targets.len(),
+            path::format_display_path(dest)
+        );
+
+        let _ = workers.fileop_tx().send(WorkerTask::FileOp {
+            op: FileOperation::Copy {
+                src: targets.into_iter().collect(),
+                dest: dest.to_path_buf(),
+                cut,
+                focus,
+            },
+        });
+        if cut {
+            self.actions.set_last_repeat(RepeatAction::Move {
+                dest: dest.to_path_buf(),
+            });
+        }
+        self.nav.finish_action();
+        self.push_overlay_message(msg, Duration::from_secs(3), None);
+    }
+
     /// Creates a new file with the name in the input buffer.
     /// Calls actions::action_create with `is_folder` set to false.
     pub(super) fn create_file(&mut self, workers: &Workers) {
@@ -212,7 +390,7 @@ impl AppState {
             return;
         }
 
-        let target = self.nav.current_dir().join(self.actions.input_buffer());
+        let target = self.nav.selected_dir().join(self.actions.input_buffer());
         if target.exists() {
             let name = target
                 .file_name()
@@ -232,6 +410,10 @@ impl AppState {
             return;
         }
 
+        self.actions.set_last_repeat(RepeatAction::Create {
+            name: self.actions.input_buffer().to_string(),
+            is_dir: false,
+        });
         let fileop_tx = workers.fileop_tx();
         self.actions.action_create(&mut self.nav, false, fileop_tx);
     }
@@ -243,7 +425,7 @@ impl AppState {
             return;
         }
 
-        let target = self.nav.current_dir().join(self.actions.input_buffer());
+        let target = self.nav.selected_dir().join(self.actions.input_buffer());
         if target.exists() {
             let name = target
                 .file_name()
@@ -263,6 +445,10 @@ impl AppState {
             return;
         }
 
+        self.actions.set_last_repeat(RepeatAction::Create {
+            name: self.actions.input_buffer().to_string(),
+            is_dir: true,
+        });
         let fileop_tx = workers.fileop_tx();
         self.actions.action_create(&mut self.nav, true, fileop_tx);
     }
@@ -273,8 +459,7 @@ impl AppState {
         if self.actions.input_buffer().is_empty() {
             return;
         }
-        if let Some(entry) = self.nav.selected_entry() {
-            let old_path = self.nav.current_dir().join(entry.name());
+        if let Some(old_path) = self.nav.selected_path() {
             let new_path = old_path.with_file_name(self.actions.input_buffer());
 
             if old_path == new_path {
@@ -330,6 +515,9 @@ impl AppState {
 
         let fileop_tx = workers.fileop_tx();
 
+        self.actions.set_last_repeat(RepeatAction::Delete {
+            is_trash: move_to_trash,
+        });
         self.actions
             .action_delete(&mut self.nav, fileop_tx, move_to_trash);
     }
@@ -353,8 +541,9 @@ impl AppState {
 
     /// Opens a specified path/file in the configured editor ("nvim" or "vim" etc.).
     ///
-    /// Temporary disables raw mode and exits alternate sceen while the editor runs.
-    /// On return, restores raw mode and alternate sceen.
+    /// Temporary disables raw mode, mouse capture and bracketed paste and exits alternate sceen
+    /// while the editor runs. On return, restores raw mode and alternate sceen, the event loop
+    /// enables mouse capture and bracketed paste again.
     fn open_in_editor(editor: &Editor, file_path: &Path) -> std::io::Result<()> {
         let cmd = editor.cmd(file_path);
         let binary = cmd.first().ok_or_else(|| {
@@ -372,7 +561,12 @@ impl AppState {
 
         let mut stdout = io::stdout();
         disable_raw_mode()?;
-        execute!(stdout, LeaveAlternateScreen)?;
+        execute!(
+            stdout,
+            DisableMouseCapture,
+            DisableBracketedPaste,
+            LeaveAlternateScreen
+        )?;
 
         let mut editor_cmd = std::process::Command::new(editor_path);
         editor_cmd.args(args).arg(file_path);
@@ -16,13 +16,17 @@ use std::time::Duration;
 use crossterm::event::{KeyCode::*, KeyEvent};
 
 use crate::app::{
-    Workers,
+    Clipboard, Workers,
     actions::{ActionMode, InputMode},
-    keymap::{Action, NavAction, PrefixCommand, SystemAction},
+    history::{HistoryCursor, HistoryKind},
+    keymap::{Action, NavAction, SystemAction},
+    macros::MacroCommand,
+    nav::MarkMode,
     state::{AppState, KeypressResult},
 };
-use crate::core::proc;
-use crate::core::sort::{SortConfig, SortOrder};
+use crate::config::input::EditKeys;
+use crate::core::sort::{SortConfig, SortMode, SortOrder};
+use crate::core::{cache::ListOption, proc};
 use crate::ui::overlays::OverlayKind;
 use crate::utils::{os, path};
 
@@ -45,6 +49,27 @@ impl AppState {
             return KeypressResult::Continue;
         };
 
+        // The arrow keys select the results of the find prompt instead of walking its history.
+        if matches!(mode, InputMode::Find) && key.modifiers.is_empty() {
+            match key.code {
+                Up => {
+                    self.actions.find_mut().select_prev();
+                    return KeypressResult::Consumed;
+                }
+                Down => {
+                    self.actions.find_mut().select_next();
+                    return KeypressResult::Consumed;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(edit) = self.keymap.lookup_edit(&key) {
+            let mode = mode.clone();
+            self.handle_edit_action(workers, &mode, edit);
+            return KeypressResult::Consumed;
+        }
+
         if let Some(action) = self.keymap.lookup(key) {
             match action {
                 Action::Nav(NavAction::ScrollUp) => {
@@ -61,6 +86,7 @@ impl AppState {
 
         match key.code {
             Enter => {
+                let entry = self.actions.input_buffer().to_string();
                 match mode {
                     InputMode::NewFile => self.create_file(workers),
                     InputMode::NewFolder => self.create_folder(workers),
@@ -71,11 +97,18 @@ impl AppState {
                     InputMode::Find => self.handle_find(workers),
                     InputMode::MoveFile => self.move_file(workers),
                     InputMode::GoToPath => self.handle_go_to_path(workers),
+                    InputMode::MarkMatching => self.handle_mark_matching(workers, MarkMode::Mark),
+                    InputMode::UnmarkMatching => {
+                        self.handle_mark_matching(workers, MarkMode::Unmark)
+                    }
                 }
                 if self.actions().mode() == &prev_action_mode {
                     self.exit_input_mode();
                 }
-                KeypressResult::Consumed
+                match HistoryKind::of(mode) {
+                    Some(kind) => KeypressResult::History(kind, entry),
+                    None => KeypressResult::Consumed,
+                }
             }
 
             Esc => {
@@ -83,63 +116,10 @@ impl AppState {
                 KeypressResult::Consumed
             }
 
-            Left => {
-                self.actions.action_move_cursor_left();
-                KeypressResult::Consumed
-            }
-
-            Up => {
-                if matches!(mode, InputMode::Find) {
-                    self.actions.find_mut().select_prev();
-                    KeypressResult::Consumed
-                } else {
-                    KeypressResult::Continue
-                }
-            }
-
-            Down => {
-                if matches!(mode, InputMode::Find) {
-                    self.actions.find_mut().select_next();
-                    KeypressResult::Consumed
-                } else {
-                    KeypressResult::Continue
-                }
-            }
-
-            Right => {
-                self.actions.action_move_cursor_right();
-                KeypressResult::Consumed
-            }
-
-            Home => {
-                self.actions.action_cursor_home();
-                KeypressResult::Consumed
-            }
-
-            End => {
-                self.actions.action_cursor_end();
-                KeypressResult::Consumed
-            }
-
-            Backspace => {
-                self.actions.action_backspace_at_cursor();
-                if matches!(mode, InputMode::Filter) {
-                    self.apply_filter(workers);
-                }
-                if matches!(mode, InputMode::Find) {
-                    self.actions.find_debounce(Duration::from_millis(90));
-                }
-                KeypressResult::Consumed
-            }
-
             Tab => {
                 if matches!(mode, InputMode::MoveFile | InputMode::GoToPath) {
-                    if os::fd_binary().is_ok() {
-                        self.tab_autocomplete();
-                        KeypressResult::Consumed
-                    } else {
-                        KeypressResult::Continue
-                    }
+                    self.tab_autocomplete();
+                    KeypressResult::Consumed
                 } else {
                     KeypressResult::Continue
                 }
@@ -164,7 +144,9 @@ impl AppState {
                 | InputMode::NewFile
                 | InputMode::NewFolder
                 | InputMode::MoveFile
-                | InputMode::GoToPath => {
+                | InputMode::GoToPath
+                | InputMode::MarkMatching
+                | InputMode::UnmarkMatching => {
                     self.actions.action_insert_at_cursor(c);
                     KeypressResult::Consumed
                 }
@@ -179,37 +161,216 @@ impl AppState {
         }
     }
 
-    pub(in crate::app) fn handle_sys_action(&mut self, action: SystemAction) -> KeypressResult {
+    /// Runs an editing action of the prompt and updates the filter or find results
+    /// if the input changed.
+    fn handle_edit_action(&mut self, workers: &Workers, mode: &InputMode, edit: EditKeys) {
+        let changed = match edit {
+            EditKeys::CursorLeft => {
+                self.actions.action_move_cursor_left();
+                false
+            }
+            EditKeys::CursorRight => {
+                self.actions.action_move_cursor_right();
+                false
+            }
+            EditKeys::WordLeft => {
+                self.actions.action_move_word_left();
+                false
+            }
+            EditKeys::WordRight => {
+                self.actions.action_move_word_right();
+                false
+            }
+            EditKeys::LineStart => {
+                self.actions.action_cursor_home();
+                false
+            }
+            EditKeys::LineEnd => {
+                self.actions.action_cursor_end();
+                false
+            }
+            EditKeys::DeleteBack => self.actions.action_backspace_at_cursor(),
+            EditKeys::DeleteForward => self.actions.action_delete_at_cursor(),
+            EditKeys::DeleteWordBack => self.actions.action_kill_word_back(),
+            EditKeys::DeleteWordForward => self.actions.action_kill_word_forward(),
+            EditKeys::KillToEnd => self.actions.action_kill_to_end(),
+            EditKeys::KillToStart => self.actions.action_kill_to_start(),
+            EditKeys::Yank => self.actions.action_yank(),
+            EditKeys::Transpose => self.actions.action_transpose_chars(),
+            EditKeys::HistoryPrev => self.recall_history(HistoryCursor::prev),
+            EditKeys::HistoryNext => self.recall_history(HistoryCursor::next),
+            EditKeys::HistorySearch => self.recall_history(HistoryCursor::search),
+        };
+
+        if changed {
+            self.input_changed(workers, mode);
+        }
+    }
+
+    /// Replaces the input with an entry of the prompt history.
+    /// Returns true if an entry was recalled.
+    fn recall_history(
+        &mut self,
+        recall: impl FnOnce(&mut HistoryCursor, &str) -> Option<String>,
+    ) -> bool {
+        let input = self.actions.input_buffer().to_string();
+        match recall(self.actions.history_mut(), &input) {
+            Some(entry) => {
+                self.actions.set_input_buffer(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handles text pasted into the terminal.
+    ///
+    /// The text is inserted at the cursor of the prompt, line breaks are joined with spaces.
+    /// Pastes outside of a text prompt are ignored.
+    pub(crate) fn handle_paste(&mut self, workers: &Workers, text: &str) -> KeypressResult {
+        let ActionMode::Input { mode, .. } = self.actions().mode() else {
+            return KeypressResult::Continue;
+        };
+        if matches!(
+            mode,
+            InputMode::ConfirmDelete { .. } | InputMode::ConfirmOverwrite { .. }
+        ) {
+            return KeypressResult::Consumed;
+        }
+
+        let mode = mode.clone();
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .lines()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+        if !text.is_empty() {
+            self.actions.action_insert_str_at_cursor(&text);
+            self.input_changed(workers, &mode);
+        }
+        KeypressResult::Consumed
+    }
+
+    /// Updates the filter or the find results after the input of the prompt changed.
+    fn input_changed(&mut self, workers: &Workers, mode: &InputMode) {
+        match mode {
+            InputMode::Filter => {
+                self.apply_filter(workers);
+                self.update_file_info_cache(workers);
+            }
+            InputMode::Find => self.actions.find_debounce(Duration::from_millis(90)),
+            _ => {}
+        }
+    }
+
+    pub(in crate::app) fn handle_sys_action(
+        &mut self,
+        workers: &Workers,
+        action: SystemAction,
+    ) -> KeypressResult {
         match action {
             SystemAction::Quit => KeypressResult::Quit,
             SystemAction::KeyBindHelp => {
                 self.toggle_keybind_help();
                 KeypressResult::Consumed
             }
+            SystemAction::ThemePicker => {
+                self.open_theme_picker();
+                KeypressResult::Consumed
+            }
             SystemAction::Reload => KeypressResult::UiReload,
+            SystemAction::RecordMacro => KeypressResult::Macro(MacroCommand::Record),
+            SystemAction::PlayMacro => KeypressResult::Macro(MacroCommand::Play { count: None }),
+            SystemAction::ToggleHidden => self.toggle_list_option(workers, ListOption::ShowHidden),
+            SystemAction::ToggleSystem => self.toggle_list_option(workers, ListOption::ShowSystem),
+            SystemAction::ToggleSymlink => {
+                self.toggle_list_option(workers, ListOption::ShowSymlink)
+            }
+            SystemAction::ToggleDirsFirst => {
+                self.toggle_list_option(workers, ListOption::DirsFirst)
+            }
+            SystemAction::ToggleCaseInsensitive => {
+                self.toggle_list_option(workers, ListOption::CaseInsensitive)
+            }
+        }
+    }
+
+    /// Collects digits typed outside of a prefix into a count for the next action.
+    ///
+    /// Digits bound to an action or starting a key sequence keep their binding, and Esc drops
+    /// a pending count.
+    pub(in crate::app) fn handle_count_dispatch(
+        &mut self,
+        key: &KeyEvent,
+    ) -> Option<KeypressResult> {
+        if self.actions.prefix_recognizer().is_active() {
+            return None;
         }
+
+        if key.code == Esc && self.actions.take_count().is_some() {
+            return Some(KeypressResult::Consumed);
+        }
+
+        if !key.modifiers.is_empty()
+            || self.keymap.lookup(*key).is_some()
+            || self.keymap.starts_sequence(key)
+        {
+            return None;
+        }
+
+        let Char(c) = key.code else {
+            return None;
+        };
+        let digit = c.to_digit(10)?;
+        self.actions
+            .push_count_digit(digit)
+            .then_some(KeypressResult::Consumed)
     }
 
+    /// Handles the keys of the visual selection: Esc cancels it and the swap key
+    /// moves the cursor to the other end of the range.
+    pub(in crate::app) fn handle_visual_dispatch(
+        &mut self,
+        key: &KeyEvent,
+    ) -> Option<KeypressResult> {
+        if !self.nav.visual_active() || self.actions.prefix_recognizer().is_active() {
+            return None;
+        }
+
+        if key.code == Esc {
+            self.nav.cancel_visual();
+        } else if self.keymap.is_visual_swap(key) {
+            self.actions.take_count();
+            self.nav.swap_visual_ends();
+            self.preview.mark_pending();
+        } else {
+            return None;
+        }
+        Some(KeypressResult::Consumed)
+    }
+
+    /// Feeds the key into the pending key sequence and runs the action of a completed one.
+    /// Keys which neither start nor continue a sequence are left to the other handlers.
     pub(in crate::app) fn handle_prefix_dispatch(
         &mut self,
         workers: &Workers,
         key: &KeyEvent,
+        clipboard: &mut Clipboard,
     ) -> Option<KeypressResult> {
-        let gmap = self.keymap.gmap();
-        let sort_map = self.keymap.sortmap();
-
-        let (started, exited, result, consumed) = {
+        let (started, active, exited, result, consumed) = {
             let prefix = self.actions.prefix_recognizer_mut();
-            let was_g = prefix.is_g_state() || prefix.is_sort_state();
-            let g_prefix = self.keymap.g_prefix();
-            let sort_prefix = self.keymap.sort_prefix();
+            let was_prefix = prefix.is_active();
 
-            let result = prefix.feed(key, gmap, sort_map, sort_prefix, g_prefix);
+            let result = prefix.feed(key, &self.keymap);
 
-            let consumed = was_g && key.code == Esc;
+            let consumed = was_prefix && key.code == Esc;
 
             (
                 prefix.started_prefix(),
+                was_prefix && prefix.is_active(),
                 prefix.exited_prefix(),
                 result,
                 consumed,
@@ -223,62 +384,53 @@ impl AppState {
             self.hide_prefix_help();
         }
 
-        if consumed {
+        if consumed || started || active {
             return Some(KeypressResult::Consumed);
         }
 
-        if let Some(cmd) = result {
-            return self.handle_prefix_action(workers, cmd);
-        }
+        // The count typed before a sequence applies to its action.
+        let count = if exited {
+            self.actions.take_count()
+        } else {
+            None
+        };
 
-        None
+        result.map(|action| {
+            let action = self.pane_switch_fallback(action, key);
+            self.dispatch_action(workers, action, count, clipboard)
+        })
     }
 
-    fn handle_prefix_action(
+    /// Sorts the current directory by the mode, or toggles the sort order if it is already
+    /// sorted by it.
+    pub(in crate::app) fn handle_sort_action(
         &mut self,
         workers: &Workers,
-        prefix: PrefixCommand,
-    ) -> Option<KeypressResult> {
-        match prefix {
-            PrefixCommand::Nav(NavAction::GoToTop) => {
-                self.handle_go_to_top(workers);
-                self.update_file_info_cache(workers);
-                self.refresh_show_info_if_open();
-                Some(KeypressResult::Consumed)
-            }
-            PrefixCommand::Nav(NavAction::GoToHome) => {
-                self.handle_go_to_home(workers);
-                self.refresh_show_info_if_open();
-                Some(KeypressResult::Consumed)
-            }
-            PrefixCommand::Nav(NavAction::GoToPath) => {
-                self.prompt_go_to_path();
-                self.refresh_show_info_if_open();
-                Some(KeypressResult::Consumed)
-            }
-            PrefixCommand::Sort(sort_mode) => {
-                let mut sort_config: SortConfig = self.nav.sort_config();
+        sort_mode: SortMode,
+    ) -> KeypressResult {
+        let mut sort_config: SortConfig = self.nav.sort_for(self.nav.current_dir());
 
-                if sort_config.mode() == sort_mode {
-                    sort_config.set_order(sort_config.order().toggle());
-                } else {
-                    sort_config.set_mode(sort_mode);
-                    sort_config.set_order(SortOrder::Ascending);
-                }
+        if sort_config.mode() == sort_mode {
+            sort_config.set_order(sort_config.order().toggle());
+        } else {
+            sort_config.set_mode(sort_mode);
+            sort_config.set_order(SortOrder::Ascending);
+        }
 
-                self.nav.set_sort_config(sort_config);
+        let tab_wide = self.nav.change_sort(sort_config);
 
-                let focus = self
-                    .nav
-                    .selected_entry()
-                    .map(|file_entry| file_entry.name().to_os_string());
+        let focus = self
+            .nav
+            .selected_entry()
+            .map(|file_entry| file_entry.name().to_os_string());
 
-                self.request_dir_sort(workers, focus);
-                self.request_parent_content(workers);
-                self.preview.mark_pending();
-                Some(KeypressResult::Sort(sort_config))
-            }
-            _ => None,
+        self.request_dir_sort(workers, focus);
+        self.request_parent_content(workers);
+        self.preview.mark_pending();
+        if tab_wide {
+            KeypressResult::Sort(sort_config)
+        } else {
+            KeypressResult::Consumed
         }
     }
 
@@ -298,9 +450,10 @@ impl AppState {
     }
 
     /// Exits the current input mode.
-    /// Simple wrapper around actions::exit_mode.
+    /// A counted range which the prompt was for is dropped again.
     pub(super) fn exit_input_mode(&mut self) {
         self.actions.exit_mode();
+        self.nav.clear_count_targets();
     }
 
     fn process_confirm_char<F>(&mut self, workers: &Workers, c: char, on_confirm: F)
@@ -336,8 +489,12 @@ impl AppState {
 
     /// Prompts the user to rename the selected entry.
     pub(super) fn prompt_rename(&mut self) {
-        if let Some(entry) = self.nav.selected_entry() {
-            let name = entry.name().to_string_lossy().to_string();
+        // Flatten entries are named by their relative path, so only the file name is renamed.
+        if let Some(name) = self
+            .nav
+            .selected_path()
+            .and_then(|p| p.file_name().map(|name| name.to_string_lossy().to_string()))
+        {
             self.enter_input_mode(InputMode::Rename, "Rename: ".to_string(), Some(name));
         }
     }
@@ -382,16 +539,24 @@ impl AppState {
         self.enter_input_mode(InputMode::MoveFile, prompt, None);
     }
 
-    fn prompt_go_to_path(&mut self) {
+    /// Prompts the user to enter a filter query to mark or unmark all matching entries.
+    pub(super) fn prompt_mark_matching(&mut self, mode: MarkMode) {
+        let (input_mode, prompt) = match mode {
+            MarkMode::Mark => (InputMode::MarkMatching, "Mark matching: "),
+            MarkMode::Unmark => (InputMode::UnmarkMatching, "Unmark matching: "),
+        };
+        self.enter_input_mode(input_mode, prompt.to_string(), None);
+    }
+
+    pub(super) fn prompt_go_to_path(&mut self) {
         self.enter_input_mode(InputMode::GoToPath, "Go To Path:".to_string(), None);
     }
 
-    /// Handles the autocomplete for move to directory action
+    /// Handles the autocomplete of the move and go to path prompts.
+    ///
+    /// Entries of the prompt history which continue the input are suggested first,
+    /// followed by the directories found with fd, if it is installed.
     fn tab_autocomplete(&mut self) {
-        if os::fd_binary().is_err() {
-            return;
-        }
-
         let input = self.actions.input_buffer().to_string();
         let expanded = path::expand_home_path(input.trim());
 
@@ -402,15 +567,37 @@ impl AppState {
             (self.nav.current_dir(), expanded.as_str())
         };
 
-        let show_hidden = self.config.general().show_hidden();
+        let show_hidden = self.view_for(self.nav.current_dir()).1.show_hidden;
+
+        let trimmed = input.trim();
+        let history: Vec<String> = self
+            .actions
+            .history()
+            .entries()
+            .iter()
+            .filter(|entry| entry.starts_with(trimmed) && entry.as_str() != trimmed)
+            .cloned()
+            .collect();
 
         let suggestion_opt = {
             let ac = self.actions.autocomplete_mut();
 
             let needs_update = ac.last_input() != input || ac.suggestions().is_empty();
             if needs_update {
-                let suggestions =
-                    proc::complete_dirs_with_fd(base_dir, prefix, show_hidden).unwrap_or_default();
+                let mut suggestions = history;
+                if os::fd_binary().is_ok() {
+                    let dirs = proc::complete_dirs_with_fd(base_dir, prefix, show_hidden)
+                        .unwrap_or_default();
+                    for dir in dirs {
+                        let mut out = base_dir.join(dir).to_string_lossy().to_string();
+                        if !out.ends_with(MAIN_SEPARATOR) {
+                            out.push(MAIN_SEPARATOR);
+                        }
+                        if !suggestions.contains(&out) {
+                            suggestions.push(out);
+                        }
+                    }
+                }
                 ac.update(suggestions, &input);
             }
 
@@ -424,13 +611,7 @@ impl AppState {
         };
 
         if let Some(suggestion) = suggestion_opt {
-            let mut completed_path = base_dir.to_path_buf();
-            completed_path.push(&suggestion);
-            let mut out = completed_path.to_string_lossy().to_string();
-            if !out.ends_with(MAIN_SEPARATOR) {
-                out.push(MAIN_SEPARATOR);
-            }
-            self.actions.set_input_buffer(out);
+            self.actions.set_input_buffer(suggestion);
         }
     }
 }
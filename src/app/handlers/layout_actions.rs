@@ -0,0 +1,74 @@
+//! Layout action handlers for AppState.
+//!
+//! Toggles and resizes the panes of the runtime layout, toggles the details view of the
+//! main pane and zooms the preview into a full-screen pager, in which the movement keys
+//! scroll the preview.
+
+use crossterm::event::KeyEvent;
+
+use crate::app::keymap::{Action, LayoutAction, NavAction, SystemAction};
+use crate::app::layout::LayoutState;
+use crate::app::state::{AppState, KeypressResult};
+
+impl AppState {
+    /// Handles a layout action, a count widens or narrows the main pane by count steps.
+    pub(in crate::app) fn handle_layout_action(
+        &mut self,
+        action: LayoutAction,
+        count: usize,
+    ) -> KeypressResult {
+        let steps = i32::try_from(count).unwrap_or(i32::MAX);
+        match action {
+            LayoutAction::ToggleParent => self.layout.toggle_parent(),
+            LayoutAction::TogglePreview => self.layout.toggle_preview(),
+            LayoutAction::ZoomPreview => self.layout.toggle_zoom(),
+            LayoutAction::WidenMain => {
+                self.layout.resize_main(steps);
+            }
+            LayoutAction::NarrowMain => {
+                self.layout.resize_main(-steps);
+            }
+            LayoutAction::Reset => self.layout = LayoutState::new(self.config.display()),
+            LayoutAction::ToggleDetails => self.details.toggle(),
+        }
+        KeypressResult::Consumed
+    }
+
+    /// Handles the pager keys of the zoomed preview.
+    ///
+    /// The movement and scroll keys scroll the preview and quit leaves the zoom.
+    /// Other keys are handled as usual.
+    pub(in crate::app) fn handle_zoom_dispatch(
+        &mut self,
+        key: &KeyEvent,
+    ) -> Option<KeypressResult> {
+        if !self.layout.zoomed() || self.overlays.needs_scroll() {
+            return None;
+        }
+
+        let up = match self.keymap.lookup(*key)? {
+            Action::Nav(NavAction::GoUp | NavAction::ScrollUp) => true,
+            Action::Nav(NavAction::GoDown | NavAction::ScrollDown) => false,
+            Action::System(SystemAction::Quit) => {
+                self.actions.take_count();
+                self.layout.toggle_zoom();
+                return Some(KeypressResult::Consumed);
+            }
+            _ => return None,
+        };
+
+        let count = self
+            .actions
+            .take_count()
+            .unwrap_or(1)
+            .min(usize::from(u16::MAX));
+        for _ in 0..count {
+            if up {
+                self.preview.scroll_up();
+            } else {
+                self.preview.scroll_down();
+            }
+        }
+        Some(KeypressResult::Consumed)
+    }
+}
@@ -0,0 +1,141 @@
+//! Mouse event handler for AppState
+//!
+//! Maps clicks and wheel scrolling onto the panes, entries and tabs of the last rendered frame.
+//! Clicking an entry selects it, a double-click opens it, clicking the parent pane navigates up
+//! and clicking the preview pane enters the previewed directory.
+//!
+//! Mouse events are only handled if `display.mouse` is enabled.
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+use crate::app::{
+    Clipboard, PreviewData, Workers,
+    keymap::{FileAction, NavAction, TabAction},
+    mouse::MouseTarget,
+    state::{AppState, KeypressResult},
+};
+
+impl AppState {
+    /// Handles a mouse event.
+    /// Returns a [KeypressResult] indicating how the event was handled.
+    pub(crate) fn handle_mouse(
+        &mut self,
+        mouse: MouseEvent,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        if !self.config.display().mouse() || self.actions.is_input_mode() {
+            return KeypressResult::Continue;
+        }
+
+        let result = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.handle_click(mouse.column, mouse.row, workers, clipboard)
+            }
+            MouseEventKind::ScrollUp => {
+                self.handle_wheel(mouse.column, mouse.row, workers, clipboard, true)
+            }
+            MouseEventKind::ScrollDown => {
+                self.handle_wheel(mouse.column, mouse.row, workers, clipboard, false)
+            }
+            _ => KeypressResult::Continue,
+        };
+        self.nav.sync_visual();
+        result
+    }
+
+    /// Handles a left click on the element at the given position.
+    fn handle_click(
+        &mut self,
+        column: u16,
+        row: u16,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        let Some(target) = self.mouse.regions().target_at(column, row) else {
+            return KeypressResult::Continue;
+        };
+        let is_double = self.mouse.register_click(target);
+
+        match target {
+            MouseTarget::Main(idx) => {
+                if idx >= self.nav.shown_entries_len() {
+                    return KeypressResult::Continue;
+                }
+                self.move_nav_if_possible(workers, |nav| nav.select_visible(idx));
+
+                if !is_double {
+                    return KeypressResult::Continue;
+                }
+                if self.nav.selected_entry().is_some_and(|e| e.is_dir()) {
+                    self.handle_nav_action(workers, NavAction::GoIntoDir, clipboard)
+                } else {
+                    self.handle_file_action(workers, FileAction::Open, clipboard)
+                }
+            }
+            MouseTarget::Parent(idx) => {
+                let focus = self
+                    .parent
+                    .entries()
+                    .get(idx)
+                    .map(|e| e.name().to_os_string());
+                let res = self.handle_go_parent(workers, focus);
+                self.refresh_show_info_if_open();
+                res
+            }
+            MouseTarget::Preview(idx) => {
+                let PreviewData::Directory { entries, .. } = self.preview.data() else {
+                    return KeypressResult::Continue;
+                };
+                let (Some(entry), Some(dir)) = (entries.get(idx), self.preview.current_path())
+                else {
+                    return KeypressResult::Continue;
+                };
+
+                let focus = Some(entry.name().to_os_string());
+                self.navigate_to(dir.to_path_buf(), focus, workers);
+                self.refresh_show_info_if_open();
+                KeypressResult::Continue
+            }
+            MouseTarget::Tab(idx) => u8::try_from(idx + 1).map_or(KeypressResult::Continue, |n| {
+                KeypressResult::Tab(TabAction::Switch(n))
+            }),
+        }
+    }
+
+    /// Handles the mouse wheel.
+    ///
+    /// Moves the selection of the keybind help, scrolls the preview or an open overlay,
+    /// otherwise moves the selection of the main pane without wrapping around.
+    fn handle_wheel(
+        &mut self,
+        column: u16,
+        row: u16,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
+        up: bool,
+    ) -> KeypressResult {
+        if let Some(help) = self.overlays.find_keybind_help_mut() {
+            help.select_by(if up { -1 } else { 1 });
+            return KeypressResult::Consumed;
+        }
+
+        if self.overlays.needs_scroll() || self.mouse.regions().in_preview(column, row) {
+            let action = if up {
+                NavAction::ScrollUp
+            } else {
+                NavAction::ScrollDown
+            };
+            return self.handle_nav_action(workers, action, clipboard);
+        }
+
+        let selected = self.nav.selected_idx();
+        let target = if up {
+            selected.saturating_sub(1)
+        } else {
+            selected + 1
+        };
+        self.move_nav_if_possible(workers, |nav| nav.select_visible(target));
+        KeypressResult::Continue
+    }
+}
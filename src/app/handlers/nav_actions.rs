@@ -17,8 +17,10 @@ use std::time::Duration;
 use crate::app::{
     Clipboard, NavState, Workers,
     keymap::NavAction,
+    nav::MarkMode,
     state::{AppState, KeypressResult},
 };
+use crate::core::query::FilterQuery;
 use crate::utils::{os, path, timings::Timings};
 
 impl AppState {
@@ -38,11 +40,19 @@ impl AppState {
                 self.move_nav_if_possible(workers, |nav| nav.move_down());
             }
             NavAction::GoParent => {
-                let res = self.handle_go_parent(workers);
+                if self.nav.collapse_selected() {
+                    self.after_tree_change(workers);
+                    return KeypressResult::Continue;
+                }
+                let res = self.handle_go_parent(workers, None);
                 self.refresh_show_info_if_open();
                 return res;
             }
             NavAction::GoIntoDir => {
+                if !self.nav.is_selected_expanded() && self.nav.expand_selected() {
+                    self.after_tree_change(workers);
+                    return KeypressResult::Continue;
+                }
                 let res = self.handle_go_into_dir(workers);
                 self.refresh_show_info_if_open();
                 return res;
@@ -96,16 +106,190 @@ impl AppState {
                 self.preview.mark_pending();
                 self.update_file_info_cache(workers);
             }
-            _ => {}
+            NavAction::MarkMatching => {
+                self.prompt_mark_matching(MarkMode::Mark);
+            }
+            NavAction::UnmarkMatching => {
+                self.prompt_mark_matching(MarkMode::Unmark);
+            }
+            NavAction::InvertMarkers => {
+                self.nav.invert_markers();
+                self.preview.mark_pending();
+                self.update_file_info_cache(workers);
+            }
+            NavAction::VisualMode => {
+                self.nav.toggle_visual();
+            }
+            NavAction::ToggleTree => {
+                let msg = if self.nav.toggle_tree() {
+                    "Tree view enabled"
+                } else {
+                    "Tree view disabled"
+                };
+                self.push_overlay_message(msg.to_string(), Duration::from_secs(2), None);
+                self.after_tree_change(workers);
+            }
+            NavAction::ToggleFlatten => {
+                let focus = self.nav.selected_root_name();
+                if self.nav.toggle_flatten() {
+                    self.request_flatten(workers);
+                } else {
+                    self.request_dir_load(workers, focus);
+                }
+                self.preview.mark_pending();
+            }
+            NavAction::TreeExpand
+            | NavAction::TreeCollapse
+            | NavAction::TreeToggleFold
+            | NavAction::TreeCollapseAll => {
+                self.handle_tree_action(workers, action);
+            }
+            NavAction::GoToTop => {
+                self.handle_go_to_top(workers);
+                self.update_file_info_cache(workers);
+                self.refresh_show_info_if_open();
+            }
+            NavAction::GoToHome => {
+                self.handle_go_to_home(workers);
+                self.refresh_show_info_if_open();
+            }
+            NavAction::GoToPath => {
+                self.prompt_go_to_path();
+                self.refresh_show_info_if_open();
+            }
         }
         KeypressResult::Continue
     }
 
+    /// Marks or unmarks all shown entries matching the filter query in the input buffer.
+    ///
+    /// If the query needs metadata, the marking is finished once the worker has loaded it.
+    pub(super) fn handle_mark_matching(&mut self, workers: &Workers, mode: MarkMode) {
+        let input = self.actions.input_buffer().trim().to_string();
+        if input.is_empty() {
+            return;
+        }
+
+        match FilterQuery::parse(&input) {
+            Ok(query) => {
+                let res = match mode {
+                    MarkMode::Mark => self.nav.mark_matching(query),
+                    MarkMode::Unmark => self.nav.unmark_matching(query),
+                };
+                match res {
+                    Some(count) => self.push_marked_message(mode, count),
+                    None => self.request_filter_meta(workers),
+                }
+            }
+            Err(e) => {
+                self.push_overlay_message(
+                    format!("Invalid query: {}", e),
+                    Duration::from_secs(4),
+                    None,
+                );
+            }
+        }
+        self.preview.mark_pending();
+    }
+
+    /// Handles the tree fold actions.
+    pub(in crate::app) fn handle_tree_action(&mut self, workers: &Workers, action: NavAction) {
+        let changed = match action {
+            NavAction::TreeExpand => self.nav.expand_selected(),
+            NavAction::TreeCollapse => self.nav.collapse_selected(),
+            NavAction::TreeToggleFold => self.nav.toggle_fold_selected(),
+            NavAction::TreeCollapseAll => self.nav.collapse_all(),
+            _ => false,
+        };
+
+        if changed {
+            self.after_tree_change(workers);
+        } else if !self.nav.tree_enabled() {
+            self.push_overlay_message(
+                "Tree view is disabled".to_string(),
+                Duration::from_secs(2),
+                None,
+            );
+        }
+    }
+
+    /// Loads missing tree children and refreshes the preview after the tree rows changed.
+    fn after_tree_change(&mut self, workers: &Workers) {
+        self.request_tree_children(workers);
+        self.update_file_info_cache(workers);
+        self.refresh_show_info_if_open();
+        self.preview.mark_pending();
+    }
+
+    pub(in crate::app) fn push_marked_message(&mut self, mode: MarkMode, count: usize) {
+        let label = if count == 1 { "entry" } else { "entries" };
+        let verb = match mode {
+            MarkMode::Mark => "Marked",
+            MarkMode::Unmark => "Unmarked",
+        };
+        self.push_overlay_message(
+            format!("{} {} {}", verb, count, label),
+            Duration::from_secs(2),
+            None,
+        );
+    }
+
+    /// Handles navigation actions with a count prefix (`5j`, `10G`, `3h`).
+    ///
+    /// Motions move by the count, go-to actions jump to the entry at the count
+    /// and the remaining repeatable actions are applied count times.
+    pub(in crate::app) fn handle_nav_count(
+        &mut self,
+        workers: &Workers,
+        action: NavAction,
+        count: usize,
+        clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        let selected = self.nav.selected_idx();
+        let last = self.nav.shown_entries_len().saturating_sub(1);
+
+        let repeat = match action {
+            NavAction::GoUp => {
+                let target = selected.saturating_sub(count);
+                self.move_nav_if_possible(workers, |nav| nav.select_visible(target));
+                return KeypressResult::Continue;
+            }
+            NavAction::GoDown => {
+                let target = selected.saturating_add(count).min(last);
+                self.move_nav_if_possible(workers, |nav| nav.select_visible(target));
+                return KeypressResult::Continue;
+            }
+            NavAction::GoToTop | NavAction::GoToBottom => {
+                self.go_to_entry(workers, count);
+                return KeypressResult::Continue;
+            }
+            NavAction::ToggleMarker => count.min(last + 1),
+            NavAction::GoParent | NavAction::ScrollUp | NavAction::ScrollDown => count,
+            _ => 1,
+        };
+
+        let mut result = KeypressResult::Continue;
+        for _ in 0..repeat {
+            result = self.handle_nav_action(workers, action, clipboard);
+            if !matches!(result, KeypressResult::Continue) {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Selects the visible entry at the 1-based `line`, clamped to the last entry.
+    pub(super) fn go_to_entry(&mut self, workers: &Workers, line: usize) {
+        let last = self.nav.shown_entries_len().saturating_sub(1);
+        let target = line.saturating_sub(1).min(last);
+        self.move_nav_if_possible(workers, |nav| nav.select_visible(target));
+    }
+
     /// Calls the provided function to move navigation if possible.
     ///
     /// If the movement was successful (f returns true), marks the preview as pending refresh.
     /// Used to encapsulate common logic for nav actions that change selection or directory.
-    fn move_nav_if_possible<F>(&mut self, workers: &Workers, f: F)
+    pub(super) fn move_nav_if_possible<F>(&mut self, workers: &Workers, f: F)
     where
         F: FnOnce(&mut NavState) -> bool,
     {
@@ -115,8 +299,7 @@ impl AppState {
 
         let allow_immediate = self.nav_time.can_trigger(Timings::NAV_THROTTLE_MS);
 
-        let selected_changed_preview = if let Some(entry) = self.nav.selected_entry() {
-            let sel_path = self.nav.current_dir().join(entry.name());
+        let selected_changed_preview = if let Some(sel_path) = self.nav.selected_path() {
             self.preview.current_path() != Some(sel_path.as_path())
         } else {
             true
@@ -143,7 +326,12 @@ impl AppState {
     ///
     /// If the current directory has a parent, navigates to it, saves the current position,
     /// and requests loading of the new directory and its parent content.
-    fn handle_go_parent(&mut self, workers: &Workers) -> KeypressResult {
+    /// Focuses `focus` in the parent, or the exited directory if none is given.
+    pub(super) fn handle_go_parent(
+        &mut self,
+        workers: &Workers,
+        focus: Option<OsString>,
+    ) -> KeypressResult {
         let current = self.nav.current_dir();
 
         let Some(parent) = current.parent() else {
@@ -161,8 +349,8 @@ impl AppState {
             return KeypressResult::Consumed;
         }
 
-        let exited_name = current.file_name().map(|n| n.to_os_string());
-        self.navigate_to(parent_path, exited_name, workers);
+        let focus = focus.or_else(|| current.file_name().map(|n| n.to_os_string()));
+        self.navigate_to(parent_path, focus, workers);
         KeypressResult::Continue
     }
 
@@ -171,12 +359,10 @@ impl AppState {
     /// If the selected entry is a directory, navigates into it, saves the current position,
     /// and requests loading of the new directory and its parent content.
     fn handle_go_into_dir(&mut self, workers: &Workers) -> KeypressResult {
-        let Some(entry) = self.nav.selected_entry() else {
+        let Some(entry_path) = self.nav.selected_path() else {
             return KeypressResult::Continue;
         };
 
-        let entry_path = self.nav.current_dir().join(entry.name());
-
         let Ok(meta) = std::fs::metadata(&entry_path) else {
             return KeypressResult::Continue;
         };
@@ -283,12 +469,16 @@ impl AppState {
         self.request_preview(workers);
     }
 
-    fn navigate_to(&mut self, path: PathBuf, focus: Option<OsString>, workers: &Workers) {
+    pub(super) fn navigate_to(
+        &mut self,
+        path: PathBuf,
+        focus: Option<OsString>,
+        workers: &Workers,
+    ) {
         self.nav.save_position();
         self.nav.set_path(path.clone());
 
-        let sort_config = self.nav.sort_config();
-        let list_opts = self.dir_list_options();
+        let (sort_config, list_opts) = self.view_for(&path);
 
         if let Some(val) = workers.cache().get(&path, sort_config, &list_opts) {
             let (entries, sort_col, _rid, _ts) = &*val;
@@ -299,10 +489,7 @@ impl AppState {
             self.is_loading = false;
         } else if let Some(parent) = self.nav.current_dir().parent()
             && path == parent
-            && let Some(val) =
-                workers
-                    .cache()
-                    .get(parent, self.nav.sort_config(), &self.dir_list_options())
+            && let Some(val) = workers.cache().get(parent, sort_config, &list_opts)
         {
             let (entries, sort_col, _rid, _ts) = &*val;
             let entries_vec = entries.clone();
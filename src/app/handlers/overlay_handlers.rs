@@ -6,12 +6,20 @@
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crossterm::event::{KeyCode::*, KeyEvent};
+use crossterm::event::{KeyCode::*, KeyEvent, KeyModifiers};
 use ratatui::layout::Alignment;
 
-use crate::app::state::{AppState, KeypressResult};
+use crate::app::{
+    Clipboard, Workers,
+    help::{self, HelpPalette},
+    keymap::{Action, NavAction, SystemAction},
+    state::{AppState, KeypressResult},
+    theme_picker::ThemePicker,
+};
+use crate::config::{Theme, input::EditKeys};
 use crate::core::metadata::FileMetadataCache;
 use crate::ui::overlays::{Overlay, OverlayKind};
+use crate::utils::os;
 
 /// AppState input and action handlers
 impl AppState {
@@ -85,10 +93,155 @@ impl AppState {
         if is_open {
             self.overlays_mut().remove_kind(OverlayKind::KeybindHelp);
         } else {
-            self.overlays_mut().push(Overlay::KeybindHelp);
+            let rows = help::help_rows(self.keymap(), self.config());
+            self.actions.scroll().reset();
+            self.overlays_mut().push(Overlay::KeybindHelp {
+                help: Box::new(HelpPalette::new(rows)),
+            });
         }
     }
 
+    /// Handles a key while the keybind help is open.
+    ///
+    /// Typed characters filter the help, the history keys of the prompt and the arrow keys
+    /// move the selection and Enter runs the selected action. The help key closes it again.
+    pub(in crate::app) fn handle_keybind_help_dispatch(
+        &mut self,
+        workers: &Workers,
+        key: &KeyEvent,
+        clipboard: &mut Clipboard,
+    ) -> Option<KeypressResult> {
+        const PAGE: isize = 10;
+
+        if !self.overlays().is_open(OverlayKind::KeybindHelp) {
+            return None;
+        }
+        if self.keymap().lookup(*key) == Some(Action::System(SystemAction::KeyBindHelp)) {
+            self.toggle_keybind_help();
+            return Some(KeypressResult::Consumed);
+        }
+
+        let edit = self.keymap().lookup_edit(key);
+        let help = self.overlays.find_keybind_help_mut()?;
+        let query_len = help.query().len();
+
+        match (key.code, edit) {
+            (Enter, _) => {
+                let action = help.selected_action();
+                self.overlays_mut().remove_kind(OverlayKind::KeybindHelp);
+                return Some(match action {
+                    // Without a pane switch key the chosen pane switch goes to the next tab.
+                    Some(action) => {
+                        let action = self.pane_switch_fallback(action, key);
+                        self.dispatch_action(workers, action, None, clipboard)
+                    }
+                    None => KeypressResult::Consumed,
+                });
+            }
+            (Up | BackTab, _) | (_, Some(EditKeys::HistoryPrev)) => help.select_by(-1),
+            (Down | Tab, _) | (_, Some(EditKeys::HistoryNext)) => help.select_by(1),
+            (PageUp, _) => help.select_by(-PAGE),
+            (PageDown, _) => help.select_by(PAGE),
+            (Home, _) => help.select_by(isize::MIN),
+            (End, _) => help.select_by(isize::MAX),
+            (_, Some(EditKeys::DeleteBack)) => help.pop_char(),
+            (_, Some(EditKeys::DeleteWordBack | EditKeys::KillToStart)) => help.clear_query(),
+            (Char(c), _)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                help.push_char(c)
+            }
+            _ => {}
+        }
+
+        if help.query().len() != query_len {
+            self.actions.scroll().reset();
+        }
+        Some(KeypressResult::Consumed)
+    }
+
+    /// Opens the theme picker with the presets and the theme files.
+    pub(super) fn open_theme_picker(&mut self) {
+        if self.overlays().is_open(OverlayKind::ThemePicker) {
+            return;
+        }
+        let themes_dir = os::default_themes_dir();
+        let names = Theme::available(themes_dir.as_deref());
+        let picker = ThemePicker::new(names, self.config().theme().name());
+        self.actions.scroll().reset();
+        self.overlays_mut().push(Overlay::ThemePicker { picker });
+    }
+
+    /// Handles a key while the theme picker is open.
+    ///
+    /// Moving the selection shows the selected theme, Enter keeps it and Esc, quit or the
+    /// theme picker key go back to the theme of the config.
+    pub(in crate::app) fn handle_theme_picker_dispatch(
+        &mut self,
+        key: &KeyEvent,
+    ) -> Option<KeypressResult> {
+        const PAGE: isize = 10;
+
+        let action = self.keymap().lookup(*key);
+        let picker = self.overlays.find_theme_picker_mut()?;
+
+        let delta = match (key.code, action) {
+            (Enter, _) => {
+                let name = picker.selected_name().map(str::to_string);
+                self.overlays_mut().remove_kind(OverlayKind::ThemePicker);
+                if let Some(name) = name {
+                    self.push_overlay_message(
+                        format!("Theme: {name}"),
+                        Duration::from_secs(2),
+                        None,
+                    );
+                }
+                return Some(KeypressResult::Consumed);
+            }
+            (Esc, _)
+            | (_, Some(Action::System(SystemAction::Quit | SystemAction::ThemePicker))) => {
+                self.overlays_mut().remove_kind(OverlayKind::ThemePicker);
+                return Some(KeypressResult::Theme(None));
+            }
+            (Up, _) | (_, Some(Action::Nav(NavAction::GoUp))) => -1,
+            (Down, _) | (_, Some(Action::Nav(NavAction::GoDown))) => 1,
+            (PageUp, _) => -PAGE,
+            (PageDown, _) => PAGE,
+            (Home, _) => isize::MIN,
+            (End, _) => isize::MAX,
+            _ => 0,
+        };
+
+        if delta != 0 && picker.select_by(delta) {
+            return Some(KeypressResult::Theme(
+                picker.selected_name().map(str::to_string),
+            ));
+        }
+        Some(KeypressResult::Consumed)
+    }
+
+    /// Shows the problems found in the keybindings, if there are any.
+    pub(crate) fn push_key_issues(&mut self) {
+        const SHOWN_ISSUES: usize = 5;
+
+        let issues = self.keymap().issues();
+        if issues.is_empty() {
+            return;
+        }
+
+        let mut text = String::from("Keybinding issues (see rn --check-keys):");
+        for issue in issues.iter().take(SHOWN_ISSUES) {
+            text.push_str("\n  ");
+            text.push_str(&issue.to_string());
+        }
+        if issues.len() > SHOWN_ISSUES {
+            text.push_str(&format!("\n  ... and {} more", issues.len() - SHOWN_ISSUES));
+        }
+        self.push_overlay_message(text, Duration::from_secs(8), Some(Alignment::Left));
+    }
+
     /// Pushes a message overlay that lasts for the specified duration.
     pub(crate) fn push_overlay_message(
         &mut self,
@@ -0,0 +1,398 @@
+//! Keybinding help for runa.
+//!
+//! The help lists every action with the keys bound to it in the live [Keymap], so custom
+//! bindings, named macros and prefix groups show up as configured. [HELP_DATA] holds the
+//! description and category of every action.
+//!
+//! The open help is a [HelpPalette]: typing filters the rows and the selected action can be
+//! run directly, like a command palette.
+
+use crate::Config;
+use crate::app::keymap::{Action, BindingLabel, Keymap};
+use crate::config::input::InputKeys;
+
+struct HelpEntry {
+    key: InputKeys,
+    desc: &'static str,
+}
+
+struct HelpSection {
+    name: &'static str,
+    entries: &'static [HelpEntry],
+}
+
+#[rustfmt::skip]
+const HELP_DATA: &[HelpSection] = &[
+    HelpSection {
+        name: "Navigation",
+        entries: &[
+            HelpEntry { key: InputKeys::GoUp, desc: "Move selection up" },
+            HelpEntry { key: InputKeys::GoDown, desc: "Move selection down" },
+            HelpEntry { key: InputKeys::GoParent, desc: "Go to parent directory" },
+            HelpEntry { key: InputKeys::GoIntoDir, desc: "Enter directory" },
+            HelpEntry { key: InputKeys::ToggleMarker, desc: "Toggle marker" },
+            HelpEntry { key: InputKeys::ClearMarkers, desc: "Clear markers" },
+            HelpEntry { key: InputKeys::ClearFilter, desc: "Clear filter" },
+            HelpEntry { key: InputKeys::ClearAll, desc: "Clear all markers and filters" },
+            HelpEntry { key: InputKeys::SelectAll, desc: "Select all entries in directory" },
+            HelpEntry { key: InputKeys::MarkMatching, desc: "Mark entries matching a query" },
+            HelpEntry { key: InputKeys::UnmarkMatching, desc: "Unmark entries matching a query" },
+            HelpEntry { key: InputKeys::InvertMarkers, desc: "Invert markers of shown entries" },
+            HelpEntry { key: InputKeys::VisualMode, desc: "Visual selection (Esc cancels)" },
+            HelpEntry { key: InputKeys::VisualSwap, desc: "Swap ends of visual selection" },
+            HelpEntry { key: InputKeys::ToggleFlatten, desc: "Toggle flatten view (all files below)" },
+            HelpEntry { key: InputKeys::GoToBottom, desc: "Go to bottom" },
+            HelpEntry { key: InputKeys::ScrollUp, desc: "Scroll widget up" },
+            HelpEntry { key: InputKeys::ScrollDown, desc: "Scroll widget down" },
+        ],
+    },
+    HelpSection {
+        name: "Tabs",
+        entries: &[
+            HelpEntry { key: InputKeys::TabNew, desc: "Create a new tab" },
+            HelpEntry { key: InputKeys::TabClose, desc: "Close the selected tab" },
+            HelpEntry { key: InputKeys::TabNext, desc: "Switch to next tab" },
+            HelpEntry { key: InputKeys::TabPrev, desc: "Switch to previous tab" },
+            HelpEntry { key: InputKeys::PaneSwitch, desc: "Switch pane (dual layout)" },
+            HelpEntry { key: InputKeys::CopyToPane, desc: "Copy to other pane (dual layout)" },
+            HelpEntry { key: InputKeys::MoveToPane, desc: "Move to other pane (dual layout)" },
+        ],
+    },
+    HelpSection {
+        name: "Layout",
+        entries: &[
+            HelpEntry { key: InputKeys::ToggleParent, desc: "Show/hide parent pane" },
+            HelpEntry { key: InputKeys::TogglePreview, desc: "Show/hide preview pane" },
+            HelpEntry { key: InputKeys::ZoomPreview, desc: "Zoom preview (pager)" },
+            HelpEntry { key: InputKeys::WidenMain, desc: "Widen main pane" },
+            HelpEntry { key: InputKeys::NarrowMain, desc: "Narrow main pane" },
+            HelpEntry { key: InputKeys::ResetLayout, desc: "Reset layout to config" },
+            HelpEntry { key: InputKeys::ToggleDetails, desc: "Toggle details view" },
+        ],
+    },
+    HelpSection {
+        name: "File",
+        entries: &[
+            HelpEntry { key: InputKeys::OpenFile, desc: "Open file in editor" },
+            HelpEntry { key: InputKeys::Copy, desc: "Copy/Yank selection" },
+            HelpEntry { key: InputKeys::Paste, desc: "Paste" },
+            HelpEntry { key: InputKeys::Rename, desc: "Rename" },
+            HelpEntry { key: InputKeys::Create, desc: "Create file" },
+            HelpEntry { key: InputKeys::CreateDirectory, desc: "Create directory" },
+            HelpEntry { key: InputKeys::Delete, desc: "Delete / move to trash" },
+            HelpEntry { key: InputKeys::AlternateDelete, desc: "Alternate delete mode" },
+            HelpEntry { key: InputKeys::Filter, desc: "Filter entries" },
+            HelpEntry { key: InputKeys::Find, desc: "Find (fuzzy)" },
+            HelpEntry { key: InputKeys::MoveFile, desc: "Move file(s)" },
+            HelpEntry { key: InputKeys::ShowInfo, desc: "Toggle file info" },
+            HelpEntry { key: InputKeys::ClearClipboard, desc: "Clear copied entries" },
+            HelpEntry { key: InputKeys::Repeat, desc: "Repeat last file operation" },
+        ],
+    },
+    HelpSection {
+        name: "Tree",
+        entries: &[
+            HelpEntry { key: InputKeys::ToggleTree, desc: "Toggle tree view" },
+            HelpEntry { key: InputKeys::PrefixFold, desc: "Fold prefix" },
+            HelpEntry { key: InputKeys::TreeExpand, desc: "Expand directory" },
+            HelpEntry { key: InputKeys::TreeCollapse, desc: "Collapse directory" },
+            HelpEntry { key: InputKeys::TreeToggleFold, desc: "Toggle directory" },
+            HelpEntry { key: InputKeys::TreeCollapseAll, desc: "Collapse all directories" },
+        ],
+    },
+    HelpSection {
+        name: "Go To",
+        entries: &[
+            HelpEntry { key: InputKeys::PrefixGoTo, desc: "Go to prefix" },
+            HelpEntry { key: InputKeys::GoToTop, desc: "Go to top" },
+            HelpEntry { key: InputKeys::GoToHome, desc: "Go to home" },
+            HelpEntry { key: InputKeys::GoToPath, desc: "Go to path" },
+        ],
+    },
+    HelpSection {
+        name: "Sort",
+        entries: &[
+            HelpEntry { key: InputKeys::Sort, desc: "Sort prefix" },
+            HelpEntry { key: InputKeys::SortByName, desc: "Sort by name" },
+            HelpEntry { key: InputKeys::SortByNatural, desc: "Sort by natural order" },
+            HelpEntry { key: InputKeys::SortByExtension, desc: "Sort by extension" },
+            HelpEntry { key: InputKeys::SortBySize, desc: "Sort by size" },
+            HelpEntry { key: InputKeys::SortByModified, desc: "Sort by modified time" },
+            HelpEntry { key: InputKeys::SortByCreated, desc: "Sort by created time" },
+            HelpEntry { key: InputKeys::SortByAccessed, desc: "Sort by accessed time" },
+        ],
+    },
+    HelpSection {
+        name: "System",
+        entries: &[
+            HelpEntry { key: InputKeys::Quit, desc: "Quit" },
+            HelpEntry { key: InputKeys::KeybindHelp, desc: "Toggle keybind help" },
+            HelpEntry { key: InputKeys::ThemePicker, desc: "Pick a theme (previewed live)" },
+            HelpEntry { key: InputKeys::Reload, desc: "Redraw the UI" },
+            HelpEntry { key: InputKeys::RecordMacro, desc: "Record macro / stop recording" },
+            HelpEntry { key: InputKeys::PlayMacro, desc: "Play macro" },
+            HelpEntry { key: InputKeys::ToggleHidden, desc: "Toggle hidden files" },
+            HelpEntry { key: InputKeys::ToggleSystem, desc: "Toggle system files" },
+            HelpEntry { key: InputKeys::ToggleSymlink, desc: "Toggle symlinks" },
+            HelpEntry { key: InputKeys::ToggleDirsFirst, desc: "Toggle directories first" },
+            HelpEntry { key: InputKeys::ToggleCaseInsensitive, desc: "Toggle case insensitive sort" },
+        ],
+    },
+];
+
+/// Returns the description of a binding.
+pub(crate) fn describe(label: &BindingLabel) -> String {
+    match label {
+        BindingLabel::Key(key) => HELP_DATA
+            .iter()
+            .flat_map(|section| section.entries)
+            .find(|entry| entry.key == *key)
+            .map_or_else(|| key.name().to_string(), |entry| entry.desc.to_string()),
+        BindingLabel::Macro(name) => format!("Macro {name}"),
+    }
+}
+
+/// A row of the keybinding help.
+#[derive(Debug)]
+pub(crate) struct HelpRow {
+    section: &'static str,
+    keys: String,
+    desc: String,
+    /// The name of the config entry, which is searched as well
+    name: String,
+    /// The action run when the row is chosen, None for prefixes and builtin keys
+    action: Option<Action>,
+}
+
+impl HelpRow {
+    crate::getters! {
+        section: &str,
+        keys: &str,
+        desc: &str,
+    }
+
+    /// Returns true if every word of the lowercase query is found in the row.
+    fn matches(&self, query: &str) -> bool {
+        let text =
+            format!("{} {} {} {}", self.section, self.keys, self.desc, self.name).to_lowercase();
+        query.split_whitespace().all(|word| text.contains(word))
+    }
+}
+
+/// Builds the rows of the help from the keymap, by category.
+///
+/// The actions come in the order of [HELP_DATA], followed by the named macros and the
+/// prefix groups of the config.
+pub(crate) fn help_rows(keymap: &Keymap, config: &Config) -> Vec<HelpRow> {
+    let mut bindings = keymap.action_bindings(config);
+    let mut rows = Vec::with_capacity(bindings.len() + config.key_groups().len() + 2);
+
+    let mut take_keys = |label: &BindingLabel| {
+        bindings
+            .iter_mut()
+            .find(|(l, _)| l == label)
+            .map(|(_, seqs)| std::mem::take(seqs))
+            .unwrap_or_default()
+    };
+
+    for section in HELP_DATA {
+        for entry in section.entries {
+            let label = BindingLabel::Key(entry.key);
+            rows.push(HelpRow {
+                section: section.name,
+                keys: join_keys(&take_keys(&label)),
+                desc: entry.desc.to_string(),
+                name: label.to_string(),
+                action: keymap.action(&label),
+            });
+        }
+
+        let builtin = match section.name {
+            "Navigation" => Some(("[1-9][0-9]*", "Count prefix for the next action")),
+            "Tabs" => Some(("<m-[1-9]>", "Switch to tab by index")),
+            _ => None,
+        };
+        if let Some((keys, desc)) = builtin {
+            rows.push(HelpRow {
+                section: section.name,
+                keys: keys.to_string(),
+                desc: desc.to_string(),
+                name: String::new(),
+                action: None,
+            });
+        }
+    }
+
+    for (name, mac) in config.macros() {
+        let label = BindingLabel::Macro(name.as_str().into());
+        rows.push(HelpRow {
+            section: "Macros",
+            keys: join_keys(&take_keys(&label)),
+            desc: format!("{} ({})", describe(&label), mac.keys().join(" ")),
+            name: label.to_string(),
+            action: keymap.action(&label),
+        });
+    }
+
+    for (keys, group) in config.key_groups() {
+        rows.push(HelpRow {
+            section: "Groups",
+            keys: keys.clone(),
+            desc: format!("+{group}"),
+            name: "key_groups".to_string(),
+            action: None,
+        });
+    }
+    rows
+}
+
+fn join_keys(seqs: &[String]) -> String {
+    if seqs.is_empty() {
+        "-".to_string()
+    } else {
+        seqs.join(", ")
+    }
+}
+
+/// State of the open keybinding help: the search query and the selected row.
+#[derive(Debug)]
+pub(crate) struct HelpPalette {
+    rows: Vec<HelpRow>,
+    query: String,
+    /// Indices of the rows matching the query
+    shown: Vec<usize>,
+    selected: usize,
+}
+
+impl HelpPalette {
+    pub(crate) fn new(rows: Vec<HelpRow>) -> Self {
+        let shown = (0..rows.len()).collect();
+        Self {
+            rows,
+            query: String::new(),
+            shown,
+            selected: 0,
+        }
+    }
+
+    crate::getters! {
+        query: &str,
+        selected: usize,
+    }
+
+    /// Returns the number of all rows, matching or not.
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Returns the rows matching the query.
+    pub(crate) fn shown(&self) -> impl Iterator<Item = &HelpRow> {
+        self.shown.iter().map(|&i| &self.rows[i])
+    }
+
+    pub(crate) fn shown_len(&self) -> usize {
+        self.shown.len()
+    }
+
+    /// Returns the action of the selected row.
+    pub(crate) fn selected_action(&self) -> Option<Action> {
+        self.shown
+            .get(self.selected)
+            .and_then(|&i| self.rows[i].action)
+    }
+
+    /// Moves the selection by `delta` rows, stopping at the first and last row.
+    pub(crate) fn select_by(&mut self, delta: isize) {
+        let last = self.shown.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub(crate) fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.refilter();
+    }
+
+    pub(crate) fn pop_char(&mut self) {
+        if self.query.pop().is_some() {
+            self.refilter();
+        }
+    }
+
+    pub(crate) fn clear_query(&mut self) {
+        self.query.clear();
+        self.refilter();
+    }
+
+    fn refilter(&mut self) {
+        let query = self.query.to_lowercase();
+        self.shown = (0..self.rows.len())
+            .filter(|&i| self.rows[i].matches(&query))
+            .collect();
+        self.selected = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::keymap::SystemAction;
+    use crate::config::load::RawConfig;
+
+    #[test]
+    fn help_lists_live_bindings_and_filters() -> Result<(), Box<dyn std::error::Error>> {
+        let raw: RawConfig = toml::from_str(
+            r#"
+            [keys]
+            reload = ["<c-x> <c-s>"]
+
+            [macros.top]
+            keys = ["g", "g"]
+            bind = ["T"]
+
+            [key_groups]
+            "space" = "Leader"
+            "#,
+        )?;
+        let config = Config::from(raw);
+        let keymap = Keymap::from_config(&config);
+
+        let rows = help_rows(&keymap, &config);
+        for input in InputKeys::ALL {
+            let name = input.name();
+            assert!(rows.iter().any(|row| row.name == name), "{name} has no row");
+        }
+
+        let mut palette = HelpPalette::new(rows);
+        for c in "reload".chars() {
+            palette.push_char(c);
+        }
+        let row = palette.shown().next().ok_or("no reload row")?;
+        assert_eq!(row.keys(), "<c-x> <c-s>");
+        assert_eq!(
+            palette.selected_action(),
+            Some(Action::System(SystemAction::Reload))
+        );
+
+        palette.clear_query();
+        for c in "MACROS top".chars() {
+            palette.push_char(c);
+        }
+        assert_eq!(palette.shown_len(), 1);
+        assert_eq!(palette.selected_action(), Some(Action::Macro(0)));
+
+        palette.clear_query();
+        for c in "leader".chars() {
+            palette.push_char(c);
+        }
+        let row = palette.shown().next().ok_or("no group row")?;
+        assert_eq!((row.keys(), row.desc()), ("space", "+Leader"));
+        assert_eq!(palette.selected_action(), None);
+
+        palette.clear_query();
+        palette.select_by(-3);
+        assert_eq!(palette.selected(), 0);
+        palette.select_by(isize::MAX);
+        assert_eq!(palette.selected(), palette.len() - 1);
+        Ok(())
+    }
+}
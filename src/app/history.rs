@@ -0,0 +1,306 @@
+//! Prompt history for runa.
+//!
+//! [PromptHistory] keeps the accepted inputs of the filter, find, go to path and move prompts,
+//! newest first and without duplicates. It is shared by all tabs, read from the history file
+//! on start and written back on quit. Inputs of other runa instances which quit in the
+//! meantime are kept, the inputs of this instance are added to the file as the newest ones.
+//!
+//! An open prompt gets a snapshot of its history in a [HistoryCursor], which is walked with the
+//! history keys of `[keys.input]`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::actions::InputMode;
+use crate::utils::os;
+
+/// The prompts which keep a history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HistoryKind {
+    Filter,
+    Find,
+    GoToPath,
+    MoveFile,
+}
+
+impl HistoryKind {
+    /// Returns the history of the prompt, if it keeps one.
+    pub(crate) fn of(mode: &InputMode) -> Option<Self> {
+        match mode {
+            InputMode::Filter => Some(HistoryKind::Filter),
+            InputMode::Find => Some(HistoryKind::Find),
+            InputMode::GoToPath => Some(HistoryKind::GoToPath),
+            InputMode::MoveFile => Some(HistoryKind::MoveFile),
+            _ => None,
+        }
+    }
+}
+
+/// The saved inputs of all prompts, newest first.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct PromptHistory {
+    filter: Vec<String>,
+    find: Vec<String>,
+    go_to_path: Vec<String>,
+    move_file: Vec<String>,
+    #[serde(skip)]
+    limit: usize,
+    /// The inputs pushed since the history was loaded, oldest first.
+    #[serde(skip)]
+    added: Vec<(HistoryKind, String)>,
+}
+
+impl PromptHistory {
+    /// Creates an empty history which keeps up to `limit` entries per prompt.
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Self::default()
+        }
+    }
+
+    /// Reads the history file. A missing or broken file gives an empty history.
+    pub(crate) fn load(path: &Path, limit: usize) -> Self {
+        let mut history = Self::read(path).unwrap_or_default();
+        history.set_limit(limit);
+        history
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+    }
+
+    /// Writes the history file atomically.
+    ///
+    /// The inputs of this instance are pushed onto the history currently on disk,
+    /// so the inputs saved by other instances since it was loaded are not lost.
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        let content = match Self::read(path) {
+            Some(mut on_disk) => {
+                on_disk.merge(self);
+                toml::to_string(&on_disk)
+            }
+            None => toml::to_string(self),
+        };
+        os::write_atomic(path, &content.map_err(io::Error::other)?)
+    }
+
+    /// Pushes the inputs added to the other history onto this one.
+    fn merge(&mut self, other: &Self) {
+        self.set_limit(other.limit);
+        for (kind, entry) in &other.added {
+            self.push(*kind, entry);
+        }
+    }
+
+    /// Returns the entries of the prompt, newest first.
+    pub(crate) fn entries(&self, kind: HistoryKind) -> &[String] {
+        match kind {
+            HistoryKind::Filter => &self.filter,
+            HistoryKind::Find => &self.find,
+            HistoryKind::GoToPath => &self.go_to_path,
+            HistoryKind::MoveFile => &self.move_file,
+        }
+    }
+
+    /// Changes the number of kept entries, dropping the oldest ones.
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        for entries in [
+            &mut self.filter,
+            &mut self.find,
+            &mut self.go_to_path,
+            &mut self.move_file,
+        ] {
+            entries.truncate(limit);
+        }
+    }
+
+    /// Adds an accepted input as the newest entry, removing an older copy of it.
+    pub(crate) fn push(&mut self, kind: HistoryKind, entry: &str) {
+        let entry = entry.trim();
+        if entry.is_empty() || self.limit == 0 {
+            return;
+        }
+
+        let limit = self.limit;
+        let entries = self.entries_mut(kind);
+        entries.retain(|e| e != entry);
+        entries.insert(0, entry.to_string());
+        entries.truncate(limit);
+        self.added.push((kind, entry.to_string()));
+    }
+
+    fn entries_mut(&mut self, kind: HistoryKind) -> &mut Vec<String> {
+        match kind {
+            HistoryKind::Filter => &mut self.filter,
+            HistoryKind::Find => &mut self.find,
+            HistoryKind::GoToPath => &mut self.go_to_path,
+            HistoryKind::MoveFile => &mut self.move_file,
+        }
+    }
+}
+
+/// Position of an open prompt in its history.
+///
+/// The input typed before the history was entered is kept as the draft and comes back
+/// after the newest entry. Editing a recalled entry starts over from the newest one.
+#[derive(Debug, Default)]
+pub(crate) struct HistoryCursor {
+    kind: Option<HistoryKind>,
+    loaded: bool,
+    entries: Vec<String>,
+    index: Option<usize>,
+    draft: String,
+    query: Option<String>,
+}
+
+impl HistoryCursor {
+    /// Creates the cursor of a newly opened prompt. The entries are loaded afterwards.
+    pub(crate) fn new(kind: Option<HistoryKind>) -> Self {
+        Self {
+            kind,
+            ..Self::default()
+        }
+    }
+
+    crate::getters! {
+        entries: &[String],
+    }
+
+    /// Returns the history which still has to be loaded into the cursor.
+    pub(crate) fn pending(&self) -> Option<HistoryKind> {
+        self.kind.filter(|_| !self.loaded)
+    }
+
+    pub(crate) fn load(&mut self, entries: Vec<String>) {
+        self.entries = entries;
+        self.loaded = true;
+    }
+
+    /// Returns the next older entry.
+    pub(crate) fn prev(&mut self, input: &str) -> Option<String> {
+        self.sync(input);
+        let next = self.index.map_or(0, |i| i + 1);
+        if next >= self.entries.len() {
+            return None;
+        }
+        self.show(next, input)
+    }
+
+    /// Returns the next newer entry, or the draft after the newest one.
+    pub(crate) fn next(&mut self, input: &str) -> Option<String> {
+        self.sync(input);
+        self.query = None;
+        match self.index? {
+            0 => {
+                self.index = None;
+                Some(std::mem::take(&mut self.draft))
+            }
+            i => self.show(i - 1, input),
+        }
+    }
+
+    /// Returns the next older entry which contains the search query.
+    ///
+    /// The query is the input when the search starts, repeated searches keep it.
+    pub(crate) fn search(&mut self, input: &str) -> Option<String> {
+        self.sync(input);
+        let query = self
+            .query
+            .get_or_insert_with(|| input.to_lowercase())
+            .clone();
+        let start = self.index.map_or(0, |i| i + 1);
+        let found = self
+            .entries
+            .iter()
+            .skip(start)
+            .position(|entry| entry.to_lowercase().contains(&query))?;
+        self.show(start + found, input)
+    }
+
+    fn show(&mut self, index: usize, input: &str) -> Option<String> {
+        if self.index.is_none() {
+            self.draft = input.to_string();
+        }
+        self.index = Some(index);
+        self.entries.get(index).cloned()
+    }
+
+    /// Leaves the history if the shown entry was edited.
+    fn sync(&mut self, input: &str) {
+        if let Some(i) = self.index
+            && self.entries.get(i).map(String::as_str) != Some(input)
+        {
+            self.index = None;
+            self.query = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_history_collapses_duplicates() {
+        let mut history = PromptHistory::new(3);
+        for entry in ["a", "b", "a", " c ", "", "d"] {
+            history.push(HistoryKind::Find, entry);
+        }
+        assert_eq!(history.entries(HistoryKind::Find), ["d", "c", "a"]);
+        assert!(history.entries(HistoryKind::Filter).is_empty());
+
+        history.set_limit(1);
+        assert_eq!(history.entries(HistoryKind::Find), ["d"]);
+    }
+
+    #[test]
+    fn prompt_history_save_keeps_inputs_of_other_instances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.toml");
+        let mut first = PromptHistory::new(3);
+        first.push(HistoryKind::Find, "old");
+        first.save(&path).unwrap();
+
+        let mut this = PromptHistory::load(&path, 3);
+        let mut other = PromptHistory::load(&path, 3);
+        this.push(HistoryKind::Find, "mine");
+        other.push(HistoryKind::Find, "theirs");
+        other.push(HistoryKind::Filter, "rs");
+        other.save(&path).unwrap();
+        this.save(&path).unwrap();
+
+        let saved = PromptHistory::load(&path, 3);
+        assert_eq!(saved.entries(HistoryKind::Find), ["mine", "theirs", "old"]);
+        assert_eq!(saved.entries(HistoryKind::Filter), ["rs"]);
+    }
+
+    #[test]
+    fn history_cursor_walks_and_searches() {
+        let mut cursor = HistoryCursor::new(Some(HistoryKind::GoToPath));
+        assert_eq!(cursor.pending(), Some(HistoryKind::GoToPath));
+        cursor.load(vec!["~/src".into(), "/tmp".into(), "~/src/runa".into()]);
+        assert_eq!(cursor.pending(), None);
+
+        assert_eq!(cursor.prev("dra").as_deref(), Some("~/src"));
+        assert_eq!(cursor.prev("~/src").as_deref(), Some("/tmp"));
+        assert_eq!(cursor.next("/tmp").as_deref(), Some("~/src"));
+        assert_eq!(cursor.next("~/src").as_deref(), Some("dra"));
+        assert_eq!(cursor.next("dra"), None);
+
+        // Repeated searches keep the query and continue with older entries.
+        assert_eq!(cursor.search("src").as_deref(), Some("~/src"));
+        assert_eq!(cursor.search("~/src").as_deref(), Some("~/src/runa"));
+        assert_eq!(cursor.search("~/src/runa"), None);
+
+        // Editing a recalled entry starts over from the newest one.
+        assert_eq!(cursor.prev("~/src/run").as_deref(), Some("~/src"));
+    }
+}
@@ -2,13 +2,19 @@
 //!
 //! Defines key to an action, parsing from the config, and enum variants
 //! for all navigation, file and actions used by runa.
+//!
+//! Bindings are key sequences like `"g g"` or `"space f r"`, stored in a [KeyNode] trie.
+//! The [KeyPrefix] recognizer walks the trie while a sequence is typed.
 
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::Config;
+use crate::config::input::{EditBindings, EditKeys, InputKeys};
 use crate::core::sort::SortMode;
 
 /// Represents any action in the app: navigation, file, or system.
@@ -18,6 +24,12 @@ pub(crate) enum Action {
     File(FileAction),
     System(SystemAction),
     Tab(TabAction),
+    Pane(PaneAction),
+    Layout(LayoutAction),
+    /// Sorts by the mode, or toggles the sort order if already sorted by it
+    Sort(SortMode),
+    /// Index of a named macro of the config
+    Macro(usize),
 }
 
 /// Navigation actions (move, into_parent, markers, etc.)
@@ -36,6 +48,16 @@ pub(crate) enum NavAction {
     ClearFilter,
     ClearAll,
     SelectAll,
+    MarkMatching,
+    UnmarkMatching,
+    InvertMarkers,
+    VisualMode,
+    ToggleTree,
+    ToggleFlatten,
+    TreeExpand,
+    TreeCollapse,
+    TreeToggleFold,
+    TreeCollapseAll,
     ScrollUp,
     ScrollDown,
 }
@@ -56,6 +78,7 @@ pub(crate) enum FileAction {
     MoveFile,
     AlternateDelete,
     ClearClipboard,
+    Repeat,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -67,18 +90,40 @@ pub(crate) enum TabAction {
     Switch(u8),
 }
 
-/// System actions (quit)
+/// Actions of the dual pane layout
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum PaneAction {
+    Switch,
+    CopyTo,
+    MoveTo,
+}
+
+/// Runtime layout actions (toggle and resize panes, zoom the preview)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum LayoutAction {
+    ToggleParent,
+    TogglePreview,
+    ZoomPreview,
+    WidenMain,
+    NarrowMain,
+    Reset,
+    ToggleDetails,
+}
+
+/// System actions (quit, listing toggles, etc.)
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub(crate) enum SystemAction {
     Quit,
     KeyBindHelp,
+    ThemePicker,
     Reload,
-}
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub(crate) enum PrefixCommand {
-    Nav(NavAction),
-    Sort(SortMode),
+    RecordMacro,
+    PlayMacro,
+    ToggleHidden,
+    ToggleSystem,
+    ToggleSymlink,
+    ToggleDirsFirst,
+    ToggleCaseInsensitive,
 }
 
 /// Key + modifiers as used in keybind/keymap
@@ -88,175 +133,583 @@ pub(crate) struct Key {
     pub(crate) modifiers: KeyModifiers,
 }
 
-/// Stores the mapping from Key to action, which is built in the config
+impl From<&KeyEvent> for Key {
+    fn from(key: &KeyEvent) -> Self {
+        // Some terminals send shift + tab without the shift modifier.
+        let modifiers = if key.code == KeyCode::BackTab {
+            key.modifiers | KeyModifiers::SHIFT
+        } else {
+            key.modifiers
+        };
+        Key {
+            code: key.code,
+            modifiers,
+        }
+    }
+}
+
+/// Formats the key in the notation of the config, e.g. `<c-x>`, `space` or `G`.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("f{n}"),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Backspace => "back".to_string(),
+            KeyCode::Tab | KeyCode::BackTab => "tab".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::PageUp => "pgup".to_string(),
+            KeyCode::PageDown => "pgdn".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            _ => "?".to_string(),
+        };
+
+        let mut mods = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            mods.push_str("c-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            mods.push_str("m-");
+        }
+        // Shifted characters are shown as the uppercase character.
+        if self.modifiers.contains(KeyModifiers::SHIFT) && !matches!(self.code, KeyCode::Char(_)) {
+            mods.push_str("s-");
+        }
+
+        if mods.is_empty() {
+            write!(f, "{name}")
+        } else {
+            write!(f, "<{mods}{name}>")
+        }
+    }
+}
+
+/// What a binding is shown as in the prefix help.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum BindingLabel {
+    /// The configured key of the action
+    Key(InputKeys),
+    /// The name of a named macro
+    Macro(Arc<str>),
+}
+
+/// Formats the label as the name of its config entry.
+impl fmt::Display for BindingLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindingLabel::Key(key) => write!(f, "{}", key.name()),
+            BindingLabel::Macro(name) => write!(f, "macros.{name}"),
+        }
+    }
+}
+
+/// A problem with the configured keybindings, found while building the [Keymap].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum KeyIssue {
+    /// A key could not be parsed, so its binding is dropped.
+    Unparsable { source: String, key: String },
+    /// The sequence is bound more than once and only the last binding is used.
+    Duplicate {
+        keys: String,
+        shadowed: String,
+        winner: String,
+    },
+    /// The binding also starts longer sequences and only runs once they time out.
+    Prefix { keys: String, source: String },
+    /// The binding replaces a builtin key.
+    Builtin {
+        keys: String,
+        source: String,
+        builtin: &'static str,
+    },
+    /// The visual_swap key hides the binding while a visual selection is active.
+    VisualSwap { keys: String, source: String },
+}
+
+impl fmt::Display for KeyIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyIssue::Unparsable { source, key } => {
+                write!(f, "{source}: unknown key \"{key}\", the binding is dropped")
+            }
+            KeyIssue::Duplicate {
+                keys,
+                shadowed,
+                winner,
+            } => write!(
+                f,
+                "{keys}: bound to {shadowed} and {winner}, {winner} is used"
+            ),
+            KeyIssue::Prefix { keys, source } => write!(
+                f,
+                "{keys}: {source} also starts longer sequences and waits for the timeout"
+            ),
+            KeyIssue::Builtin {
+                keys,
+                source,
+                builtin,
+            } => write!(f, "{keys}: {source} replaces {builtin}"),
+            KeyIssue::VisualSwap { keys, source } => write!(
+                f,
+                "{keys}: visual_swap hides {source} during a visual selection"
+            ),
+        }
+    }
+}
+
+/// An action bound to a key sequence.
+#[derive(Clone, Debug)]
+pub(crate) struct Binding {
+    action: Action,
+    label: BindingLabel,
+}
+
+impl Binding {
+    crate::getters! {
+        action: Action,
+        label: &BindingLabel,
+    }
+}
+
+/// A node of the key trie, reached by typing the keys on the path from the root.
+///
+/// Nodes with children are prefixes of longer sequences. A node which is bound and also
+/// a prefix is ambiguous and runs its binding once the sequence times out.
+#[derive(Default, Debug)]
+pub(crate) struct KeyNode {
+    binding: Option<Binding>,
+    group: Option<Arc<str>>,
+    children: HashMap<Key, KeyNode>,
+}
+
+impl KeyNode {
+    pub(crate) fn binding(&self) -> Option<&Binding> {
+        self.binding.as_ref()
+    }
+
+    /// Returns the name of the prefix group, if one was configured.
+    pub(crate) fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Returns true if longer sequences continue from this node.
+    pub(crate) fn is_prefix(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    /// Returns the child nodes, sorted by their key.
+    pub(crate) fn children(&self) -> Vec<(&Key, &KeyNode)> {
+        let mut children: Vec<_> = self.children.iter().collect();
+        children.sort_by_cached_key(|(key, _)| key.to_string());
+        children
+    }
+
+    /// Returns the node at the end of the sequence.
+    pub(crate) fn find(&self, keys: &[Key]) -> Option<&KeyNode> {
+        keys.iter()
+            .try_fold(self, |node, key| node.children.get(key))
+    }
+
+    /// Returns the child reached by the key event and the key it is stored with.
+    /// Characters typed with shift also match keys bound without it.
+    fn child(&self, key: &KeyEvent) -> Option<(Key, &KeyNode)> {
+        let k = Key::from(key);
+        if let Some((k, node)) = self.children.get_key_value(&k) {
+            return Some((*k, node));
+        }
+
+        if matches!(k.code, KeyCode::Char(_)) && k.modifiers.contains(KeyModifiers::SHIFT) {
+            let unshifted = Key {
+                code: k.code,
+                modifiers: k.modifiers - KeyModifiers::SHIFT,
+            };
+            return self
+                .children
+                .get_key_value(&unshifted)
+                .map(|(k, node)| (*k, node));
+        }
+        None
+    }
+
+    fn node_mut(&mut self, keys: &[Key]) -> &mut KeyNode {
+        keys.iter()
+            .fold(self, |node, key| node.children.entry(*key).or_default())
+    }
+
+    /// Binds the sequence and returns the binding it replaced.
+    fn insert(&mut self, keys: &[Key], binding: Binding) -> Option<Binding> {
+        self.node_mut(keys).binding.replace(binding)
+    }
+
+    /// Calls `f` with every bound sequence below this node.
+    fn for_each_binding(&self, path: &mut Vec<Key>, f: &mut impl FnMut(&[Key], &KeyNode)) {
+        if self.binding.is_some() {
+            f(path, self);
+        }
+        for (key, child) in &self.children {
+            path.push(*key);
+            child.for_each_binding(path, f);
+            path.pop();
+        }
+    }
+
+    fn set_group(&mut self, keys: &[Key], name: &str) {
+        self.node_mut(keys).group = Some(Arc::from(name));
+    }
+}
+
+/// Stores the key trie of all bindings, which is built from the config
 pub(crate) struct Keymap {
-    map: HashMap<Key, Action>,
-    gmap: HashMap<KeyCode, PrefixCommand>,
-    sortmap: HashMap<KeyCode, PrefixCommand>,
-    g_prefix: Vec<Key>,
-    sort_prefix: Vec<Key>,
+    root: KeyNode,
+    actions: HashMap<BindingLabel, Action>,
+    visual_swap: Vec<Key>,
+    macros: Vec<Arc<[KeyEvent]>>,
+    input: HashMap<Key, EditKeys>,
+    issues: Vec<KeyIssue>,
 }
 
 impl Keymap {
     /// Builds the keymap from the config
+    ///
+    /// The go to, sort and fold actions are bound after every key of their prefix.
+    /// Problems with the bindings are collected into the [KeyIssue]s of the keymap.
     #[rustfmt::skip]
     pub(crate) fn from_config(config: &Config) -> Self {
-        let mut map = HashMap::new();
-        let mut gmap = HashMap::new();
-        let mut sortmap = HashMap::new();
+        let mut root = KeyNode::default();
+        let mut issues = Vec::new();
         let keys = config.keys();
-        let sort_prefix: Vec<Key> = keys
-            .sort().iter()
-            .filter_map(|k| parse_key(k))
-            .collect();
 
-        let g_prefix: Vec<Key> = keys
-            .prefix_go_to()
+        let top_prefix = [Vec::new()];
+        let g_prefix = parse_list(keys.get(InputKeys::PrefixGoTo), InputKeys::PrefixGoTo.name(), &mut issues);
+        let sort_prefix = parse_list(keys.get(InputKeys::Sort), InputKeys::Sort.name(), &mut issues);
+        let fold_prefix = parse_list(keys.get(InputKeys::PrefixFold), InputKeys::PrefixFold.name(), &mut issues);
+
+        let mut actions = HashMap::new();
+
+        let visual_swap: Vec<Key> = keys
+            .get(InputKeys::VisualSwap)
             .iter()
-            .filter_map(|k| parse_key(k))
+            .filter_map(|k| {
+                let key = parse_key(k);
+                if key.is_none() {
+                    issues.push(KeyIssue::Unparsable {
+                        source: InputKeys::VisualSwap.name().to_string(),
+                        key: k.clone(),
+                    });
+                }
+                key
+            })
             .collect();
 
         macro_rules! bind {
-            ($keys:expr, $action:expr) => {
-                bind($keys, $action, &mut map);
-            };
-        }
-
-        macro_rules! bind_prefix {
-            ($keys:expr, $action:expr, $prefix:expr) => {
-                bind_prefix($keys, $prefix, &mut gmap);
-            };
-        }
-
-        macro_rules! bind_sort {
-            ($keys:expr, $mode:expr) => {
-                bind_prefix($keys, PrefixCommand::Sort($mode), &mut sortmap);
+            ($input:ident, $action:expr) => {
+                bind!(&top_prefix, $input, $action)
             };
+            ($prefixes:expr, $input:ident, $action:expr) => {{
+                let binding = Binding { action: $action, label: BindingLabel::Key(InputKeys::$input) };
+                bind(&mut root, $prefixes, keys.get(InputKeys::$input), &binding, &mut issues);
+                actions.insert(binding.label, binding.action);
+            }};
         }
 
         use NavAction as N;
         use FileAction as F;
         use SystemAction as S;
         use TabAction as T;
+        use PaneAction as P;
+        use LayoutAction as L;
 
         // NavActions
-        bind!(keys.go_parent(),         Action::Nav(N::GoParent));
-        bind!(keys.go_into_dir(),       Action::Nav(N::GoIntoDir));
-        bind!(keys.go_up(),             Action::Nav(N::GoUp));
-        bind!(keys.go_down(),           Action::Nav(N::GoDown));
-        bind!(keys.toggle_marker(),     Action::Nav(N::ToggleMarker));
-        bind!(keys.clear_filter(),      Action::Nav(N::ClearFilter));
-        bind!(keys.clear_markers(),     Action::Nav(N::ClearMarker));
-        bind!(keys.clear_all(),         Action::Nav(N::ClearAll));
-        bind!(keys.go_to_bottom(),      Action::Nav(N::GoToBottom));
-        bind!(keys.scroll_up(),         Action::Nav(N::ScrollUp));
-        bind!(keys.scroll_down(),       Action::Nav(N::ScrollDown));
-        bind!(keys.select_all(),        Action::Nav(N::SelectAll));
+        bind!(GoParent,             Action::Nav(N::GoParent));
+        bind!(GoIntoDir,            Action::Nav(N::GoIntoDir));
+        bind!(GoUp,                 Action::Nav(N::GoUp));
+        bind!(GoDown,               Action::Nav(N::GoDown));
+        bind!(ToggleMarker,         Action::Nav(N::ToggleMarker));
+        bind!(ClearFilter,          Action::Nav(N::ClearFilter));
+        bind!(ClearMarkers,         Action::Nav(N::ClearMarker));
+        bind!(ClearAll,             Action::Nav(N::ClearAll));
+        bind!(GoToBottom,           Action::Nav(N::GoToBottom));
+        bind!(ScrollUp,             Action::Nav(N::ScrollUp));
+        bind!(ScrollDown,           Action::Nav(N::ScrollDown));
+        bind!(SelectAll,            Action::Nav(N::SelectAll));
+        bind!(MarkMatching,         Action::Nav(N::MarkMatching));
+        bind!(UnmarkMatching,       Action::Nav(N::UnmarkMatching));
+        bind!(InvertMarkers,        Action::Nav(N::InvertMarkers));
+        bind!(VisualMode,           Action::Nav(N::VisualMode));
+        bind!(ToggleTree,           Action::Nav(N::ToggleTree));
+        bind!(ToggleFlatten,        Action::Nav(N::ToggleFlatten));
 
         // FileActions
-        bind!(keys.open_file(),         Action::File(F::Open));
-        bind!(keys.delete(),            Action::File(F::Delete));
-        bind!(keys.copy(),              Action::File(F::Copy));
-        bind!(keys.paste(),             Action::File(F::Paste));
-        bind!(keys.move_file(),         Action::File(F::MoveFile));
-        bind!(keys.rename(),            Action::File(F::Rename));
-        bind!(keys.create(),            Action::File(F::Create));
-        bind!(keys.create_directory(),  Action::File(F::CreateDirectory));
-        bind!(keys.filter(),            Action::File(F::Filter));
-        bind!(keys.show_info(),         Action::File(F::ShowInfo));
-        bind!(keys.find(),              Action::File(F::Find));
-        bind!(keys.clear_clipboard(),   Action::File(F::ClearClipboard));
-        bind!(keys.alternate_delete(),  Action::File(F::AlternateDelete));
+        bind!(OpenFile,             Action::File(F::Open));
+        bind!(Delete,               Action::File(F::Delete));
+        bind!(Copy,                 Action::File(F::Copy));
+        bind!(Paste,                Action::File(F::Paste));
+        bind!(MoveFile,             Action::File(F::MoveFile));
+        bind!(Rename,               Action::File(F::Rename));
+        bind!(Create,               Action::File(F::Create));
+        bind!(CreateDirectory,      Action::File(F::CreateDirectory));
+        bind!(Filter,               Action::File(F::Filter));
+        bind!(ShowInfo,             Action::File(F::ShowInfo));
+        bind!(Find,                 Action::File(F::Find));
+        bind!(ClearClipboard,       Action::File(F::ClearClipboard));
+        bind!(AlternateDelete,      Action::File(F::AlternateDelete));
+        bind!(Repeat,               Action::File(F::Repeat));
 
         // TabActions
-        bind!(keys.tab_new(),           Action::Tab(T::New));
-        bind!(keys.tab_close(),         Action::Tab(T::Close));
-        bind!(keys.tab_next(),          Action::Tab(T::Next));
-        bind!(keys.tab_prev(),          Action::Tab(T::Prev));
+        bind!(TabNew,               Action::Tab(T::New));
+        bind!(TabClose,             Action::Tab(T::Close));
+        bind!(TabNext,              Action::Tab(T::Next));
+        bind!(TabPrev,              Action::Tab(T::Prev));
+
+        // PaneActions
+        bind!(PaneSwitch,           Action::Pane(P::Switch));
+        bind!(CopyToPane,           Action::Pane(P::CopyTo));
+        bind!(MoveToPane,           Action::Pane(P::MoveTo));
+
+        // LayoutActions
+        bind!(ToggleParent,         Action::Layout(L::ToggleParent));
+        bind!(TogglePreview,        Action::Layout(L::TogglePreview));
+        bind!(ZoomPreview,          Action::Layout(L::ZoomPreview));
+        bind!(WidenMain,            Action::Layout(L::WidenMain));
+        bind!(NarrowMain,           Action::Layout(L::NarrowMain));
+        bind!(ResetLayout,          Action::Layout(L::Reset));
+        bind!(ToggleDetails,        Action::Layout(L::ToggleDetails));
 
         // SystemActions
-        bind!(keys.keybind_help(),      Action::System(S::KeyBindHelp));
-        bind!(keys.quit(),              Action::System(S::Quit));
-        bind!(keys.reload(),            Action::System(S::Reload));
+        bind!(KeybindHelp,          Action::System(S::KeyBindHelp));
+        bind!(ThemePicker,          Action::System(S::ThemePicker));
+        bind!(Quit,                 Action::System(S::Quit));
+        bind!(Reload,               Action::System(S::Reload));
+        bind!(RecordMacro,          Action::System(S::RecordMacro));
+        bind!(PlayMacro,            Action::System(S::PlayMacro));
+        bind!(ToggleHidden,         Action::System(S::ToggleHidden));
+        bind!(ToggleSystem,         Action::System(S::ToggleSystem));
+        bind!(ToggleSymlink,        Action::System(S::ToggleSymlink));
+        bind!(ToggleDirsFirst,      Action::System(S::ToggleDirsFirst));
+        bind!(ToggleCaseInsensitive, Action::System(S::ToggleCaseInsensitive));
 
-        // Prefix actions
-        bind_prefix!(keys.go_to_top(),  Action::Nav(N::GoToTop),  PrefixCommand::Nav(N::GoToTop));
-        bind_prefix!(keys.go_to_home(), Action::Nav(N::GoToHome), PrefixCommand::Nav(N::GoToHome));
-        bind_prefix!(keys.go_to_path(), Action::Nav(N::GoToPath), PrefixCommand::Nav(N::GoToPath));
+        // Prefixed actions
+        bind!(&g_prefix, GoToTop,   Action::Nav(N::GoToTop));
+        bind!(&g_prefix, GoToHome,  Action::Nav(N::GoToHome));
+        bind!(&g_prefix, GoToPath,  Action::Nav(N::GoToPath));
 
-        bind_sort!(keys.sort_by_name(),         SortMode::Name);
-        bind_sort!(keys.sort_by_natural(),      SortMode::Natural);
-        bind_sort!(keys.sort_by_modified(),     SortMode::Modified);
-        bind_sort!(keys.sort_by_created(),      SortMode::Created);
-        bind_sort!(keys.sort_by_accessed(),     SortMode::Accessed);
-        bind_sort!(keys.sort_by_size(),         SortMode::Size);
-        bind_sort!(keys.sort_by_extension(),    SortMode::Extension);
+        bind!(&sort_prefix, SortByName,         Action::Sort(SortMode::Name));
+        bind!(&sort_prefix, SortByNatural,      Action::Sort(SortMode::Natural));
+        bind!(&sort_prefix, SortByModified,     Action::Sort(SortMode::Modified));
+        bind!(&sort_prefix, SortByCreated,      Action::Sort(SortMode::Created));
+        bind!(&sort_prefix, SortByAccessed,     Action::Sort(SortMode::Accessed));
+        bind!(&sort_prefix, SortBySize,         Action::Sort(SortMode::Size));
+        bind!(&sort_prefix, SortByExtension,    Action::Sort(SortMode::Extension));
 
-        Keymap { map, gmap, sortmap, g_prefix, sort_prefix }
-    }
+        bind!(&fold_prefix, TreeExpand,         Action::Nav(N::TreeExpand));
+        bind!(&fold_prefix, TreeCollapse,       Action::Nav(N::TreeCollapse));
+        bind!(&fold_prefix, TreeToggleFold,     Action::Nav(N::TreeToggleFold));
+        bind!(&fold_prefix, TreeCollapseAll,    Action::Nav(N::TreeCollapseAll));
 
-    /// Looks up the action for a given key event
-    pub(crate) fn lookup(&self, key: KeyEvent) -> Option<Action> {
-        let k = Key {
-            code: key.code,
-            modifiers: key.modifiers,
-        };
+        // Named macros are bound last, so they take precedence over the actions above.
+        let mut macros = Vec::new();
+        for (name, mac) in config.macros() {
+            let label = BindingLabel::Macro(Arc::from(name.as_str()));
+            let mut keys = Vec::with_capacity(mac.keys().len());
+            for k in mac.keys() {
+                match parse_key(k) {
+                    Some(key) => keys.push(KeyEvent::new(key.code, key.modifiers)),
+                    None => issues.push(KeyIssue::Unparsable { source: label.to_string(), key: k.clone() }),
+                }
+            }
+            if keys.is_empty() || keys.len() < mac.keys().len() {
+                continue;
+            }
+            let binding = Binding { action: Action::Macro(macros.len()), label };
+            bind(&mut root, &top_prefix, mac.bind(), &binding, &mut issues);
+            actions.insert(binding.label, binding.action);
+            macros.push(Arc::from(keys));
+        }
 
-        if let Some(action) = self.map.get(&k).copied() {
-            return Some(action);
+        // Prefix groups of the config override the names of the builtin prefixes.
+        for (prefixes, name) in [(&g_prefix, "Go to"), (&sort_prefix, "Sort"), (&fold_prefix, "Fold")] {
+            for prefix in prefixes {
+                root.set_group(prefix, name);
+            }
+        }
+        for (seq, name) in config.key_groups() {
+            match parse_sequence(seq) {
+                Some(prefix) => root.set_group(&prefix, name),
+                None => issues.push(KeyIssue::Unparsable { source: "key_groups".to_string(), key: seq.clone() }),
+            }
         }
 
-        if matches!(key.code, KeyCode::Char(_)) && key.modifiers.contains(KeyModifiers::SHIFT) {
-            let k2 = Key {
-                code: key.code,
-                modifiers: key.modifiers - KeyModifiers::SHIFT,
+        let input = bind_edit_keys(keys.input(), &mut issues);
+
+        root.for_each_binding(&mut Vec::new(), &mut |seq, node| {
+            let Some(binding) = node.binding() else {
+                return;
             };
-            return self.map.get(&k2).copied();
+            if node.is_prefix() {
+                issues.push(KeyIssue::Prefix { keys: format_sequence(seq), source: binding.label.to_string() });
+            }
+            if let Some(builtin) = builtin_key(seq) {
+                issues.push(KeyIssue::Builtin { keys: format_sequence(seq), source: binding.label.to_string(), builtin });
+            }
+        });
+        // Prefixes are hidden during the selection on purpose, like `o` in vim.
+        for key in &visual_swap {
+            let keys = std::slice::from_ref(key);
+            if let Some(binding) = root.find(keys).and_then(KeyNode::binding) {
+                issues.push(KeyIssue::VisualSwap { keys: format_sequence(keys), source: binding.label.to_string() });
+            }
         }
+        // Nodes are visited in hash order, the issues are sorted for a stable report.
+        issues.sort_by_cached_key(ToString::to_string);
 
-        if key.modifiers.is_empty()
-            && let KeyCode::Char(c) = key.code
-            && let Some(digit) = c.to_digit(10)
-        {
-            return Some(Action::Tab(TabAction::Switch(digit as u8)));
+        Keymap { root, actions, visual_swap, macros, input, issues }
+    }
+
+    /// Returns the editing action of the prompt bound to the key.
+    pub(crate) fn lookup_edit(&self, key: &KeyEvent) -> Option<EditKeys> {
+        self.input.get(&Key::from(key)).copied()
+    }
+
+    /// Returns the problems found in the configured keybindings.
+    pub(crate) fn issues(&self) -> &[KeyIssue] {
+        &self.issues
+    }
+
+    /// Returns the action of an action key or named macro, whether it is bound or not.
+    pub(crate) fn action(&self, label: &BindingLabel) -> Option<Action> {
+        self.actions.get(label).copied()
+    }
+
+    /// Returns the effective key sequences of every action and named macro, in the order
+    /// of the config. Actions without a working binding have no sequences.
+    pub(crate) fn action_bindings(&self, config: &Config) -> Vec<(BindingLabel, Vec<String>)> {
+        let mut bound: HashMap<BindingLabel, Vec<String>> = HashMap::new();
+        self.root
+            .for_each_binding(&mut Vec::new(), &mut |seq, node| {
+                if let Some(binding) = node.binding() {
+                    bound
+                        .entry(binding.label.clone())
+                        .or_default()
+                        .push(format_sequence(seq));
+                }
+            });
+
+        let mut table = Vec::with_capacity(InputKeys::ALL.len() + config.macros().len());
+        for input in InputKeys::ALL {
+            // Prefixes and the visual swap key are not bindings of the trie.
+            let mut seqs = match input {
+                InputKeys::PrefixGoTo
+                | InputKeys::Sort
+                | InputKeys::PrefixFold
+                | InputKeys::VisualSwap => config
+                    .keys()
+                    .get(*input)
+                    .iter()
+                    .filter_map(|k| parse_sequence(k))
+                    .map(|seq| format_sequence(&seq))
+                    .collect(),
+                _ => bound.remove(&BindingLabel::Key(*input)).unwrap_or_default(),
+            };
+            seqs.sort();
+            table.push((BindingLabel::Key(*input), seqs));
+        }
+        for name in config.macros().keys() {
+            let label = BindingLabel::Macro(Arc::from(name.as_str()));
+            let mut seqs = bound.remove(&label).unwrap_or_default();
+            seqs.sort();
+            table.push((label, seqs));
         }
+        table
+    }
 
-        None
+    /// Returns [Keymap::action_bindings] by config name, followed by the prompt editing keys.
+    pub(crate) fn effective_bindings(&self, config: &Config) -> Vec<(String, Vec<String>)> {
+        let mut table: Vec<(String, Vec<String>)> = self
+            .action_bindings(config)
+            .into_iter()
+            .map(|(label, seqs)| (label.to_string(), seqs))
+            .collect();
+        for edit in EditKeys::ALL {
+            let mut seqs: Vec<String> = self
+                .input
+                .iter()
+                .filter(|(_, action)| *action == edit)
+                .map(|(key, _)| key.to_string())
+                .collect();
+            seqs.sort();
+            table.push((format!("input.{}", edit.name()), seqs));
+        }
+        table
     }
 
-    pub(crate) fn gmap(&self) -> &HashMap<KeyCode, PrefixCommand> {
-        &self.gmap
+    /// Returns the root of the key trie.
+    pub(crate) fn root(&self) -> &KeyNode {
+        &self.root
     }
 
-    pub(crate) fn sortmap(&self) -> &HashMap<KeyCode, PrefixCommand> {
-        &self.sortmap
+    /// Returns true if the key swaps the ends of the visual selection.
+    /// Characters typed with shift also match keys bound without it.
+    pub(crate) fn is_visual_swap(&self, key: &KeyEvent) -> bool {
+        let k = Key::from(key);
+        let unshifted = Key {
+            code: k.code,
+            modifiers: k.modifiers - KeyModifiers::SHIFT,
+        };
+        self.visual_swap.contains(&k)
+            || (matches!(k.code, KeyCode::Char(_)) && self.visual_swap.contains(&unshifted))
     }
 
-    crate::getters! {
-        sort_prefix: &[Key],
-        g_prefix: &[Key],
+    /// Returns the key sequence of the named macro bound by [Action::Macro].
+    pub(crate) fn macro_keys(&self, idx: usize) -> Option<Arc<[KeyEvent]>> {
+        self.macros.get(idx).cloned()
+    }
+
+    /// Returns true if the key starts longer key sequences.
+    pub(crate) fn starts_sequence(&self, key: &KeyEvent) -> bool {
+        self.root
+            .child(key)
+            .is_some_and(|(_, node)| node.is_prefix())
+    }
+
+    /// Looks up the action bound to a single key event
+    pub(crate) fn lookup(&self, key: KeyEvent) -> Option<Action> {
+        if let Some(binding) = self.root.child(&key).and_then(|(_, node)| node.binding()) {
+            return Some(binding.action);
+        }
+
+        // Bare digits are count prefixes, so tabs are switched with alt + digit.
+        if key.modifiers == KeyModifiers::ALT
+            && let KeyCode::Char(c) = key.code
+            && let Some(digit) = c.to_digit(10)
+            && digit > 0
+        {
+            return Some(Action::Tab(TabAction::Switch(digit as u8)));
+        }
+
+        None
     }
 }
 
+/// Recognizes key sequences by walking the key trie of the [Keymap].
 pub(crate) struct KeyPrefix {
-    state: PrefixState,
+    pending: Vec<Key>,
     last_time: Option<Instant>,
     timeout: Duration,
     started: bool,
     exited: bool,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-enum PrefixState {
-    None,
-    G,
-    Sort,
-}
-
 impl KeyPrefix {
     pub(crate) fn new(timeout: Duration) -> Self {
         Self {
-            state: PrefixState::None,
+            pending: Vec::new(),
             last_time: None,
             timeout,
             started: false,
@@ -264,67 +717,54 @@ impl KeyPrefix {
         }
     }
 
-    pub(crate) fn feed(
-        &mut self,
-        key: &KeyEvent,
-        gmap: &HashMap<KeyCode, PrefixCommand>,
-        sortmap: &HashMap<KeyCode, PrefixCommand>,
-        sort_prefix: &[Key],
-        g_prefix: &[Key],
-    ) -> Option<PrefixCommand> {
+    /// Feeds a key into the pending sequence and returns the action of a completed sequence.
+    ///
+    /// Keys which start no sequence are left to the single key bindings.
+    /// A key which continues no pending sequence ends it.
+    pub(crate) fn feed(&mut self, key: &KeyEvent, keymap: &Keymap) -> Option<Action> {
         self.started = false;
         self.exited = false;
         let now = Instant::now();
-        match self.state {
-            PrefixState::None => {
-                let k = Key {
-                    code: key.code,
-                    modifiers: key.modifiers,
-                };
-
-                if g_prefix.contains(&k) {
-                    self.state = PrefixState::G;
-                    self.last_time = Some(now);
-                    self.started = true;
-                    None
-                } else if sort_prefix.contains(&k) {
-                    self.state = PrefixState::Sort;
-                    self.last_time = Some(now);
-                    self.started = true;
-                    None
-                } else {
-                    None
-                }
+
+        if self.pending.is_empty() {
+            let (k, node) = keymap.root.child(key)?;
+            if !node.is_prefix() {
+                return None;
             }
-            PrefixState::G => {
-                let elapsed = self
-                    .last_time
-                    .map_or(Duration::MAX, |t| now.duration_since(t));
-                self.state = PrefixState::None;
-                self.last_time = None;
-                self.exited = true;
-                if elapsed <= self.timeout {
-                    gmap.get(&key.code).copied()
-                } else {
-                    None
-                }
+            self.pending.push(k);
+            self.last_time = Some(now);
+            self.started = true;
+            return None;
+        }
+
+        let elapsed = self
+            .last_time
+            .map_or(Duration::MAX, |t| now.duration_since(t));
+        let next = keymap
+            .root
+            .find(&self.pending)
+            .and_then(|node| node.child(key))
+            .filter(|_| elapsed <= self.timeout);
+
+        match next {
+            Some((k, node)) if node.is_prefix() => {
+                self.pending.push(k);
+                self.last_time = Some(now);
+                None
             }
-            PrefixState::Sort => {
-                let elapsed = self
-                    .last_time
-                    .map_or(Duration::MAX, |t| now.duration_since(t));
-                self.state = PrefixState::None;
-                self.last_time = None;
-                self.exited = true;
-                if elapsed <= self.timeout {
-                    sortmap.get(&key.code).copied()
-                } else {
-                    None
-                }
+            next => {
+                self.cancel();
+                next.and_then(|(_, node)| node.binding())
+                    .map(Binding::action)
             }
         }
     }
 
+    /// Returns the keys of the pending sequence.
+    pub(crate) fn pending(&self) -> &[Key] {
+        &self.pending
+    }
+
     #[inline]
     pub(crate) fn started_prefix(&self) -> bool {
         self.started
@@ -335,28 +775,34 @@ impl KeyPrefix {
         self.exited
     }
 
-    pub(crate) fn is_g_state(&self) -> bool {
-        self.state == PrefixState::G
-    }
-
-    pub(crate) fn is_sort_state(&self) -> bool {
-        self.state == PrefixState::Sort
+    pub(crate) fn is_active(&self) -> bool {
+        !self.pending.is_empty()
     }
 
     pub(crate) fn expired(&self) -> bool {
-        (self.state == PrefixState::G || self.state == PrefixState::Sort)
+        self.is_active()
             && self
                 .last_time
                 .is_some_and(|time| time.elapsed() >= self.timeout)
     }
 
     pub(crate) fn cancel(&mut self) {
-        self.state = PrefixState::None;
+        self.pending.clear();
         self.last_time = None;
         self.exited = true;
     }
 }
 
+/// Parses a key sequence like `"g g"` or `"<c-x> <c-s>"`, with the keys separated by spaces.
+fn parse_sequence(s: &str) -> Option<Vec<Key>> {
+    // `" "` binds the space key itself.
+    if s == " " {
+        return parse_key(s).map(|key| vec![key]);
+    }
+    let keys: Option<Vec<Key>> = s.split_whitespace().map(parse_key).collect();
+    keys.filter(|keys| !keys.is_empty())
+}
+
 fn parse_key(s: &str) -> Option<Key> {
     let mut modifiers = KeyModifiers::NONE;
     let mut code: Option<KeyCode> = None;
@@ -394,11 +840,16 @@ fn parse_key(s: &str) -> Option<Key> {
             "esc" => code = Some(KeyCode::Esc),
             "backspace" | "back" => code = Some(KeyCode::Backspace),
             "tab" => code = Some(KeyCode::Tab),
+            "backtab" => {
+                modifiers |= KeyModifiers::SHIFT;
+                code = Some(KeyCode::BackTab);
+            }
             "space" | "spc" => code = Some(KeyCode::Char(' ')),
             "page_up" | "pageup" | "pgup" => code = Some(KeyCode::PageUp),
             "page_down" | "pagedown" | "pgdn" => code = Some(KeyCode::PageDown),
             "home" => code = Some(KeyCode::Home),
             "end" => code = Some(KeyCode::End),
+            "delete" | "del" => code = Some(KeyCode::Delete),
             _ => {
                 if part.len() == 1 {
                     let mut c = part.chars().next()?;
@@ -421,30 +872,304 @@ fn parse_key(s: &str) -> Option<Key> {
         }
     }
 
-    Some(Key {
-        code: code?,
-        modifiers,
-    })
+    // Terminals report shift + tab as back tab.
+    let code = match code? {
+        KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        code => code,
+    };
+
+    Some(Key { code, modifiers })
 }
 
-fn bind(key_list: &[String], action: Action, map: &mut HashMap<Key, Action>) {
+/// Parses the key sequences of the list, reporting the ones which can't be parsed.
+fn parse_list(key_list: &[String], source: &str, issues: &mut Vec<KeyIssue>) -> Vec<Vec<Key>> {
+    let mut sequences = Vec::with_capacity(key_list.len());
     for k in key_list {
-        if let Some(key) = parse_key(k) {
-            map.insert(key, action);
+        match parse_sequence(k) {
+            Some(seq) => sequences.push(seq),
+            None => issues.push(KeyIssue::Unparsable {
+                source: source.to_string(),
+                key: k.clone(),
+            }),
+        }
+    }
+    sequences
+}
+
+/// Formats a key sequence as it is written in the config.
+fn format_sequence(keys: &[Key]) -> String {
+    keys.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the builtin meaning of the first key of a sequence which a binding replaces.
+fn builtin_key(keys: &[Key]) -> Option<&'static str> {
+    let key = keys.first()?;
+    let KeyCode::Char(c) = key.code else {
+        return None;
+    };
+    if !c.is_ascii_digit() {
+        return None;
+    }
+    if key.modifiers.is_empty() {
+        Some("the count digit")
+    } else if key.modifiers == KeyModifiers::ALT && c != '0' {
+        Some("the tab switch")
+    } else {
+        None
+    }
+}
+
+/// Builds the editing keys of the prompt. Prompt keys are single keys, not sequences.
+fn bind_edit_keys(bindings: &EditBindings, issues: &mut Vec<KeyIssue>) -> HashMap<Key, EditKeys> {
+    let mut input = HashMap::new();
+    for edit in EditKeys::ALL {
+        let source = format!("input.{}", edit.name());
+        for k in bindings.get(*edit) {
+            let Some(key) = parse_key(k) else {
+                issues.push(KeyIssue::Unparsable {
+                    source: source.clone(),
+                    key: k.clone(),
+                });
+                continue;
+            };
+            if let Some(prev) = input.insert(key, *edit)
+                && prev != *edit
+            {
+                issues.push(KeyIssue::Duplicate {
+                    keys: key.to_string(),
+                    shadowed: format!("input.{}", prev.name()),
+                    winner: source.clone(),
+                });
+            }
         }
     }
+    input
 }
 
-fn bind_prefix(
+/// Binds every key sequence of the list after every one of the prefixes.
+/// A sequence which is already bound to another action is reported as a duplicate.
+fn bind(
+    root: &mut KeyNode,
+    prefixes: &[Vec<Key>],
     key_list: &[String],
-    prefix: PrefixCommand,
-    map: &mut HashMap<KeyCode, PrefixCommand>,
+    binding: &Binding,
+    issues: &mut Vec<KeyIssue>,
 ) {
-    for k in key_list {
-        if let Some(key) = parse_key(k)
-            && key.modifiers.is_empty()
-        {
-            map.insert(key.code, prefix);
+    for seq in parse_list(key_list, &binding.label.to_string(), issues) {
+        for prefix in prefixes {
+            let keys: Vec<Key> = prefix.iter().chain(&seq).copied().collect();
+            if let Some(prev) = root.insert(&keys, binding.clone())
+                && prev.label != binding.label
+            {
+                issues.push(KeyIssue::Duplicate {
+                    keys: format_sequence(&keys),
+                    shadowed: prev.label.to_string(),
+                    winner: binding.label.to_string(),
+                });
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::load::RawConfig;
+
+    fn keymap_from(toml: &str) -> Result<Keymap, toml::de::Error> {
+        let raw: RawConfig = toml::from_str(toml)?;
+        Ok(Keymap::from_config(&Config::from(raw)))
+    }
+
+    fn press(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn key_sequences_resolve_through_the_trie() -> Result<(), Box<dyn std::error::Error>> {
+        let keymap = keymap_from(
+            r#"
+            [keys]
+            find = ["space f f"]
+            reload = ["<c-x> <c-s>"]
+
+            [key_groups]
+            "space f" = "Files"
+            "#,
+        )?;
+        let mut prefix = KeyPrefix::new(Duration::from_secs(4));
+
+        assert_eq!(prefix.feed(&press(' '), &keymap), None);
+        assert!(prefix.started_prefix());
+        assert_eq!(prefix.feed(&press('f'), &keymap), None);
+        let node = keymap
+            .root()
+            .find(prefix.pending())
+            .ok_or("pending sequence should be in the trie")?;
+        assert_eq!(node.group(), Some("Files"));
+        assert_eq!(
+            prefix.feed(&press('f'), &keymap),
+            Some(Action::File(FileAction::Find))
+        );
+        assert!(!prefix.is_active());
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(prefix.feed(&ctrl('x'), &keymap), None);
+        assert_eq!(
+            prefix.feed(&ctrl('s'), &keymap),
+            Some(Action::System(SystemAction::Reload))
+        );
+
+        // The builtin prefixes are sequences as well.
+        assert_eq!(prefix.feed(&press('g'), &keymap), None);
+        assert_eq!(
+            prefix.feed(&press('g'), &keymap),
+            Some(Action::Nav(NavAction::GoToTop))
+        );
+        assert_eq!(prefix.feed(&press('o'), &keymap), None);
+        assert_eq!(
+            keymap
+                .root()
+                .find(prefix.pending())
+                .and_then(KeyNode::group),
+            Some("Sort")
+        );
+
+        // A key which continues no sequence ends it.
+        assert_eq!(prefix.feed(&press('x'), &keymap), None);
+        assert!(!prefix.is_active() && prefix.exited_prefix());
+        Ok(())
+    }
+
+    #[test]
+    fn ambiguous_prefix_keeps_its_binding() -> Result<(), Box<dyn std::error::Error>> {
+        let keymap = keymap_from(
+            r#"
+            [keys]
+            delete = ["d"]
+            copy = ["d d"]
+            "#,
+        )?;
+        let mut prefix = KeyPrefix::new(Duration::ZERO);
+
+        // `d` starts a sequence and runs its own binding once the sequence times out.
+        assert_eq!(prefix.feed(&press('d'), &keymap), None);
+        assert!(prefix.is_active() && prefix.expired());
+        let pending = keymap
+            .root()
+            .find(prefix.pending())
+            .and_then(KeyNode::binding)
+            .map(Binding::action);
+        assert_eq!(pending, Some(Action::File(FileAction::Delete)));
+        assert_eq!(
+            keymap.lookup(press('d')),
+            Some(Action::File(FileAction::Delete))
+        );
+
+        let key = Key {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::CONTROL,
+        };
+        assert_eq!(key.to_string(), "<c-x>");
+        Ok(())
+    }
+
+    #[test]
+    fn keymap_reports_conflicting_bindings() -> Result<(), Box<dyn std::error::Error>> {
+        let defaults = keymap_from("")?;
+        assert!(defaults.issues().is_empty());
+        assert!(defaults.is_visual_swap(&press('o')));
+        let shift_o = KeyEvent::new(KeyCode::Char('O'), KeyModifiers::SHIFT);
+        assert!(!defaults.is_visual_swap(&shift_o));
+
+        let keymap = keymap_from(
+            r#"
+            [keys]
+            copy = ["y", "<c-q>"]
+            paste = ["y"]
+            find = ["<hyper-z>"]
+            filter = ["f", "f x"]
+            rename = ["<m-3>"]
+            create = ["1 a"]
+            visual_swap = ["o", "i", "S"]
+            "#,
+        )?;
+        let issues = keymap.issues();
+        assert!(issues.contains(&KeyIssue::Duplicate {
+            keys: "y".into(),
+            shadowed: "copy".into(),
+            winner: "paste".into(),
+        }));
+        assert!(issues.contains(&KeyIssue::Unparsable {
+            source: "find".into(),
+            key: "<hyper-z>".into(),
+        }));
+        assert!(issues.contains(&KeyIssue::Prefix {
+            keys: "f".into(),
+            source: "filter".into(),
+        }));
+        assert!(issues.contains(&KeyIssue::Builtin {
+            keys: "<m-3>".into(),
+            source: "rename".into(),
+            builtin: "the tab switch",
+        }));
+        assert!(issues.contains(&KeyIssue::Builtin {
+            keys: "1 a".into(),
+            source: "create".into(),
+            builtin: "the count digit",
+        }));
+        assert!(issues.contains(&KeyIssue::VisualSwap {
+            keys: "i".into(),
+            source: "show_info".into(),
+        }));
+        assert!(!issues.iter().any(|issue| matches!(
+            issue,
+            KeyIssue::VisualSwap { keys, .. } if keys == "o"
+        )));
+        let shift_s = KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT);
+        assert!(keymap.is_visual_swap(&shift_s));
+
+        let config = Config::from(toml::from_str::<RawConfig>(
+            "[keys]\ncopy = [\"y\", \"<c-q>\"]",
+        )?);
+        let table = Keymap::from_config(&config).effective_bindings(&config);
+        let copy = table
+            .iter()
+            .find(|(action, _)| action == "copy")
+            .ok_or("copy should be in the table")?;
+        assert_eq!(copy.1, ["<c-q>", "y"]);
+        Ok(())
+    }
+
+    #[test]
+    fn prompt_keys_start_from_the_preset() -> Result<(), Box<dyn std::error::Error>> {
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+
+        let emacs = keymap_from("")?;
+        assert_eq!(emacs.lookup_edit(&ctrl('k')), Some(EditKeys::KillToEnd));
+        assert_eq!(emacs.lookup_edit(&press('k')), None);
+
+        let vi = keymap_from(
+            r#"
+            [keys.input]
+            preset = "vi-insert"
+            kill_to_end = ["<c-l>"]
+            "#,
+        )?;
+        assert_eq!(vi.lookup_edit(&ctrl('k')), None);
+        assert_eq!(vi.lookup_edit(&ctrl('l')), Some(EditKeys::KillToEnd));
+        assert_eq!(vi.lookup_edit(&ctrl('w')), Some(EditKeys::DeleteWordBack));
+        assert_eq!(
+            vi.lookup_edit(&KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE)),
+            Some(EditKeys::DeleteForward)
+        );
+        assert!(vi.issues().is_empty());
+
+        // The normal bindings are still read next to the prompt keys.
+        assert_eq!(vi.lookup(press('j')), Some(Action::Nav(NavAction::GoDown)));
+        Ok(())
+    }
+}
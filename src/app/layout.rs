@@ -0,0 +1,229 @@
+//! Runtime pane layout of runa.
+//!
+//! Holds the pane visibility and ratios of a tab, which start from the `[display]` config
+//! and can be changed with keybindings while runa is running.
+//!
+//! [LayoutState::resolve] turns them into the [PaneLayout] of a frame, collapsing panes which
+//! would be narrower than `display.layout.min_pane_width` and stacking the main and preview
+//! pane on tall, narrow terminals.
+
+use crate::config::display::{Display, StackMode};
+
+/// Ratio steps used by widening and narrowing the main pane.
+const RESIZE_STEP: u16 = 5;
+const MIN_MAIN_RATIO: u16 = 10;
+const MAX_MAIN_RATIO: u16 = 90;
+
+/// The panes of a single frame, as resolved from the [LayoutState] and the terminal size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PaneLayout {
+    pub(crate) parent: bool,
+    pub(crate) main: bool,
+    pub(crate) preview: bool,
+    pub(crate) stacked: bool,
+    pub(crate) parent_ratio: u16,
+    pub(crate) main_ratio: u16,
+    pub(crate) preview_ratio: u16,
+}
+
+/// The pane layout of a tab which can be changed at runtime.
+#[derive(Debug, Clone)]
+pub(crate) struct LayoutState {
+    parent: bool,
+    preview: bool,
+    parent_ratio: u16,
+    main_ratio: u16,
+    preview_ratio: u16,
+    zoomed: bool,
+}
+
+impl LayoutState {
+    /// Creates the layout of the config.
+    /// The dual pane layout starts with the parent and preview pane hidden.
+    pub(crate) fn new(display: &Display) -> Self {
+        let dual = display.is_dual();
+        let total = (u32::from(display.parent_ratio())
+            + u32::from(display.main_ratio())
+            + u32::from(display.preview_ratio()))
+        .max(1);
+        let percent = |ratio: u16| (u32::from(ratio) * 100 / total) as u16;
+
+        Self {
+            parent: display.parent() && !dual,
+            preview: display.preview() && !dual,
+            parent_ratio: percent(display.parent_ratio()),
+            main_ratio: percent(display.main_ratio()).clamp(MIN_MAIN_RATIO, MAX_MAIN_RATIO),
+            preview_ratio: percent(display.preview_ratio()),
+            zoomed: false,
+        }
+    }
+
+    crate::getters! {
+        zoomed: bool,
+    }
+
+    pub(crate) fn toggle_parent(&mut self) {
+        self.parent = !self.parent;
+    }
+
+    pub(crate) fn toggle_preview(&mut self) {
+        self.preview = !self.preview;
+    }
+
+    pub(crate) fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+    }
+
+    /// Widens the main pane by `steps` steps, or narrows it if `steps` is negative.
+    /// Returns false if the main pane is already at its limit.
+    pub(crate) fn resize_main(&mut self, steps: i32) -> bool {
+        let delta = steps.saturating_mul(i32::from(RESIZE_STEP));
+        let ratio = (i32::from(self.main_ratio) + delta)
+            .clamp(i32::from(MIN_MAIN_RATIO), i32::from(MAX_MAIN_RATIO)) as u16;
+        if ratio == self.main_ratio {
+            return false;
+        }
+        self.main_ratio = ratio;
+        true
+    }
+
+    /// Resolves the panes of a view with the given size.
+    ///
+    /// A zoomed preview fills the whole view. Otherwise the panes are stacked if the
+    /// stack mode asks for it, and the parent, then the preview pane is collapsed
+    /// if it would be narrower than the minimum pane width.
+    pub(crate) fn resolve(&self, display: &Display, width: u16, height: u16) -> PaneLayout {
+        let mut layout = PaneLayout {
+            parent: self.parent,
+            main: true,
+            preview: self.preview,
+            stacked: false,
+            parent_ratio: self.parent_ratio,
+            main_ratio: self.main_ratio,
+            preview_ratio: self.preview_ratio,
+        };
+
+        if self.zoomed {
+            layout.parent = false;
+            layout.main = false;
+            layout.preview = true;
+            return layout;
+        }
+
+        // Terminal cells are about twice as tall as wide.
+        layout.stacked = match display.stack_mode() {
+            StackMode::Always => true,
+            StackMode::Never => false,
+            StackMode::Auto => u32::from(width) < u32::from(height) * 2,
+        };
+        if layout.stacked {
+            layout.parent = false;
+            return layout;
+        }
+
+        let min_width = display.min_pane_width();
+        if min_width == 0 {
+            return layout;
+        }
+        if layout.parent && layout.width_of(layout.parent_ratio, width) < min_width {
+            layout.parent = false;
+        }
+        if layout.preview && layout.width_of(layout.preview_ratio, width) < min_width {
+            layout.preview = false;
+        }
+        layout
+    }
+}
+
+impl PaneLayout {
+    /// Returns the number of shown panes.
+    pub(crate) fn pane_count(&self) -> usize {
+        [self.parent, self.main, self.preview]
+            .iter()
+            .filter(|shown| **shown)
+            .count()
+    }
+
+    /// Returns the approximate width of a shown pane with the ratio.
+    fn width_of(&self, ratio: u16, width: u16) -> u16 {
+        let total = [
+            (self.parent_ratio, self.parent),
+            (self.main_ratio, self.main),
+            (self.preview_ratio, self.preview),
+        ]
+        .iter()
+        .filter(|(_, shown)| *shown)
+        .map(|(ratio, _)| u32::from(*ratio))
+        .sum::<u32>()
+        .max(1);
+        (u32::from(width) * u32::from(ratio) / total) as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::config::load::RawConfig;
+
+    fn config_from(toml: &str) -> Result<Config, toml::de::Error> {
+        let raw: RawConfig = toml::from_str(toml)?;
+        Ok(Config::from(raw))
+    }
+
+    #[test]
+    fn layout_collapses_and_stacks_panes() -> Result<(), Box<dyn std::error::Error>> {
+        let config = config_from(
+            r#"
+            [display.layout]
+            stack = "auto"
+            min_pane_width = 10
+            "#,
+        )?;
+        let mut layout = LayoutState::new(config.display());
+
+        let wide = layout.resolve(config.display(), 120, 30);
+        assert!(wide.parent && wide.main && wide.preview && !wide.stacked);
+
+        // 20% of 40 columns is too narrow for the parent pane.
+        let narrow = layout.resolve(config.display(), 40, 15);
+        assert!(!narrow.parent && narrow.preview);
+
+        let tall = layout.resolve(config.display(), 60, 50);
+        assert!(tall.stacked && !tall.parent && tall.preview);
+
+        // Stacking is opt-in.
+        let default = Config::default();
+        let unstacked = LayoutState::new(default.display()).resolve(default.display(), 60, 50);
+        assert!(!unstacked.stacked);
+
+        layout.toggle_zoom();
+        let zoomed = layout.resolve(config.display(), 120, 30);
+        assert_eq!(zoomed.pane_count(), 1);
+        assert!(zoomed.preview);
+        Ok(())
+    }
+
+    #[test]
+    fn layout_resizes_main_pane() -> Result<(), Box<dyn std::error::Error>> {
+        let config = config_from(
+            r#"
+            [display.layout]
+            parent = 1
+            main = 2
+            preview = 2
+            "#,
+        )?;
+        let mut layout = LayoutState::new(config.display());
+        assert_eq!(layout.main_ratio, 40);
+
+        assert!(layout.resize_main(2));
+        assert_eq!(layout.main_ratio, 50);
+        assert!(layout.resize_main(100));
+        assert_eq!(layout.main_ratio, MAX_MAIN_RATIO);
+        assert!(!layout.resize_main(1));
+        assert!(layout.resize_main(-100));
+        assert_eq!(layout.main_ratio, MIN_MAIN_RATIO);
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+//! Keyboard macros for runa.
+//!
+//! Records keypresses into named registers (`Q<reg>` … `Q`) and replays them (`@<reg>`).
+//! Replayed keys go through [AppState::handle_keypress](crate::app::AppState::handle_keypress)
+//! just like typed keys, one key at a time once the app has no pending worker responses,
+//! so a macro sees the same directory listings as the recording did.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Upper bound of replayed keys per playback, which stops recursive macros.
+pub(crate) const MAX_PLAYBACK_KEYS: usize = 10_000;
+
+/// Macro command returned by [AppState::handle_keypress](crate::app::AppState::handle_keypress).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum MacroCommand {
+    /// Starts recording into the next typed register, or stops the running recording.
+    Record,
+    /// Plays the next typed register `count` times.
+    Play { count: Option<usize> },
+    /// Plays a named macro from the config `count` times.
+    Keys {
+        keys: Arc<[KeyEvent]>,
+        count: Option<usize>,
+    },
+}
+
+/// Result of feeding a register name to the macro state.
+#[derive(Debug, PartialEq)]
+pub(crate) enum RegisterResult {
+    /// The key was not a register name, because no register was requested.
+    Ignored,
+    /// The key was used as a register name.
+    Consumed,
+    /// The register name was invalid or the register is empty.
+    Failed(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PendingRegister {
+    Record,
+    Play(usize),
+}
+
+/// Macro registers, the running recording and the keys waiting to be replayed.
+#[derive(Default)]
+pub(crate) struct MacroState {
+    registers: HashMap<char, Arc<[KeyEvent]>>,
+    recording: Option<(char, Vec<KeyEvent>)>,
+    pending: Option<PendingRegister>,
+    playback: VecDeque<KeyEvent>,
+    played: usize,
+    last_played: Option<char>,
+    waiting_since: Option<Instant>,
+}
+
+impl MacroState {
+    /// Returns the register which is currently recorded into.
+    pub(crate) fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(reg, _)| *reg)
+    }
+
+    /// Returns true if replayed keys are waiting to be handled.
+    pub(crate) fn is_playing(&self) -> bool {
+        !self.playback.is_empty()
+    }
+
+    /// Applies a macro command of the keymap.
+    pub(crate) fn command(&mut self, cmd: MacroCommand) -> Result<(), String> {
+        match cmd {
+            MacroCommand::Record => {
+                if let Some((reg, keys)) = self.recording.take() {
+                    self.registers.insert(reg, Arc::from(keys));
+                } else {
+                    self.pending = Some(PendingRegister::Record);
+                }
+                Ok(())
+            }
+            MacroCommand::Play { count } => {
+                self.pending = Some(PendingRegister::Play(count.unwrap_or(1)));
+                Ok(())
+            }
+            MacroCommand::Keys { keys, count } => self.queue(&keys, count.unwrap_or(1)),
+        }
+    }
+
+    /// Uses the key as a register name if a register was requested.
+    ///
+    /// Esc cancels the request, and `@` plays the register which was played last.
+    pub(crate) fn feed_register(&mut self, key: &KeyEvent) -> RegisterResult {
+        let Some(pending) = self.pending.take() else {
+            return RegisterResult::Ignored;
+        };
+        if key.code == KeyCode::Esc {
+            return RegisterResult::Consumed;
+        }
+        let KeyCode::Char(c) = key.code else {
+            return RegisterResult::Failed("Invalid macro register".into());
+        };
+
+        match pending {
+            PendingRegister::Record if c.is_ascii_alphanumeric() => {
+                self.recording = Some((c, Vec::new()));
+                RegisterResult::Consumed
+            }
+            PendingRegister::Record => RegisterResult::Failed(format!("Invalid register: {c}")),
+            PendingRegister::Play(count) => {
+                let reg = if c == '@' {
+                    match self.last_played {
+                        Some(reg) => reg,
+                        None => return RegisterResult::Failed("No macro played yet".into()),
+                    }
+                } else {
+                    c
+                };
+                let Some(keys) = self.registers.get(&reg).cloned() else {
+                    return RegisterResult::Failed(format!("Register @{reg} is empty"));
+                };
+
+                self.last_played = Some(reg);
+                match self.queue(&keys, count) {
+                    Ok(()) => RegisterResult::Consumed,
+                    Err(e) => RegisterResult::Failed(e),
+                }
+            }
+        }
+    }
+
+    /// Appends a typed key to the running recording.
+    pub(crate) fn record(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    /// Returns the next key to replay, unless the app is still busy.
+    ///
+    /// A busy app is waited for at most `max_wait_ms`, so a lost worker response
+    /// does not stall the playback forever.
+    pub(crate) fn next_key(&mut self, busy: bool, max_wait_ms: u64) -> Option<KeyEvent> {
+        if self.playback.is_empty() {
+            self.played = 0;
+            return None;
+        }
+        if busy {
+            let since = *self.waiting_since.get_or_insert_with(Instant::now);
+            if since.elapsed().as_millis() < u128::from(max_wait_ms) {
+                return None;
+            }
+        }
+        self.waiting_since = None;
+        self.playback.pop_front()
+    }
+
+    /// Stops the running playback.
+    pub(crate) fn cancel_playback(&mut self) {
+        self.playback.clear();
+        self.played = 0;
+        self.waiting_since = None;
+    }
+
+    /// Queues the keys `count` times in front of the remaining playback,
+    /// so a macro played from a macro runs before the rest of the outer one.
+    fn queue(&mut self, keys: &[KeyEvent], count: usize) -> Result<(), String> {
+        let total = keys.len().saturating_mul(count);
+        self.played = self.played.saturating_add(total);
+        if self.played > MAX_PLAYBACK_KEYS {
+            self.cancel_playback();
+            return Err(format!("Macro stopped after {MAX_PLAYBACK_KEYS} keys"));
+        }
+
+        for _ in 0..count {
+            for key in keys.iter().rev() {
+                self.playback.push_front(*key);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn macro_record_and_play() -> Result<(), String> {
+        let mut macros = MacroState::default();
+
+        macros.command(MacroCommand::Record)?;
+        assert_eq!(macros.feed_register(&key('a')), RegisterResult::Consumed);
+        assert_eq!(macros.recording(), Some('a'));
+        macros.record(key('j'));
+        macros.record(key('y'));
+        macros.command(MacroCommand::Record)?;
+        assert_eq!(macros.recording(), None);
+
+        macros.command(MacroCommand::Play { count: Some(2) })?;
+        assert_eq!(macros.feed_register(&key('a')), RegisterResult::Consumed);
+        assert!(macros.next_key(true, 1_000).is_none());
+        let played: Vec<_> = std::iter::from_fn(|| macros.next_key(false, 1_000)).collect();
+        assert_eq!(played, vec![key('j'), key('y'), key('j'), key('y')]);
+
+        macros.command(MacroCommand::Play { count: None })?;
+        assert_eq!(macros.feed_register(&key('@')), RegisterResult::Consumed);
+        assert!(macros.is_playing());
+        macros.cancel_playback();
+        assert!(!macros.is_playing());
+
+        macros.command(MacroCommand::Play { count: None })?;
+        assert!(matches!(
+            macros.feed_register(&key('b')),
+            RegisterResult::Failed(_)
+        ));
+        assert_eq!(macros.feed_register(&key('b')), RegisterResult::Ignored);
+        Ok(())
+    }
+
+    #[test]
+    fn macro_playback_is_bounded() {
+        let mut macros = MacroState::default();
+        let keys: Arc<[KeyEvent]> = Arc::from(vec![key('j'); 10]);
+
+        let res = macros.command(MacroCommand::Keys {
+            keys,
+            count: Some(MAX_PLAYBACK_KEYS),
+        });
+        assert!(res.is_err());
+        assert!(!macros.is_playing());
+    }
+}
@@ -0,0 +1,156 @@
+//! Mouse state for runa.
+//!
+//! Holds the clickable regions of the last rendered frame and the last click,
+//! which are used to map mouse events onto panes, entries and tabs.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use ratatui::layout::{Position, Rect};
+
+use crate::utils::timings::Timings;
+
+/// A clickable element of the UI, as resolved from a mouse position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum MouseTarget {
+    /// Row index into the parent pane entries
+    Parent(usize),
+    /// Visible row index into the main pane entries
+    Main(usize),
+    /// Row index into the preview pane entries
+    Preview(usize),
+    /// Index of a tab in the tab line
+    Tab(usize),
+}
+
+/// The inner area of a rendered list and its scroll offset.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub(crate) struct ListRegion {
+    area: Rect,
+    offset: usize,
+}
+
+impl ListRegion {
+    pub(crate) fn new(area: Rect, offset: usize) -> Self {
+        Self { area, offset }
+    }
+
+    /// Returns the indices of the rows shown in the region.
+    pub(crate) fn rows(&self) -> Range<usize> {
+        self.offset..self.offset + self.area.height as usize
+    }
+
+    fn row_at(&self, pos: Position) -> Option<usize> {
+        if !self.area.contains(pos) {
+            return None;
+        }
+        Some(self.offset + (pos.y - self.area.y) as usize)
+    }
+}
+
+/// Clickable regions of the last rendered frame.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MouseRegions {
+    parent: Option<ListRegion>,
+    main: Option<ListRegion>,
+    preview: Option<ListRegion>,
+    tabs: Vec<(Rect, usize)>,
+}
+
+impl MouseRegions {
+    pub(crate) fn new(
+        parent: Option<ListRegion>,
+        main: Option<ListRegion>,
+        preview: Option<ListRegion>,
+        tabs: Vec<(Rect, usize)>,
+    ) -> Self {
+        Self {
+            parent,
+            main,
+            preview,
+            tabs,
+        }
+    }
+
+    /// Resolves a terminal cell to the element drawn at it.
+    pub(crate) fn target_at(&self, column: u16, row: u16) -> Option<MouseTarget> {
+        let pos = Position::new(column, row);
+
+        if let Some(&(_, idx)) = self.tabs.iter().find(|(rect, _)| rect.contains(pos)) {
+            return Some(MouseTarget::Tab(idx));
+        }
+
+        let region_row = |region: &Option<ListRegion>| region.as_ref().and_then(|r| r.row_at(pos));
+        if let Some(idx) = region_row(&self.main) {
+            Some(MouseTarget::Main(idx))
+        } else if let Some(idx) = region_row(&self.parent) {
+            Some(MouseTarget::Parent(idx))
+        } else {
+            region_row(&self.preview).map(MouseTarget::Preview)
+        }
+    }
+
+    /// Returns true if the position lies inside the preview pane.
+    pub(crate) fn in_preview(&self, column: u16, row: u16) -> bool {
+        self.preview
+            .is_some_and(|r| r.area.contains(Position::new(column, row)))
+    }
+}
+
+/// Mouse state of a tab: the regions of the last frame and the last click.
+#[derive(Default)]
+pub(crate) struct MouseState {
+    regions: MouseRegions,
+    last_click: Option<(Instant, MouseTarget)>,
+}
+
+impl MouseState {
+    crate::getters! {
+        regions: &MouseRegions,
+    }
+
+    pub(crate) fn set_regions(&mut self, regions: MouseRegions) {
+        self.regions = regions;
+    }
+
+    /// Records a click on the target and returns true if it completes a double-click.
+    pub(crate) fn register_click(&mut self, target: MouseTarget) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_millis(Timings::DOUBLE_CLICK_MS);
+        let is_double = self
+            .last_click
+            .is_some_and(|(at, last)| last == target && now.duration_since(at) <= window);
+
+        // A double-click consumes the click, so a third click starts a new one.
+        self.last_click = if is_double { None } else { Some((now, target)) };
+        is_double
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mouse_regions_resolve_targets() {
+        let regions = MouseRegions::new(
+            Some(ListRegion::new(Rect::new(1, 1, 10, 5), 0)),
+            Some(ListRegion::new(Rect::new(12, 1, 20, 5), 3)),
+            Some(ListRegion::new(Rect::new(33, 1, 20, 5), 0)),
+            vec![(Rect::new(40, 0, 5, 1), 0), (Rect::new(46, 0, 5, 1), 1)],
+        );
+
+        assert_eq!(regions.target_at(2, 1), Some(MouseTarget::Parent(0)));
+        assert_eq!(regions.target_at(12, 2), Some(MouseTarget::Main(4)));
+        assert_eq!(regions.target_at(40, 5), Some(MouseTarget::Preview(4)));
+        assert_eq!(regions.target_at(47, 0), Some(MouseTarget::Tab(1)));
+        assert_eq!(regions.target_at(0, 0), None);
+        assert!(regions.in_preview(35, 3));
+
+        let mut state = MouseState::default();
+        assert!(!state.register_click(MouseTarget::Main(2)));
+        assert!(state.register_click(MouseTarget::Main(2)));
+        assert!(!state.register_click(MouseTarget::Main(2)));
+        assert!(!state.register_click(MouseTarget::Main(3)));
+    }
+}
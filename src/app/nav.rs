@@ -2,32 +2,98 @@
 //!
 //! Manages the current directory, file entries, selection, markers and filters.
 //! Provides helpers for pane navigation, selection, filtering, and bulk actions.
+//!
+//! In tree mode the entries are the flattened rows of the [TreeState], so entries
+//! may live in a nested directory. Use [NavState::entry_path] to resolve their paths.
+//!
+//! In the flatten view the entries are every file below the current directory,
+//! named by their path relative to it.
 
-use std::collections::{HashMap, HashSet};
-use std::ffi::OsString;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-
-use crate::core::{FileEntry, sort::SortConfig};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::SystemTime;
+
+use crate::app::tree::{TreeNode, TreeState};
+use crate::config::DirRules;
+use crate::core::{
+    FileEntry,
+    query::{FilterMetaMap, FilterQuery},
+    sort::SortConfig,
+};
 use crate::utils::{path, text::StrBuffer};
 
 const MAX_SAVED_POSITIONS: usize = 100;
 const MAX_SAVED_FILTERS: usize = 50;
+const MAX_SAVED_SORTS: usize = 50;
+
+/// State of the flatten view of a directory.
+struct FlattenView {
+    dir: PathBuf,
+    cancel: Option<Arc<AtomicBool>>,
+    received: bool,
+    truncated: bool,
+}
+
+impl FlattenView {
+    fn cancel(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            cancel.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Whether a query marks or unmarks the matching entries.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum MarkMode {
+    Mark,
+    Unmark,
+}
+
+/// Visual selection: every shown entry between the anchor and the cursor is marked.
+struct VisualRange {
+    dir: PathBuf,
+    anchor: PathBuf,
+    /// Markers added by the range, which are removed again once they leave it
+    added: HashSet<PathBuf>,
+}
 
 /// Holds the navigation, selection and file list state of a pane.
 pub(crate) struct NavState {
     current_dir: PathBuf,
+    root_entries: Arc<[FileEntry]>,
     entries: Arc<[FileEntry]>,
+    tree: TreeState,
+    flatten: Option<FlattenView>,
     selected: usize,
     shown_indices: Vec<usize>,
     positions: HashMap<PathBuf, OsString>,
     markers: HashSet<PathBuf>,
+    /// Changes on every change of the markers, so their users only rebuild what they derive
+    /// from them when needed.
+    markers_generation: u64,
+    /// Targets of a counted action (`3d`), used instead of the markers until the action ends.
+    count_targets: Option<HashSet<PathBuf>>,
+    visual: Option<VisualRange>,
     active_filter: String,
     filters: HashMap<PathBuf, String>,
+    filter_query: Option<FilterQuery>,
+    filter_meta: Option<(PathBuf, Arc<FilterMetaMap>)>,
+    filter_meta_stale: bool,
+    filter_meta_request: u64,
+    pending_mark: Option<(FilterQuery, MarkMode)>,
     sort_config: SortConfig,
+    dir_sorts: HashMap<PathBuf, SortConfig>,
+    /// The directories of `dir_sorts`, least recently sorted first.
+    dir_sort_order: VecDeque<PathBuf>,
+    dir_rules: Arc<DirRules>,
     request_id: u64,
 
     // UI releted fields
+    root_sort_column: Option<Arc<StrBuffer>>,
     sort_column: Option<Arc<StrBuffer>>,
     display_path: String,
 }
@@ -37,14 +103,29 @@ impl NavState {
         let display_path = path::format_display_path(&path);
         Self {
             current_dir: path,
+            root_entries: Arc::default(),
             entries: Arc::default(),
+            tree: TreeState::default(),
+            flatten: None,
             selected: 0,
             shown_indices: Vec::new(),
             positions: HashMap::new(),
             markers: HashSet::new(),
+            markers_generation: 0,
+            count_targets: None,
+            visual: None,
             active_filter: String::new(),
             filters: HashMap::new(),
+            filter_query: None,
+            filter_meta: None,
+            filter_meta_stale: false,
+            filter_meta_request: 0,
+            pending_mark: None,
             sort_config: SortConfig::default(),
+            dir_sorts: HashMap::new(),
+            dir_sort_order: VecDeque::new(),
+            dir_rules: Arc::default(),
+            root_sort_column: None,
             sort_column: None,
             display_path,
             request_id: 0,
@@ -59,13 +140,91 @@ impl NavState {
         sort_config: SortConfig,
         sort_column: &Option<Arc<StrBuffer>>,
         markers: &HashSet<PathBuf>,
+        markers_generation: u64,
         active_filter: &str,
         display_path: &str,
         request_id: u64,
     }
 
+    /// Returns the entries of the current directory, without any expanded tree children.
     pub(crate) fn entries_arc(&self) -> Arc<[FileEntry]> {
-        Arc::clone(&self.entries)
+        Arc::clone(&self.root_entries)
+    }
+
+    /// Returns the directory the entry at `idx` lives in.
+    pub(crate) fn entry_dir(&self, idx: usize) -> &Path {
+        self.tree
+            .node(idx)
+            .map(TreeNode::dir)
+            .unwrap_or(&self.current_dir)
+    }
+
+    /// Returns the full path of the entry at `idx`.
+    pub(crate) fn entry_path(&self, idx: usize) -> Option<PathBuf> {
+        let entry = self.entries.get(idx)?;
+        Some(self.entry_dir(idx).join(entry.name()))
+    }
+
+    /// Returns the key of the entry at `idx`: its name, or its path relative to the current
+    /// directory for nested tree entries.
+    pub(crate) fn row_key(&self, idx: usize) -> Option<Cow<'_, OsStr>> {
+        let entry = self.entries.get(idx)?;
+        let rel_dir = self
+            .tree
+            .node(idx)
+            .filter(|n| n.depth() > 0)
+            .and_then(|n| n.dir().strip_prefix(&self.current_dir).ok());
+        Some(match rel_dir {
+            Some(rel) => Cow::Owned(rel.join(entry.name()).into_os_string()),
+            None => Cow::Borrowed(entry.name()),
+        })
+    }
+
+    /// Returns the full path of the selected entry.
+    pub(crate) fn selected_path(&self) -> Option<PathBuf> {
+        self.entry_path(*self.shown_indices.get(self.selected)?)
+    }
+
+    /// Returns the directory of the selected entry, which is the current directory
+    /// unless a nested tree entry is selected.
+    pub(crate) fn selected_dir(&self) -> &Path {
+        self.shown_indices
+            .get(self.selected)
+            .map_or(&self.current_dir, |&idx| self.entry_dir(idx))
+    }
+
+    /// Returns the name of the entry in the current directory which holds the selected entry.
+    /// For nested tree or flatten entries this is their top level ancestor.
+    pub(crate) fn selected_root_name(&self) -> Option<OsString> {
+        let path = self.selected_path()?;
+        path.strip_prefix(&self.current_dir)
+            .ok()
+            .and_then(|rel| rel.components().next())
+            .map(|c| c.as_os_str().to_os_string())
+    }
+
+    /// Returns the tree row info for the entry at `idx`, if the tree is active.
+    pub(crate) fn tree_node(&self, idx: usize) -> Option<&TreeNode> {
+        self.tree.node(idx)
+    }
+
+    pub(crate) fn tree_enabled(&self) -> bool {
+        self.tree.enabled()
+    }
+
+    /// Returns true if children of expanded tree directories are still loading.
+    pub(crate) fn tree_loading(&self) -> bool {
+        self.tree.is_loading()
+    }
+
+    fn is_root_row(&self, idx: usize) -> bool {
+        self.tree.node(idx).is_none_or(|n| n.depth() == 0)
+    }
+
+    fn shown_paths(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.shown_indices
+            .iter()
+            .filter_map(|&i| self.entry_path(i))
     }
 
     pub(crate) fn first_selected(&mut self) -> usize {
@@ -84,11 +243,13 @@ impl NavState {
             return;
         }
 
-        let paths_to_mark: Vec<PathBuf> = self
-            .shown_entries()
-            .map(|e| self.current_dir.join(e.name()))
-            .collect();
+        let paths_to_mark: Vec<PathBuf> = self.shown_paths().collect();
         self.markers.extend(paths_to_mark);
+        self.markers_changed();
+    }
+
+    fn markers_changed(&mut self) {
+        self.markers_generation = self.markers_generation.wrapping_add(1);
     }
 
     // Navigation functions
@@ -127,11 +288,20 @@ impl NavState {
         true
     }
 
+    /// Selects the visible entry at `idx`.
+    /// Returns false if `idx` is out of range or already selected.
+    pub(crate) fn select_visible(&mut self, idx: usize) -> bool {
+        if idx >= self.shown_entries_len() || idx == self.selected {
+            return false;
+        }
+        self.selected = idx;
+        true
+    }
+
     /// Saves the current selection position for the current directory.
     pub(crate) fn save_position(&mut self) {
-        if let Some(entry) = self.selected_entry() {
-            self.positions
-                .insert(self.current_dir.clone(), entry.name().to_os_string());
+        if let Some(name) = self.selected_root_name() {
+            self.positions.insert(self.current_dir.clone(), name);
 
             if self.positions.len() > MAX_SAVED_POSITIONS
                 && let Some(key) = self.positions.keys().next().cloned()
@@ -146,12 +316,83 @@ impl NavState {
         &self.positions
     }
 
+    /// Returns a reference to the saved per-directory filters.
+    pub(crate) fn saved_filters(&self) -> &HashMap<PathBuf, String> {
+        &self.filters
+    }
+
+    /// Returns a reference to the sort configs remembered for single directories.
+    pub(crate) fn saved_sorts(&self) -> &HashMap<PathBuf, SortConfig> {
+        &self.dir_sorts
+    }
+
+    /// Replaces the saved positions, filters and sorts, e.g. with the ones of a restored session.
+    /// They are applied once the directories are loaded.
+    pub(crate) fn restore_saved(
+        &mut self,
+        positions: HashMap<PathBuf, OsString>,
+        filters: HashMap<PathBuf, String>,
+        sorts: HashMap<PathBuf, SortConfig>,
+    ) {
+        self.positions = positions;
+        self.filters = filters;
+        self.dir_sort_order = sorts.keys().cloned().collect();
+        self.dir_sorts = sorts;
+    }
+
+    /// Sets the `[[dir_rules]]` of the config.
+    pub(crate) fn set_dir_rules(&mut self, rules: Arc<DirRules>) {
+        self.dir_rules = rules;
+    }
+
+    /// Returns the sort config of the directory.
+    ///
+    /// A sort remembered for the directory wins over the sort of a matching `[[dir_rules]]`,
+    /// which wins over the tab-wide sort config.
+    pub(crate) fn sort_for(&self, dir: &Path) -> SortConfig {
+        self.dir_sorts
+            .get(dir)
+            .copied()
+            .or_else(|| self.dir_rules.view_for(dir).sort)
+            .unwrap_or(self.sort_config)
+    }
+
+    /// Changes the sort config of the current directory.
+    ///
+    /// Directories with their own sort, either remembered or from a `[[dir_rules]]`,
+    /// remember the new sort for themselves. Otherwise the tab-wide sort config is changed.
+    /// Returns true if the tab-wide sort config was changed.
+    pub(crate) fn change_sort(&mut self, sort_config: SortConfig) -> bool {
+        let dir = &self.current_dir;
+        if !self.dir_sorts.contains_key(dir) && self.dir_rules.view_for(dir).sort.is_none() {
+            self.sort_config = sort_config;
+            return true;
+        }
+
+        // Forget the least recently sorted directory when too many are remembered.
+        if self.dir_sorts.insert(dir.clone(), sort_config).is_some() {
+            self.dir_sort_order.retain(|d| d != dir);
+        }
+        self.dir_sort_order.push_back(dir.clone());
+        if self.dir_sorts.len() > MAX_SAVED_SORTS
+            && let Some(oldest) = self.dir_sort_order.pop_front()
+        {
+            self.dir_sorts.remove(&oldest);
+        }
+        false
+    }
+
     /// Sets a new current directory path, clearing entries and selection.
     /// Increments the request ID to cancel pending requests.
     pub(crate) fn set_path(&mut self, path: PathBuf) {
         self.save_position();
 
+        if let Some(mut flatten) = self.flatten.take_if(|f| f.dir != path) {
+            flatten.cancel();
+        }
+
         self.current_dir = path;
+        self.pending_mark = None;
         // instantly ends all pending messages from the previous directory.
         self.request_id = self.request_id.wrapping_add(1);
     }
@@ -165,26 +406,49 @@ impl NavState {
         sort_column: Option<Arc<StrBuffer>>,
         focus: Option<OsString>,
     ) {
+        // Keep a nested tree selection when the same directory is reloaded.
+        let nested = self
+            .selected_path()
+            .filter(|p| path == self.current_dir && p.parent() != Some(path.as_path()))
+            .filter(|p| focus.is_none() || focus.as_deref() == p.file_name());
+
         self.current_dir = path;
         self.display_path = path::format_display_path(&self.current_dir);
-        self.entries = entries;
-        self.sort_column = sort_column;
+        self.root_entries = entries;
+        self.root_sort_column = sort_column;
+        self.flatten_tree();
 
         self.restore_filter_for_current_dir();
         self.rebuild_shown_cache();
 
+        // The entries might have changed on disk, so the filter metadata has to be refreshed.
+        if self
+            .filter_query
+            .as_ref()
+            .is_some_and(FilterQuery::needs_meta)
+        {
+            self.filter_meta_stale = true;
+        }
+
         if self.entries.is_empty() || self.shown_indices.is_empty() {
             self.selected = 0;
             return;
         }
 
+        if let Some(path) = nested {
+            self.select_path_or_ancestor(&path);
+            return;
+        }
+
         let target_name = focus.or_else(|| self.positions.get(&self.current_dir).cloned());
 
         if let Some(name) = target_name {
             self.selected = self
                 .shown_indices
                 .iter()
-                .position(|&abs_idx| self.entries[abs_idx].name() == name)
+                .position(|&abs_idx| {
+                    self.is_root_row(abs_idx) && self.entries[abs_idx].name() == name
+                })
                 .unwrap_or(0);
         } else {
             self.selected = 0;
@@ -194,9 +458,8 @@ impl NavState {
     /// Toggles the marker state of the currently selected entry.
     /// If the entry is in the clipboard, it is unmarked and removed from the clipboard.
     pub(crate) fn toggle_marker(&mut self, clipboard: &mut Option<HashSet<PathBuf>>) {
-        if let Some(entry) = self.selected_entry() {
-            let path = self.current_dir().join(entry.name());
-
+        if let Some(path) = self.selected_path() {
+            self.markers_changed();
             if let Some(clip) = clipboard
                 && clip.remove(&path)
             {
@@ -231,18 +494,151 @@ impl NavState {
         }
     }
 
-    /// Clears all markers.
+    /// Targets `count` visible entries, starting at the selection, for the next action.
+    ///
+    /// The markers are left untouched, so they are still set after the action.
+    pub(crate) fn target_range(&mut self, count: usize) {
+        let end = self
+            .selected
+            .saturating_add(count)
+            .min(self.shown_entries_len());
+        let targets = (self.selected..end)
+            .filter_map(|vis_idx| self.entry_path(self.shown_indices[vis_idx]))
+            .collect();
+        self.count_targets = Some(targets);
+    }
+
+    /// Drops the targets of a counted action.
+    pub(crate) fn clear_count_targets(&mut self) {
+        self.count_targets = None;
+    }
+
+    /// Ends an action on the targets.
+    /// Clears the markers, unless the action was on a counted range.
+    pub(crate) fn finish_action(&mut self) {
+        if self.count_targets.take().is_none() {
+            self.clear_markers();
+        }
+    }
+
+    /// Clears all markers and ends the visual selection.
     pub(crate) fn clear_markers(&mut self) {
-        self.markers.clear();
+        if !self.markers.is_empty() {
+            self.markers.clear();
+            self.markers_changed();
+        }
+        self.visual = None;
+    }
+
+    // Visual selection functions
+
+    /// Returns true if the visual selection is active.
+    pub(crate) fn visual_active(&self) -> bool {
+        self.visual.is_some()
+    }
+
+    /// Starts the visual selection at the cursor, or ends it keeping the marked range.
+    /// Returns the new state.
+    pub(crate) fn toggle_visual(&mut self) -> bool {
+        if self.visual.take().is_some() {
+            return false;
+        }
+        let Some(anchor) = self.selected_path() else {
+            return false;
+        };
+
+        self.visual = Some(VisualRange {
+            dir: self.current_dir.clone(),
+            anchor,
+            added: HashSet::new(),
+        });
+        self.sync_visual();
+        true
+    }
+
+    /// Ends the visual selection and removes the markers of its range.
+    pub(crate) fn cancel_visual(&mut self) {
+        if let Some(visual) = self.visual.take() {
+            for path in &visual.added {
+                self.markers.remove(path);
+            }
+            self.markers_changed();
+        }
+    }
+
+    /// Swaps the anchor and the cursor of the visual selection.
+    pub(crate) fn swap_visual_ends(&mut self) {
+        let Some(anchor_idx) = self.visual_anchor_idx() else {
+            return;
+        };
+        let Some(cursor) = self.selected_path() else {
+            return;
+        };
+        if let Some(visual) = self.visual.as_mut() {
+            visual.anchor = cursor;
+        }
+        self.selected = anchor_idx;
+    }
+
+    fn visual_anchor_idx(&self) -> Option<usize> {
+        let visual = self.visual.as_ref()?;
+        self.shown_indices
+            .iter()
+            .position(|&i| self.entry_path(i).as_ref() == Some(&visual.anchor))
+    }
+
+    /// Marks the entries between the anchor and the cursor of the visual selection.
+    ///
+    /// Markers which left the range are removed again, unless they were marked before.
+    /// The selection ends if the directory changed or the anchor is no longer shown.
+    pub(crate) fn sync_visual(&mut self) {
+        let Some(visual) = self.visual.as_ref() else {
+            return;
+        };
+        let anchor_idx = self.visual_anchor_idx();
+        let (Some(anchor_idx), true) = (anchor_idx, visual.dir == self.current_dir) else {
+            self.visual = None;
+            return;
+        };
+
+        let (start, end) = if anchor_idx <= self.selected {
+            (anchor_idx, self.selected)
+        } else {
+            (self.selected, anchor_idx)
+        };
+        // The anchor was found, so there is at least one shown entry.
+        let end = end.min(self.shown_indices.len() - 1);
+        let range: HashSet<PathBuf> = self.shown_indices[start..=end]
+            .iter()
+            .filter_map(|&i| self.entry_path(i))
+            .collect();
+
+        let Some(visual) = self.visual.as_mut() else {
+            return;
+        };
+        let mut changed = false;
+        for path in visual.added.iter().filter(|p| !range.contains(*p)) {
+            changed |= self.markers.remove(path);
+        }
+        visual.added.retain(|p| range.contains(p));
+        for path in range {
+            if self.markers.insert(path.clone()) {
+                visual.added.insert(path);
+                changed = true;
+            }
+        }
+        if changed {
+            self.markers_changed();
+        }
     }
 
-    /// Returns the set of action targets, either marked entries or the selected entry.
+    /// Returns the set of action targets, either the counted range, the marked entries or the
+    /// selected entry.
     pub(crate) fn get_action_targets(&self) -> HashSet<PathBuf> {
-        if self.markers.is_empty() {
-            self.selected_entry()
-                .map(|e| self.current_dir.join(e.name()))
-                .into_iter()
-                .collect()
+        if let Some(targets) = &self.count_targets {
+            targets.clone()
+        } else if self.markers.is_empty() {
+            self.selected_path().into_iter().collect()
         } else {
             self.markers.iter().cloned().collect()
         }
@@ -250,6 +646,7 @@ impl NavState {
 
     /// Returns an iterator over the entries that match the current filter.
     /// If the filter is empty, returns all entries.
+    #[cfg(test)]
     pub(crate) fn shown_entries(&self) -> impl Iterator<Item = &FileEntry> {
         self.shown_indices
             .iter()
@@ -268,33 +665,469 @@ impl NavState {
     }
 
     /// Sets a new filter string, preserving the selected entry if possible.
+    ///
+    /// The filter is parsed as a [FilterQuery].
     pub(crate) fn set_filter(&mut self, filter: String) {
         if self.active_filter == filter {
             return;
         }
 
-        let target_name = self.selected_entry().map(|e| e.name().to_os_string());
-        self.active_filter = filter;
+        self.set_active_filter(filter);
         self.save_filter_for_current_dir();
+        self.rebuild_keep_selection();
+    }
 
+    /// Marks all shown entries matching the query and returns the number of matches.
+    ///
+    /// If the query needs metadata which is not loaded yet, the marking is deferred until
+    /// [NavState::update_filter_meta] and `None` is returned.
+    pub(crate) fn mark_matching(&mut self, query: FilterQuery) -> Option<usize> {
+        self.apply_mark_query(query, MarkMode::Mark)
+    }
+
+    /// Unmarks all shown entries matching the query and returns the number of matches.
+    ///
+    /// Deferred like [NavState::mark_matching] if the query needs metadata.
+    pub(crate) fn unmark_matching(&mut self, query: FilterQuery) -> Option<usize> {
+        self.apply_mark_query(query, MarkMode::Unmark)
+    }
+
+    fn apply_mark_query(&mut self, query: FilterQuery, mode: MarkMode) -> Option<usize> {
+        if query.needs_meta() && self.current_filter_meta().is_none() {
+            self.pending_mark = Some((query, mode));
+            self.filter_meta_stale = true;
+            return None;
+        }
+
+        let meta = self.current_filter_meta();
+        let now = SystemTime::now();
+        let matched: Vec<PathBuf> = self
+            .shown_indices
+            .iter()
+            .filter(|&&i| {
+                let e = &self.entries[i];
+                let meta = meta.filter(|_| self.is_root_row(i));
+                query.matches(e, meta.and_then(|m| m.get(e.name())), now)
+            })
+            .filter_map(|&i| self.entry_path(i))
+            .collect();
+
+        let count = matched.len();
+        self.markers_changed();
+        match mode {
+            MarkMode::Mark => self.markers.extend(matched),
+            MarkMode::Unmark => {
+                for path in &matched {
+                    self.markers.remove(path);
+                }
+            }
+        }
+        Some(count)
+    }
+
+    /// Applies a marking deferred by [NavState::mark_matching] or [NavState::unmark_matching],
+    /// if its metadata has arrived.
+    pub(crate) fn apply_pending_mark(&mut self) -> Option<(MarkMode, usize)> {
+        self.current_filter_meta()?;
+        let (query, mode) = self.pending_mark.take()?;
+        self.apply_mark_query(query, mode)
+            .map(|count| (mode, count))
+    }
+
+    /// Inverts the markers of the shown entries and returns the number of marked entries.
+    pub(crate) fn invert_markers(&mut self) -> usize {
+        let paths: Vec<PathBuf> = self.shown_paths().collect();
+        self.markers_changed();
+        let mut marked = 0;
+        for path in paths {
+            if !self.markers.remove(&path) {
+                self.markers.insert(path);
+                marked += 1;
+            }
+        }
+        marked
+    }
+
+    /// Returns the directory and entries to collect filter metadata for, if needed.
+    ///
+    /// Should be sent to the worker pool as [WorkerTask::LoadFilterMeta].
+    ///
+    /// [WorkerTask::LoadFilterMeta]: crate::core::workers::WorkerTask::LoadFilterMeta
+    pub(crate) fn take_filter_meta_request(&mut self) -> Option<(PathBuf, Arc<[FileEntry]>, u64)> {
+        if !self.filter_meta_stale || self.entries.is_empty() {
+            return None;
+        }
+
+        self.filter_meta_stale = false;
+        self.filter_meta_request = self.filter_meta_request.wrapping_add(1);
+        Some((
+            self.current_dir.clone(),
+            Arc::clone(&self.entries),
+            self.filter_meta_request,
+        ))
+    }
+
+    /// Stores the filter metadata loaded by the worker and reapplies the filter.
+    ///
+    /// Returns false if the response is outdated.
+    pub(crate) fn update_filter_meta(
+        &mut self,
+        path: PathBuf,
+        meta: Arc<FilterMetaMap>,
+        request_id: u64,
+    ) -> bool {
+        if request_id != self.filter_meta_request || path != self.current_dir {
+            return false;
+        }
+
+        self.filter_meta = Some((path, meta));
+        if self
+            .filter_query
+            .as_ref()
+            .is_some_and(FilterQuery::needs_meta)
+        {
+            self.rebuild_keep_selection();
+        }
+        true
+    }
+
+    fn current_filter_meta(&self) -> Option<&Arc<FilterMetaMap>> {
+        self.filter_meta
+            .as_ref()
+            .filter(|(dir, _)| *dir == self.current_dir)
+            .map(|(_, meta)| meta)
+    }
+
+    /// Rebuilds the shown entries, keeping the selected entry if it is still shown.
+    fn rebuild_keep_selection(&mut self) {
+        let target = self.selected_path();
         self.rebuild_shown_cache();
 
-        if let Some(name) = target_name {
-            self.selected = self
+        match target {
+            Some(path) => self.select_path_or_ancestor(&path),
+            None => self.selected = 0,
+        }
+    }
+
+    /// Selects the shown entry with the given path, or its closest shown ancestor.
+    fn select_path_or_ancestor(&mut self, target: &Path) {
+        let mut candidate = Some(target);
+        while let Some(path) = candidate {
+            if let Some(pos) = self
                 .shown_indices
                 .iter()
-                .position(|&i| self.entries[i].name() == name)
-                .unwrap_or(0);
+                .position(|&i| self.entry_path(i).as_deref() == Some(path))
+            {
+                self.selected = pos;
+                return;
+            }
+            candidate = path.parent().filter(|p| p.starts_with(&self.current_dir));
+        }
+        self.selected = 0;
+    }
+
+    // Flatten functions
+
+    /// Returns true if the current directory is shown in the flatten view.
+    pub(crate) fn flatten_active(&self) -> bool {
+        self.flatten
+            .as_ref()
+            .is_some_and(|f| f.dir == self.current_dir)
+    }
+
+    /// Returns true if the flatten view was cut off at the configured maximum.
+    pub(crate) fn flatten_truncated(&self) -> bool {
+        self.flatten_active() && self.flatten.as_ref().is_some_and(|f| f.truncated)
+    }
+
+    /// Returns true if either the tree or the flatten view shows entries of nested directories.
+    pub(crate) fn shows_nested(&self) -> bool {
+        self.tree.enabled() || self.flatten_active()
+    }
+
+    /// Switches the current directory between the normal listing and the flatten view.
+    /// Returns the new state. The listing has to be (re)loaded by the caller.
+    pub(crate) fn toggle_flatten(&mut self) -> bool {
+        if let Some(mut flatten) = self.flatten.take() {
+            flatten.cancel();
+            if flatten.dir == self.current_dir {
+                return false;
+            }
+        }
+
+        self.flatten = Some(FlattenView {
+            dir: self.current_dir.clone(),
+            cancel: None,
+            received: false,
+            truncated: false,
+        });
+        true
+    }
+
+    /// Starts a new flatten walk of the current directory, cancelling the running one.
+    /// Returns the directory, request id and cancel token for the worker.
+    pub(crate) fn begin_flatten_request(&mut self) -> Option<(PathBuf, u64, Arc<AtomicBool>)> {
+        if !self.flatten_active() {
+            return None;
+        }
+        let request_id = self.prepare_new_request();
+        let flatten = self.flatten.as_mut()?;
+        flatten.cancel();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        flatten.cancel = Some(Arc::clone(&cancel));
+        flatten.received = false;
+
+        Some((self.current_dir.clone(), request_id, cancel))
+    }
+
+    fn is_flatten_response(&self, path: &Path, request_id: u64) -> bool {
+        self.flatten_active() && request_id == self.request_id && path == self.current_dir
+    }
+
+    /// Adds a batch of streamed flatten results.
+    /// The first batch of a walk replaces the previous entries.
+    pub(crate) fn append_flatten_batch(
+        &mut self,
+        path: &Path,
+        batch: Vec<FileEntry>,
+        request_id: u64,
+    ) -> bool {
+        if !self.is_flatten_response(path, request_id) {
+            return false;
+        }
+        let Some(flatten) = self.flatten.as_mut() else {
+            return false;
+        };
+
+        let entries: Arc<[FileEntry]> = if flatten.received {
+            self.root_entries.iter().cloned().chain(batch).collect()
         } else {
-            self.selected = 0;
+            Arc::from(batch)
+        };
+        flatten.received = true;
+
+        self.set_root_entries(entries, None);
+        true
+    }
+
+    /// Replaces the streamed flatten results with the final sorted list.
+    pub(crate) fn finish_flatten(
+        &mut self,
+        path: &Path,
+        entries: Arc<[FileEntry]>,
+        sort_column: Option<Arc<StrBuffer>>,
+        truncated: bool,
+        request_id: u64,
+    ) -> bool {
+        if !self.is_flatten_response(path, request_id) {
+            return false;
+        }
+        if let Some(flatten) = self.flatten.as_mut() {
+            flatten.cancel = None;
+            flatten.truncated = truncated;
+        }
+
+        self.set_root_entries(entries, sort_column);
+        true
+    }
+
+    /// Replaces the entries of the current directory, keeping the selected entry if possible.
+    fn set_root_entries(&mut self, entries: Arc<[FileEntry]>, sort_column: Option<Arc<StrBuffer>>) {
+        let target = self.selected_path();
+
+        self.root_entries = entries;
+        self.root_sort_column = sort_column;
+        self.flatten_tree();
+        self.rebuild_shown_cache();
+
+        if self
+            .filter_query
+            .as_ref()
+            .is_some_and(FilterQuery::needs_meta)
+        {
+            self.filter_meta_stale = true;
         }
+
+        match target {
+            Some(path) => self.select_path_or_ancestor(&path),
+            None => self.selected = 0,
+        }
+    }
+
+    // Tree functions
+
+    /// Rebuilds the flattened tree rows from the root entries and loaded children.
+    fn flatten_tree(&mut self) {
+        match self.tree.flatten(
+            &self.current_dir,
+            &self.root_entries,
+            &self.root_sort_column,
+        ) {
+            Some(flat) => {
+                self.entries = flat.entries;
+                self.sort_column = flat.sort_column;
+            }
+            None => {
+                self.entries = Arc::clone(&self.root_entries);
+                self.sort_column = self.root_sort_column.clone();
+            }
+        }
+    }
+
+    fn reflatten_keep_selection(&mut self) {
+        let target = self.selected_path();
+        self.flatten_tree();
+        self.rebuild_shown_cache();
+
+        match target {
+            Some(path) => self.select_path_or_ancestor(&path),
+            None => self.selected = 0,
+        }
+    }
+
+    /// Switches between the flat listing and the tree view.
+    /// Expanded directories are kept, so they show up again when the tree is re-enabled.
+    pub(crate) fn toggle_tree(&mut self) -> bool {
+        self.tree.set_enabled(!self.tree.enabled());
+        self.reflatten_keep_selection();
+        self.tree.enabled()
+    }
+
+    /// Expands the selected directory in tree mode.
+    /// Returns false if nothing changed.
+    pub(crate) fn expand_selected(&mut self) -> bool {
+        if !self.tree.enabled() {
+            return false;
+        }
+        let Some(entry) = self.selected_entry() else {
+            return false;
+        };
+        if !entry.is_dir() {
+            return false;
+        }
+        let Some(path) = self.selected_path() else {
+            return false;
+        };
+
+        if !self.tree.expand(path) {
+            return false;
+        }
+        self.reflatten_keep_selection();
+        true
+    }
+
+    /// Collapses the selected directory, or the directory containing the selected
+    /// nested entry, which then becomes selected.
+    /// Returns false if nothing changed.
+    pub(crate) fn collapse_selected(&mut self) -> bool {
+        if !self.tree.enabled() {
+            return false;
+        }
+        let Some(path) = self.selected_path() else {
+            return false;
+        };
+
+        if self.tree.is_expanded(&path) {
+            self.tree.collapse(&path);
+            self.reflatten_keep_selection();
+            return true;
+        }
+
+        let dir = self.selected_dir().to_path_buf();
+        if dir == self.current_dir || !self.tree.collapse(&dir) {
+            return false;
+        }
+        self.flatten_tree();
+        self.rebuild_shown_cache();
+        self.select_path_or_ancestor(&dir);
+        true
+    }
+
+    /// Expands or collapses the selected directory.
+    pub(crate) fn toggle_fold_selected(&mut self) -> bool {
+        let expanded = self
+            .selected_path()
+            .is_some_and(|path| self.tree.is_expanded(&path));
+
+        if expanded {
+            self.collapse_selected()
+        } else {
+            self.expand_selected()
+        }
+    }
+
+    /// Collapses every expanded directory below the current directory.
+    pub(crate) fn collapse_all(&mut self) -> bool {
+        if !self.tree.collapse_all(&self.current_dir) {
+            return false;
+        }
+        self.reflatten_keep_selection();
+        true
+    }
+
+    /// Returns true if the selected entry is an expanded directory.
+    pub(crate) fn is_selected_expanded(&self) -> bool {
+        self.selected_path()
+            .is_some_and(|path| self.tree.is_expanded(&path))
+    }
+
+    /// Takes the expanded directories whose children need to be loaded
+    /// and registers a request id for each of them.
+    pub(crate) fn take_tree_requests(&mut self) -> Vec<(PathBuf, u64)> {
+        self.tree
+            .take_missing()
+            .into_iter()
+            .map(|path| {
+                let id = self.tree.begin_request(path.clone());
+                (path, id)
+            })
+            .collect()
+    }
+
+    pub(crate) fn is_tree_pending(&self, path: &Path, request_id: u64) -> bool {
+        self.tree.is_pending(path, request_id)
+    }
+
+    /// Stores the loaded children of an expanded directory and rebuilds the tree rows.
+    ///
+    /// Children from the cache are passed without a `request_id`.
+    pub(crate) fn update_tree_children(
+        &mut self,
+        path: PathBuf,
+        entries: Arc<[FileEntry]>,
+        sort_column: Option<Arc<StrBuffer>>,
+        request_id: Option<u64>,
+    ) -> bool {
+        if !self
+            .tree
+            .insert_children(path, entries, sort_column, request_id)
+        {
+            return false;
+        }
+        self.reflatten_keep_selection();
+        true
+    }
+
+    /// Marks the loaded children of the given directories as outdated, so they get reloaded.
+    pub(crate) fn invalidate_tree_dirs(&mut self, dirs: &[PathBuf]) {
+        self.tree.invalidate(dirs);
+        self.flatten_tree();
+        self.rebuild_keep_selection();
+    }
+
+    /// Marks all loaded children as outdated, e.g. after a sort change.
+    pub(crate) fn invalidate_tree(&mut self) {
+        self.tree.invalidate_all();
+        self.flatten_tree();
+        self.rebuild_keep_selection();
     }
 
     /// Clears the current filter.
     pub(crate) fn clear_filters(&mut self) {
         let prev_abs = self.shown_indices.get(self.selected).cloned();
 
-        self.active_filter.clear();
+        self.set_active_filter(String::new());
         self.save_filter_for_current_dir();
 
         self.rebuild_shown_cache();
@@ -311,8 +1144,11 @@ impl NavState {
     }
 
     /// Saves the current filter for the current directory.
+    /// A cleared filter is saved as empty if a `[[dir_rules]]` sets a filter,
+    /// so the rule filter does not come back.
     fn save_filter_for_current_dir(&mut self) {
-        if self.active_filter.is_empty() {
+        let has_rule_filter = || self.dir_rules.view_for(&self.current_dir).filter.is_some();
+        if self.active_filter.is_empty() && !has_rule_filter() {
             self.filters.remove(&self.current_dir);
         } else {
             self.filters
@@ -326,27 +1162,50 @@ impl NavState {
         }
     }
 
-    /// Restores the saved filter for the current directory, if any.
+    /// Restores the saved filter for the current directory,
+    /// or the filter of a matching `[[dir_rules]]`.
     fn restore_filter_for_current_dir(&mut self) {
-        self.active_filter = self
-            .filters
-            .get(&self.current_dir)
-            .cloned()
-            .unwrap_or_default();
+        let filter = match self.filters.get(&self.current_dir) {
+            Some(filter) => filter.clone(),
+            None => self
+                .dir_rules
+                .view_for(&self.current_dir)
+                .filter
+                .unwrap_or_default()
+                .to_string(),
+        };
+        if filter != self.active_filter {
+            self.set_active_filter(filter);
+        }
+    }
+
+    /// Sets the filter string and parses it once as the [FilterQuery] used by the rebuilds.
+    fn set_active_filter(&mut self, filter: String) {
+        self.filter_query = (!filter.is_empty()).then(|| FilterQuery::parse_lenient(&filter));
+        self.active_filter = filter;
     }
 
     fn rebuild_shown_cache(&mut self) {
-        if self.active_filter.is_empty() {
-            self.shown_indices = (0..self.entries.len()).collect();
-        } else {
-            let filter_lower = self.active_filter.to_lowercase();
+        if let Some(query) = &self.filter_query {
+            let meta = self.current_filter_meta().cloned();
+            if query.needs_meta() && meta.is_none() {
+                self.filter_meta_stale = true;
+            }
+
+            // The metadata only covers the current directory, not nested tree entries.
+            let now = SystemTime::now();
             self.shown_indices = self
                 .entries
                 .iter()
                 .enumerate()
-                .filter(|(_, e)| e.lowered().contains(&filter_lower))
+                .filter(|(i, e)| {
+                    let meta = meta.as_ref().filter(|_| self.is_root_row(*i));
+                    query.matches(e, meta.and_then(|m| m.get(e.name())), now)
+                })
                 .map(|(i, _)| i)
                 .collect();
+        } else {
+            self.shown_indices = (0..self.entries.len()).collect();
         }
 
         let len = self.shown_indices.len();
@@ -708,4 +1567,330 @@ mod tests {
         assert!(nav.markers().contains(&base_path.join("banana.txt")));
         Ok(())
     }
+
+    #[test]
+    fn navstate_metadata_filter_and_mark() -> Result<(), Box<dyn error::Error>> {
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+
+        fs::write(base_path.join("big.bin"), vec![0u8; 4096])?;
+        fs::write(base_path.join("small.txt"), "tiny")?;
+        fs::write(base_path.join("notes.txt"), vec![b'a'; 2048])?;
+        fs::create_dir(base_path.join("sub"))?;
+
+        let entries = fm::browse_dir(&base_path)?;
+        let mut nav = NavState::new(base_path.clone());
+        nav.update_from_worker(base_path.clone(), Arc::from(entries), None, None);
+
+        nav.set_filter("size>1k".to_string());
+        assert_eq!(nav.shown_entries_len(), 0, "No metadata loaded yet");
+
+        let (path, entries, request_id) = nav
+            .take_filter_meta_request()
+            .ok_or("Metadata should be requested")?;
+        assert!(nav.take_filter_meta_request().is_none());
+
+        let meta = crate::core::query::collect_filter_meta(
+            &path,
+            &entries,
+            #[cfg(unix)]
+            &mut crate::core::metadata::unix_meta::UserGroupCache::new(),
+        );
+
+        assert!(!nav.update_filter_meta(path.clone(), Arc::new(meta.clone()), request_id + 1));
+        assert!(nav.update_filter_meta(path, Arc::new(meta), request_id));
+
+        let mut shown: Vec<&str> = nav.shown_entries().map(|e| e.name_str()).collect();
+        shown.sort();
+        assert_eq!(shown, vec!["big.bin", "notes.txt"]);
+
+        let marked = nav.mark_matching(FilterQuery::parse("ext:txt")?);
+        assert_eq!(marked, Some(1));
+        assert!(nav.markers().contains(&base_path.join("notes.txt")));
+
+        nav.clear_filters();
+        let marked = nav.mark_matching(FilterQuery::parse("type:dir or size<1k")?);
+        assert_eq!(marked, Some(2));
+        assert!(nav.markers().contains(&base_path.join("sub")));
+        assert!(nav.markers().contains(&base_path.join("small.txt")));
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_mark_by_pattern_and_invert() -> Result<(), Box<dyn error::Error>> {
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+        let sub = base_path.join("sub");
+        fs::create_dir(&sub)?;
+
+        for name in ["main.rs", "main.rs.orig", "lib.rs.orig", "notes.txt"] {
+            fs::write(base_path.join(name), "")?;
+        }
+
+        let mut nav = NavState::new(base_path.clone());
+        let entries = fm::browse_dir(&base_path)?;
+        nav.update_from_worker(base_path.clone(), Arc::from(entries), None, None);
+
+        assert_eq!(nav.mark_matching(FilterQuery::parse("*.orig")?), Some(2));
+        assert_eq!(nav.unmark_matching(FilterQuery::parse("lib*")?), Some(1));
+        assert_eq!(nav.markers().len(), 1);
+
+        // Everything but main.rs.orig is marked after inverting.
+        assert_eq!(nav.invert_markers(), 4);
+        assert!(!nav.markers().contains(&base_path.join("main.rs.orig")));
+        assert!(nav.markers().contains(&base_path.join("lib.rs.orig")));
+
+        // Marks survive navigating into another directory and back.
+        nav.set_path(sub.clone());
+        nav.update_from_worker(sub.clone(), Arc::from(fm::browse_dir(&sub)?), None, None);
+        nav.set_path(base_path.clone());
+        let entries = fm::browse_dir(&base_path)?;
+        nav.update_from_worker(base_path.clone(), Arc::from(entries), None, None);
+        assert_eq!(nav.markers().len(), 4);
+        assert_eq!(nav.invert_markers(), 1);
+        assert_eq!(nav.get_action_targets().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_dir_sorts_fall_back_to_tab_sort() -> Result<(), Box<dyn error::Error>> {
+        use crate::config::DirRule;
+        use crate::core::sort::{SortMode, SortOrder};
+
+        #[derive(serde::Deserialize)]
+        struct Rules {
+            dir_rules: Vec<DirRule>,
+        }
+
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+        let downloads = base_path.join("downloads");
+        fs::create_dir(&downloads)?;
+
+        let raw: Rules = toml::from_str(&format!(
+            "[[dir_rules]]\npath = '{}'\nsort = 'modified desc'\nfilter = 'ext:iso'",
+            downloads.display()
+        ))?;
+        let mut nav = NavState::new(base_path.clone());
+        nav.set_dir_rules(Arc::new(DirRules::from(raw.dir_rules)));
+
+        let by_size = SortConfig::from((SortMode::Size, SortOrder::Ascending));
+        let by_modified = SortConfig::from((SortMode::Modified, SortOrder::Descending));
+        assert!(nav.change_sort(by_size));
+        assert_eq!(nav.sort_for(&base_path), by_size);
+        assert_eq!(nav.sort_for(&downloads), by_modified);
+
+        // Sorting a directory with a rule remembers the sort for that directory only.
+        nav.set_path(downloads.clone());
+        nav.update_from_worker(downloads.clone(), Arc::default(), None, None);
+        assert_eq!(nav.active_filter(), "ext:iso");
+        let by_name = SortConfig::from((SortMode::Name, SortOrder::Descending));
+        assert!(!nav.change_sort(by_name));
+        assert_eq!(nav.sort_for(&downloads), by_name);
+        assert_eq!(nav.sort_for(&base_path), by_size);
+        assert_eq!(nav.saved_sorts().len(), 1);
+
+        // A cleared rule filter stays cleared.
+        nav.set_filter(String::new());
+        nav.set_path(base_path.clone());
+        nav.set_path(downloads.clone());
+        nav.update_from_worker(downloads.clone(), Arc::default(), None, None);
+        assert_eq!(nav.active_filter(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_dir_sorts_forget_least_recently_sorted() -> Result<(), Box<dyn error::Error>> {
+        use crate::config::DirRule;
+        use crate::core::sort::{SortMode, SortOrder};
+
+        #[derive(serde::Deserialize)]
+        struct Rules {
+            dir_rules: Vec<DirRule>,
+        }
+
+        let raw: Rules = toml::from_str("[[dir_rules]]\npath = '/data/*'\nsort = 'size'")?;
+        let mut nav = NavState::new(PathBuf::from("/data"));
+        nav.set_dir_rules(Arc::new(DirRules::from(raw.dir_rules)));
+
+        let by_name = SortConfig::from((SortMode::Name, SortOrder::Descending));
+        let dir = |i: usize| PathBuf::from(format!("/data/d{i}"));
+        for i in (0..MAX_SAVED_SORTS).chain([0, MAX_SAVED_SORTS]) {
+            nav.set_path(dir(i));
+            assert!(!nav.change_sort(by_name));
+        }
+
+        // The recently sorted d0 is kept, the least recently sorted d1 is forgotten.
+        assert_eq!(nav.saved_sorts().len(), MAX_SAVED_SORTS);
+        assert!(nav.saved_sorts().contains_key(&dir(0)));
+        assert!(!nav.saved_sorts().contains_key(&dir(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_tree_expand_and_collapse() -> Result<(), Box<dyn error::Error>> {
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+        let sub = base_path.join("sub");
+
+        fs::create_dir(&sub)?;
+        File::create(sub.join("inner.txt"))?;
+        File::create(base_path.join("top.txt"))?;
+
+        let entries = fm::browse_dir(&base_path)?;
+        let mut nav = NavState::new(base_path.clone());
+        nav.update_from_worker(base_path.clone(), Arc::from(entries), None, None);
+
+        let sub_pos = nav
+            .shown_entries()
+            .position(|e| e.name_str() == "sub")
+            .ok_or("sub should be listed")?;
+        nav.selected = sub_pos;
+
+        assert!(!nav.expand_selected(), "Tree view is disabled");
+        assert!(nav.toggle_tree());
+        assert!(nav.expand_selected());
+        assert!(nav.is_selected_expanded());
+
+        let requests = nav.take_tree_requests();
+        assert_eq!(requests.len(), 1);
+        let (path, request_id) = requests[0].clone();
+        assert_eq!(path, sub);
+        assert!(nav.take_tree_requests().is_empty(), "Request is pending");
+
+        let children = fm::browse_dir(&sub)?;
+        assert!(!nav.update_tree_children(
+            path.clone(),
+            Arc::from(children.clone()),
+            None,
+            Some(request_id + 1)
+        ));
+        assert!(nav.update_tree_children(path, Arc::from(children), None, Some(request_id)));
+
+        assert_eq!(nav.shown_entries_len(), 3);
+        assert_eq!(nav.selected_path(), Some(sub.clone()));
+
+        let inner_idx = nav
+            .shown_entries()
+            .position(|e| e.name_str() == "inner.txt")
+            .ok_or("inner.txt should be shown")?;
+        assert_eq!(
+            nav.tree_node(nav.shown_indices()[inner_idx])
+                .map(|n| n.depth()),
+            Some(1)
+        );
+
+        nav.selected = inner_idx;
+        assert_eq!(nav.selected_path(), Some(sub.join("inner.txt")));
+        assert_eq!(nav.selected_dir(), sub.as_path());
+
+        nav.toggle_marker(&mut None);
+        assert_eq!(nav.get_action_targets().len(), 1);
+        assert!(nav.markers().contains(&sub.join("inner.txt")));
+
+        assert!(nav.collapse_selected());
+        assert_eq!(nav.shown_entries_len(), 2);
+        assert_eq!(nav.selected_path(), Some(sub));
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_flatten_view_streams_and_resets() -> Result<(), Box<dyn error::Error>> {
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+        let nested = PathBuf::from("sub").join("deep.log");
+
+        let mut nav = NavState::new(base_path.clone());
+        nav.update_from_worker(base_path.clone(), Arc::from(Vec::new()), None, None);
+
+        assert!(nav.begin_flatten_request().is_none(), "Flatten view is off");
+        assert!(nav.toggle_flatten());
+        let (path, request_id, cancel) = nav
+            .begin_flatten_request()
+            .ok_or("Flatten walk should be requested")?;
+        assert_eq!(path, base_path);
+
+        let first = vec![FileEntry::new(nested.clone().into_os_string(), 0, None)];
+        assert!(!nav.append_flatten_batch(&path, first.clone(), request_id + 1));
+        assert!(nav.append_flatten_batch(&path, first, request_id));
+
+        let second = vec![FileEntry::new(OsString::from("top.log"), 0, None)];
+        assert!(nav.append_flatten_batch(&path, second, request_id));
+        assert_eq!(nav.shown_entries_len(), 2);
+
+        nav.selected = 0;
+        assert_eq!(nav.selected_path(), Some(base_path.join(&nested)));
+        assert_eq!(nav.selected_root_name(), Some(OsString::from("sub")));
+
+        let sorted: Arc<[FileEntry]> = Arc::from(vec![
+            FileEntry::new(OsString::from("top.log"), 0, None),
+            FileEntry::new(nested.clone().into_os_string(), 0, None),
+        ]);
+        assert!(nav.finish_flatten(&path, sorted, None, true, request_id));
+        assert!(nav.flatten_truncated());
+        assert_eq!(nav.selected_path(), Some(base_path.join(&nested)));
+
+        let (_, _, next_cancel) = nav
+            .begin_flatten_request()
+            .ok_or("Flatten walk should be requested")?;
+        assert!(!cancel.load(Ordering::Acquire));
+        assert!(!next_cancel.load(Ordering::Acquire));
+
+        nav.set_path(base_path.join("sub"));
+        assert!(
+            next_cancel.load(Ordering::Acquire),
+            "Leaving the dir cancels the walk"
+        );
+        assert!(!nav.flatten_active());
+        Ok(())
+    }
+
+    #[test]
+    fn navstate_visual_selection() -> Result<(), Box<dyn error::Error>> {
+        let dir = tempdir()?;
+        let base_path = dir.path().to_path_buf();
+
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+            fs::write(base_path.join(name), "")?;
+        }
+
+        let mut entries = fm::browse_dir(&base_path)?;
+        entries.sort_by(|a, b| a.name_str().cmp(b.name_str()));
+        let mut nav = NavState::new(base_path.clone());
+        nav.update_from_worker(base_path.clone(), Arc::from(entries), None, None);
+
+        let mut clipboard: Option<HashSet<PathBuf>> = None;
+        nav.move_down();
+        nav.toggle_marker(&mut clipboard);
+        nav.move_down();
+
+        assert!(nav.toggle_visual());
+        nav.move_down();
+        nav.sync_visual();
+        let targets = nav.get_action_targets();
+        assert_eq!(targets.len(), 3);
+        assert!(targets.contains(&base_path.join("d.txt")));
+
+        // Swapping the ends and moving past the anchor shrinks the range on the other side.
+        nav.swap_visual_ends();
+        assert_eq!(nav.selected_entry().map(|e| e.name_str()), Some("c.txt"));
+        nav.move_up();
+        nav.move_up();
+        nav.sync_visual();
+        let targets = nav.get_action_targets();
+        assert_eq!(targets.len(), 4);
+
+        // Cancelling keeps the marker which existed before the selection.
+        nav.cancel_visual();
+        assert!(!nav.visual_active());
+        assert_eq!(nav.markers().len(), 1);
+        assert!(nav.markers().contains(&base_path.join("b.txt")));
+
+        assert!(nav.toggle_visual());
+        nav.move_down();
+        nav.sync_visual();
+        assert!(!nav.toggle_visual());
+        assert_eq!(nav.markers().len(), 2);
+        Ok(())
+    }
 }
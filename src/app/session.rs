@@ -0,0 +1,242 @@
+//! Session persistence for runa.
+//!
+//! On quit the open tabs, their directories and focused entries, the saved per-directory
+//! filters, positions and sorts, the sort config and the clipboard are written to the session file.
+//! The session is restored on start if `general.startup.restore_session` is enabled or
+//! runa is started with `rn --restore`.
+//!
+//! The file is written to a temporary file first and then renamed over the old session,
+//! so a crash while saving never leaves a half written session behind.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::{AppContainer, AppState, Clipboard};
+use crate::config::Config;
+use crate::core::{sort::SortConfig, workers::Workers};
+use crate::utils::os;
+
+/// Saved state of a single tab.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct TabSession {
+    dir: PathBuf,
+    focus: Option<String>,
+    sort: SortConfig,
+    positions: Vec<(PathBuf, String)>,
+    filters: Vec<(PathBuf, String)>,
+    sorts: Vec<(PathBuf, SortConfig)>,
+}
+
+/// Saved clipboard contents.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct ClipboardSession {
+    entries: Vec<PathBuf>,
+    is_cut: bool,
+}
+
+/// The saved session of runa.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct Session {
+    active: usize,
+    tabs: Vec<TabSession>,
+    clipboard: ClipboardSession,
+}
+
+impl TabSession {
+    fn capture(app: &AppState) -> Self {
+        let nav = app.nav();
+        // Paths which are not valid UTF-8 can't be stored in toml and are skipped.
+        let positions = nav
+            .get_position()
+            .iter()
+            .filter(|(dir, _)| dir.to_str().is_some())
+            .filter_map(|(dir, name)| Some((dir.clone(), name.to_str()?.to_string())))
+            .collect();
+        let filters = nav
+            .saved_filters()
+            .iter()
+            .filter(|(dir, _)| dir.to_str().is_some())
+            .map(|(dir, filter)| (dir.clone(), filter.clone()))
+            .collect();
+        let sorts = nav
+            .saved_sorts()
+            .iter()
+            .filter(|(dir, _)| dir.to_str().is_some())
+            .map(|(dir, sort)| (dir.clone(), *sort))
+            .collect();
+
+        Self {
+            dir: nav.current_dir().to_path_buf(),
+            focus: nav
+                .selected_root_name()
+                .and_then(|name| name.into_string().ok()),
+            sort: nav.sort_config(),
+            positions,
+            filters,
+            sorts,
+        }
+    }
+
+    /// Creates the tab, restoring the saved navigation state.
+    /// Returns None if the directory no longer exists.
+    fn restore(self, config: &Arc<Config>, workers: &Workers, tab_id: usize) -> Option<AppState> {
+        if !self.dir.is_dir() {
+            return None;
+        }
+        let mut app = AppState::from_dir(Arc::clone(config), &self.dir).ok()?;
+
+        let positions: HashMap<PathBuf, OsString> = self
+            .positions
+            .into_iter()
+            .map(|(dir, name)| (dir, OsString::from(name)))
+            .collect();
+        let filters: HashMap<PathBuf, String> = self.filters.into_iter().collect();
+        let sorts: HashMap<PathBuf, SortConfig> = self.sorts.into_iter().collect();
+
+        app.restore_session(self.sort, positions, filters, sorts);
+        app.set_tab_id(tab_id);
+        app.initialize(workers, self.focus.map(OsString::from));
+        Some(app)
+    }
+}
+
+impl Session {
+    /// Captures the session of all tabs and the clipboard.
+    pub(crate) fn capture(container: &AppContainer, clipboard: &Clipboard) -> Self {
+        let (tabs, active) = match container {
+            AppContainer::Single(app) => (vec![TabSession::capture(app)], 0),
+            AppContainer::Tabs(tabs) => (
+                tabs.tabs.iter().map(TabSession::capture).collect(),
+                tabs.current,
+            ),
+        };
+
+        let mut entries: Vec<PathBuf> = clipboard
+            .entries
+            .iter()
+            .flatten()
+            .filter(|p| p.to_str().is_some())
+            .cloned()
+            .collect();
+        entries.sort();
+
+        Self {
+            active,
+            tabs,
+            clipboard: ClipboardSession {
+                entries,
+                is_cut: clipboard.is_cut,
+            },
+        }
+    }
+
+    /// Loads the session file.
+    /// Returns None if there is no session or it can't be parsed.
+    pub(crate) fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        toml::from_str(&content).ok()
+    }
+
+    /// Writes the session file atomically.
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        os::write_atomic(path, &content)
+    }
+
+    /// Restores the tabs of the session.
+    /// Tabs whose directory no longer exists are dropped. Returns None if no tab is left.
+    pub(crate) fn restore(
+        self,
+        config: &Arc<Config>,
+        workers: &Workers,
+    ) -> Option<(AppContainer, Clipboard)> {
+        let mut tabs = Vec::with_capacity(self.tabs.len());
+        let mut active = 0;
+        for (idx, tab) in self.tabs.into_iter().take(9).enumerate() {
+            if let Some(app) = tab.restore(config, workers, tabs.len()) {
+                if idx <= self.active {
+                    active = tabs.len();
+                }
+                tabs.push(app);
+            }
+        }
+
+        let container = match tabs.len() {
+            0 => return None,
+            1 => AppContainer::Single(Box::new(tabs.pop()?)),
+            _ => {
+                let mut container = AppContainer::create_tabs(tabs);
+                if let AppContainer::Tabs(manager) = &mut container {
+                    manager.current = active;
+                    manager.sync_tab_line();
+                }
+                container
+            }
+        };
+
+        let entries: HashSet<PathBuf> = self
+            .clipboard
+            .entries
+            .into_iter()
+            .filter(|p| p.exists())
+            .collect();
+        let clipboard = Clipboard {
+            is_cut: self.clipboard.is_cut && !entries.is_empty(),
+            entries: (!entries.is_empty()).then_some(entries),
+        };
+
+        Some((container, clipboard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::sort::{SortMode, SortOrder};
+    use tempfile::tempdir;
+
+    #[test]
+    fn session_roundtrip_is_atomic() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("state/session.toml");
+
+        let session = Session {
+            active: 1,
+            tabs: vec![
+                TabSession {
+                    dir: dir.path().to_path_buf(),
+                    focus: Some("notes.txt".into()),
+                    sort: SortConfig::from((SortMode::Size, SortOrder::Descending)),
+                    positions: vec![(dir.path().join("sub"), "a.txt".into())],
+                    filters: vec![(dir.path().to_path_buf(), "ext:rs".into())],
+                    sorts: vec![(
+                        dir.path().join("sub"),
+                        SortConfig::from((SortMode::Modified, SortOrder::Descending)),
+                    )],
+                },
+                TabSession::default(),
+            ],
+            clipboard: ClipboardSession {
+                entries: vec![dir.path().join("notes.txt")],
+                is_cut: true,
+            },
+        };
+
+        session.save(&path)?;
+        assert!(!path.with_extension("toml.tmp").exists());
+        assert_eq!(Session::load(&path), Some(session));
+
+        fs::write(&path, "tabs = [")?;
+        assert_eq!(Session::load(&path), None);
+        Ok(())
+    }
+}
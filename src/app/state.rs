@@ -3,27 +3,36 @@
 //! This module defines the overall [AppState] struct, which holds all major application
 //! information and passes it to relevant UI/Terminal functions
 
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::text::Span;
 
 use crate::app::{
     Clipboard, NavState, ParentState, PreviewState,
     actions::{ActionContext, ActionMode, InputMode},
-    keymap::{Action, Keymap, TabAction},
+    details::DetailsState,
+    dir_sizes::DirSizeState,
+    history::HistoryKind,
+    keymap::{Action, Binding, KeyNode, Keymap, PaneAction, SystemAction, TabAction},
+    layout::{LayoutState, PaneLayout},
+    macros::MacroCommand,
     metadata::MetadataState,
+    mouse::{ListRegion, MouseRegions, MouseState},
+    status::StatusState,
 };
 use crate::config::Config;
-use crate::config::display::PreviewMethod;
+use crate::config::display::{PreviewMethod, StatusPosition};
 use crate::core::{
-    cache::DirListOptions,
+    cache::{DirListOptions, ListOption, ListOverrides},
     metadata::{FileMetadataCache, MetadataNeeds},
-    sort::SortConfig,
+    sort::{SortConfig, SortMode},
+    status::StatusRequest,
     workers::{PreviewMode, WorkerResponse, WorkerTask, Workers},
 };
 
@@ -41,7 +50,13 @@ pub(crate) enum KeypressResult {
     Recovered,
     UiReload,
     Tab(TabAction),
+    Pane(PaneAction),
     Sort(SortConfig),
+    Macro(MacroCommand),
+    /// An input accepted in a prompt with a history.
+    History(HistoryKind, String),
+    /// A theme to show in all tabs, None goes back to the theme of the config.
+    Theme(Option<String>),
 }
 
 /// Enumeration which holds the metrics of the layout of the TUI
@@ -75,6 +90,11 @@ pub(crate) struct AppState {
     pub(super) keymap: Keymap,
 
     pub(super) metrics: LayoutMetrics,
+    pub(super) layout: LayoutState,
+    pub(super) list_overrides: ListOverrides,
+    pub(super) details: DetailsState,
+    pub(super) dir_sizes: DirSizeState,
+    pub(super) status: StatusState,
 
     pub(super) nav: NavState,
     pub(super) actions: ActionContext,
@@ -93,6 +113,9 @@ pub(crate) struct AppState {
 
     pub(super) overlays: OverlayStack,
 
+    pub(super) mouse: MouseState,
+    pub(super) macro_recording: Option<char>,
+
     pub(super) tab_id: Option<usize>,
     pub(super) tab_line: Arc<Vec<Span<'static>>>,
 }
@@ -106,6 +129,13 @@ impl AppState {
     pub(crate) fn new_current_dir(&self) -> std::io::Result<Self> {
         let mut app = Self::from_dir(Arc::clone(&self.config), self.nav.current_dir())?;
         app.nav.set_sort_config(self.nav.sort_config());
+        app.list_overrides = self.list_overrides;
+        app.details = DetailsState::new(self.details.enabled());
+        app.nav.restore_saved(
+            HashMap::new(),
+            HashMap::new(),
+            self.nav.saved_sorts().clone(),
+        );
         Ok(app)
     }
 
@@ -116,11 +146,19 @@ impl AppState {
             std::env::current_dir()?
         };
 
+        let mut nav = NavState::new(current_dir);
+        nav.set_dir_rules(Arc::clone(config.dir_rules()));
+
         let app = Self {
             keymap: Keymap::from_config(config.as_ref()),
+            layout: LayoutState::new(config.display()),
+            details: DetailsState::new(config.display().details().enabled()),
+            dir_sizes: DirSizeState::default(),
+            status: StatusState::default(),
             config,
             metrics: LayoutMetrics::default(),
-            nav: NavState::new(current_dir),
+            list_overrides: ListOverrides::default(),
+            nav,
             actions: ActionContext::default(),
             preview: PreviewState::default(),
             parent: ParentState::default(),
@@ -131,6 +169,8 @@ impl AppState {
             preview_request_time: Throttler::new(),
             worker_time: None,
             overlays: OverlayStack::new(),
+            mouse: MouseState::default(),
+            macro_recording: None,
             tab_line: Arc::new(Vec::new()),
             tab_id: None,
         };
@@ -138,9 +178,18 @@ impl AppState {
         Ok(app)
     }
 
+    /// Swaps the config without resetting the keymap or the runtime layout.
+    /// Used when only the theme of the config changed.
+    pub(crate) fn apply_theme_config(&mut self, config: Arc<Config>) {
+        self.config = config;
+    }
+
     pub(crate) fn apply_new_config(&mut self, config: Arc<Config>) {
         self.config = config;
         self.keymap = Keymap::from_config(self.config.as_ref());
+        self.nav.set_dir_rules(Arc::clone(self.config.dir_rules()));
+        self.layout = LayoutState::new(self.config.display());
+        self.details = DetailsState::new(self.config.display().details().enabled());
     }
 
     /// Initializes the AppState by requesting the initial directory load and parent content.
@@ -155,8 +204,40 @@ impl AppState {
         preview: &PreviewState,
         parent: &ParentState,
         is_loading: bool,
+        layout: &LayoutState,
+        details: &DetailsState,
+        dir_sizes: &DirSizeState,
+        status: &StatusState,
+        keymap: &Keymap,
         worker_time: &Option<Instant>,
         overlays: &OverlayStack,
+        macro_recording: Option<char>,
+    }
+
+    /// Resolves the panes of a view with the given size.
+    pub(crate) fn pane_layout(&self, width: u16, height: u16) -> PaneLayout {
+        self.layout.resolve(self.config.display(), width, height)
+    }
+
+    /// Applies the sort config and saved navigation state of a restored session.
+    pub(crate) fn restore_session(
+        &mut self,
+        sort: SortConfig,
+        positions: HashMap<PathBuf, OsString>,
+        filters: HashMap<PathBuf, String>,
+        sorts: HashMap<PathBuf, SortConfig>,
+    ) {
+        self.nav.set_sort_config(sort);
+        self.nav.restore_saved(positions, filters, sorts);
+    }
+
+    pub(crate) fn set_macro_recording(&mut self, register: Option<char>) {
+        self.macro_recording = register;
+    }
+
+    /// Returns true if a directory or tree listing of this tab is still loading.
+    pub(crate) fn is_busy(&self) -> bool {
+        self.is_loading || self.nav.tree_loading()
     }
 
     #[inline]
@@ -169,6 +250,12 @@ impl AppState {
         &mut self.overlays
     }
 
+    /// Stores the clickable regions of the frame that was just rendered.
+    #[inline]
+    pub(crate) fn set_mouse_regions(&mut self, regions: MouseRegions) {
+        self.mouse.set_regions(regions);
+    }
+
     #[inline]
     pub(crate) fn tab_line(&self) -> &Arc<Vec<Span<'_>>> {
         &self.tab_line
@@ -218,6 +305,117 @@ impl AppState {
         }
     }
 
+    /// Requests the detail columns of the main pane rows shown in the region,
+    /// if the details view is enabled. Rows which are loaded or pending are skipped.
+    pub(crate) fn request_visible_details(&mut self, workers: &Workers, region: &ListRegion) {
+        let columns = self.config.display().details().columns();
+        if !self.details.enabled() || columns.is_empty() {
+            return;
+        }
+
+        let shown = self.nav.shown_indices();
+        let rows = region.rows();
+        let visible = &shown[rows.start.min(shown.len())..rows.end.min(shown.len())];
+        let keys: Vec<_> = visible
+            .iter()
+            .filter_map(|&idx| self.nav.row_key(idx))
+            .collect();
+
+        let path = self.nav.current_dir();
+        if let Some((request_id, names)) = self
+            .details
+            .take_missing(path, keys.iter().map(AsRef::as_ref))
+        {
+            let _ = workers.details_tx().send(WorkerTask::LoadDetails {
+                path: path.to_path_buf(),
+                names,
+                columns: Arc::from(columns),
+                date_format: Arc::from(self.config.display().sort_date_format()),
+                request_id,
+                tab_id: self.tab_id(),
+            });
+        }
+    }
+
+    /// Requests the recursive sizes of the subdirectories of the current directory,
+    /// if `general.dir_sizes` is enabled.
+    ///
+    /// Sizes found in the cache are used right away and a calculation still running
+    /// for a previous listing is cancelled.
+    pub(crate) fn request_dir_sizes(&mut self, workers: &Workers) {
+        let dir_size_cfg = self.config.general().dir_sizes();
+        if !dir_size_cfg.enabled() {
+            return;
+        }
+
+        let dir = self.nav.current_dir();
+        self.dir_sizes.set_dir(dir);
+        workers
+            .dir_sizes()
+            .set_cross_fs(dir_size_cfg.cross_filesystems());
+
+        let mut missing = Vec::new();
+        for (idx, entry) in self.nav.entries().iter().enumerate() {
+            let nested = self.nav.tree_node(idx).is_some_and(|n| n.depth() > 0);
+            if !entry.is_dir() || entry.is_symlink() || nested {
+                continue;
+            }
+            match workers.dir_sizes().get(&dir.join(entry.name())) {
+                Some(size) => self.dir_sizes.set_size(entry.name().to_os_string(), size),
+                None => missing.push(entry.name().to_os_string()),
+            }
+        }
+
+        if missing.is_empty() {
+            self.dir_sizes.cancel();
+            return;
+        }
+
+        let (request_id, cancel) = self.dir_sizes.begin_request();
+        let _ = workers.dir_size_tx().send(WorkerTask::CalculateDirSizes {
+            path: dir.to_path_buf(),
+            names: missing,
+            cross_fs: dir_size_cfg.cross_filesystems(),
+            cancel,
+            request_id,
+            tab_id: self.tab_id(),
+        });
+    }
+
+    /// Returns the formatted recursive size of the selected directory, if it is known.
+    pub(crate) fn selected_dir_size(&self) -> Option<&str> {
+        let entry = self.nav.selected_entry()?;
+        if !entry.is_dir() || self.nav.selected_dir() != self.nav.current_dir() {
+            return None;
+        }
+        self.dir_sizes.size_of(entry.name())
+    }
+
+    /// Returns the status segments of the current directory for the status worker.
+    pub(crate) fn status_request(&mut self) -> StatusRequest {
+        let status = self.config.display().status();
+        let shown = |position| position != StatusPosition::None;
+        let marked = shown(status.marked_size()).then(|| {
+            self.status
+                .marked(self.nav.markers_generation(), self.nav.markers())
+        });
+
+        StatusRequest {
+            dir: self.nav.current_dir().to_path_buf(),
+            git: shown(status.git()),
+            disk: shown(status.disk()),
+            commands: status
+                .command()
+                .iter()
+                .filter(|cmd| shown(cmd.position()))
+                .map(|cmd| cmd.command().to_string())
+                .collect(),
+            marked,
+            refresh: status.refresh(),
+            tab_id: self.tab_id(),
+        }
+    }
+
     pub(crate) fn update_file_info_cache(&mut self, workers: &Workers) {
         let status_info = self.config.display().info().status_bar();
         let info_overlay = self.overlays().is_open(OverlayKind::ShowInfo);
@@ -229,18 +427,19 @@ impl AppState {
             return;
         }
 
-        let Some(entry) = self.nav.selected_entry() else {
+        let Some(path) = self.nav.selected_path() else {
             self.metadata.clear();
             return;
         };
 
         if let Some(selected_cache) = self.metadata.selected_arc()
-            && entry.name() == selected_cache.name()
+            && path
+                .file_name()
+                .is_some_and(|name| name == selected_cache.name())
         {
             return;
         }
 
-        let path = self.nav.current_dir().join(entry.name());
         if self.metadata.is_pending_path(&path) {
             return;
         }
@@ -278,6 +477,12 @@ impl AppState {
     pub(crate) fn tick(&mut self, workers: &Workers) -> bool {
         let mut changed = false;
 
+        let status = self.config.display().status();
+        if status.clock() != StatusPosition::None && self.status.update_clock(status.clock_format())
+        {
+            changed = true;
+        }
+
         if let Some(expiry) = self.notification_time
             && Instant::now() >= expiry
         {
@@ -299,10 +504,8 @@ impl AppState {
             changed = true;
         }
 
-        let prefix_recognizer = self.actions.prefix_recognizer_mut();
-        if prefix_recognizer.is_g_state() && prefix_recognizer.expired() {
-            prefix_recognizer.cancel();
-            self.hide_prefix_help();
+        self.request_filter_meta(workers);
+        if self.request_tree_children(workers) {
             changed = true;
         }
 
@@ -344,19 +547,30 @@ impl AppState {
                 if request_id == self.nav.request_id() && path == self.nav.current_dir() {
                     self.nav
                         .update_from_worker(path, entries, sort_column, focus);
+                    self.details.invalidate();
                     self.is_loading = false;
 
+                    self.request_dir_sizes(workers);
+
                     self.request_parent_content(workers);
                     self.request_preview_force(workers);
                     self.update_file_info_cache(workers);
                     self.refresh_show_info_if_open();
                     return;
                 }
+                // TREE CHECK: Children of an expanded directory in tree mode
+                if self.nav.is_tree_pending(&path, request_id) {
+                    self.nav.update_tree_children(
+                        path.clone(),
+                        entries.clone(),
+                        sort_column.clone(),
+                        Some(request_id),
+                    );
+                    self.preview.mark_pending();
+                }
                 // PREVIEW CHECK: Must match the current preview request
                 if request_id == self.preview.request_id()
-                    && let Some(entry) = self.nav.selected_entry()
-                    && path.parent() == Some(self.nav.current_dir())
-                    && path.file_name() == Some(entry.name())
+                    && self.nav.selected_path().as_deref() == Some(path.as_path())
                 {
                     self.preview
                         .update_from_entries(entries.clone(), sort_column, request_id);
@@ -418,8 +632,10 @@ impl AppState {
 
                     for path in &invalidation_paths {
                         workers.cache().invalidate_path(path);
+                        workers.dir_sizes().invalidate(path);
                         self.parent.invalidate_if_path(path);
                     }
+                    self.nav.invalidate_tree_dirs(&invalidation_paths);
 
                     let should_refresh_preview = self.preview.current_path().is_some_and(|path| {
                         invalidation_paths
@@ -446,10 +662,7 @@ impl AppState {
                 tab_id: _tab_id,
             } => {
                 if self.metadata.matches_pending(request_id, &path) {
-                    if path.parent() == Some(self.nav.current_dir())
-                        && let Some(sel) = self.nav.selected_entry()
-                        && path.file_name() == Some(sel.name())
-                    {
+                    if self.nav.selected_path().as_deref() == Some(path.as_path()) {
                         self.metadata.set_selected(Some(metadata));
                         self.refresh_show_info_if_open();
                     }
@@ -457,6 +670,89 @@ impl AppState {
                 }
             }
 
+            WorkerResponse::FilterMetaLoaded {
+                path,
+                meta,
+                request_id,
+                tab_id: _tab_id,
+            } => {
+                if self.nav.update_filter_meta(path, meta, request_id) {
+                    if let Some((mode, count)) = self.nav.apply_pending_mark() {
+                        self.push_marked_message(mode, count);
+                    }
+                    self.preview.mark_pending();
+                    self.update_file_info_cache(workers);
+                }
+            }
+
+            WorkerResponse::DetailsLoaded {
+                path,
+                rows,
+                request_id,
+                tab_id: _tab_id,
+            } => {
+                self.details.apply(&path, rows, request_id);
+            }
+
+            WorkerResponse::DirSizeLoaded {
+                name,
+                size,
+                done,
+                request_id,
+                tab_id: _tab_id,
+            } => {
+                if self.dir_sizes.is_current(request_id) {
+                    self.dir_sizes.set_size(name, size);
+                    if done {
+                        self.dir_sizes.finish();
+                        // The listing was sorted before the sizes were known.
+                        let (sort, _) = self.view_for(self.nav.current_dir());
+                        if sort.mode() == SortMode::Size {
+                            let focus = self.nav.selected_entry().map(|e| e.name().to_os_string());
+                            self.request_dir_sort(workers, focus);
+                        }
+                    }
+                }
+            }
+
+            WorkerResponse::FlattenBatch {
+                path,
+                entries,
+                request_id,
+                tab_id: _tab_id,
+            } => {
+                if self.nav.append_flatten_batch(&path, entries, request_id) {
+                    self.preview.mark_pending();
+                }
+            }
+
+            WorkerResponse::FlattenLoaded {
+                path,
+                entries,
+                sort_column,
+                truncated,
+                request_id,
+                tab_id: _tab_id,
+            } => {
+                if self
+                    .nav
+                    .finish_flatten(&path, entries, sort_column, truncated, request_id)
+                {
+                    self.is_loading = false;
+                    if truncated {
+                        let max = self.config.general().max_flatten_entries();
+                        self.push_overlay_message(
+                            format!("Flatten view limited to {} entries", max),
+                            Duration::from_secs(3),
+                            None,
+                        );
+                    }
+                    self.request_preview_force(workers);
+                    self.update_file_info_cache(workers);
+                    self.refresh_show_info_if_open();
+                }
+            }
+
             WorkerResponse::FindResults {
                 base_dir,
                 results,
@@ -482,6 +778,10 @@ impl AppState {
                 }
             }
 
+            WorkerResponse::StatusLoaded { info, tab_id: _ } => {
+                self.status.set_info(info);
+            }
+
             WorkerResponse::ConfigChanged => {}
         }
     }
@@ -494,45 +794,167 @@ impl AppState {
         key: KeyEvent,
         workers: &Workers,
         clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        let result = self.dispatch_keypress(key, workers, clipboard);
+        // The visual selection follows every cursor movement.
+        self.nav.sync_visual();
+        result
+    }
+
+    fn dispatch_keypress(
+        &mut self,
+        key: KeyEvent,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
     ) -> KeypressResult {
         if self.actions.is_input_mode() {
             return self.handle_input_mode(workers, key);
         }
+        // The counted range of an action that did not run ends with the next key.
+        self.nav.clear_count_targets();
 
         if let Some(res) = self.handle_esc_close_overlays(&key) {
             return res;
         }
 
-        if let Some(res) = self.handle_prefix_dispatch(workers, &key) {
+        if let Some(res) = self.handle_keybind_help_dispatch(workers, &key, clipboard) {
             return res;
         }
 
-        if let Some(action) = self.keymap.lookup(key) {
-            match action {
-                Action::System(sys_act) => return self.handle_sys_action(sys_act),
-                Action::Nav(nav_act) => return self.handle_nav_action(workers, nav_act, clipboard),
-                Action::File(file_act) => {
-                    return self.handle_file_action(workers, file_act, clipboard);
-                }
-                Action::Tab(tab_act) => return KeypressResult::Tab(tab_act),
+        if let Some(res) = self.handle_theme_picker_dispatch(&key) {
+            return res;
+        }
+
+        if let Some(res) = self.handle_count_dispatch(&key) {
+            return res;
+        }
+
+        if let Some(res) = self.handle_visual_dispatch(&key) {
+            return res;
+        }
+
+        if let Some(res) = self.handle_prefix_dispatch(workers, &key, clipboard) {
+            return res;
+        }
+
+        if let Some(res) = self.handle_zoom_dispatch(&key) {
+            return res;
+        }
+
+        // A pending count applies to the next action only.
+        let count = self.actions.take_count();
+
+        match self.keymap.lookup(key) {
+            Some(action) => {
+                let action = self.pane_switch_fallback(action, &key);
+                self.dispatch_action(workers, action, count, clipboard)
             }
+            None => KeypressResult::Continue,
         }
+    }
+
+    /// Outside the dual pane layout the pane switch keys switch tabs instead,
+    /// `<s-tab>` to the previous one.
+    pub(in crate::app) fn pane_switch_fallback(&self, action: Action, key: &KeyEvent) -> Action {
+        if action != Action::Pane(PaneAction::Switch) || self.config.display().is_dual() {
+            return action;
+        }
+        if key.code == KeyCode::BackTab {
+            Action::Tab(TabAction::Prev)
+        } else {
+            Action::Tab(TabAction::Next)
+        }
+    }
+
+    /// Runs a bound action with the count typed before its keys.
+    pub(in crate::app) fn dispatch_action(
+        &mut self,
+        workers: &Workers,
+        action: Action,
+        count: Option<usize>,
+        clipboard: &mut Clipboard,
+    ) -> KeypressResult {
+        match (action, count) {
+            (Action::System(SystemAction::PlayMacro), Some(n)) => {
+                KeypressResult::Macro(MacroCommand::Play { count: Some(n) })
+            }
+            (Action::System(sys_act), _) => self.handle_sys_action(workers, sys_act),
+            (Action::Nav(nav_act), Some(n)) => {
+                self.handle_nav_count(workers, nav_act, n, clipboard)
+            }
+            (Action::Nav(nav_act), None) => self.handle_nav_action(workers, nav_act, clipboard),
+            (Action::File(file_act), Some(n)) => {
+                self.handle_file_count(workers, file_act, n, clipboard)
+            }
+            (Action::File(file_act), None) => self.handle_file_action(workers, file_act, clipboard),
+            // `3<c-n>` switches to the third tab, like `3gt` in vim.
+            (Action::Tab(TabAction::Next | TabAction::Prev), Some(n)) => {
+                let idx = u8::try_from(n).unwrap_or(u8::MAX);
+                KeypressResult::Tab(TabAction::Switch(idx))
+            }
+            (Action::Tab(tab_act), _) => KeypressResult::Tab(tab_act),
+            // `3<f5>` copies the count entries from the selection to the other pane.
+            (Action::Pane(act @ (PaneAction::CopyTo | PaneAction::MoveTo)), Some(n)) => {
+                self.nav.target_range(n);
+                KeypressResult::Pane(act)
+            }
+            (Action::Pane(pane_act), _) => KeypressResult::Pane(pane_act),
+            (Action::Layout(layout_act), count) => {
+                self.handle_layout_action(layout_act, count.unwrap_or(1))
+            }
+            (Action::Sort(mode), _) => self.handle_sort_action(workers, mode),
+            (Action::Macro(idx), count) => match self.keymap.macro_keys(idx) {
+                Some(keys) => KeypressResult::Macro(MacroCommand::Keys { keys, count }),
+                None => KeypressResult::Continue,
+            },
+        }
+    }
 
-        KeypressResult::Continue
+    /// Ends a key sequence which timed out and runs the action bound to the keys typed so far.
+    /// Returns None if no sequence timed out.
+    pub(crate) fn resolve_expired_keys(
+        &mut self,
+        workers: &Workers,
+        clipboard: &mut Clipboard,
+    ) -> Option<KeypressResult> {
+        let prefix = self.actions.prefix_recognizer_mut();
+        if !prefix.expired() {
+            return None;
+        }
+        let action = self
+            .keymap
+            .root()
+            .find(prefix.pending())
+            .and_then(KeyNode::binding)
+            .map(Binding::action);
+        prefix.cancel();
+        self.hide_prefix_help();
+
+        let count = self.actions.take_count();
+        let result = match action {
+            Some(action) => self.dispatch_action(workers, action, count, clipboard),
+            None => KeypressResult::Consumed,
+        };
+        self.nav.sync_visual();
+        Some(result)
     }
 
     // Worker requests functions for directory loading, preview and parent pane content
 
     /// Requests a directory load for the current navigation directory
     pub(crate) fn request_dir_load(&mut self, workers: &Workers, focus: Option<OsString>) {
+        if self.nav.flatten_active() {
+            self.request_flatten(workers);
+            return;
+        }
         self.is_loading = true;
         let request_id = self.nav.prepare_new_request();
-        let sort_config = self.nav.sort_config();
+        let (sort_config, list) = self.view_for(self.nav.current_dir());
         let sort_date_format: Arc<str> = Arc::from(self.config.display().sort_date_format());
         let _ = workers.nav_io_tx().try_send(WorkerTask::LoadDirectory {
             path: self.nav.current_dir().to_path_buf(),
             focus,
-            list: self.dir_list_options(),
+            list,
             sort_config,
             sort_date_format,
             always_show: Arc::clone(self.config.general().always_show()),
@@ -541,6 +963,83 @@ impl AppState {
         });
     }
 
+    /// Requests a new recursive walk of the current directory for the flatten view.
+    pub(crate) fn request_flatten(&mut self, workers: &Workers) {
+        let Some((path, request_id, cancel)) = self.nav.begin_flatten_request() else {
+            return;
+        };
+        self.is_loading = true;
+        let (sort_config, list) = self.view_for(&path);
+
+        let _ = workers.flatten_tx().send(WorkerTask::FlattenDirectory {
+            path,
+            list,
+            sort_config,
+            sort_date_format: Arc::from(self.config.display().sort_date_format()),
+            always_show: Arc::clone(self.config.general().always_show()),
+            max_entries: self.config.general().max_flatten_entries(),
+            cancel,
+            request_id,
+            tab_id: self.tab_id(),
+        });
+    }
+
+    /// Requests the metadata needed by the active filter query or a pending pattern mark.
+    pub(crate) fn request_filter_meta(&mut self, workers: &Workers) {
+        if let Some((path, entries, request_id)) = self.nav.take_filter_meta_request() {
+            let _ = workers.filter_meta_tx().send(WorkerTask::LoadFilterMeta {
+                path,
+                entries,
+                request_id,
+                tab_id: self.tab_id(),
+            });
+        }
+    }
+
+    /// Requests the children of expanded tree directories which are not loaded yet.
+    ///
+    /// Children found in the [DirCache](crate::core::cache::DirCache) are used right away.
+    /// Returns true if the tree rows changed.
+    pub(crate) fn request_tree_children(&mut self, workers: &Workers) -> bool {
+        let requests = self.nav.take_tree_requests();
+        if requests.is_empty() {
+            return false;
+        }
+
+        // Nested directories are shown in the listing of the current directory, so they use its view.
+        let (sort_config, list_opts) = self.view_for(self.nav.current_dir());
+        let mut changed = false;
+
+        for (path, request_id) in requests {
+            if let Some(val) = workers.cache().get(&path, sort_config, &list_opts) {
+                let (entries, sort_col, _rid, _ts) = &*val;
+                changed |= self.nav.update_tree_children(
+                    path,
+                    entries.clone(),
+                    sort_col.clone(),
+                    Some(request_id),
+                );
+                continue;
+            }
+
+            let _ = workers.tree_io_tx().send(WorkerTask::LoadDirectory {
+                path,
+                focus: None,
+                list: list_opts.clone(),
+                sort_config,
+                sort_date_format: Arc::from(self.config.display().sort_date_format()),
+                always_show: Arc::clone(self.config.general().always_show()),
+                request_id,
+                tab_id: self.tab_id(),
+            });
+        }
+
+        if changed {
+            self.preview.mark_pending();
+        }
+        changed
+    }
+
     #[inline]
     pub(crate) fn request_preview(&mut self, workers: &Workers) {
         self.do_request_preview(workers, false);
@@ -558,8 +1057,7 @@ impl AppState {
             return;
         };
 
-        let sort_config = self.nav.sort_config();
-        let list_opts = self.dir_list_options();
+        let (sort_config, list_opts) = self.view_for(parent_path);
 
         if let Some(val) = workers.cache().get(parent_path, sort_config, &list_opts) {
             let (entries, sort_col, _rid, _ts) = &*val;
@@ -588,7 +1086,7 @@ impl AppState {
         let _ = workers.parent_io_tx().try_send(WorkerTask::LoadDirectory {
             path: parent_path_buf,
             focus: None,
-            list: self.dir_list_options(),
+            list: list_opts,
             sort_config,
             sort_date_format,
             always_show: Arc::clone(self.config.general().always_show()),
@@ -604,7 +1102,7 @@ impl AppState {
         let request_id = self.actions.find_mut().prepare_new_request();
         let cancel_token = Arc::new(AtomicBool::new(false));
 
-        let show_hidden = self.config.general().show_hidden();
+        let show_hidden = self.view_for(self.nav.current_dir()).1.show_hidden;
 
         self.actions
             .set_cancel_find_token(Arc::clone(&cancel_token));
@@ -621,17 +1119,24 @@ impl AppState {
     }
 
     pub(crate) fn request_dir_sort(&mut self, workers: &Workers, focus: Option<OsString>) {
+        // The flatten view is never sorted by the sort worker, since its result would
+        // end up in the directory cache. The walk sorts its results instead.
+        if self.nav.flatten_active() {
+            self.request_flatten(workers);
+            return;
+        }
         self.is_loading = true;
         let request_id = self.nav.request_id();
-        let sort_config = self.nav.sort_config();
+        let (sort_config, list) = self.view_for(self.nav.current_dir());
         let sort_date_format: Arc<str> = Arc::from(self.config.display().sort_date_format());
         let entries = self.nav.entries_arc();
+        self.nav.invalidate_tree();
 
         let _ = workers.sort_io_tx().try_send(WorkerTask::SortDirectory {
             path: self.nav.current_dir().to_path_buf(),
             entries,
             focus,
-            list: self.dir_list_options(),
+            list,
             sort_config,
             sort_date_format,
             always_show: Arc::clone(self.config.general().always_show()),
@@ -640,20 +1145,57 @@ impl AppState {
         });
     }
 
-    pub(crate) fn dir_list_options(&self) -> DirListOptions {
-        DirListOptions {
-            dirs_first: self.config.general().dirs_first(),
-            show_hidden: self.config.general().show_hidden(),
-            show_symlink: self.config.general().show_symlink(),
-            show_system: self.config.general().show_system(),
-            case_insensitive: self.config.general().case_insensitive(),
-        }
+    /// Returns the sort config and list options of the directory.
+    ///
+    /// Settings of a matching `[[dir_rules]]` and a sort remembered for the directory
+    /// are applied over the tab-wide settings.
+    pub(crate) fn view_for(&self, dir: &Path) -> (SortConfig, DirListOptions) {
+        let general = self.config.general();
+        let view = self.config.dir_rules().view_for(dir);
+        let mut list = DirListOptions {
+            dirs_first: view.dirs_first.unwrap_or(general.dirs_first()),
+            show_hidden: view.show_hidden.unwrap_or(general.show_hidden()),
+            show_symlink: general.show_symlink(),
+            show_system: general.show_system(),
+            case_insensitive: general.case_insensitive(),
+        };
+        self.list_overrides.apply(&mut list);
+        (self.nav.sort_for(dir), list)
+    }
+
+    /// Toggles a listing option of this tab and reloads the panes with it.
+    pub(crate) fn toggle_list_option(
+        &mut self,
+        workers: &Workers,
+        option: ListOption,
+    ) -> KeypressResult {
+        let (_, current) = self.view_for(self.nav.current_dir());
+        let value = self.list_overrides.toggle(option, &current);
+
+        let name = match option {
+            ListOption::DirsFirst => "Directories first",
+            ListOption::ShowHidden => "Hidden files",
+            ListOption::ShowSymlink => "Symlinks",
+            ListOption::ShowSystem => "System files",
+            ListOption::CaseInsensitive => "Case insensitive sort",
+        };
+        let state = if value { "on" } else { "off" };
+        self.push_overlay_message(format!("{name}: {state}"), Duration::from_secs(1), None);
+
+        let focus = self
+            .nav
+            .selected_entry()
+            .map(|entry| entry.name().to_os_string());
+        self.nav.invalidate_tree();
+        self.request_dir_load(workers, focus);
+        self.request_parent_content(workers);
+        self.request_preview_force(workers);
+        KeypressResult::Consumed
     }
 
     /// Requests a preview load for the currently selected entry in the navigation pane
     fn do_request_preview(&mut self, workers: &Workers, force: bool) {
-        if let Some(entry) = self.nav.selected_entry() {
-            let path = self.nav.current_dir().join(entry.name());
+        if let (Some(entry), Some(path)) = (self.nav.selected_entry(), self.nav.selected_path()) {
             if !force
                 && let Some(current) = self.preview.current_path()
                 && current == path
@@ -672,8 +1214,7 @@ impl AppState {
             }
 
             let req_id = self.preview.prepare_new_request(path.clone());
-            let sort_config = self.nav.sort_config();
-            let list_opts = self.dir_list_options();
+            let (sort_config, list_opts) = self.view_for(&path);
             let sort_date_format: Arc<str> = Arc::from(self.config.display().sort_date_format());
 
             if entry.is_dir() || entry.is_symlink() {
@@ -773,8 +1314,10 @@ impl AppState {
     fn refresh_changed_dirs(&mut self, workers: &Workers, dirs: &[PathBuf]) {
         for path in dirs {
             workers.cache().invalidate_path(path);
+            workers.dir_sizes().invalidate(path);
             self.parent.invalidate_if_path(path);
         }
+        self.nav.invalidate_tree_dirs(dirs);
 
         let current = self.nav.current_dir();
         let touches_current = dirs.iter().any(|d| d == current);
@@ -821,10 +1364,12 @@ impl AppState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::load::RawConfig;
     use crate::core::FileEntry;
-    use crate::ui::overlays::Overlay;
+    use crate::ui::overlays::{Overlay, OverlayKind};
     use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
     use ratatui::layout::Alignment;
+    use std::collections::HashSet;
     use std::ffi::OsString;
     use std::time::{Duration, Instant};
     use tempfile::tempdir;
@@ -968,4 +1513,209 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn handle_keypress_count_prefix_moves_selection() -> Result<(), Box<dyn std::error::Error>> {
+        let config = dummy_config();
+        let workers = dummy_workers();
+        let temp = tempdir()?;
+        let mut clipboard = Clipboard::default();
+        let mut app = AppState::from_dir(Arc::new(config), temp.path())?;
+
+        let entries: Vec<FileEntry> = (0..20)
+            .map(|i| FileEntry::new(OsString::from(format!("file_{:02}", i)), 0, None))
+            .collect();
+        app.nav
+            .update_from_worker(temp.path().to_path_buf(), Arc::from(entries), None, None);
+
+        let press = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        // `12j` moves twelve entries down and shows the count while typing.
+        app.handle_keypress(press('1'), &workers, &mut clipboard);
+        app.handle_keypress(press('2'), &workers, &mut clipboard);
+        assert_eq!(app.actions().count(), Some(12));
+        app.handle_keypress(press('j'), &workers, &mut clipboard);
+        assert_eq!(app.nav().selected_idx(), 12);
+        assert_eq!(app.actions().count(), None);
+
+        // Counts are clamped to the list.
+        for c in ['5', '0', 'j'] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert_eq!(app.nav().selected_idx(), 19);
+
+        // `3G` jumps to the third entry.
+        for c in ['3', 'G'] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert_eq!(app.nav().selected_idx(), 2);
+
+        // Esc drops a pending count instead of quitting.
+        app.handle_keypress(press('4'), &workers, &mut clipboard);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        let result = app.handle_keypress(esc, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::Consumed));
+        assert_eq!(app.actions().count(), None);
+
+        // `2<space>` marks two entries.
+        for c in ['2', ' '] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert_eq!(app.nav().markers().len(), 2);
+        assert_eq!(app.nav().selected_idx(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn status_request_reuses_the_marked_entries() -> Result<(), Box<dyn std::error::Error>> {
+        let temp = tempdir()?;
+        let raw: RawConfig = toml::from_str("[display.status]\nmarked_size = \"footer\"")?;
+        let mut app = AppState::from_dir(Arc::new(Config::from(raw)), temp.path())?;
+
+        let entries: Vec<FileEntry> = (0..3)
+            .map(|i| FileEntry::new(OsString::from(format!("file_{:02}", i)), 0, None))
+            .collect();
+        app.nav
+            .update_from_worker(temp.path().to_path_buf(), Arc::from(entries), None, None);
+        app.nav.select_all();
+
+        let first = app.status_request().marked.ok_or("marked size is shown")?;
+        let second = app.status_request().marked.ok_or("marked size is shown")?;
+        assert_eq!(first.len(), 3);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        app.nav.toggle_marker(&mut None);
+        let third = app.status_request().marked.ok_or("marked size is shown")?;
+        assert_eq!(third.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn sequences_starting_with_a_digit_are_not_counts() -> Result<(), Box<dyn std::error::Error>> {
+        let workers = dummy_workers();
+        let temp = tempdir()?;
+        let mut clipboard = Clipboard::default();
+        let raw: RawConfig = toml::from_str("[keys]\ncopy = [\"1 a\"]")?;
+        let mut app = AppState::from_dir(Arc::new(Config::from(raw)), temp.path())?;
+
+        let entries: Vec<FileEntry> = (0..5)
+            .map(|i| FileEntry::new(OsString::from(format!("file_{:02}", i)), 0, None))
+            .collect();
+        app.nav
+            .update_from_worker(temp.path().to_path_buf(), Arc::from(entries), None, None);
+
+        let press = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        for c in ['1', 'a'] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert_eq!(app.actions().count(), None);
+        let copied: HashSet<PathBuf> = [temp.path().join("file_00")].into();
+        assert_eq!(clipboard.entries, Some(copied));
+
+        // Digits which start no sequence are still counts.
+        app.handle_keypress(press('2'), &workers, &mut clipboard);
+        assert_eq!(app.actions().count(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn pane_switch_switches_tabs_outside_the_dual_layout() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let workers = dummy_workers();
+        let temp = tempdir()?;
+        let mut clipboard = Clipboard::default();
+        let mut app = AppState::from_dir(Arc::new(dummy_config()), temp.path())?;
+
+        let tab = KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE);
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        let result = app.handle_keypress(tab, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::Tab(TabAction::Next)));
+        let result = app.handle_keypress(back_tab, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::Tab(TabAction::Prev)));
+
+        let dual: RawConfig = toml::from_str("[display.layout]\nmode = \"dual\"")?;
+        let mut app = AppState::from_dir(Arc::new(Config::from(dual)), temp.path())?;
+        let result = app.handle_keypress(back_tab, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::Pane(PaneAction::Switch)));
+        Ok(())
+    }
+
+    #[test]
+    fn cancelled_count_delete_keeps_the_markers() -> Result<(), Box<dyn std::error::Error>> {
+        let config = dummy_config();
+        let workers = dummy_workers();
+        let temp = tempdir()?;
+        let mut clipboard = Clipboard::default();
+        let mut app = AppState::from_dir(Arc::new(config), temp.path())?;
+
+        let entries: Vec<FileEntry> = (0..10)
+            .map(|i| FileEntry::new(OsString::from(format!("file_{:02}", i)), 0, None))
+            .collect();
+        app.nav
+            .update_from_worker(temp.path().to_path_buf(), Arc::from(entries), None, None);
+
+        let press = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+        let marked: HashSet<PathBuf> = [temp.path().join("file_00")].into();
+
+        // `<space>` marks file_00, `3d` only targets the three entries after it.
+        app.handle_keypress(press(' '), &workers, &mut clipboard);
+        for c in ['3', 'd'] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert!(app.actions().is_input_mode());
+        let range: HashSet<PathBuf> = (1..4)
+            .map(|i| temp.path().join(format!("file_{:02}", i)))
+            .collect();
+        assert_eq!(app.nav().get_action_targets(), range);
+        assert_eq!(app.nav().markers(), &marked);
+
+        // Cancelling the prompt leaves only the markers the user had set.
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        app.handle_keypress(esc, &workers, &mut clipboard);
+        assert!(!app.actions().is_input_mode());
+        assert_eq!(app.nav().markers(), &marked);
+        assert_eq!(app.nav().get_action_targets(), marked);
+
+        // So does answering `n`.
+        for c in ['3', 'd', 'n'] {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        assert!(!app.actions().is_input_mode());
+        assert_eq!(app.nav().get_action_targets(), marked);
+
+        Ok(())
+    }
+
+    #[test]
+    fn keybind_help_filters_and_runs_the_selected_action() -> Result<(), Box<dyn std::error::Error>>
+    {
+        let workers = dummy_workers();
+        let temp = tempdir()?;
+        let mut clipboard = Clipboard::default();
+        let mut app = AppState::from_dir(Arc::new(dummy_config()), temp.path())?;
+        let press = |c: char| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+
+        app.handle_keypress(press('?'), &workers, &mut clipboard);
+        assert!(app.overlays().is_open(OverlayKind::KeybindHelp));
+
+        // Typed keys filter the help instead of running their actions.
+        for c in "reload".chars() {
+            let result = app.handle_keypress(press(c), &workers, &mut clipboard);
+            assert!(matches!(result, KeypressResult::Consumed));
+        }
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        let result = app.handle_keypress(enter, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::UiReload));
+        assert!(!app.overlays().is_open(OverlayKind::KeybindHelp));
+
+        // The pane switch chosen outside the dual layout switches tabs, like its keys do.
+        app.handle_keypress(press('?'), &workers, &mut clipboard);
+        for c in "pane_switch".chars() {
+            app.handle_keypress(press(c), &workers, &mut clipboard);
+        }
+        let result = app.handle_keypress(enter, &workers, &mut clipboard);
+        assert!(matches!(result, KeypressResult::Tab(TabAction::Next)));
+        Ok(())
+    }
 }
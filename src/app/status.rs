@@ -0,0 +1,56 @@
+//! Status segment state for AppState.
+//!
+//! [StatusState] holds the status segments the status worker computed for the tab, the
+//! time shown by the clock segment, which is formatted again on every tick, and the sorted
+//! marked entries sent to the worker, which are only rebuilt once the markers changed.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Local;
+
+use crate::core::status::StatusInfo;
+
+#[derive(Debug, Default)]
+pub(crate) struct StatusState {
+    info: Arc<StatusInfo>,
+    clock: String,
+    marked: Option<(u64, Arc<[PathBuf]>)>,
+}
+
+impl StatusState {
+    crate::getters! {
+        info: &StatusInfo,
+        clock: &str,
+    }
+
+    pub(crate) fn set_info(&mut self, info: Arc<StatusInfo>) {
+        self.info = info;
+    }
+
+    /// Returns the sorted marked entries of the marker generation.
+    pub(crate) fn marked(&mut self, generation: u64, markers: &HashSet<PathBuf>) -> Arc<[PathBuf]> {
+        if let Some((built, marked)) = &self.marked
+            && *built == generation
+        {
+            return Arc::clone(marked);
+        }
+        let mut marked: Vec<PathBuf> = markers.iter().cloned().collect();
+        marked.sort_unstable();
+        let marked: Arc<[PathBuf]> = Arc::from(marked);
+        self.marked = Some((generation, Arc::clone(&marked)));
+        marked
+    }
+
+    /// Formats the current time with the format.
+    /// Returns true if the shown time changed.
+    pub(crate) fn update_clock(&mut self, format: &str) -> bool {
+        let now = Local::now().format(format).to_string();
+        if now == self.clock {
+            return false;
+        }
+        self.clock = now;
+        true
+    }
+}
@@ -4,20 +4,28 @@
 //! allowing to switch between them, add new tabs, and close existing ones.
 //!
 //! It also includes the [handle_tab_action] function, which processes tab actions related to tab management.
+//!
+//! In the dual pane layout the current tab is shown next to the opposite tab,
+//! which is the target of [handle_pane_action].
 
 use std::ffi::OsString;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use ratatui::text::Span;
 
-use crate::app::{AppContainer, AppState, KeypressResult, keymap::TabAction};
+use crate::app::{
+    AppContainer, AppState, KeypressResult,
+    keymap::{PaneAction, TabAction},
+};
 use crate::core::{sort::SortConfig, workers::Workers};
 use crate::utils::path;
 
 pub(crate) struct TabManager {
     pub(crate) tabs: Vec<AppState>,
     pub(crate) current: usize,
+    opposite: usize,
     next_tab_id: usize,
 }
 
@@ -38,6 +46,7 @@ impl TabManager {
         let mut manager = Self {
             tabs: vec![existing, new_tab],
             current: 1,
+            opposite: 0,
             next_tab_id: 2,
         };
         manager.sync_tab_line();
@@ -55,6 +64,7 @@ impl TabManager {
         let mut manager = Self {
             tabs,
             current: 0,
+            opposite: 1,
             next_tab_id: next_id,
         };
         manager.sync_tab_line();
@@ -79,6 +89,48 @@ impl TabManager {
         &mut self.tabs[self.current]
     }
 
+    /// Returns the index of the opposite pane of the dual pane layout,
+    /// or None if the dual pane layout is off or there is only one tab.
+    pub(crate) fn opposite(&self) -> Option<usize> {
+        let dual = self.current_tab().config().display().is_dual();
+        (dual && self.opposite != self.current && self.opposite < self.tabs.len())
+            .then_some(self.opposite)
+    }
+
+    /// Returns the current and the opposite pane of the dual pane layout.
+    pub(crate) fn dual_panes_mut(&mut self) -> Option<(&mut AppState, &mut AppState)> {
+        let opposite = self.opposite()?;
+        let [current, opposite] = self.tabs.get_disjoint_mut([self.current, opposite]).ok()?;
+        Some((current, opposite))
+    }
+
+    /// Returns true if the current pane is drawn on the left side of the dual pane layout.
+    pub(crate) fn current_is_left(&self) -> bool {
+        self.current < self.opposite
+    }
+
+    /// Moves the focus to the opposite pane.
+    pub(crate) fn swap_panes(&mut self) -> bool {
+        let Some(opposite) = self.opposite() else {
+            return false;
+        };
+        self.opposite = self.current;
+        self.current = opposite;
+        self.sync_tab_line();
+        true
+    }
+
+    /// Keeps the opposite pane valid after the current tab or the tab count changed.
+    /// Switching to the opposite tab swaps the panes.
+    fn sync_opposite(&mut self, previous: usize) {
+        if self.opposite == self.current {
+            self.opposite = previous;
+        }
+        if self.opposite == self.current || self.opposite >= self.tabs.len() {
+            self.opposite = if self.current == 0 { 1 } else { 0 };
+        }
+    }
+
     pub(crate) fn add_tab(
         &mut self,
         mut tab: AppState,
@@ -93,7 +145,9 @@ impl TabManager {
         self.next_tab_id = self.next_tab_id.saturating_add(1);
         tab.initialize(workers, focus);
         self.tabs.push(tab);
+        let previous = self.current;
         self.current = self.tabs.len() - 1;
+        self.sync_opposite(previous);
         self.sync_tab_line();
         self.current
     }
@@ -103,13 +157,17 @@ impl TabManager {
         if n == 0 {
             return;
         }
+        let previous = self.current;
         self.current = ((self.current as isize + dir + n) % n) as usize;
+        self.sync_opposite(previous);
         self.sync_tab_line();
     }
 
     pub(crate) fn set_active(&mut self, idx: usize) {
         if idx < self.tabs.len() {
+            let previous = self.current;
             self.current = idx;
+            self.sync_opposite(previous);
             self.sync_tab_line();
         }
     }
@@ -120,6 +178,9 @@ impl TabManager {
         }
 
         self.tabs.remove(idx);
+        if self.opposite > idx {
+            self.opposite -= 1;
+        }
         if self.tabs.is_empty() {
             self.current = 0;
             false
@@ -127,6 +188,7 @@ impl TabManager {
             if self.current >= self.tabs.len() {
                 self.current = self.tabs.len() - 1;
             }
+            self.sync_opposite(self.current);
             self.sync_tab_line();
             true
         }
@@ -269,3 +331,83 @@ pub(crate) fn handle_sort_action(container: &mut AppContainer, config: SortConfi
         }
     }
 }
+
+/// Handles the actions of the dual pane layout.
+///
+/// Copying and moving sends the marked entries of the current pane
+/// into the directory of the opposite pane.
+pub(crate) fn handle_pane_action(
+    workers: &Workers,
+    container: &mut AppContainer,
+    action: PaneAction,
+) -> KeypressResult {
+    if action == PaneAction::Switch {
+        if let AppContainer::Tabs(tabs) = container
+            && tabs.swap_panes()
+        {
+            tabs.current_tab_mut().refresh_show_info_if_open();
+            return KeypressResult::Consumed;
+        }
+        return KeypressResult::Continue;
+    }
+
+    if let AppContainer::Tabs(tabs) = container
+        && let Some((current, opposite)) = tabs.dual_panes_mut()
+    {
+        let dest = opposite.nav().current_dir().to_path_buf();
+        current.transfer_to_pane(workers, &dest, action == PaneAction::MoveTo);
+        return KeypressResult::Consumed;
+    }
+
+    let app = match container {
+        AppContainer::Single(app) => app.as_mut(),
+        AppContainer::Tabs(tabs) => tabs.current_tab_mut(),
+    };
+    app.push_overlay_message(
+        "No opposite pane, enable the dual pane layout".into(),
+        Duration::from_secs(2),
+        None,
+    );
+    KeypressResult::Consumed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::config::load::RawConfig;
+
+    #[test]
+    fn tabs_track_the_opposite_pane() -> Result<(), Box<dyn std::error::Error>> {
+        let raw: RawConfig = toml::from_str(
+            r#"
+            [display.layout]
+            mode = "dual"
+            "#,
+        )?;
+        let config = Arc::new(Config::from(raw));
+        let tabs = (0..3)
+            .map(|_| AppState::new(Arc::clone(&config)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut manager = TabManager::from_vec(tabs);
+        assert_eq!(manager.opposite(), Some(1));
+        assert!(manager.current_is_left());
+
+        assert!(manager.swap_panes());
+        assert_eq!((manager.current, manager.opposite()), (1, Some(0)));
+        assert!(!manager.current_is_left());
+
+        // Switching to a third tab replaces the current pane and keeps the opposite one.
+        manager.set_active(2);
+        assert_eq!(manager.opposite(), Some(0));
+
+        // Switching to the opposite tab swaps the panes.
+        manager.set_active(0);
+        assert_eq!(manager.opposite(), Some(2));
+
+        manager.close_tab(2);
+        assert_eq!((manager.current, manager.opposite()), (0, Some(1)));
+        Ok(())
+    }
+}
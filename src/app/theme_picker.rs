@@ -0,0 +1,51 @@
+//! Theme picker for runa.
+//!
+//! Lists the preset themes and the theme files of the themes directory. The selected theme
+//! is shown in all tabs right away, Enter keeps it for the session and Esc goes back to the
+//! theme of the config.
+
+/// State of the open theme picker.
+#[derive(Debug)]
+pub(crate) struct ThemePicker {
+    names: Vec<String>,
+    selected: usize,
+    /// The theme of the config when the picker was opened
+    current: Option<String>,
+}
+
+impl ThemePicker {
+    /// Creates the picker with the theme of the config selected.
+    pub(crate) fn new(names: Vec<String>, current: Option<&str>) -> Self {
+        let selected = current
+            .and_then(|name| names.iter().position(|n| n == name))
+            .unwrap_or(0);
+        Self {
+            names,
+            selected,
+            current: current.map(str::to_string),
+        }
+    }
+
+    crate::getters! {
+        names: &[String],
+        selected: usize,
+    }
+
+    pub(crate) fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub(crate) fn selected_name(&self) -> Option<&str> {
+        self.names.get(self.selected).map(String::as_str)
+    }
+
+    /// Moves the selection by `delta` themes, stopping at the first and last one.
+    /// Returns true if the selection changed.
+    pub(crate) fn select_by(&mut self, delta: isize) -> bool {
+        let last = self.names.len().saturating_sub(1);
+        let selected = self.selected.saturating_add_signed(delta).min(last);
+        let changed = selected != self.selected;
+        self.selected = selected;
+        changed
+    }
+}
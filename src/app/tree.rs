@@ -0,0 +1,278 @@
+//! Tree view state for the main pane.
+//!
+//! Holds the expanded directories and their lazily loaded children, and flattens them
+//! into a single list of rows. [NavState](crate::app::NavState) then treats the
+//! flattened rows like a regular directory listing.
+//!
+//! Each row keeps the directory it lives in, its depth and its indentation guide,
+//! so actions on nested entries resolve to the right path.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::FileEntry;
+use crate::utils::text::StrBuffer;
+
+const MAX_TREE_DEPTH: u16 = 32;
+const MAX_CACHED_CHILDREN: usize = 100;
+
+/// A single flattened row of the tree.
+pub(crate) struct TreeNode {
+    dir: Arc<Path>,
+    depth: u16,
+    guide: Box<str>,
+}
+
+impl TreeNode {
+    crate::getters! {
+        depth: u16,
+        guide: &str,
+    }
+
+    /// The directory the entry of this row lives in.
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+struct TreeChildren {
+    entries: Arc<[FileEntry]>,
+    sort_column: Option<Arc<StrBuffer>>,
+}
+
+/// Flattened rows of the tree, ready to be used as the entries of the main pane.
+pub(crate) struct Flattened {
+    pub(crate) entries: Arc<[FileEntry]>,
+    pub(crate) sort_column: Option<Arc<StrBuffer>>,
+}
+
+#[derive(Default)]
+pub(crate) struct TreeState {
+    enabled: bool,
+    expanded: HashSet<PathBuf>,
+    children: HashMap<PathBuf, TreeChildren>,
+    stale: HashSet<PathBuf>,
+    pending: HashMap<PathBuf, u64>,
+    missing: Vec<PathBuf>,
+    request_id: u64,
+    nodes: Vec<TreeNode>,
+}
+
+impl TreeState {
+    crate::getters! {
+        enabled: bool,
+    }
+
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub(crate) fn is_expanded(&self, path: &Path) -> bool {
+        self.enabled && self.expanded.contains(path)
+    }
+
+    /// Returns the row info for a flattened index, `None` if the tree is not active.
+    pub(crate) fn node(&self, idx: usize) -> Option<&TreeNode> {
+        self.nodes.get(idx)
+    }
+
+    pub(crate) fn expand(&mut self, path: PathBuf) -> bool {
+        self.expanded.insert(path)
+    }
+
+    pub(crate) fn collapse(&mut self, path: &Path) -> bool {
+        self.expanded.remove(path)
+    }
+
+    /// Collapses every expanded directory below `root`.
+    pub(crate) fn collapse_all(&mut self, root: &Path) -> bool {
+        let before = self.expanded.len();
+        self.expanded.retain(|p| !p.starts_with(root));
+        before != self.expanded.len()
+    }
+
+    /// Takes the expanded directories whose children have to be (re)loaded.
+    pub(crate) fn take_missing(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.missing)
+    }
+
+    /// Registers a new children request for `path` and returns its request id.
+    pub(crate) fn begin_request(&mut self, path: PathBuf) -> u64 {
+        self.request_id = self.request_id.wrapping_add(1);
+        self.pending.insert(path, self.request_id);
+        self.request_id
+    }
+
+    pub(crate) fn is_loading(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    pub(crate) fn is_pending(&self, path: &Path, request_id: u64) -> bool {
+        self.pending.get(path) == Some(&request_id)
+    }
+
+    /// Stores the loaded children of `path`.
+    ///
+    /// With a `request_id` the children are only stored if they answer the pending request.
+    pub(crate) fn insert_children(
+        &mut self,
+        path: PathBuf,
+        entries: Arc<[FileEntry]>,
+        sort_column: Option<Arc<StrBuffer>>,
+        request_id: Option<u64>,
+    ) -> bool {
+        if let Some(id) = request_id {
+            if !self.is_pending(&path, id) {
+                return false;
+            }
+            self.pending.remove(&path);
+        }
+
+        if self.children.len() >= MAX_CACHED_CHILDREN {
+            let expanded = &self.expanded;
+            self.children.retain(|p, _| expanded.contains(p));
+        }
+
+        self.stale.remove(&path);
+        self.children.insert(
+            path,
+            TreeChildren {
+                entries,
+                sort_column,
+            },
+        );
+        true
+    }
+
+    /// Marks the loaded children of the given directories as outdated.
+    pub(crate) fn invalidate<'a>(&mut self, dirs: impl IntoIterator<Item = &'a PathBuf>) {
+        for dir in dirs {
+            if self.children.contains_key(dir) {
+                self.stale.insert(dir.clone());
+            }
+        }
+    }
+
+    /// Marks all loaded children as outdated, e.g. after the sort config changed.
+    pub(crate) fn invalidate_all(&mut self) {
+        self.stale.extend(self.children.keys().cloned());
+    }
+
+    /// Flattens the root entries with all expanded and loaded children.
+    ///
+    /// Returns `None` if the tree is disabled or nothing is expanded, in which
+    /// case the root entries are used as they are.
+    pub(crate) fn flatten(
+        &mut self,
+        root: &Path,
+        entries: &Arc<[FileEntry]>,
+        sort_column: &Option<Arc<StrBuffer>>,
+    ) -> Option<Flattened> {
+        self.nodes.clear();
+        self.missing.clear();
+
+        if !self.enabled || !self.expanded.iter().any(|p| p.starts_with(root)) {
+            return None;
+        }
+
+        let mut out_entries = Vec::with_capacity(entries.len());
+        let mut out_sort = sort_column
+            .as_ref()
+            .map(|_| Vec::with_capacity(entries.len()));
+        let mut nodes = Vec::with_capacity(entries.len());
+
+        self.push_level(
+            Arc::from(root),
+            entries,
+            sort_column.as_deref(),
+            0,
+            "",
+            &mut out_entries,
+            &mut out_sort,
+            &mut nodes,
+        );
+
+        self.nodes = nodes;
+        Some(Flattened {
+            entries: Arc::from(out_entries),
+            sort_column: out_sort.map(|col| Arc::new(StrBuffer::from_iter(col))),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_level(
+        &mut self,
+        dir: Arc<Path>,
+        entries: &[FileEntry],
+        sort_column: Option<&StrBuffer>,
+        depth: u16,
+        prefix: &str,
+        out_entries: &mut Vec<FileEntry>,
+        out_sort: &mut Option<Vec<String>>,
+        nodes: &mut Vec<TreeNode>,
+    ) {
+        let len = entries.len();
+        for (idx, entry) in entries.iter().enumerate() {
+            let is_last = idx + 1 == len;
+            let guide = if depth == 0 {
+                String::new()
+            } else {
+                format!("{}{}", prefix, if is_last { "└ " } else { "├ " })
+            };
+
+            out_entries.push(entry.clone());
+            if let Some(col) = out_sort.as_mut() {
+                col.push(
+                    sort_column
+                        .filter(|c| idx < c.len())
+                        .map(|c| c.get(idx).to_string())
+                        .unwrap_or_default(),
+                );
+            }
+            nodes.push(TreeNode {
+                dir: Arc::clone(&dir),
+                depth,
+                guide: guide.into_boxed_str(),
+            });
+
+            if !entry.is_dir() || depth >= MAX_TREE_DEPTH {
+                continue;
+            }
+
+            let path = dir.join(entry.name());
+            if !self.expanded.contains(&path) {
+                continue;
+            }
+
+            if (!self.children.contains_key(&path) || self.stale.contains(&path))
+                && !self.pending.contains_key(&path)
+            {
+                self.missing.push(path.clone());
+            }
+
+            let Some(children) = self.children.get(&path) else {
+                continue;
+            };
+            let child_entries = Arc::clone(&children.entries);
+            let child_sort = children.sort_column.clone();
+
+            let child_prefix = if depth == 0 {
+                String::new()
+            } else {
+                format!("{}{}", prefix, if is_last { "  " } else { "│ " })
+            };
+
+            self.push_level(
+                Arc::from(path),
+                &child_entries,
+                child_sort.as_deref(),
+                depth + 1,
+                &child_prefix,
+                out_entries,
+                out_sort,
+                nodes,
+            );
+        }
+    }
+}
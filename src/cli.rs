@@ -6,12 +6,14 @@
 use std::io::{self, BufWriter, Write};
 use std::path::PathBuf;
 
+use crate::app::keymap::Keymap;
 use crate::config::{Config, assets::FULL_TOML};
 use crate::utils::os;
 
 pub(crate) enum CliAction {
     RunApp,
     RunAppAtPath(Vec<PathBuf>),
+    RunAppRestore,
     Exit,
 }
 
@@ -58,6 +60,23 @@ pub(crate) fn handle_args() -> CliAction {
                 }
                 CliAction::Exit
             }
+            "--check-keys" => {
+                if check_no_extra_args(first_arg, &args) {
+                    return CliAction::Exit;
+                }
+                if let Err(e) = print_key_check()
+                    && e.kind() != io::ErrorKind::BrokenPipe
+                {
+                    eprintln!("[runa] Error displaying keybindings: {}", e);
+                }
+                CliAction::Exit
+            }
+            "--restore" => {
+                if check_no_extra_args(first_arg, &args) {
+                    return CliAction::Exit;
+                }
+                CliAction::RunAppRestore
+            }
             "--config-help" => {
                 let section_arg = args.get(2).map(|s| s.as_str());
 
@@ -137,6 +156,9 @@ OPTIONS:
       --init-full         Generate the full configuration with all options
       --config-help       Display all the configuration options. Use '--config-help [SECTION]'
                           to show a specific part (e.g. 'theme' or 'keys')
+      --check-keys        Print the effective keybindings and report conflicting,
+                          unknown or shadowed keys
+      --restore           Restore the tabs, cursors, filters and clipboard of the last session
   -h, --help              Print help information
   -v, --version           Display the current installed version of runa
 
@@ -238,6 +260,47 @@ TIP:
     Ok(())
 }
 
+/// Prints the effective key sequences of every action, followed by the keybinding issues.
+fn print_key_check() -> io::Result<()> {
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("[runa] Config error: {}", e);
+        Config::default()
+    });
+    let keymap = Keymap::from_config(&config);
+    let table = keymap.effective_bindings(&config);
+
+    let stdout = io::stdout();
+    let mut handle = BufWriter::new(stdout.lock());
+
+    let width = table
+        .iter()
+        .map(|(action, _)| action.len())
+        .max()
+        .unwrap_or(0)
+        .max("ACTION".len());
+
+    writeln!(handle, "{:<width$}  KEYS", "ACTION")?;
+    for (action, keys) in &table {
+        let keys = if keys.is_empty() {
+            "-".to_string()
+        } else {
+            keys.join(", ")
+        };
+        writeln!(handle, "{:<width$}  {}", action, keys)?;
+    }
+
+    writeln!(handle)?;
+    if keymap.issues().is_empty() {
+        writeln!(handle, "No keybinding issues found.")?;
+    } else {
+        writeln!(handle, "ISSUES:")?;
+        for issue in keymap.issues() {
+            writeln!(handle, "  {}", issue)?;
+        }
+    }
+    handle.flush()
+}
+
 fn check_no_extra_args(flag: &str, args: &[String]) -> bool {
     if args.len() > 2 {
         eprintln!("[runa] Error: {} does not take arguments.", flag);
@@ -4,15 +4,18 @@
 //! configuration options for runa, including display settings, input keybindings,
 
 pub(crate) mod assets;
+pub(crate) mod dir_rules;
 pub(crate) mod display;
 pub(crate) mod general;
+pub(crate) mod icons;
 pub(crate) mod input;
 pub(crate) mod load;
 pub(crate) mod presets;
 pub(crate) mod theme;
 
+pub(crate) use dir_rules::{DirRule, DirRules};
 pub(crate) use display::Display;
 pub(crate) use general::{General, InternalGeneral};
-pub(crate) use input::{Editor, Keys};
+pub(crate) use input::{Editor, Keys, Macro};
 pub(crate) use load::Config;
 pub(crate) use theme::Theme;
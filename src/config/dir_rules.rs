@@ -0,0 +1,163 @@
+//! Per-directory view rules for runa.
+//!
+//! This module defines the [DirRule] struct for deserializing the `[[dir_rules]]`
+//! tables of the runa.toml and the [DirRules] struct, which resolves the view settings
+//! (sort, hidden files, directories first and filter) for a directory.
+//!
+//! Settings which no rule sets fall back to the tab-wide settings.
+
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::core::{query::glob_matches, sort::SortConfig};
+use crate::utils::path::expand_home_path;
+
+/// A `[[dir_rules]]` table as read from the toml file.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct DirRule {
+    path: String,
+    #[serde(default, deserialize_with = "deserialize_sort")]
+    sort: Option<SortConfig>,
+    show_hidden: Option<bool>,
+    dirs_first: Option<bool>,
+    filter: Option<String>,
+}
+
+/// The view settings of a directory which differ from the tab-wide settings.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct DirView<'a> {
+    pub(crate) sort: Option<SortConfig>,
+    pub(crate) show_hidden: Option<bool>,
+    pub(crate) dirs_first: Option<bool>,
+    pub(crate) filter: Option<&'a str>,
+}
+
+#[derive(Debug)]
+struct InternalDirRule {
+    pattern: Box<[char]>,
+    sort: Option<SortConfig>,
+    show_hidden: Option<bool>,
+    dirs_first: Option<bool>,
+    filter: Option<String>,
+}
+
+/// The processed `[[dir_rules]]` of the config.
+#[derive(Debug, Default)]
+pub(crate) struct DirRules {
+    rules: Vec<InternalDirRule>,
+}
+
+impl From<Vec<DirRule>> for DirRules {
+    fn from(rules: Vec<DirRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let expanded = expand_home_path(&rule.path);
+                let trimmed = match expanded.trim_end_matches(['/', '\\']) {
+                    "" => expanded.as_str(),
+                    trimmed => trimmed,
+                };
+                InternalDirRule {
+                    pattern: trimmed.chars().collect(),
+                    sort: rule.sort,
+                    show_hidden: rule.show_hidden,
+                    dirs_first: rule.dirs_first,
+                    filter: rule.filter.filter(|f| !f.is_empty()),
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+}
+
+impl DirRules {
+    /// Resolves the view settings of the directory.
+    ///
+    /// All rules whose path pattern matches the directory are applied in order,
+    /// so a later rule overrides the settings of an earlier one.
+    pub(crate) fn view_for(&self, dir: &Path) -> DirView<'_> {
+        let mut view = DirView::default();
+        if self.rules.is_empty() {
+            return view;
+        }
+
+        let dir = dir.to_string_lossy();
+        for rule in self.rules.iter().filter(|r| glob_matches(&r.pattern, &dir)) {
+            view.sort = rule.sort.or(view.sort);
+            view.show_hidden = rule.show_hidden.or(view.show_hidden);
+            view.dirs_first = rule.dirs_first.or(view.dirs_first);
+            view.filter = rule.filter.as_deref().or(view.filter);
+        }
+        view
+    }
+}
+
+fn deserialize_sort<'de, D>(deserializer: D) -> Result<Option<SortConfig>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::sort::{SortMode, SortOrder};
+
+    #[test]
+    fn dir_rules_resolve_views() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct Rules {
+            dir_rules: Vec<DirRule>,
+        }
+
+        let raw: Rules = toml::from_str(
+            r#"
+            [[dir_rules]]
+            path = "/data/*"
+            sort = "modified desc"
+            show_hidden = false
+
+            [[dir_rules]]
+            path = "/data/src/"
+            sort = "name"
+            filter = "ext:rs"
+            "#,
+        )?;
+        let rules = DirRules::from(raw.dir_rules);
+
+        let downloads = rules.view_for(Path::new("/data/downloads"));
+        assert_eq!(
+            downloads.sort,
+            Some(SortConfig::from((
+                SortMode::Modified,
+                SortOrder::Descending
+            )))
+        );
+        assert_eq!(downloads.show_hidden, Some(false));
+        assert_eq!(downloads.filter, None);
+
+        let src = rules.view_for(Path::new("/data/src"));
+        assert_eq!(
+            src.sort,
+            Some(SortConfig::from((SortMode::Name, SortOrder::Ascending)))
+        );
+        assert_eq!(src.show_hidden, Some(false));
+        assert_eq!(src.filter, Some("ext:rs"));
+
+        assert_eq!(rules.view_for(Path::new("/data")), DirView::default());
+
+        let invalid: Result<Rules, _> = toml::from_str(
+            r#"
+            [[dir_rules]]
+            path = "/data"
+            sort = "modified sideways"
+            "#,
+        );
+        assert!(invalid.is_err());
+        Ok(())
+    }
+}
@@ -3,10 +3,13 @@
 //! This module defines the display configuration options which are read from the runa.toml
 //! configuration file.
 
+use std::time::Duration;
+
 use chrono::format::{Item, StrftimeItems};
 use ratatui::widgets::BorderType;
 use serde::{Deserialize, Deserializer};
 
+use crate::core::details::DetailColumn;
 use crate::ui::widgets::DialogPosition;
 
 /// Display configuration options
@@ -37,6 +40,8 @@ pub(crate) struct Display {
     scroll_padding: usize,
     toggle_marker_jump: bool,
     instant_preview: bool,
+    mouse: bool,
+    colors: ColorDepth,
     #[serde(
         default = "Display::default_sort_date_format",
         deserialize_with = "deserialize_sort_date_format"
@@ -44,6 +49,7 @@ pub(crate) struct Display {
     sort_date_format: String,
     preview_options: PreviewOptions,
     layout: LayoutConfig,
+    details: DetailsConfig,
     info: ShowInfoOptions,
     status: StatusElements,
 }
@@ -64,8 +70,11 @@ impl Display {
         scroll_padding: usize,
         toggle_marker_jump: bool,
         instant_preview: bool,
+        mouse: bool,
+        colors: ColorDepth,
         sort_date_format: &str,
         preview_options: &PreviewOptions,
+        details: &DetailsConfig,
         info: &ShowInfoOptions,
         status: &StatusElements,
     }
@@ -85,6 +94,11 @@ impl Display {
         matches!(self.borders, BorderStyle::None)
     }
 
+    #[inline]
+    pub(crate) fn is_dual(&self) -> bool {
+        self.layout.mode == LayoutMode::Dual
+    }
+
     #[inline]
     pub(crate) fn parent_ratio(&self) -> u16 {
         self.layout.parent_ratio()
@@ -100,6 +114,16 @@ impl Display {
         self.layout.preview_ratio()
     }
 
+    #[inline]
+    pub(crate) fn stack_mode(&self) -> StackMode {
+        self.layout.stack
+    }
+
+    #[inline]
+    pub(crate) fn min_pane_width(&self) -> u16 {
+        self.layout.min_pane_width
+    }
+
     fn default_sort_date_format() -> String {
         "%b %e %H:%M".to_string()
     }
@@ -136,8 +160,11 @@ impl Default for Display {
             scroll_padding: 5,
             toggle_marker_jump: false,
             instant_preview: true,
+            mouse: false,
+            colors: ColorDepth::Auto,
             sort_date_format: Display::default_sort_date_format(),
             layout: LayoutConfig::default(),
+            details: DetailsConfig::default(),
             preview_options: PreviewOptions::default(),
             info: ShowInfoOptions::default(),
             status: StatusElements::default(),
@@ -146,13 +173,40 @@ impl Default for Display {
 }
 
 /// Layout configuration for the display panes
-/// This struct holds the ratio settings for the parent, main, and preview panes
+/// This struct holds the layout mode, the ratio settings for the parent, main, and preview panes
+/// and the rules for collapsing and stacking them on small terminals
 #[derive(Deserialize, Debug)]
 #[serde(default)]
 pub(crate) struct LayoutConfig {
+    mode: LayoutMode,
     parent: u16,
     main: u16,
     preview: u16,
+    stack: StackMode,
+    min_pane_width: u16,
+}
+
+/// Layout mode options
+/// `Columns` shows the parent, main and preview panes of the current tab,
+/// `Dual` shows two independent file panes side by side.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LayoutMode {
+    #[default]
+    Columns,
+    Dual,
+}
+
+/// Stacking options of the panes
+/// `Auto` stacks the main and preview pane vertically if the terminal is taller than wide on
+/// screen, `Always` and `Never` force or disable the stacked layout.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StackMode {
+    Auto,
+    Always,
+    #[default]
+    Never,
 }
 
 /// Public methods for accessing layout configuration options
@@ -167,9 +221,43 @@ impl LayoutConfig {
 impl Default for LayoutConfig {
     fn default() -> Self {
         Self {
+            mode: LayoutMode::Columns,
             parent: 20,
             main: 40,
             preview: 40,
+            stack: StackMode::Never,
+            min_pane_width: 10,
+        }
+    }
+}
+
+/// Details view configuration of the main pane
+/// This struct holds whether the details view is shown on startup
+/// and the columns shown next to the entry names, from left to right
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub(crate) struct DetailsConfig {
+    enabled: bool,
+    columns: Vec<DetailColumn>,
+}
+
+/// Public methods for accessing details configuration options
+impl DetailsConfig {
+    crate::getters! {
+        enabled: bool,
+        columns: &[DetailColumn],
+    }
+}
+
+impl Default for DetailsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            columns: vec![
+                DetailColumn::Permissions,
+                DetailColumn::Size,
+                DetailColumn::Modified,
+            ],
         }
     }
 }
@@ -367,35 +455,96 @@ pub(crate) enum StatusPosition {
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 #[serde(default)]
 pub(crate) struct StatusElements {
+    count: StatusPosition,
+    recording: StatusPosition,
     entry_count: StatusPosition,
     filter: StatusPosition,
     markers: StatusPosition,
     clipboard: StatusPosition,
     tasks: StatusPosition,
     tabs: StatusPosition,
+    listing: StatusPosition,
+    git: StatusPosition,
+    disk: StatusPosition,
+    marked_size: StatusPosition,
+    clock: StatusPosition,
+    sort: StatusPosition,
+    #[serde(
+        default = "StatusElements::default_clock_format",
+        deserialize_with = "deserialize_clock_format"
+    )]
+    clock_format: String,
+    refresh: u64,
+    command: Vec<StatusCommand>,
 }
 
 impl Default for StatusElements {
     fn default() -> Self {
         Self {
+            count: StatusPosition::Footer,
+            recording: StatusPosition::Footer,
             entry_count: StatusPosition::Footer,
             filter: StatusPosition::Header,
             markers: StatusPosition::Footer,
             clipboard: StatusPosition::Footer,
             tasks: StatusPosition::Footer,
             tabs: StatusPosition::Header,
+            listing: StatusPosition::Footer,
+            git: StatusPosition::None,
+            disk: StatusPosition::None,
+            marked_size: StatusPosition::None,
+            clock: StatusPosition::None,
+            sort: StatusPosition::None,
+            clock_format: Self::default_clock_format(),
+            refresh: 5,
+            command: Vec::new(),
         }
     }
 }
 
 impl StatusElements {
     crate::getters! {
+        count: StatusPosition,
+        recording: StatusPosition,
         entry_count: StatusPosition,
         filter: StatusPosition,
         markers: StatusPosition,
         clipboard: StatusPosition,
         tasks: StatusPosition,
         tabs: StatusPosition,
+        listing: StatusPosition,
+        git: StatusPosition,
+        disk: StatusPosition,
+        marked_size: StatusPosition,
+        clock: StatusPosition,
+        sort: StatusPosition,
+        clock_format: &str,
+        command: &[StatusCommand],
+    }
+
+    fn default_clock_format() -> String {
+        "%H:%M".to_string()
+    }
+
+    /// Returns the interval the git, disk and command segments are refreshed on.
+    pub(crate) fn refresh(&self) -> Duration {
+        Duration::from_secs(self.refresh.max(1))
+    }
+}
+
+/// A `[[display.status.command]]` segment, which shows the first line of output of a shell
+/// command run in the current directory.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct StatusCommand {
+    command: String,
+    #[serde(default)]
+    position: StatusPosition,
+}
+
+impl StatusCommand {
+    crate::getters! {
+        command: &str,
+        position: StatusPosition,
     }
 }
 
@@ -515,6 +664,32 @@ pub(crate) enum BorderStyle {
     Split,
 }
 
+/// Color depth options
+/// This enum defines how many colors the terminal is expected to show.
+/// Colors beyond the depth are downsampled to the nearest color the terminal can show.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ColorDepth {
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Returns the configured depth, or the detected depth of the terminal for `auto`.
+    pub(crate) fn resolve(self) -> ColorDepth {
+        match self {
+            ColorDepth::Auto => crate::utils::term::color_depth(),
+            depth => depth,
+        }
+    }
+}
+
 /// Border shape options
 /// This enum defines the different border shapes that can be used in the UI
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -607,6 +782,18 @@ fn validate_strftime_format(fmt: Option<String>, default_fmt: &str, max_len: usi
     }
 }
 
+fn deserialize_clock_format<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw: Option<String> = Option::<String>::deserialize(deserializer)?;
+    Ok(validate_strftime_format(
+        raw,
+        &StatusElements::default_clock_format(),
+        32,
+    ))
+}
+
 fn deserialize_sort_date_format<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
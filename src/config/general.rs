@@ -21,6 +21,15 @@ pub(crate) const MIN_FIND_RESULTS: usize = 15;
 pub(crate) const MAX_FIND_RESULTS_LIMIT: usize = 1000000;
 pub(crate) const DEFAULT_FIND_RESULTS: usize = 20000;
 
+/// The minimum entries of the flatten view, used if the configured value is lower.
+pub(crate) const MIN_FLATTEN_ENTRIES: usize = 100;
+/// The maximum entries the flatten view can be configured to.
+pub(crate) const MAX_FLATTEN_ENTRIES_LIMIT: usize = 1000000;
+pub(crate) const DEFAULT_FLATTEN_ENTRIES: usize = 10000;
+
+/// The number of entries kept per prompt in the prompt history.
+pub(crate) const DEFAULT_HISTORY_SIZE: usize = 100;
+
 #[derive(Deserialize, Debug)]
 #[serde(default)]
 pub(crate) struct General {
@@ -32,7 +41,12 @@ pub(crate) struct General {
     always_show: Vec<String>,
     #[serde(default = "default_find_results")]
     max_find_results: usize,
+    #[serde(default = "default_flatten_entries")]
+    max_flatten_entries: usize,
     move_to_trash: bool,
+    #[serde(default = "default_history_size")]
+    history_size: usize,
+    dir_sizes: DirSizeConfig,
     startup: StartupConfig,
 }
 
@@ -46,7 +60,10 @@ impl Default for General {
             case_insensitive: true,
             always_show: Vec::new(),
             max_find_results: DEFAULT_FIND_RESULTS,
+            max_flatten_entries: DEFAULT_FLATTEN_ENTRIES,
             move_to_trash: true,
+            history_size: DEFAULT_HISTORY_SIZE,
+            dir_sizes: DirSizeConfig::default(),
             startup: StartupConfig::default(),
         }
     }
@@ -61,7 +78,10 @@ pub(crate) struct InternalGeneral {
     case_insensitive: bool,
     always_show: Arc<HashSet<OsString>>,
     max_find_results: usize,
+    max_flatten_entries: usize,
     move_to_trash: bool,
+    history_size: usize,
+    dir_sizes: DirSizeConfig,
     startup: InternalStartup,
 }
 
@@ -75,6 +95,7 @@ impl From<General> for InternalGeneral {
 
         let internal_startup = InternalStartup {
             tabs: g.startup.tabs.into_iter().map(PathBuf::from).collect(),
+            restore_session: g.startup.restore_session,
         };
 
         Self {
@@ -85,7 +106,10 @@ impl From<General> for InternalGeneral {
             case_insensitive: g.case_insensitive,
             always_show: Arc::new(set),
             max_find_results: clamp_find_results(g.max_find_results),
+            max_flatten_entries: clamp_flatten_entries(g.max_flatten_entries),
             move_to_trash: g.move_to_trash,
+            history_size: g.history_size,
+            dir_sizes: g.dir_sizes,
             startup: internal_startup,
         }
     }
@@ -100,24 +124,49 @@ impl InternalGeneral {
         case_insensitive: bool,
         always_show: &Arc<HashSet<OsString>>,
         max_find_results: usize,
+        max_flatten_entries: usize,
         move_to_trash: bool,
+        history_size: usize,
+        dir_sizes: &DirSizeConfig,
     }
 
     #[inline]
     pub(crate) fn startup_tabs(&self) -> &[PathBuf] {
         &self.startup.tabs
     }
+
+    #[inline]
+    pub(crate) fn restore_session(&self) -> bool {
+        self.startup.restore_session
+    }
+}
+
+/// Settings of the recursive directory sizes.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub(crate) struct DirSizeConfig {
+    enabled: bool,
+    cross_filesystems: bool,
+}
+
+impl DirSizeConfig {
+    crate::getters! {
+        enabled: bool,
+        cross_filesystems: bool,
+    }
 }
 
 #[derive(Deserialize, Debug, Default)]
 #[serde(default)]
 pub(super) struct StartupConfig {
     pub(super) tabs: Vec<String>,
+    pub(super) restore_session: bool,
 }
 
 #[derive(Debug, Default)]
 pub(crate) struct InternalStartup {
     pub(crate) tabs: Vec<PathBuf>,
+    pub(crate) restore_session: bool,
 }
 
 /// Helper function for default max_find_results
@@ -135,3 +184,24 @@ fn clamp_find_results(value: usize) -> usize {
     }
     clamped
 }
+
+/// Helper function for default history_size
+fn default_history_size() -> usize {
+    DEFAULT_HISTORY_SIZE
+}
+
+/// Helper function for default max_flatten_entries
+fn default_flatten_entries() -> usize {
+    DEFAULT_FLATTEN_ENTRIES
+}
+
+fn clamp_flatten_entries(value: usize) -> usize {
+    let clamped = value.clamp(MIN_FLATTEN_ENTRIES, MAX_FLATTEN_ENTRIES_LIMIT);
+    if clamped != value {
+        eprintln!(
+            "[Warning] max_flatten_entries={} out of range ({}..={}), clamped to {}",
+            value, MIN_FLATTEN_ENTRIES, MAX_FLATTEN_ENTRIES_LIMIT, clamped
+        );
+    }
+    clamped
+}
@@ -0,0 +1,230 @@
+//! Icon configuration for runa.
+//!
+//! This module defines the [IconConfig] struct for deserializing the `[icons]` table of the
+//! runa.toml and the [Icons] struct, which holds the icon set and the icon mappings of the
+//! user, prepared for the lookups while rendering.
+//!
+//! The mappings are looked up before the builtin icons of the icon set.
+
+use std::collections::HashMap;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+
+use crate::core::{FileEntry, query::glob_matches};
+use crate::utils::text;
+
+/// The builtin icons to draw entries with.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum IconSet {
+    /// Nerd Font v3 icons, which need a patched font.
+    #[default]
+    Nerd,
+    Emoji,
+    Ascii,
+}
+
+impl IconSet {
+    /// Returns the width of the icons of the set in columns.
+    pub(crate) fn width(self) -> usize {
+        match self {
+            IconSet::Emoji => 2,
+            IconSet::Nerd | IconSet::Ascii => 1,
+        }
+    }
+}
+
+/// An icon and its color, both optional, as mapped in the `[icons]` table.
+///
+/// Can be written as the icon only, e.g. `rs = ""`, or as a table, e.g.
+/// `rs = { icon = "", color = "#dea584" }`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub(crate) struct IconRule {
+    icon: Option<String>,
+    color: Option<Color>,
+}
+
+impl<'de> Deserialize<'de> for IconRule {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RuleFormat {
+            Short(String),
+            Full {
+                icon: Option<String>,
+                color: Option<String>,
+            },
+        }
+
+        Ok(match RuleFormat::deserialize(deserializer)? {
+            RuleFormat::Short(icon) => IconRule {
+                icon: Some(icon),
+                color: None,
+            },
+            RuleFormat::Full { icon, color } => IconRule {
+                icon,
+                color: color.as_deref().map(text::parse_color),
+            },
+        })
+    }
+}
+
+impl IconRule {
+    pub(crate) fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    crate::getters! {
+        color: Option<Color>,
+    }
+}
+
+/// A `[[icons.glob]]` table as read from the toml file.
+#[derive(Deserialize, Debug)]
+struct GlobRule {
+    pattern: String,
+    #[serde(flatten)]
+    rule: IconRule,
+}
+
+/// The `[icons]` table as read from the toml file.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub(crate) struct IconConfig {
+    set: IconSet,
+    ext: HashMap<String, IconRule>,
+    filename: HashMap<String, IconRule>,
+    dir: HashMap<String, IconRule>,
+    glob: Vec<GlobRule>,
+}
+
+/// The processed `[icons]` table of the config.
+#[derive(Debug, Default)]
+pub(crate) struct Icons {
+    set: IconSet,
+    ext: HashMap<String, IconRule>,
+    filename: HashMap<String, IconRule>,
+    dir: HashMap<String, IconRule>,
+    globs: Vec<(Box<[char]>, IconRule)>,
+}
+
+impl From<IconConfig> for Icons {
+    fn from(config: IconConfig) -> Self {
+        Self {
+            set: config.set,
+            // Extensions of entries are lowercase.
+            ext: config
+                .ext
+                .into_iter()
+                .map(|(ext, rule)| (ext.to_lowercase(), rule))
+                .collect(),
+            filename: config.filename,
+            dir: config.dir,
+            globs: config
+                .glob
+                .into_iter()
+                .map(|glob| (glob.pattern.to_lowercase().chars().collect(), glob.rule))
+                .collect(),
+        }
+    }
+}
+
+impl Icons {
+    crate::getters! {
+        set: IconSet,
+    }
+
+    /// Returns the mapping of the entry.
+    ///
+    /// The exact file or directory name comes first, then the globs in their order and then
+    /// the extension.
+    pub(crate) fn rule(&self, entry: &FileEntry) -> Option<&IconRule> {
+        // Names are relative paths in the flatten view.
+        let name = entry
+            .name_str()
+            .rsplit(std::path::is_separator)
+            .next()
+            .unwrap_or_default();
+        let names = if entry.is_dir() {
+            &self.dir
+        } else {
+            &self.filename
+        };
+
+        names
+            .get(name)
+            .or_else(|| {
+                if self.globs.is_empty() {
+                    return None;
+                }
+                let lowered = entry
+                    .lowered()
+                    .rsplit(std::path::is_separator)
+                    .next()
+                    .unwrap_or_default();
+                self.globs
+                    .iter()
+                    .find(|(pattern, _)| glob_matches(pattern, lowered))
+                    .map(|(_, rule)| rule)
+            })
+            .or_else(|| {
+                let ext = entry.ext().filter(|_| !entry.is_dir())?;
+                self.ext.get(ext)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Theme;
+    use crate::ui::icons::entry_icon;
+    use std::ffi::OsString;
+
+    #[test]
+    fn icon_mappings_come_before_the_icon_set() -> Result<(), Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct Raw {
+            icons: IconConfig,
+        }
+
+        let raw: Raw = toml::from_str(
+            r##"
+            [icons]
+            set = "ascii"
+            ext = { RS = "R", log = { color = "#808080" } }
+            filename = { "justfile" = { icon = "J", color = "yellow" } }
+            dir = { "src" = "S" }
+
+            [[icons.glob]]
+            pattern = "*.test.*"
+            icon = "T"
+            "##,
+        )?;
+        let icons = Icons::from(raw.icons);
+        let theme = Theme::default();
+        let icon = |name: &str, flags: u16| {
+            let entry = FileEntry::new(OsString::from(name), flags, None);
+            entry_icon(&entry, &theme, &icons)
+        };
+
+        assert_eq!(
+            icon("main.rs", 0),
+            ("R", Some(Color::Rgb(0xde, 0xa5, 0x84)))
+        );
+        assert_eq!(icon("justfile", 0), ("J", Some(Color::Yellow)));
+        assert_eq!(icon("src", FileEntry::IS_DIR), ("S", None));
+        assert_eq!(icon("app.test.rs", 0).0, "T");
+        assert_eq!(
+            icon("run.log", 0),
+            ("-", Some(Color::Rgb(0x80, 0x80, 0x80)))
+        );
+        assert_eq!(icon("docs", FileEntry::IS_DIR), ("+", None));
+        assert_eq!(icon("link", FileEntry::IS_SYMLINK), ("@", None));
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct InputKeyLists(pub Box<[String]>);
 
 impl<'de> Deserialize<'de> for InputKeyLists {
@@ -32,22 +32,32 @@ impl<'de> Deserialize<'de> for InputKeyLists {
 
 macro_rules! define_keys {
     ($($variant:ident => $method:ident = [$($default:expr),*]),+ $(,)?) => {
-        #[derive(Deserialize, Debug, Hash, Eq, PartialEq)]
+        #[derive(Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq)]
         #[serde(rename_all = "snake_case")]
         pub(crate) enum InputKeys {
             $($variant),+
         }
 
-        impl Keys {
-            $(
-                #[inline]
-                pub(crate) fn $method(&self) -> &[String] {
-                    self.bindings
-                        .get(&InputKeys::$variant)
-                        .map(|v| v.0.as_ref())
-                        .unwrap_or(&[])
+        impl InputKeys {
+            /// All actions, in the order of their definition.
+            pub(crate) const ALL: &[InputKeys] = &[$(InputKeys::$variant),+];
+
+            /// Returns the name of the action in the `[keys]` table.
+            pub(crate) fn name(self) -> &'static str {
+                match self {
+                    $(InputKeys::$variant => stringify!($method)),+
                 }
-            )+
+            }
+        }
+
+        impl Keys {
+            /// Returns the configured keys of the action.
+            pub(crate) fn get(&self, key: InputKeys) -> &[String] {
+                self.bindings
+                    .get(&key)
+                    .map(|v| v.0.as_ref())
+                    .unwrap_or(&[])
+            }
         }
 
         impl Default for Keys {
@@ -60,17 +70,60 @@ macro_rules! define_keys {
                                 InputKeyLists(Box::new([$($default.into()),*]))
                             )
                         ),+
-                    ])
+                    ]),
+                    input: EditBindings::default(),
+                }
+            }
+        }
+    };
+}
+
+macro_rules! define_edit_keys {
+    ($($variant:ident => $method:ident = emacs [$($emacs:expr),*], vi [$($vi:expr),*]),+ $(,)?) => {
+        /// The editing actions of the prompt.
+        #[derive(Deserialize, Debug, Clone, Copy, Hash, Eq, PartialEq)]
+        #[serde(rename_all = "snake_case")]
+        pub(crate) enum EditKeys {
+            $($variant),+
+        }
+
+        impl EditKeys {
+            /// All editing actions, in the order of their definition.
+            pub(crate) const ALL: &[EditKeys] = &[$(EditKeys::$variant),+];
+
+            /// Returns the name of the action in the `[keys.input]` table.
+            pub(crate) fn name(self) -> &'static str {
+                match self {
+                    $(EditKeys::$variant => stringify!($method)),+
+                }
+            }
+        }
+
+        impl EditPreset {
+            /// Returns the bindings of the preset.
+            fn bindings(self) -> HashMap<EditKeys, InputKeyLists> {
+                match self {
+                    EditPreset::Emacs => HashMap::from([
+                        $((EditKeys::$variant, key_list(&[$($emacs),*]))),+
+                    ]),
+                    EditPreset::ViInsert => HashMap::from([
+                        $((EditKeys::$variant, key_list(&[$($vi),*]))),+
+                    ]),
                 }
             }
         }
     };
 }
 
+fn key_list(keys: &[&str]) -> InputKeyLists {
+    InputKeyLists(keys.iter().map(|k| k.to_string()).collect())
+}
+
 /// Input configuration options of all actions
 #[derive(Debug)]
 pub(crate) struct Keys {
     bindings: HashMap<InputKeys, InputKeyLists>,
+    input: EditBindings,
 }
 
 impl<'de> Deserialize<'de> for Keys {
@@ -78,13 +131,79 @@ impl<'de> Deserialize<'de> for Keys {
     where
         D: serde::Deserializer<'de>,
     {
-        let user_bindings = HashMap::<InputKeys, InputKeyLists>::deserialize(deserializer)?;
+        #[derive(Deserialize)]
+        struct RawKeys {
+            #[serde(default)]
+            input: EditBindings,
+            #[serde(flatten)]
+            bindings: HashMap<InputKeys, InputKeyLists>,
+        }
+
+        let raw = RawKeys::deserialize(deserializer)?;
         let mut keys = Keys::default();
-        keys.bindings.extend(user_bindings);
+        keys.bindings.extend(raw.bindings);
+        keys.input = raw.input;
         Ok(keys)
     }
 }
 
+impl Keys {
+    /// Returns the editing keys of the prompt.
+    pub(crate) fn input(&self) -> &EditBindings {
+        &self.input
+    }
+}
+
+/// The set of editing keys the `[keys.input]` table starts from.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum EditPreset {
+    #[default]
+    Emacs,
+    ViInsert,
+}
+
+/// Editing keys of the prompt, the keys of the preset overridden by the configured ones.
+#[derive(Debug)]
+pub(crate) struct EditBindings {
+    bindings: HashMap<EditKeys, InputKeyLists>,
+}
+
+impl EditBindings {
+    /// Returns the configured keys of the editing action.
+    pub(crate) fn get(&self, key: EditKeys) -> &[String] {
+        self.bindings.get(&key).map(|v| v.0.as_ref()).unwrap_or(&[])
+    }
+}
+
+impl Default for EditBindings {
+    fn default() -> Self {
+        EditBindings {
+            bindings: EditPreset::default().bindings(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EditBindings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawEditBindings {
+            #[serde(default)]
+            preset: EditPreset,
+            #[serde(flatten)]
+            bindings: HashMap<EditKeys, InputKeyLists>,
+        }
+
+        let raw = RawEditBindings::deserialize(deserializer)?;
+        let mut bindings = raw.preset.bindings();
+        bindings.extend(raw.bindings);
+        Ok(EditBindings { bindings })
+    }
+}
+
 define_keys!(
     OpenFile => open_file = ["enter"],
     GoUp => go_up = ["k", "up"],
@@ -109,6 +228,19 @@ define_keys!(
     ClearAll => clear_all = ["<c-l>"],
     AlternateDelete => alternate_delete = ["<m-d>"],
     SelectAll => select_all = ["<c-a>"],
+    MarkMatching => mark_matching = ["*"],
+    UnmarkMatching => unmark_matching = ["U"],
+    InvertMarkers => invert_markers = ["~"],
+    VisualMode => visual_mode = ["V"],
+    VisualSwap => visual_swap = ["o"],
+    Repeat => repeat = ["."],
+    ToggleTree => toggle_tree = ["t"],
+    ToggleFlatten => toggle_flatten = ["F"],
+    PrefixFold => prefix_fold = ["z"],
+    TreeExpand => tree_expand = ["o"],
+    TreeCollapse => tree_collapse = ["c"],
+    TreeToggleFold => tree_toggle_fold = ["a"],
+    TreeCollapseAll => tree_collapse_all = ["M"],
     PrefixGoTo => prefix_go_to = ["g"],
     GoToTop => go_to_top = ["g"],
     GoToHome => go_to_home = ["h"],
@@ -118,6 +250,16 @@ define_keys!(
     TabClose => tab_close = ["<c-w>"],
     TabNext => tab_next = ["<c-n>"],
     TabPrev => tab_prev = ["<c-p>"],
+    PaneSwitch => pane_switch = ["tab", "<s-tab>"],
+    CopyToPane => copy_to_pane = ["<f5>"],
+    MoveToPane => move_to_pane = ["<f6>"],
+    ToggleParent => toggle_parent = ["<m-p>"],
+    TogglePreview => toggle_preview = ["<m-v>"],
+    ZoomPreview => zoom_preview = ["Z"],
+    WidenMain => widen_main = ["]"],
+    NarrowMain => narrow_main = ["["],
+    ResetLayout => reset_layout = ["="],
+    ToggleDetails => toggle_details = ["<m-t>"],
     ScrollUp => scroll_up = ["pgup"],
     ScrollDown => scroll_down = ["pgdn"],
     Sort => sort = ["o"],
@@ -129,9 +271,55 @@ define_keys!(
     SortByAccessed => sort_by_accessed = ["a"],
     SortByCreated => sort_by_created = ["c"],
     KeybindHelp => keybind_help = ["?"],
+    ThemePicker => theme_picker = ["<m-c>"],
     Reload => reload = ["<c-r>"],
+    RecordMacro => record_macro = ["Q"],
+    PlayMacro => play_macro = ["@"],
+    ToggleHidden => toggle_hidden = ["<m-h>"],
+    ToggleSystem => toggle_system = ["<m-s>"],
+    ToggleSymlink => toggle_symlink = ["<m-l>"],
+    ToggleDirsFirst => toggle_dirs_first = ["<m-f>"],
+    ToggleCaseInsensitive => toggle_case_insensitive = ["<m-i>"],
+);
+
+define_edit_keys!(
+    CursorLeft => cursor_left = emacs ["left", "<c-b>"], vi ["left"],
+    CursorRight => cursor_right = emacs ["right", "<c-f>"], vi ["right"],
+    WordLeft => word_left = emacs ["<m-b>", "<c-left>"], vi ["<s-left>", "<c-left>"],
+    WordRight => word_right = emacs ["<m-f>", "<c-right>"], vi ["<s-right>", "<c-right>"],
+    LineStart => line_start = emacs ["home", "<c-a>"], vi ["home"],
+    LineEnd => line_end = emacs ["end", "<c-e>"], vi ["end"],
+    DeleteBack => delete_back = emacs ["back", "<c-h>"], vi ["back", "<c-h>"],
+    DeleteForward => delete_forward = emacs ["delete", "<c-d>"], vi ["delete"],
+    DeleteWordBack => delete_word_back = emacs ["<c-w>", "<m-back>"], vi ["<c-w>"],
+    DeleteWordForward => delete_word_forward = emacs ["<m-d>"], vi [],
+    KillToEnd => kill_to_end = emacs ["<c-k>"], vi [],
+    KillToStart => kill_to_start = emacs ["<c-u>"], vi ["<c-u>"],
+    Yank => yank = emacs ["<c-y>"], vi ["<c-y>"],
+    Transpose => transpose = emacs ["<c-t>"], vi [],
+    HistoryPrev => history_prev = emacs ["up", "<c-p>"], vi ["up", "<c-p>"],
+    HistoryNext => history_next = emacs ["down", "<c-n>"], vi ["down", "<c-n>"],
+    HistorySearch => history_search = emacs ["<c-r>"], vi ["<c-r>"],
 );
 
+/// A named macro: a key sequence which is replayed when one of its bindings is pressed.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub(crate) struct Macro {
+    keys: InputKeyLists,
+    bind: InputKeyLists,
+}
+
+impl Macro {
+    pub(crate) fn keys(&self) -> &[String] {
+        &self.keys.0
+    }
+
+    pub(crate) fn bind(&self) -> &[String] {
+        &self.bind.0
+    }
+}
+
 /// Editor configuration options
 #[derive(Deserialize, Debug)]
 #[serde(default)]
@@ -6,14 +6,16 @@
 //!
 //! Also implements default config initialization when `runa.toml` is not present.
 
-use std::{fs, io, path::PathBuf};
+use std::{collections::BTreeMap, fs, io, path::PathBuf, sync::Arc};
 
 use serde::Deserialize;
 
 use crate::config::{
-    Display, Editor, General, InternalGeneral, Keys, Theme,
+    DirRule, DirRules, Display, Editor, General, InternalGeneral, Keys, Macro, Theme,
     assets::{FULL_TOML, MINIMAL_TOML},
+    icons::{IconConfig, Icons},
 };
+use crate::ui::terminal;
 use crate::utils::os;
 
 /// Raw configuration as read from the toml file
@@ -25,8 +27,12 @@ pub(crate) struct RawConfig {
     general: General,
     display: Display,
     theme: Theme,
+    icons: IconConfig,
     editor: Editor,
     keys: Keys,
+    macros: BTreeMap<String, Macro>,
+    key_groups: BTreeMap<String, String>,
+    dir_rules: Vec<DirRule>,
 }
 
 /// Default values for RawConfig
@@ -37,8 +43,12 @@ impl Default for RawConfig {
             general: General::default(),
             display: Display::default(),
             theme: Theme::default(),
+            icons: IconConfig::default(),
             editor: Editor::default(),
             keys: Keys::default(),
+            macros: BTreeMap::new(),
+            key_groups: BTreeMap::new(),
+            dir_rules: Vec::new(),
         }
     }
 }
@@ -50,8 +60,12 @@ pub(crate) struct Config {
     general: InternalGeneral,
     display: Display,
     theme: Theme,
+    icons: Icons,
     editor: Editor,
     keys: Keys,
+    macros: BTreeMap<String, Macro>,
+    key_groups: BTreeMap<String, String>,
+    dir_rules: Arc<DirRules>,
 }
 
 /// Conversion from RawConfig to Config
@@ -62,8 +76,12 @@ impl From<RawConfig> for Config {
             general: InternalGeneral::from(raw.general),
             display: raw.display,
             theme: raw.theme,
+            icons: Icons::from(raw.icons),
             editor: raw.editor,
             keys: raw.keys,
+            macros: raw.macros,
+            key_groups: raw.key_groups,
+            dir_rules: Arc::new(DirRules::from(raw.dir_rules)),
         }
     }
 }
@@ -71,22 +89,37 @@ impl From<RawConfig> for Config {
 /// Public methods for loading and accessing the configuration
 impl Config {
     /// Load configuration from the default path
-    /// If the file does not exist or fails to parse, returns the default configuration.
+    /// If the file does not exist, returns the default configuration.
     /// Also applies any necessary overrides to the theme after loading.
+    /// If the theme names a `light` or `dark` theme, the terminal background is queried once
+    /// to pick between them.
     ///
     /// Called by entry point to load config at startup.
     pub(crate) fn load() -> Result<Self, String> {
+        Self::load_with_theme(None)
+    }
+
+    /// Load configuration like [Config::load], with the `[theme]` table replaced by the named
+    /// theme if one is given.
+    ///
+    /// Used to preview the themes of the theme picker.
+    pub(crate) fn load_with_theme(theme: Option<&str>) -> Result<Self, String> {
         let path = os::default_config_path();
-        let content = match fs::read_to_string(&path) {
-            Ok(content) => content,
-            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+        let mut raw = match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str::<RawConfig>(&content)
+                .map_err(|e| format!("Config syntax error: {}", e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => RawConfig::default(),
             Err(e) => return Err(format!("Failed to read config file: {}", e)),
         };
 
-        let mut raw = toml::from_str::<RawConfig>(&content)
-            .map_err(|e| format!("Config syntax error: {}", e))?;
-
-        raw.theme = raw.theme.with_overrides();
+        if let Some(name) = theme {
+            raw.theme = Theme::named(name);
+        } else if raw.theme.follows_background() {
+            raw.theme.follow_background(terminal::light_background());
+        }
+        let themes_dir = os::default_themes_dir();
+        raw.theme = raw.theme.with_overrides(themes_dir.as_deref())?;
+        raw.theme.read_ls_colors();
         Ok(raw.into())
     }
 
@@ -94,8 +127,12 @@ impl Config {
         general: &InternalGeneral,
         display: &Display,
         theme: &Theme,
+        icons: &Icons,
         editor: &Editor,
         keys: &Keys,
+        macros: &BTreeMap<String, Macro>,
+        key_groups: &BTreeMap<String, String>,
+        dir_rules: &Arc<DirRules>,
     }
 
     pub(crate) fn bat_args_for_preview(&self, pane_width: usize) -> Vec<String> {
@@ -136,8 +173,12 @@ impl Default for Config {
             general: InternalGeneral::from(General::default()),
             display: Display::default(),
             theme: Theme::default(),
+            icons: Icons::default(),
             editor: Editor::default(),
             keys: Keys::default(),
+            macros: BTreeMap::new(),
+            key_groups: BTreeMap::new(),
+            dir_rules: Arc::default(),
         }
     }
 }
@@ -4,20 +4,26 @@
 //! configuration file.
 //!
 //! Also holds the internal themes and the logic to apply user overrides on top of them.
+//! Themes can also be read from the files of the themes directory, see [files].
 
 mod colorpair;
 mod components;
+mod files;
+mod ls_colors;
 
 use colorpair::ColorPair;
 use components::*;
+use ls_colors::LsColors;
 
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::LazyLock;
 
 use ratatui::style::{Color, Style};
 use serde::{Deserialize, Deserializer};
 
 use crate::config::presets;
+use crate::core::FileEntry;
 use crate::utils::text;
 
 trait ColorFallback {
@@ -34,6 +40,22 @@ impl ColorFallback for Color {
     }
 }
 
+/// Color of an entry which overrides the pane styles.
+pub(crate) enum EntryColor {
+    /// Colors the name, the executable and symlink colors still apply.
+    Name(Style),
+    /// Colors the whole entry, from `LS_COLORS` which take precedence over the theme.
+    Entry(Style),
+}
+
+impl EntryColor {
+    pub(crate) fn style(&self) -> Style {
+        match self {
+            EntryColor::Name(style) | EntryColor::Entry(style) => *style,
+        }
+    }
+}
+
 /// Theme configuration options
 /// Holds all color and style options for the application.
 /// Also holds the internal themes and the logic to apply user overrides on top of them.
@@ -41,6 +63,9 @@ impl ColorFallback for Color {
 #[serde(default)]
 pub(crate) struct Theme {
     name: Option<String>,
+    inherit: Option<String>,
+    light: Option<String>,
+    dark: Option<String>,
     selection: ColorPair,
     underline: ColorPair,
     accent: ColorPair,
@@ -63,7 +88,11 @@ pub(crate) struct Theme {
     widget: WidgetTheme,
     tab: TabTheme,
     info: InfoStatusTheme,
+    ls_colors: LsColors,
 
+    /// The preset the theme is built on, which selects the bat theme
+    #[serde(skip)]
+    preset: Option<String>,
     #[serde(skip)]
     filename_cache: HashMap<String, Style>,
     #[serde(skip)]
@@ -76,6 +105,9 @@ impl Default for Theme {
     fn default() -> Self {
         Theme {
             name: None,
+            inherit: None,
+            light: None,
+            dark: None,
             accent: ColorPair::new(Color::Indexed(238), Color::Reset),
             selection: ColorPair::new(Color::Reset, Color::Indexed(236)),
             underline: ColorPair::default(),
@@ -96,6 +128,8 @@ impl Default for Theme {
             widget: WidgetTheme::default(),
             tab: TabTheme::default(),
             info: InfoStatusTheme::default(),
+            ls_colors: LsColors::default(),
+            preset: None,
             filename_cache: HashMap::new(),
             extension_cache: HashMap::new(),
             icon_color_cache: HashMap::new(),
@@ -103,6 +137,41 @@ impl Default for Theme {
     }
 }
 
+/// The preset themes by name, with the marker icon of each.
+#[rustfmt::skip]
+const PRESETS: &[(&str, Palette, &str)] = &[
+    ("gruvbox-dark-hard", presets::GRUV_DARK_HARD, "*"),
+    ("gruvbox-dark", presets::GRUV_DARK, "*"),
+    ("gruvbox-light", presets::GRUV_LIGHT, "*"),
+
+    ("catppuccin-mocha", presets::MOCHA, "┃"),
+    ("catppuccin-macchiato", presets::MOCHA, "┃"),
+    ("catppuccin-frappe", presets::FRAPPE, "┃"),
+    ("catppuccin-latte", presets::LATTE, "┃"),
+
+    ("nord", presets::NORD, "*"),
+    ("two-dark", presets::TWO_DARK, "*"),
+    ("one-dark", presets::ONE_DARK, "*"),
+
+    ("solarized-dark", presets::SOLARIZED_DARK, "*"),
+    ("solarized-light", presets::SOLARIZED_LIGHT, "*"),
+
+    ("dracula", presets::DRACULA, "┃"),
+    ("monokai", presets::MONOKAI, "┃"),
+    ("nightfox", presets::NIGHTFOX, "┃"),
+    ("carbonfox", presets::CARBON, "┃"),
+
+    ("tokyonight-storm", presets::TOKYO_STORM, "┃"),
+    ("tokyonight-night", presets::TOKYO_NIGHT, "┃"),
+    ("tokyonight-day", presets::TOKYO_DAY, "┃"),
+
+    ("everforest", presets::FOREST, "*"),
+    ("rose-pine", presets::ROSE_PINE, "*"),
+];
+
+/// How many theme files can build on each other, which stops inheritance cycles.
+const MAX_INHERIT_DEPTH: usize = 8;
+
 /// Macro to override a field in the target theme if it differs from the default theme.
 /// This is used to apply user-defined overrides on top of a preset theme.
 macro_rules! override_themes {
@@ -137,6 +206,29 @@ impl Theme {
         &DEFAULT
     }
 
+    /// Creates a theme which only names its base theme.
+    pub(crate) fn named(name: &str) -> Self {
+        Theme {
+            name: Some(name.to_string()),
+            ..Theme::default()
+        }
+    }
+
+    /// Returns the names of the preset themes and the theme files, sorted.
+    pub(crate) fn available(themes_dir: Option<&Path>) -> Vec<String> {
+        let mut names: Vec<String> = PRESETS.iter().map(|(name, ..)| name.to_string()).collect();
+        if let Some(dir) = themes_dir {
+            names.extend(files::names(dir));
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
     crate::getters! {
         exe_color: Color,
         icon_color_cache: &HashMap<String, Color>,
@@ -147,6 +239,7 @@ impl Theme {
         widget: &WidgetTheme,
         info: &InfoStatusTheme,
         tab: &TabTheme,
+        ls_colors: &LsColors,
     }
 
     // Getters for various theme properties with fallbacks to internal defaults
@@ -183,12 +276,35 @@ impl Theme {
         self.symlink.with_fallback(defaults)
     }
 
-    pub(crate) fn entry_color_override(
-        &self,
-        name: &str,
-        is_dir: bool,
-        ext: Option<&str>,
-    ) -> Option<Style> {
+    /// Returns the color of the entry which overrides the pane styles.
+    ///
+    /// The `filename` and `ext` colors of the theme come first, then the colors of
+    /// `LS_COLORS` and then the builtin colors. If `LS_COLORS` takes precedence, its color
+    /// comes before all of them.
+    pub(crate) fn entry_color(&self, entry: &FileEntry) -> Option<EntryColor> {
+        let ls_style = self.ls_colors.style(entry);
+        if self.ls_colors.overrides_theme()
+            && let Some(style) = ls_style
+        {
+            return Some(EntryColor::Entry(style));
+        }
+
+        let (name, is_dir, ext) = (entry.name_str(), entry.is_dir(), entry.ext());
+        self.user_entry_style(name, is_dir, ext)
+            .or(ls_style)
+            .or_else(|| Self::get_default_style(name, ext, is_dir))
+            .map(EntryColor::Name)
+    }
+
+    /// Returns the `LS_COLORS` color of the entry for its icon, if icons are colored by it.
+    pub(crate) fn ls_icon_color(&self, entry: &FileEntry) -> Option<Color> {
+        if !self.ls_colors.icons() {
+            return None;
+        }
+        self.ls_colors.style(entry).and_then(|style| style.fg)
+    }
+
+    fn user_entry_style(&self, name: &str, is_dir: bool, ext: Option<&str>) -> Option<Style> {
         if let Some(s) = self.filename_cache.get(name) {
             return Some(*s);
         }
@@ -199,8 +315,7 @@ impl Theme {
         {
             return Some(*s);
         }
-
-        Self::get_default_style(name, ext, is_dir)
+        None
     }
 
     fn get_default_style(name: &str, ext: Option<&str>, is_dir: bool) -> Option<Style> {
@@ -225,71 +340,107 @@ impl Theme {
         None
     }
 
-    /// Apply user overrides on top of a preset theme if a known preset name is provided.
-    /// If no preset name is provided or the name is unknown, returns the theme as is.
+    /// Reads the colors of `LS_COLORS` and `EZA_COLORS`, if `[theme.ls_colors]` enables them.
+    pub(super) fn read_ls_colors(&mut self) {
+        self.ls_colors.read_env();
+    }
+
+    /// Returns true if the theme names a theme for light or dark terminal backgrounds.
+    pub(super) fn follows_background(&self) -> bool {
+        self.light.is_some() || self.dark.is_some()
+    }
+
+    /// Builds the theme on the `light` or `dark` theme, whichever matches the background.
+    ///
+    /// If the background is not known, the theme stays on its own base, or on the `dark`
+    /// theme if it names none.
+    pub(super) fn follow_background(&mut self, light: Option<bool>) {
+        let variant = match light {
+            Some(true) => self.light.take(),
+            Some(false) => self.dark.take(),
+            None if self.inherit.is_none() && self.name.is_none() => self.dark.take(),
+            None => None,
+        };
+        if variant.is_some() {
+            self.inherit = variant;
+        }
+    }
+
+    /// Apply user overrides on top of the base theme.
+    ///
+    /// The base is named by `inherit`, or by `name` if there is no `inherit`. Theme files of
+    /// the themes directory are looked up before the presets. If no base is named or the name
+    /// is unknown, returns the theme as is.
     #[inline(never)]
-    pub(super) fn with_overrides(mut self) -> Self {
-        let preset_name = self.name.clone();
+    pub(super) fn with_overrides(self, themes_dir: Option<&Path>) -> Result<Self, String> {
+        let base = self.inherit.clone().or_else(|| self.name.clone());
+        self.resolve(base.as_deref(), themes_dir, 0)
+    }
+
+    fn resolve(
+        mut self,
+        base_name: Option<&str>,
+        themes_dir: Option<&Path>,
+        depth: usize,
+    ) -> Result<Self, String> {
         let defaults = Theme::builtin();
 
         if self.accent != defaults.accent && self.separator == defaults.separator {
             self.separator = self.accent;
         }
 
-        if let Some(name) = preset_name.as_deref()
-            && let Some(mut base) = Self::get_preset_by_name(name)
+        if let Some(name) = base_name
+            && let Some(mut base) = Self::base_theme(name, themes_dir, depth)?
         {
             base.apply_user_overrides(self);
             base.build_style_maps();
-            return base;
+            return Ok(base);
         }
 
         self.build_style_maps();
-        self
+        Ok(self)
+    }
+
+    /// Returns the theme file or the preset of the name, resolved on top of its own base.
+    fn base_theme(
+        name: &str,
+        themes_dir: Option<&Path>,
+        depth: usize,
+    ) -> Result<Option<Theme>, String> {
+        if depth < MAX_INHERIT_DEPTH
+            && let Some(dir) = themes_dir
+            && let Some(theme) = files::load(dir, name)?
+        {
+            // A theme file only builds on the theme named by its `inherit`.
+            let inherit = theme.inherit.clone();
+            return theme
+                .resolve(inherit.as_deref(), themes_dir, depth + 1)
+                .map(Some);
+        }
+        Ok(Self::get_preset_by_name(name))
     }
 
     #[inline(never)]
     fn get_preset_by_name(name: &str) -> Option<Theme> {
-        let (palette, icon) = match name {
-            "gruvbox-dark-hard" => (presets::GRUV_DARK_HARD, "*"),
-            "gruvbox-dark" => (presets::GRUV_DARK, "*"),
-            "gruvbox-light" => (presets::GRUV_LIGHT, "*"),
-
-            "catppuccin-mocha" | "catppuccin-macchiato" => (presets::MOCHA, "┃"),
-            "catppuccin-frappe" => (presets::FRAPPE, "┃"),
-            "catppuccin-latte" => (presets::LATTE, "┃"),
-
-            "nord" => (presets::NORD, "*"),
-            "two-dark" => (presets::TWO_DARK, "*"),
-            "one-dark" => (presets::ONE_DARK, "*"),
-
-            "solarized-dark" => (presets::SOLARIZED_DARK, "*"),
-            "solarized-light" => (presets::SOLARIZED_LIGHT, "*"),
-
-            "dracula" => (presets::DRACULA, "┃"),
-            "monokai" => (presets::MONOKAI, "┃"),
-            "nightfox" => (presets::NIGHTFOX, "┃"),
-            "carbonfox" => (presets::CARBON, "┃"),
-
-            "tokyonight-storm" => (presets::TOKYO_STORM, "┃"),
-            "tokyonight" | "tokyonight-night" => (presets::TOKYO_NIGHT, "┃"),
-            "tokyonight-day" => (presets::TOKYO_DAY, "┃"),
-
-            "everforest" => (presets::FOREST, "*"),
-            "rose-pine" | "rose_pine" => (presets::ROSE_PINE, "*"),
-
-            _ => return None,
+        let name = match name {
+            "tokyonight" => "tokyonight-night",
+            "rose_pine" => "rose-pine",
+            name => name,
         };
+        let (name, palette, icon) = PRESETS.iter().find(|(preset, ..)| *preset == name)?;
 
-        // One single call-site for make_theme prevents monomorphization bloat
-        Some(make_theme(name, palette, icon))
+        let mut theme = make_theme(name, palette, icon);
+        theme.preset = Some(name.to_string());
+        Some(theme)
     }
 
     /// Map internal theme name to bat theme name for syntax highlighting.
+    /// Themes built on a preset use the bat theme of the preset.
     /// If no name is set, defaults to "TwoDark".
     pub(super) fn bat_theme_name(&self) -> &'static str {
-        self.name
+        self.preset
             .as_deref()
+            .or(self.name.as_deref())
             .map(Theme::map_to_bat_theme)
             .unwrap_or("TwoDark")
     }
@@ -304,6 +455,7 @@ impl Theme {
             "gruvbox-dark" | "gruvbox-dark-hard" | "gruvbox" => "gruvbox-dark",
             "gruvbox-light" => "gruvbox-light",
             "tokyonight-night" | "tokyonight" | "tokyonight-storm" => "TwoDark",
+            "tokyonight-day" => "OneHalfLight",
             "catppuccin-latte" => "Catppuccin Latte",
             "catppuccin-frappe" => "Catppuccin Frappe",
             "catppuccin-macchiato" => "Catppuccin Macchiato",
@@ -345,6 +497,7 @@ impl Theme {
             widget,
             tab,
             info,
+            ls_colors,
         ]);
 
         if user.name.is_some() {
@@ -412,8 +565,8 @@ pub(super) struct Palette {
 }
 
 /// Centralized function to create a Theme from a Palette.
-/// Used by all internal themes to avoid code duplication.
-fn make_theme(name: &str, palette: Palette, icon: &str) -> Theme {
+/// Used by all internal themes and the imported base16 schemes to avoid code duplication.
+fn make_theme(name: &str, palette: &Palette, icon: &str) -> Theme {
     let primary = rgb(palette.primary);
     let secondary = rgb(palette.secondary);
     let muted = rgb(palette.overlay);
@@ -437,3 +590,39 @@ fn make_theme(name: &str, palette: Palette, icon: &str) -> Theme {
         ..Theme::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_follows_the_terminal_background() -> Result<(), Box<dyn std::error::Error>> {
+        let variants = "light = \"tokyonight-day\"\ndark = \"tokyonight-night\"\n";
+        let resolve =
+            |toml: &str, light: Option<bool>| -> Result<Theme, Box<dyn std::error::Error>> {
+                let mut theme: Theme = toml::from_str(toml)?;
+                assert!(theme.follows_background());
+                theme.follow_background(light);
+                Ok(theme.with_overrides(None)?)
+            };
+
+        assert_eq!(
+            resolve(variants, Some(true))?.name(),
+            Some("tokyonight-day")
+        );
+        assert_eq!(
+            resolve(variants, Some(false))?.name(),
+            Some("tokyonight-night")
+        );
+        assert_eq!(resolve(variants, None)?.name(), Some("tokyonight-night"));
+
+        // Without a detected background the named theme stays.
+        let named = format!("name = \"nord\"\n{variants}");
+        assert_eq!(resolve(&named, None)?.name(), Some("nord"));
+        assert_eq!(
+            resolve(&named, Some(true))?.bat_theme_name(),
+            "OneHalfLight"
+        );
+        Ok(())
+    }
+}
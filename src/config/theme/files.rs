@@ -0,0 +1,168 @@
+//! Theme files of the themes directory.
+//!
+//! The themes directory is `themes/` next to `runa.toml`. A theme is read from `<name>.toml`,
+//! which holds the same keys as the `[theme]` table, or from a base16/base24 scheme in
+//! `<name>.yaml` or `<name>.yml`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config::theme::{Palette, Theme, make_theme};
+
+const EXTENSIONS: [&str; 3] = ["toml", "yaml", "yml"];
+
+/// Returns the names of the theme files in the directory.
+pub(super) fn names(dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let ext = path.extension()?.to_str()?;
+            if !EXTENSIONS.contains(&ext) {
+                return None;
+            }
+            path.file_stem()?.to_str().map(str::to_string)
+        })
+        .collect()
+}
+
+/// Reads the theme file of the name, if there is one.
+///
+/// The theme is named after the file unless it sets a name itself. A scheme becomes a theme
+/// like the presets do.
+pub(super) fn load(dir: &Path, name: &str) -> Result<Option<Theme>, String> {
+    // Names are plain file names, never paths.
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Ok(None);
+    }
+
+    for ext in EXTENSIONS {
+        let path = dir.join(format!("{name}.{ext}"));
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read theme {}: {}", path.display(), e)),
+        };
+
+        if ext == "toml" {
+            let mut theme: Theme = toml::from_str(&content)
+                .map_err(|e| format!("Theme syntax error in {}: {}", path.display(), e))?;
+            theme.name.get_or_insert_with(|| name.to_string());
+            return Ok(Some(theme));
+        }
+
+        let palette = parse_scheme(&content)
+            .ok_or_else(|| format!("Theme {} is not a base16 scheme", path.display()))?;
+        return Ok(Some(make_theme(name, &palette, "*")));
+    }
+    Ok(None)
+}
+
+/// Reads the colors of a base16 or base24 scheme.
+///
+/// Only the `baseXX: "rrggbb"` lines are read, so both the flat schemes and the ones with the
+/// colors below `palette:` work. The extra colors of base24 are not used.
+fn parse_scheme(content: &str) -> Option<Palette> {
+    let mut colors = HashMap::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        let key = key.trim().trim_matches(['"', '\'']).to_ascii_lowercase();
+        if !key.starts_with("base") {
+            continue;
+        }
+        let value = value.split_whitespace().next().unwrap_or_default();
+        let hex = value.trim_matches(['"', '\'']).trim_start_matches('#');
+        if let Some(color) = parse_hex(hex) {
+            colors.insert(key, color);
+        }
+    }
+
+    let color = |key: &str| colors.get(key).copied();
+    Some(Palette {
+        base: color("base00")?,
+        surface: color("base01")?,
+        overlay: color("base03")?,
+        primary: color("base0e")?,
+        secondary: color("base0c")?,
+        directory: color("base0d")?,
+    })
+}
+
+fn parse_hex(hex: &str) -> Option<(u8, u8, u8)> {
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    Some(((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn theme_files_inherit_and_import_schemes() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        fs::write(
+            dir.path().join("mine.toml"),
+            "inherit = \"nord\"\nselection_icon = \">\"\n",
+        )?;
+        fs::write(dir.path().join("loop.toml"), "inherit = \"loop\"\n")?;
+        fs::write(
+            dir.path().join("ocean.yaml"),
+            r##"
+system: "base16"
+name: "Ocean"
+palette:
+  base00: "#2b303b" # background
+  base01: "#343d46"
+  base03: "#65737e"
+  base0C: "#96b5b4"
+  base0D: "#8fa1b3"
+  base0E: "#b48ead"
+"##,
+        )?;
+        let themes = Some(dir.path());
+
+        // Only the overridden components differ from the preset.
+        let theme = Theme::named("mine").with_overrides(themes)?;
+        let nord = Theme::named("nord").with_overrides(None)?;
+        assert_eq!(theme.name(), Some("mine"));
+        assert_eq!(theme.selection_icon(), ">");
+        assert_eq!(theme.directory_style(), nord.directory_style());
+        assert_eq!(theme.bat_theme_name(), "Nord");
+
+        let theme = Theme::named("ocean").with_overrides(themes)?;
+        assert_eq!(
+            theme.selection_style().bg,
+            Some(Color::Rgb(0x34, 0x3d, 0x46))
+        );
+        assert_eq!(
+            theme.directory_style().fg,
+            Some(Color::Rgb(0x8f, 0xa1, 0xb3))
+        );
+
+        // Inheritance cycles end at the depth limit.
+        assert_eq!(
+            Theme::named("loop").with_overrides(themes)?.name(),
+            Some("loop")
+        );
+
+        fs::write(dir.path().join("broken.toml"), "selection = [")?;
+        assert!(Theme::named("broken").with_overrides(themes).is_err());
+
+        let names = Theme::available(themes);
+        for name in ["mine", "ocean", "nord", "tokyonight-night"] {
+            assert!(names.iter().any(|n| n == name), "{name} is not listed");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,275 @@
+//! Entry colors of `LS_COLORS` and `EZA_COLORS`.
+//!
+//! Both variables hold `key=sgr` pairs separated by `:`. Two letter keys such as `di` or `ex`
+//! color a file type, all other keys are patterns like `*.tar` or `*README*` which are matched
+//! against the file name, ignoring the case. `EZA_COLORS` is read after `LS_COLORS`, so its
+//! colors win, and its `reset` key drops the colors read before it.
+
+use std::collections::HashMap;
+use std::env;
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::core::{FileEntry, query::glob_matches};
+
+/// Which colors win if both the theme and `LS_COLORS` color an entry.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+enum LsPrecedence {
+    #[default]
+    Theme,
+    LsColors,
+}
+
+/// The `[theme.ls_colors]` options and the colors read from the environment.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub(crate) struct LsColors {
+    enabled: bool,
+    precedence: LsPrecedence,
+    icons: bool,
+
+    #[serde(skip)]
+    types: HashMap<String, Style>,
+    #[serde(skip)]
+    extensions: HashMap<String, Style>,
+    /// Patterns which are not a plain extension, newest first.
+    #[serde(skip)]
+    globs: Vec<(Vec<char>, Style)>,
+    /// Set by `ln=target`, which colors symlinks like the file they point to.
+    #[serde(skip)]
+    link_as_target: bool,
+}
+
+impl LsColors {
+    crate::getters! {
+        icons: bool,
+    }
+
+    /// Returns true if the colors of `LS_COLORS` win over the theme colors.
+    pub(crate) fn overrides_theme(&self) -> bool {
+        self.enabled && self.precedence == LsPrecedence::LsColors
+    }
+
+    /// Reads `LS_COLORS` and `EZA_COLORS`, if enabled.
+    pub(super) fn read_env(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        for var in ["LS_COLORS", "EZA_COLORS"] {
+            if let Ok(value) = env::var(var) {
+                self.parse(&value);
+            }
+        }
+    }
+
+    fn parse(&mut self, value: &str) {
+        for pair in value.split(':') {
+            if pair == "reset" {
+                self.types.clear();
+                self.extensions.clear();
+                self.globs.clear();
+                self.link_as_target = false;
+                continue;
+            }
+            let Some((key, sgr)) = pair.split_once('=') else {
+                continue;
+            };
+            if key == "ln" && sgr == "target" {
+                self.link_as_target = true;
+                continue;
+            }
+            let Some(style) = parse_sgr(sgr) else {
+                continue;
+            };
+
+            let is_type = key.len() == 2 && key.bytes().all(|b| b.is_ascii_lowercase());
+            if is_type {
+                if key == "ln" {
+                    self.link_as_target = false;
+                }
+                self.types.insert(key.to_string(), style);
+                continue;
+            }
+
+            let pattern = key.to_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(ext) if !ext.contains(['.', '*', '?']) => {
+                    self.extensions.insert(ext.to_string(), style);
+                }
+                _ => self.globs.insert(0, (pattern.chars().collect(), style)),
+            }
+        }
+    }
+
+    /// Returns the color of the entry.
+    ///
+    /// If the theme takes precedence, directories, symlinks and executables keep the theme
+    /// colors and so do regular files which match no pattern.
+    pub(crate) fn style(&self, entry: &FileEntry) -> Option<Style> {
+        if !self.enabled {
+            return None;
+        }
+        let theme_first = !self.overrides_theme();
+
+        // Broken symlinks have no target to take the color of.
+        let as_link = !self.link_as_target || entry.is_broken_sym();
+        let code = if entry.is_symlink() && as_link {
+            if entry.is_broken_sym() && self.types.contains_key("or") {
+                "or"
+            } else {
+                "ln"
+            }
+        } else if entry.is_dir() {
+            "di"
+        } else if entry.is_socket() {
+            "so"
+        } else if entry.is_fifo() {
+            "pi"
+        } else if entry.is_block_device() {
+            "bd"
+        } else if entry.is_char_device() {
+            "cd"
+        } else if entry.is_executable() {
+            "ex"
+        } else {
+            return self
+                .pattern_style(entry)
+                .or_else(|| self.types.get("fi").copied().filter(|_| !theme_first));
+        };
+
+        match code {
+            "ln" | "or" | "di" | "ex" if theme_first => None,
+            _ => self.types.get(code).copied(),
+        }
+    }
+
+    fn pattern_style(&self, entry: &FileEntry) -> Option<Style> {
+        // Names are relative paths in the flatten view.
+        let name = entry
+            .lowered()
+            .rsplit(std::path::is_separator)
+            .next()
+            .unwrap_or_default();
+        self.globs
+            .iter()
+            .find(|(pattern, _)| glob_matches(pattern, name))
+            .map(|(_, style)| *style)
+            .or_else(|| {
+                entry
+                    .ext()
+                    .and_then(|ext| self.extensions.get(ext).copied())
+            })
+    }
+}
+
+/// Reads the SGR codes of a color like `01;34` or `38;5;208`.
+///
+/// Returns None for invalid codes and for codes which only reset the style.
+fn parse_sgr(sgr: &str) -> Option<Style> {
+    let codes = sgr
+        .split(';')
+        .map(|code| if code.is_empty() { Ok(0) } else { code.parse() })
+        .collect::<Result<Vec<u8>, _>>()
+        .ok()?;
+
+    let mut style = Style::new();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::new(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            38 | 48 => {
+                let color = match codes.next()? {
+                    5 => Color::Indexed(codes.next()?),
+                    2 => Color::Rgb(codes.next()?, codes.next()?, codes.next()?),
+                    _ => return None,
+                };
+                if code == 38 {
+                    style.fg(color)
+                } else {
+                    style.bg(color)
+                }
+            }
+            39 => style.fg(Color::Reset),
+            49 => style.bg(Color::Reset),
+            _ => style,
+        };
+    }
+    (style != Style::new()).then_some(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsString;
+
+    #[test]
+    fn ls_colors_color_types_and_patterns() {
+        let entry = |name: &str, flags: u16| FileEntry::new(OsString::from(name), flags, None);
+        let mut ls = LsColors {
+            enabled: true,
+            ..LsColors::default()
+        };
+        ls.parse("di=01;34:ln=01;36:ex=01;32:pi=33:mh=00:*.tar=01;31:*.TXT=38;5;208:*README*=4");
+
+        let archive = Style::new()
+            .fg(Color::Indexed(1))
+            .add_modifier(Modifier::BOLD);
+        assert_eq!(ls.style(&entry("a.tar", 0)), Some(archive));
+        assert_eq!(
+            ls.style(&entry("src/notes.txt", 0)),
+            Some(Style::new().fg(Color::Indexed(208)))
+        );
+        assert_eq!(
+            ls.style(&entry("README.md", 0)),
+            Some(Style::new().add_modifier(Modifier::UNDERLINED))
+        );
+        assert_eq!(
+            ls.style(&entry("fifo", FileEntry::IS_FIFO)),
+            Some(Style::new().fg(Color::Indexed(3)))
+        );
+
+        // The theme keeps its own colors for directories and executables.
+        assert_eq!(ls.style(&entry("src", FileEntry::IS_DIR)), None);
+        assert_eq!(ls.style(&entry("run.tar", FileEntry::IS_EXECUTABLE)), None);
+
+        ls.precedence = LsPrecedence::LsColors;
+        assert_eq!(
+            ls.style(&entry("src", FileEntry::IS_DIR)),
+            Some(
+                Style::new()
+                    .fg(Color::Indexed(4))
+                    .add_modifier(Modifier::BOLD)
+            )
+        );
+        assert_eq!(
+            ls.style(&entry("run.tar", FileEntry::IS_EXECUTABLE)),
+            Some(
+                Style::new()
+                    .fg(Color::Indexed(2))
+                    .add_modifier(Modifier::BOLD)
+            )
+        );
+
+        // EZA_COLORS can drop the colors of LS_COLORS.
+        ls.parse("reset:*.rs=38;2;222;165;132");
+        assert_eq!(ls.style(&entry("a.tar", 0)), None);
+        assert_eq!(
+            ls.style(&entry("main.rs", 0)),
+            Some(Style::new().fg(Color::Rgb(222, 165, 132)))
+        );
+    }
+}
@@ -9,14 +9,22 @@
 //! - [metadata]: file metadata extraction and caching, including file properties.
 //! - [cache]: caching of FileEntry data for pane rendering.
 //! - [sort]: sorting configuration data for entry sorting.
+//! - [query]: filter query language used by the directory filter and pattern marking.
+//! - [details]: detail columns of the details view.
+//! - [dir_size]: recursive directory sizes and their cache.
+//! - [status]: status segments computed on the status worker thread.
 
 pub(crate) mod cache;
+pub(crate) mod details;
+pub(crate) mod dir_size;
 pub(crate) mod fm;
 pub(crate) mod formatter;
 pub(crate) mod fs;
 pub(crate) mod metadata;
 pub(crate) mod proc;
+pub(crate) mod query;
 pub(crate) mod sort;
+pub(crate) mod status;
 pub(crate) mod workers;
 
 pub(crate) use fm::FileEntry;
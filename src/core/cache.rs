@@ -23,6 +23,71 @@ pub(crate) struct DirListOptions {
     pub(crate) case_insensitive: bool,
 }
 
+/// A single option of the [DirListOptions].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ListOption {
+    DirsFirst,
+    ShowHidden,
+    ShowSymlink,
+    ShowSystem,
+    CaseInsensitive,
+}
+
+/// Listing options toggled at runtime.
+///
+/// They take precedence over the config and the directory rules.
+/// Since the options are part of the cache key, a toggled listing never uses
+/// a cached listing of the other option value.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct ListOverrides {
+    dirs_first: Option<bool>,
+    show_hidden: Option<bool>,
+    show_symlink: Option<bool>,
+    show_system: Option<bool>,
+    case_insensitive: Option<bool>,
+}
+
+impl DirListOptions {
+    pub(crate) fn get(&self, option: ListOption) -> bool {
+        match option {
+            ListOption::DirsFirst => self.dirs_first,
+            ListOption::ShowHidden => self.show_hidden,
+            ListOption::ShowSymlink => self.show_symlink,
+            ListOption::ShowSystem => self.show_system,
+            ListOption::CaseInsensitive => self.case_insensitive,
+        }
+    }
+}
+
+impl ListOverrides {
+    fn slot(&mut self, option: ListOption) -> &mut Option<bool> {
+        match option {
+            ListOption::DirsFirst => &mut self.dirs_first,
+            ListOption::ShowHidden => &mut self.show_hidden,
+            ListOption::ShowSymlink => &mut self.show_symlink,
+            ListOption::ShowSystem => &mut self.show_system,
+            ListOption::CaseInsensitive => &mut self.case_insensitive,
+        }
+    }
+
+    /// Overrides the option with the opposite of its value in `current`.
+    /// Returns the new value.
+    pub(crate) fn toggle(&mut self, option: ListOption, current: &DirListOptions) -> bool {
+        let value = !current.get(option);
+        *self.slot(option) = Some(value);
+        value
+    }
+
+    /// Applies the overridden options to the listing options.
+    pub(crate) fn apply(&self, list: &mut DirListOptions) {
+        list.dirs_first = self.dirs_first.unwrap_or(list.dirs_first);
+        list.show_hidden = self.show_hidden.unwrap_or(list.show_hidden);
+        list.show_symlink = self.show_symlink.unwrap_or(list.show_symlink);
+        list.show_system = self.show_system.unwrap_or(list.show_system);
+        list.case_insensitive = self.case_insensitive.unwrap_or(list.case_insensitive);
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 struct DirCacheKey {
     path: Arc<Path>,
@@ -103,3 +168,38 @@ impl DirCache {
         self.inner.retain(|key, _| &*key.path != path);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_overrides_use_their_own_cache_entries() {
+        let config = DirListOptions {
+            dirs_first: true,
+            show_hidden: false,
+            show_symlink: true,
+            show_system: false,
+            case_insensitive: true,
+        };
+        let mut overrides = ListOverrides::default();
+        assert!(overrides.toggle(ListOption::ShowHidden, &config));
+
+        let mut toggled = config.clone();
+        overrides.apply(&mut toggled);
+        assert!(toggled.show_hidden);
+        assert!(toggled.dirs_first);
+
+        let cache = DirCache::new();
+        let path = Path::new("/tmp/runa");
+        let sort = SortConfig::default();
+        cache.insert_if_newer(path, sort, &config, Arc::from(Vec::new()), None, 1);
+        assert!(cache.get(path, sort, &config).is_some());
+        assert!(cache.get(path, sort, &toggled).is_none());
+
+        cache.insert_if_newer(path, sort, &toggled, Arc::from(Vec::new()), None, 2);
+        cache.invalidate_path(path);
+        assert!(cache.get(path, sort, &config).is_none());
+        assert!(cache.get(path, sort, &toggled).is_none());
+    }
+}
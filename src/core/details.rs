@@ -0,0 +1,201 @@
+//! Detail columns of the details view for runa.
+//!
+//! Defines the [DetailColumn]s which can be shown next to the entry names of the main pane
+//! and [collect_details], which reads and formats them for a set of entries.
+//!
+//! Only the rows visible in the main pane are collected, on the details worker thread.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::core::{
+    formatter::{self, TimeFormatCtx},
+    sort::SortMode,
+};
+
+/// A column of the details view.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum DetailColumn {
+    Size,
+    Modified,
+    Created,
+    Permissions,
+    Owner,
+    Group,
+    Items,
+    Extension,
+}
+
+/// The formatted values of an entry, in the order of the requested columns.
+pub(crate) type DetailRow = Box<[Arc<str>]>;
+
+impl DetailColumn {
+    /// Title shown in the header of the details view.
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            DetailColumn::Size => "Size",
+            DetailColumn::Modified => "Modified",
+            DetailColumn::Created => "Created",
+            DetailColumn::Permissions => "Permissions",
+            DetailColumn::Owner => "Owner",
+            DetailColumn::Group => "Group",
+            DetailColumn::Items => "Items",
+            DetailColumn::Extension => "Ext",
+        }
+    }
+
+    /// Returns the sort mode which sorts by this column.
+    pub(crate) fn sort_mode(self) -> Option<SortMode> {
+        match self {
+            DetailColumn::Size => Some(SortMode::Size),
+            DetailColumn::Modified => Some(SortMode::Modified),
+            DetailColumn::Created => Some(SortMode::Created),
+            DetailColumn::Extension => Some(SortMode::Extension),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the detail columns of the entries in `dir`.
+///
+/// Entries are given by their path relative to `dir`, so nested tree rows can be collected as well.
+/// Entries which vanished are skipped.
+pub(crate) fn collect_details(
+    dir: &Path,
+    names: &[OsString],
+    columns: &[DetailColumn],
+    date_format: &str,
+    #[cfg(unix)] ug_cache: &mut crate::core::metadata::unix_meta::UserGroupCache,
+) -> Vec<(OsString, DetailRow)> {
+    let ctx = TimeFormatCtx::new(date_format, Local::now());
+    let mut rows = Vec::with_capacity(names.len());
+
+    for name in names {
+        let path = dir.join(name);
+        let Ok(md) = fs::symlink_metadata(&path) else {
+            continue;
+        };
+
+        let row = columns
+            .iter()
+            .map(|column| -> Arc<str> {
+                match column {
+                    DetailColumn::Size => {
+                        formatter::format_file_size(md.is_file().then_some(md.len()), md.is_dir())
+                            .into()
+                    }
+                    DetailColumn::Modified => {
+                        formatter::format_file_time(md.modified().ok(), &ctx).into()
+                    }
+                    DetailColumn::Created => {
+                        formatter::format_file_time(md.created().ok(), &ctx).into()
+                    }
+                    DetailColumn::Permissions => formatter::format_attributes(&md).into(),
+                    DetailColumn::Owner => owner_of(
+                        &md,
+                        #[cfg(unix)]
+                        ug_cache,
+                    ),
+                    DetailColumn::Group => group_of(
+                        &md,
+                        #[cfg(unix)]
+                        ug_cache,
+                    ),
+                    DetailColumn::Items => item_count(&path, &md),
+                    DetailColumn::Extension => path
+                        .extension()
+                        .filter(|_| !md.is_dir())
+                        .map_or_else(|| "-".into(), |ext| ext.to_string_lossy().into()),
+                }
+            })
+            .collect();
+        rows.push((name.clone(), row));
+    }
+    rows
+}
+
+/// Counts the entries of a directory. Files and unreadable directories show `-`.
+fn item_count(path: &Path, md: &fs::Metadata) -> Arc<str> {
+    let is_dir = md.is_dir() || (md.file_type().is_symlink() && path.is_dir());
+    if !is_dir {
+        return "-".into();
+    }
+    fs::read_dir(path).map_or_else(|_| "-".into(), |dir| dir.count().to_string().into())
+}
+
+#[cfg(unix)]
+fn owner_of(
+    md: &fs::Metadata,
+    ug_cache: &mut crate::core::metadata::unix_meta::UserGroupCache,
+) -> Arc<str> {
+    use std::os::unix::fs::MetadataExt;
+    ug_cache.resolve_user(md.uid())
+}
+
+#[cfg(unix)]
+fn group_of(
+    md: &fs::Metadata,
+    ug_cache: &mut crate::core::metadata::unix_meta::UserGroupCache,
+) -> Arc<str> {
+    use std::os::unix::fs::MetadataExt;
+    ug_cache.resolve_group(md.gid())
+}
+
+#[cfg(not(unix))]
+fn owner_of(_md: &fs::Metadata) -> Arc<str> {
+    "-".into()
+}
+
+#[cfg(not(unix))]
+fn group_of(_md: &fs::Metadata) -> Arc<str> {
+    "-".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn collect_details_formats_requested_columns() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::write(dir.path().join("notes.txt"), "hello")?;
+        fs::create_dir_all(dir.path().join("sub/inner"))?;
+        fs::write(dir.path().join("sub/a.rs"), "")?;
+
+        #[cfg(unix)]
+        let mut ug_cache = crate::core::metadata::unix_meta::UserGroupCache::new();
+        let names: Vec<OsString> = ["notes.txt", "sub", "sub/a.rs", "gone"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        let rows = collect_details(
+            dir.path(),
+            &names,
+            &[
+                DetailColumn::Size,
+                DetailColumn::Items,
+                DetailColumn::Extension,
+            ],
+            "%Y-%m-%d",
+            #[cfg(unix)]
+            &mut ug_cache,
+        );
+
+        assert_eq!(rows.len(), 3);
+        let row = |name: &str| rows.iter().find(|(n, _)| n == name).map(|(_, r)| r);
+        assert_eq!(row("notes.txt").map(|r| &*r[0]), Some("5 B"));
+        assert_eq!(row("notes.txt").map(|r| &*r[1]), Some("-"));
+        assert_eq!(row("notes.txt").map(|r| &*r[2]), Some("txt"));
+        assert_eq!(row("sub").map(|r| &*r[0]), Some("-"));
+        assert_eq!(row("sub").map(|r| &*r[1]), Some("2"));
+        assert_eq!(row("sub/a.rs").map(|r| &*r[2]), Some("rs"));
+        Ok(())
+    }
+}
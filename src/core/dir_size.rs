@@ -0,0 +1,245 @@
+//! Recursive directory sizes for runa.
+//!
+//! [dir_size] sums up the sizes of all files below a directory on the dir size worker thread.
+//! The results are kept in the [DirSizeCache], which is shared by all tabs and used by the
+//! size sorting of the [Formatter](crate::core::Formatter).
+//!
+//! A changed directory invalidates its own size and the sizes of all its ancestors. Changes
+//! deeper below a measured directory are not reported by the watcher, so cached sizes are
+//! only used for [DIR_SIZE_MAX_AGE] and calculated again after that.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+// Limit of cached sizes to prevent memory growth while browsing many directories.
+const DIR_SIZE_CACHE_LIMIT: usize = 20_000;
+
+/// How long a calculated size is used before it is calculated again.
+pub(crate) const DIR_SIZE_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// A calculated size and when it was calculated.
+#[derive(Debug, Clone, Copy)]
+struct CachedSize {
+    size: u64,
+    computed: Instant,
+}
+
+/// Cache of the computed recursive directory sizes.
+///
+/// Sizes are keyed by whether they cross filesystems and by the path, so changing
+/// `general.dir_sizes.cross_filesystems` never serves sizes of the other mode.
+pub(crate) struct DirSizeCache {
+    /// The sizes within one filesystem at 0 and across filesystems at 1.
+    inner: [DashMap<PathBuf, CachedSize>; 2],
+    /// The mode of the sizes read by [DirSizeCache::get].
+    cross_fs: AtomicBool,
+}
+
+impl DirSizeCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: [DashMap::new(), DashMap::new()],
+            cross_fs: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets whether the sizes read by [DirSizeCache::get] cross filesystems.
+    pub(crate) fn set_cross_fs(&self, cross_fs: bool) {
+        self.cross_fs.store(cross_fs, Ordering::Relaxed);
+    }
+
+    /// Returns the size of the directory in the mode of the config.
+    pub(crate) fn get(&self, path: &Path) -> Option<u64> {
+        self.get_in(path, self.cross_fs.load(Ordering::Relaxed))
+    }
+
+    /// Returns the size of the directory in the mode, if it is not older than [DIR_SIZE_MAX_AGE].
+    pub(crate) fn get_in(&self, path: &Path, cross_fs: bool) -> Option<u64> {
+        self.lookup(path, cross_fs, Instant::now())
+    }
+
+    fn lookup(&self, path: &Path, cross_fs: bool, now: Instant) -> Option<u64> {
+        let cached = *self.inner[usize::from(cross_fs)].get(path)?;
+        (now.saturating_duration_since(cached.computed) < DIR_SIZE_MAX_AGE).then_some(cached.size)
+    }
+
+    pub(crate) fn insert(&self, path: PathBuf, cross_fs: bool, size: u64) {
+        let inner = &self.inner[usize::from(cross_fs)];
+        if inner.len() >= DIR_SIZE_CACHE_LIMIT {
+            inner.clear();
+        }
+        let computed = Instant::now();
+        inner.insert(path, CachedSize { size, computed });
+    }
+
+    /// Removes the size of the changed directory and of all directories containing it.
+    pub(crate) fn invalidate(&self, path: &Path) {
+        for inner in &self.inner {
+            inner.retain(|dir, _| !path.starts_with(dir));
+        }
+    }
+}
+
+/// Returns the device of the filesystem the path is on.
+pub(crate) fn device(path: &Path) -> Option<u64> {
+    fs::symlink_metadata(path).ok().map(|md| device_of(&md))
+}
+
+/// Sums up the sizes of all files below `root`.
+///
+/// If `device` is set, directories on other filesystems are skipped, including `root` itself,
+/// which then has a size of 0. Pass the device of the listed directory, so subdirectories
+/// which are mount points are not walked. Symlinks are not followed and sizes of
+/// subdirectories found in the cache are used right away.
+/// Returns None if the calculation was cancelled.
+pub(crate) fn dir_size(
+    root: &Path,
+    device: Option<u64>,
+    cancel: &AtomicBool,
+    cache: &DirSizeCache,
+) -> Option<u64> {
+    let other_fs = |dev: u64| device.is_some_and(|device| device != dev);
+    let cross_fs = device.is_none();
+    if self::device(root).is_some_and(other_fs) {
+        return Some(0);
+    }
+
+    let mut total: u64 = 0;
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        if cancel.load(Ordering::Acquire) {
+            return None;
+        }
+        let Ok(read_dir) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in read_dir.flatten() {
+            let Ok(md) = entry.metadata() else {
+                continue;
+            };
+
+            if md.is_dir() {
+                if other_fs(device_of(&md)) {
+                    continue;
+                }
+                let path = entry.path();
+                match cache.get_in(&path, cross_fs) {
+                    Some(size) => total = total.saturating_add(size),
+                    None => stack.push(path),
+                }
+            } else if md.is_file() {
+                total = total.saturating_add(md.len());
+            }
+        }
+    }
+    Some(total)
+}
+
+#[cfg(unix)]
+fn device_of(md: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    md.dev()
+}
+
+#[cfg(not(unix))]
+fn device_of(_md: &fs::Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn dir_size_sums_nested_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("a/b"))?;
+        fs::write(dir.path().join("top.txt"), [0u8; 10])?;
+        fs::write(dir.path().join("a/one.txt"), [0u8; 100])?;
+        fs::write(dir.path().join("a/b/two.txt"), [0u8; 1000])?;
+
+        let cache = DirSizeCache::new();
+        let cancel = AtomicBool::new(false);
+        assert_eq!(dir_size(dir.path(), None, &cancel, &cache), Some(1110));
+        assert_eq!(
+            dir_size(&dir.path().join("a"), None, &cancel, &cache),
+            Some(1100)
+        );
+
+        // Cached subdirectory sizes are used instead of walking them again.
+        cache.insert(dir.path().join("a/b"), true, 5);
+        assert_eq!(dir_size(dir.path(), None, &cancel, &cache), Some(115));
+
+        cancel.store(true, Ordering::Release);
+        assert_eq!(dir_size(dir.path(), None, &cancel, &cache), None);
+        Ok(())
+    }
+
+    #[test]
+    fn dir_size_skips_mount_points_below_the_listing() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        fs::create_dir_all(dir.path().join("mnt/disk"))?;
+        fs::write(dir.path().join("mnt/disk/big.bin"), [0u8; 100])?;
+
+        let cache = DirSizeCache::new();
+        let cancel = AtomicBool::new(false);
+        let listed = device(dir.path());
+        assert_eq!(
+            dir_size(&dir.path().join("mnt"), listed, &cancel, &cache),
+            Some(100)
+        );
+
+        // A subdirectory on another device than the listing, like a mount point, is not walked.
+        let other = listed.map(|dev| dev.wrapping_add(1));
+        assert_eq!(
+            dir_size(&dir.path().join("mnt"), other, &cancel, &cache),
+            Some(0)
+        );
+        assert_eq!(
+            dir_size(&dir.path().join("mnt"), None, &cancel, &cache),
+            Some(100)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dir_size_cache_invalidates_ancestors() {
+        let cache = DirSizeCache::new();
+        cache.insert(PathBuf::from("/data"), false, 3);
+        cache.insert(PathBuf::from("/data/src"), false, 2);
+        cache.insert(PathBuf::from("/data/src/app"), false, 1);
+        cache.insert(PathBuf::from("/data/docs"), false, 1);
+
+        cache.invalidate(Path::new("/data/src"));
+        assert_eq!(cache.get(Path::new("/data")), None);
+        assert_eq!(cache.get(Path::new("/data/src")), None);
+        assert_eq!(cache.get(Path::new("/data/src/app")), Some(1));
+        assert_eq!(cache.get(Path::new("/data/docs")), Some(1));
+    }
+
+    #[test]
+    fn dir_size_cache_is_keyed_by_mode_and_expires() {
+        let cache = DirSizeCache::new();
+        let data = Path::new("/data");
+        cache.insert(data.to_path_buf(), false, 3);
+        assert_eq!(cache.get(data), Some(3));
+        assert_eq!(cache.get_in(data, true), None);
+
+        cache.set_cross_fs(true);
+        assert_eq!(cache.get(data), None);
+        cache.insert(data.to_path_buf(), true, 5);
+        assert_eq!(cache.get(data), Some(5));
+        assert_eq!(cache.get_in(data, false), Some(3));
+
+        // A change deep below a measured directory is not reported, so old sizes expire.
+        let later = Instant::now() + DIR_SIZE_MAX_AGE;
+        assert_eq!(cache.lookup(data, false, later), None);
+    }
+}
@@ -17,31 +17,41 @@ pub(crate) struct FileEntry {
     lowered: Box<str>,
     symlink: Option<Box<Path>>,
     ext_offset: Option<u16>,
-    flags: u8,
+    flags: u16,
 }
 
 impl FileEntry {
     // Bitflags definitions
     // These are used to set and check attributes in the flags field
-    pub(super) const IS_DIR: u8 = 1 << 0;
-    pub(super) const IS_HIDDEN: u8 = 1 << 1;
-    pub(super) const IS_SYSTEM: u8 = 1 << 2;
-    pub(super) const IS_SYMLINK: u8 = 1 << 3;
-    pub(super) const IS_BROKEN_SYM: u8 = 1 << 4;
-    pub(super) const IS_EXECUTABLE: u8 = 1 << 5;
+    pub(crate) const IS_DIR: u16 = 1 << 0;
+    pub(crate) const IS_HIDDEN: u16 = 1 << 1;
+    pub(crate) const IS_SYSTEM: u16 = 1 << 2;
+    pub(crate) const IS_SYMLINK: u16 = 1 << 3;
+    pub(crate) const IS_BROKEN_SYM: u16 = 1 << 4;
+    pub(crate) const IS_EXECUTABLE: u16 = 1 << 5;
+    pub(crate) const IS_SOCKET: u16 = 1 << 6;
+    pub(crate) const IS_FIFO: u16 = 1 << 7;
+    pub(crate) const IS_BLOCK_DEVICE: u16 = 1 << 8;
+    pub(crate) const IS_CHAR_DEVICE: u16 = 1 << 9;
 
     /// Used to set the IS_EXECUTABLE flag for files which can be executed.
     /// Used for coloring executable files in UI
     #[cfg(unix)]
     pub(super) const EXEC_FLAG: u32 = 0o111;
 
-    pub(crate) fn new(name: OsString, flags: u8, symlink: Option<Box<Path>>) -> Self {
+    pub(crate) fn new(name: OsString, flags: u16, symlink: Option<Box<Path>>) -> Self {
         let lossy_str = name.to_string_lossy();
         let lowered: Box<str> = lossy_str.to_lowercase().into_boxed_str();
         let name_str: Box<str> = lossy_str.into_owned().into_boxed_str();
 
+        // Names can be relative paths in the flatten view, so the extension
+        // has to be taken from the last path component only.
         let ext_offset = lowered.rsplit_once('.').and_then(|(base, ext)| {
-            if !base.is_empty() && !ext.is_empty() {
+            if !base.is_empty()
+                && !ext.is_empty()
+                && !base.ends_with(std::path::is_separator)
+                && !ext.contains(std::path::is_separator)
+            {
                 Some((name_str.len() - ext.len()) as u16)
             } else {
                 None
@@ -62,7 +72,13 @@ impl FileEntry {
         name: &OsStr,
         name_str: &str,
         lowered: &str,
-        flags: u8,
+        flags: u16,
+    }
+
+    /// Returns a copy of this entry with another name, keeping its flags.
+    /// Used for the flatten view, where entries are named by their relative path.
+    pub(crate) fn renamed(&self, name: OsString) -> Self {
+        FileEntry::new(name, self.flags, self.symlink.clone())
     }
 
     #[inline]
@@ -81,6 +97,11 @@ impl FileEntry {
         self.flags & Self::IS_DIR != 0
     }
 
+    #[inline]
+    pub(crate) fn is_hidden(&self) -> bool {
+        self.flags & Self::IS_HIDDEN != 0
+    }
+
     #[inline]
     pub(crate) fn is_symlink(&self) -> bool {
         self.flags & Self::IS_SYMLINK != 0
@@ -96,8 +117,28 @@ impl FileEntry {
         self.flags & Self::IS_EXECUTABLE != 0
     }
 
+    #[inline]
+    pub(crate) fn is_socket(&self) -> bool {
+        self.flags & Self::IS_SOCKET != 0
+    }
+
+    #[inline]
+    pub(crate) fn is_fifo(&self) -> bool {
+        self.flags & Self::IS_FIFO != 0
+    }
+
+    #[inline]
+    pub(crate) fn is_block_device(&self) -> bool {
+        self.flags & Self::IS_BLOCK_DEVICE != 0
+    }
+
+    #[inline]
+    pub(crate) fn is_char_device(&self) -> bool {
+        self.flags & Self::IS_CHAR_DEVICE != 0
+    }
+
     #[cfg(windows)]
-    pub(super) fn match_executable_extension(ext: &str, flags: &mut u8) {
+    pub(super) fn match_executable_extension(ext: &str, flags: &mut u16) {
         match ext {
             "exe" | "com" | "bat" | "cmd" | "ps1" => *flags |= Self::IS_EXECUTABLE,
             _ => {}
@@ -123,7 +164,7 @@ pub(crate) fn browse_dir(path: &Path) -> io::Result<Vec<FileEntry>> {
             Err(_) => continue,
         };
 
-        let mut flags = 0u8;
+        let mut flags = 0u16;
         if ft.is_dir() {
             flags |= FileEntry::IS_DIR;
         }
@@ -136,7 +177,7 @@ pub(crate) fn browse_dir(path: &Path) -> io::Result<Vec<FileEntry>> {
         #[cfg(unix)]
         {
             use std::os::unix::ffi::OsStrExt;
-            use std::os::unix::fs::PermissionsExt;
+            use std::os::unix::fs::{FileTypeExt, PermissionsExt};
 
             let md_res = if (flags & FileEntry::IS_SYMLINK) != 0 {
                 fs::metadata(path_cache.get_or_insert_with(|| entry.path()))
@@ -152,6 +193,17 @@ pub(crate) fn browse_dir(path: &Path) -> io::Result<Vec<FileEntry>> {
                 if md.permissions().mode() & FileEntry::EXEC_FLAG != 0 {
                     flags |= FileEntry::IS_EXECUTABLE;
                 }
+
+                let ft = md.file_type();
+                if ft.is_socket() {
+                    flags |= FileEntry::IS_SOCKET;
+                } else if ft.is_fifo() {
+                    flags |= FileEntry::IS_FIFO;
+                } else if ft.is_block_device() {
+                    flags |= FileEntry::IS_BLOCK_DEVICE;
+                } else if ft.is_char_device() {
+                    flags |= FileEntry::IS_CHAR_DEVICE;
+                }
             } else if path_cache.is_some() {
                 flags |= FileEntry::IS_BROKEN_SYM;
             }
@@ -227,4 +279,19 @@ mod tests {
         assert!(!fe_dir.is_symlink());
         Ok(())
     }
+
+    #[test]
+    fn file_entry_relative_name_ext() {
+        let nested = PathBuf::from("logs.d").join("app.log");
+        let fe = FileEntry::new(nested.into_os_string(), 0, None);
+        assert_eq!(fe.ext(), Some("log"));
+
+        let dotfile = PathBuf::from("conf.d").join(".env");
+        let fe = FileEntry::new(dotfile.into_os_string(), FileEntry::IS_HIDDEN, None);
+        assert_eq!(fe.ext(), None);
+
+        let no_ext = PathBuf::from("src.d").join("Makefile");
+        let fe = FileEntry::new(no_ext.into_os_string(), 0, None);
+        assert_eq!(fe.ext(), None);
+    }
 }
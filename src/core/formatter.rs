@@ -23,6 +23,7 @@ use unicode_width::UnicodeWidthChar;
 use crate::core::{
     FileEntry,
     cache::DirListOptions,
+    dir_size::DirSizeCache,
     metadata::{self, CachedMetaKey, FileType},
     sort::{SortConfig, SortMode, SortOrder},
 };
@@ -56,6 +57,7 @@ pub(crate) struct Formatter {
     sort_config: SortConfig,
     always_show: Option<Arc<HashSet<OsString>>>,
     always_show_lowercase: Option<Arc<HashSet<String>>>,
+    dir_sizes: Option<Arc<DirSizeCache>>,
 }
 
 impl Formatter {
@@ -86,9 +88,16 @@ impl Formatter {
             sort_config,
             always_show,
             always_show_lowercase,
+            dir_sizes: None,
         }
     }
 
+    /// Uses the recursive directory sizes of the cache when sorting by size.
+    pub(crate) fn with_dir_sizes(mut self, dir_sizes: Arc<DirSizeCache>) -> Self {
+        self.dir_sizes = Some(dir_sizes);
+        self
+    }
+
     #[inline]
     fn prio_for_entry(&self, entry: &FileEntry) -> u8 {
         if self.list.dirs_first && (entry.flags() & FileEntry::IS_DIR) != 0 {
@@ -271,10 +280,15 @@ impl Formatter {
             if let Some(cached) = metadata::get_or_update_cached_meta(&path_buffer, cache) {
                 let (key, display) = match metadata_sort_field {
                     MetadataSortField::Size => {
-                        let val = cached.size.unwrap_or(0);
+                        let dir_size = self
+                            .dir_sizes
+                            .as_ref()
+                            .filter(|_| file_entry.is_dir() && !file_entry.is_symlink())
+                            .and_then(|sizes| sizes.get(&path_buffer));
+                        let size = dir_size.or(cached.size);
                         (
-                            val as u128,
-                            format_file_size(cached.size, file_entry.is_dir()),
+                            size.unwrap_or(0) as u128,
+                            format_file_size(size, file_entry.is_dir() && dir_size.is_none()),
                         )
                     }
                     MetadataSortField::Modified => (
@@ -345,7 +359,7 @@ impl Formatter {
     }
 
     pub(crate) fn filter_entries(&self, entries: &mut Vec<FileEntry>) {
-        let mut hide = 0u8;
+        let mut hide = 0u16;
         if !self.list.show_hidden {
             hide |= FileEntry::IS_HIDDEN;
         }
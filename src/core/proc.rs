@@ -16,7 +16,7 @@
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::io::{self, BufRead, Read};
 use std::path::MAIN_SEPARATOR;
 use std::path::{Path, PathBuf};
@@ -43,6 +43,11 @@ const EXCLUDES: &[&str] = &[
     "venv", ".venv", "__pycache__", ".DS_Store", "build", "out", "bin", "obj", ".cache",
 ];
 
+/// Returns true if the name is in the list of [EXCLUDES].
+pub(crate) fn is_excluded(name: &OsStr) -> bool {
+    EXCLUDES.iter().any(|excl| name == *excl)
+}
+
 /// A single result from the find function.
 /// It contains the path and the score of the fuzzy match.
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -0,0 +1,756 @@
+//! Filter query language for the directory filter and pattern marking.
+//!
+//! A query is a list of terms, combined with `and`, `or` and `not` (or `!`) and grouped with
+//! parentheses. Adjacent terms are implicitly joined with `and`.
+//!
+//! Supported terms:
+//! - `size>100M`, `size<=4k`: file size (directories never match)
+//! - `mtime<7d`, `mtime>2w`: age since last modification (s, m, h, d, w, y; days by default)
+//! - `type:dir`, `type:file`, `type:link`, `type:exec`, `type:hidden`
+//! - `ext:rs,toml`: one of the given extensions
+//! - `perm:x`, `perm:rw`, `perm:755`: permission bits (letters match any class, digits exact)
+//! - `owner:root`: file owner (unix only)
+//! - `name:foo` or any plain word: case-insensitive substring of the entry name
+//!
+//! Input without any of the `key:` or comparison terms above is treated as a plain name
+//! filter, so `foo bar` keeps matching names containing "foo bar" and `rock and roll` or
+//! `photo (2)` match those names instead of being read as operators.
+//!
+//! Size, age, permission and owner terms need file metadata, which is collected
+//! by the worker pool via [collect_filter_meta].
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::core::FileEntry;
+
+/// Metadata map for a directory, keyed by entry name.
+pub(crate) type FilterMetaMap = HashMap<OsString, EntryMeta>;
+
+/// Metadata of a single entry, used by the metadata aware query terms.
+#[derive(Debug, Clone)]
+pub(crate) struct EntryMeta {
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    mode: u32,
+    owner: Option<Arc<str>>,
+}
+
+impl EntryMeta {
+    pub(crate) fn new(md: &fs::Metadata, entry: &FileEntry, owner: Option<Arc<str>>) -> Self {
+        Self {
+            size: md.is_file().then_some(md.len()),
+            modified: md.modified().ok(),
+            mode: permission_mode(md, entry),
+            owner,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn permission_mode(md: &fs::Metadata, _entry: &FileEntry) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    md.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn permission_mode(md: &fs::Metadata, entry: &FileEntry) -> u32 {
+    let mut mode = 0o444;
+    if !md.permissions().readonly() {
+        mode |= 0o222;
+    }
+    if entry.is_executable() {
+        mode |= 0o111;
+    }
+    mode
+}
+
+/// Collects the [EntryMeta] for every entry of a directory.
+///
+/// Entries which vanished or can't be read are left out of the map.
+pub(crate) fn collect_filter_meta(
+    dir: &Path,
+    entries: &[FileEntry],
+    #[cfg(unix)] ug_cache: &mut crate::core::metadata::unix_meta::UserGroupCache,
+) -> FilterMetaMap {
+    let mut map = HashMap::with_capacity(entries.len());
+    for entry in entries {
+        let Ok(md) = fs::symlink_metadata(dir.join(entry.name())) else {
+            continue;
+        };
+
+        #[cfg(unix)]
+        let owner = {
+            use std::os::unix::fs::MetadataExt;
+            Some(ug_cache.resolve_user(md.uid()))
+        };
+        #[cfg(not(unix))]
+        let owner = None;
+
+        map.insert(
+            entry.name().to_os_string(),
+            EntryMeta::new(&md, entry, owner),
+        );
+    }
+    map
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Lt,
+    Le,
+    Eq,
+    Ge,
+    Gt,
+}
+
+impl Cmp {
+    fn eval(self, lhs: u64, rhs: u64) -> bool {
+        match self {
+            Cmp::Lt => lhs < rhs,
+            Cmp::Le => lhs <= rhs,
+            Cmp::Eq => lhs == rhs,
+            Cmp::Ge => lhs >= rhs,
+            Cmp::Gt => lhs > rhs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    Dir,
+    File,
+    Symlink,
+    Exec,
+    Hidden,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Perm {
+    /// Letters given as `r`, `w`, `x` bits (4, 2, 1), matched against any class.
+    Bits(u8),
+    /// Exact octal mode.
+    Mode(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Name(String),
+    /// Name pattern with `*` and `?` wildcards, matched against the whole name.
+    Glob(Box<[char]>),
+    Size(Cmp, u64),
+    Age(Cmp, u64),
+    Kind(EntryKind),
+    Ext(Box<[String]>),
+    Perm(Perm),
+    Owner(String),
+}
+
+impl Term {
+    fn needs_meta(&self) -> bool {
+        matches!(
+            self,
+            Term::Size(..) | Term::Age(..) | Term::Perm(_) | Term::Owner(_)
+        )
+    }
+
+    fn matches(&self, entry: &FileEntry, meta: Option<&EntryMeta>, now: SystemTime) -> bool {
+        match self {
+            Term::Name(needle) => entry.lowered().contains(needle.as_str()),
+            Term::Glob(pattern) => glob_matches(pattern, entry.lowered()),
+            Term::Kind(kind) => match kind {
+                EntryKind::Dir => entry.is_dir(),
+                EntryKind::File => !entry.is_dir(),
+                EntryKind::Symlink => entry.is_symlink(),
+                EntryKind::Exec => entry.is_executable() && !entry.is_dir(),
+                EntryKind::Hidden => entry.is_hidden(),
+            },
+            Term::Ext(exts) => entry.ext().is_some_and(|ext| exts.iter().any(|e| e == ext)),
+            Term::Size(cmp, bytes) => meta
+                .and_then(|m| m.size)
+                .is_some_and(|size| cmp.eval(size, *bytes)),
+            Term::Age(cmp, secs) => meta
+                .and_then(|m| m.modified)
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| cmp.eval(age.as_secs(), *secs)),
+            Term::Perm(Perm::Bits(bits)) => meta.is_some_and(|m| {
+                [(4, 0o444), (2, 0o222), (1, 0o111)]
+                    .iter()
+                    .all(|&(bit, mask)| bits & bit == 0 || m.mode & mask != 0)
+            }),
+            Term::Perm(Perm::Mode(mode)) => meta.is_some_and(|m| m.mode & 0o7777 == *mode),
+            Term::Owner(name) => meta
+                .and_then(|m| m.owner.as_deref())
+                .is_some_and(|owner| owner == name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn needs_meta(&self) -> bool {
+        match self {
+            Expr::Term(t) => t.needs_meta(),
+            Expr::Not(e) => e.needs_meta(),
+            Expr::And(a, b) | Expr::Or(a, b) => a.needs_meta() || b.needs_meta(),
+        }
+    }
+
+    fn matches(&self, entry: &FileEntry, meta: Option<&EntryMeta>, now: SystemTime) -> bool {
+        match self {
+            Expr::Term(t) => t.matches(entry, meta, now),
+            Expr::Not(e) => !e.matches(entry, meta, now),
+            Expr::And(a, b) => a.matches(entry, meta, now) && b.matches(entry, meta, now),
+            Expr::Or(a, b) => a.matches(entry, meta, now) || b.matches(entry, meta, now),
+        }
+    }
+}
+
+/// A parsed filter query.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FilterQuery {
+    expr: Expr,
+    needs_meta: bool,
+}
+
+impl FilterQuery {
+    /// Parses a query string.
+    ///
+    /// Returns an error for malformed queries, e.g. `size>abc` or unbalanced parentheses.
+    pub(crate) fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input);
+        if !tokens.iter().any(Token::is_predicate) {
+            return Ok(Self::name(input));
+        }
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(tok) = parser.peek() {
+            return Err(format!("unexpected '{}'", tok.as_str()));
+        }
+
+        Ok(Self {
+            needs_meta: expr.needs_meta(),
+            expr,
+        })
+    }
+
+    /// Parses a query, falling back to a plain name filter if the query is malformed.
+    ///
+    /// Used while the user is still typing, where half finished queries are common.
+    pub(crate) fn parse_lenient(input: &str) -> Self {
+        Self::parse(input).unwrap_or_else(|_| Self::name(input))
+    }
+
+    /// A plain case-insensitive name filter, or a name pattern if `input` contains wildcards.
+    pub(crate) fn name(input: &str) -> Self {
+        Self {
+            expr: Expr::Term(name_term(input)),
+            needs_meta: false,
+        }
+    }
+
+    /// Returns true if the query contains terms which need [EntryMeta].
+    pub(crate) fn needs_meta(&self) -> bool {
+        self.needs_meta
+    }
+
+    /// Tests an entry against the query.
+    ///
+    /// Metadata terms never match if `meta` is missing.
+    pub(crate) fn matches(
+        &self,
+        entry: &FileEntry,
+        meta: Option<&EntryMeta>,
+        now: SystemTime,
+    ) -> bool {
+        self.expr.matches(entry, meta, now)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Word(String),
+}
+
+impl Token {
+    /// Returns true for a `key:value` or comparison term, which makes the input a query.
+    fn is_predicate(&self) -> bool {
+        matches!(self, Token::Word(w) if split_predicate(w).is_some())
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Token::And => "and",
+            Token::Or => "or",
+            Token::Not => "not",
+            Token::Open => "(",
+            Token::Close => ")",
+            Token::Word(w) => w,
+        }
+    }
+}
+
+/// Splits the input into tokens.
+///
+/// Parentheses are only tokens at the start or end of a word, so the parentheses of a
+/// name like `name:foo(1)` or `*(copy)*` stay in the word.
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for raw in input.split_whitespace() {
+        let mut word = raw;
+        loop {
+            if word.len() > 1 && word.starts_with('!') {
+                tokens.push(Token::Not);
+                word = &word[1..];
+            } else if word.starts_with('(') && opens_group(word) {
+                tokens.push(Token::Open);
+                word = &word[1..];
+            } else {
+                break;
+            }
+        }
+
+        let mut closes = 0;
+        while word.ends_with(')') && word.matches(')').count() > word.matches('(').count() {
+            closes += 1;
+            word = &word[..word.len() - 1];
+        }
+
+        if !word.is_empty() {
+            let tok = match word.to_ascii_lowercase().as_str() {
+                "and" | "&&" => Token::And,
+                "or" | "||" => Token::Or,
+                "not" | "!" => Token::Not,
+                _ => Token::Word(word.to_string()),
+            };
+            tokens.push(tok);
+        }
+        tokens.extend(std::iter::repeat_n(Token::Close, closes));
+    }
+    tokens
+}
+
+/// Returns true if the `(` starting the word is not closed within it, or only by its last character.
+fn opens_group(word: &str) -> bool {
+    let mut depth = 0usize;
+    for (i, c) in word.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if depth == 0 {
+            return i + c.len_utf8() == word.len();
+        }
+    }
+    true
+}
+
+/// Recursive descent parser: `or` binds weakest, then `and`, then `not`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                Some(Token::Or | Token::Close) | None => break,
+                Some(_) => {}
+            }
+            let rhs = self.parse_not()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Word(w)) => parse_term(&w).map(Expr::Term),
+            Some(tok) => Err(format!("unexpected '{}'", tok.as_str())),
+            None => Err("unexpected end of query".to_string()),
+        }
+    }
+}
+
+/// Splits `key<op>value` into its parts, if `key` is a known predicate.
+fn split_predicate(word: &str) -> Option<(&'static str, Cmp, &str)> {
+    let pos = word.find([':', '<', '>', '='])?;
+    let key = match word[..pos].to_ascii_lowercase().as_str() {
+        "size" => "size",
+        "mtime" | "modified" | "age" => "mtime",
+        "type" => "type",
+        "ext" => "ext",
+        "perm" | "perms" => "perm",
+        "owner" | "user" => "owner",
+        "name" => "name",
+        _ => return None,
+    };
+
+    let rest = &word[pos..];
+    let (cmp, len) = if rest.starts_with(">=") {
+        (Cmp::Ge, 2)
+    } else if rest.starts_with("<=") {
+        (Cmp::Le, 2)
+    } else if rest.starts_with('>') {
+        (Cmp::Gt, 1)
+    } else if rest.starts_with('<') {
+        (Cmp::Lt, 1)
+    } else {
+        (Cmp::Eq, 1)
+    };
+
+    Some((key, cmp, &rest[len..]))
+}
+
+fn parse_term(word: &str) -> Result<Term, String> {
+    let Some((key, cmp, value)) = split_predicate(word) else {
+        return Ok(name_term(word));
+    };
+
+    if value.is_empty() {
+        return Err(format!("missing value for '{key}'"));
+    }
+    let is_eq = cmp == Cmp::Eq;
+    let invalid = || format!("invalid {key} '{value}'");
+
+    match key {
+        "size" => parse_size(value)
+            .map(|b| Term::Size(cmp, b))
+            .ok_or_else(invalid),
+        "mtime" => parse_age(value)
+            .map(|s| Term::Age(cmp, s))
+            .ok_or_else(invalid),
+        "type" if is_eq => parse_kind(value).map(Term::Kind).ok_or_else(invalid),
+        "ext" if is_eq => Ok(Term::Ext(
+            value
+                .split(',')
+                .filter(|e| !e.is_empty())
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect(),
+        )),
+        "perm" if is_eq => parse_perm(value).map(Term::Perm).ok_or_else(invalid),
+        "owner" if is_eq => Ok(Term::Owner(value.to_string())),
+        "name" if is_eq => Ok(name_term(value)),
+        _ => Err(format!("'{key}' only supports ':'")),
+    }
+}
+
+fn name_term(input: &str) -> Term {
+    let lowered = input.to_lowercase();
+    if lowered.contains(['*', '?']) {
+        Term::Glob(lowered.chars().collect())
+    } else {
+        Term::Name(lowered)
+    }
+}
+
+/// Matches `name` against a pattern where `*` matches any run of characters
+/// and `?` matches a single character.
+pub(crate) fn glob_matches(pattern: &[char], name: &str) -> bool {
+    // The name is walked by byte offset to avoid collecting its characters for every match.
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern and the name offset it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+
+    while let Some(ch) = name[n..].chars().next() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == ch => {
+                p += 1;
+                n += ch.len_utf8();
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    // Let the last `*` swallow one more character and retry.
+                    let swallowed = name[star_n..].chars().next().map_or(1, char::len_utf8);
+                    star = Some((star_p, star_n + swallowed));
+                    p = star_p + 1;
+                    n = star_n + swallowed;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parses sizes such as `512`, `4k`, `100M` or `1.5GiB` (binary units).
+fn parse_size(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let num: f64 = num.parse().ok()?;
+
+    let unit = unit.to_ascii_lowercase();
+    let unit = unit.trim_end_matches('b').trim_end_matches('i');
+    let mult: u64 = match unit {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => return None,
+    };
+
+    Some((num * mult as f64) as u64)
+}
+
+/// Parses ages such as `30s`, `15m`, `2h`, `7d`, `2w` or `1y` into seconds.
+fn parse_age(value: &str) -> Option<u64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (num, unit) = value.split_at(split);
+    let num: f64 = num.parse().ok()?;
+
+    let mult: u64 = match unit.to_ascii_lowercase().as_str() {
+        "s" => 1,
+        "m" | "min" => 60,
+        "h" => 60 * 60,
+        "" | "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return None,
+    };
+
+    Some((num * mult as f64) as u64)
+}
+
+fn parse_kind(value: &str) -> Option<EntryKind> {
+    match value.to_ascii_lowercase().as_str() {
+        "d" | "dir" | "directory" => Some(EntryKind::Dir),
+        "f" | "file" => Some(EntryKind::File),
+        "l" | "link" | "symlink" => Some(EntryKind::Symlink),
+        "x" | "exec" | "executable" => Some(EntryKind::Exec),
+        "h" | "hidden" => Some(EntryKind::Hidden),
+        _ => None,
+    }
+}
+
+fn parse_perm(value: &str) -> Option<Perm> {
+    if value.chars().all(|c| c.is_digit(8)) {
+        return u32::from_str_radix(value, 8)
+            .ok()
+            .filter(|m| *m <= 0o7777)
+            .map(Perm::Mode);
+    }
+
+    let mut bits = 0;
+    for c in value.chars() {
+        bits |= match c.to_ascii_lowercase() {
+            'r' => 4,
+            'w' => 2,
+            'x' => 1,
+            _ => return None,
+        };
+    }
+    Some(Perm::Bits(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(name: &str, flags: u16) -> FileEntry {
+        FileEntry::new(OsString::from(name), flags, None)
+    }
+
+    fn meta(size: Option<u64>, age_secs: u64, mode: u32, now: SystemTime) -> EntryMeta {
+        EntryMeta {
+            size,
+            modified: Some(now - Duration::from_secs(age_secs)),
+            mode,
+            owner: Some(Arc::from("root")),
+        }
+    }
+
+    #[test]
+    fn query_plain_text_is_name_filter() -> Result<(), String> {
+        let q = FilterQuery::parse("Foo Bar")?;
+        let now = SystemTime::now();
+
+        assert!(!q.needs_meta());
+        assert!(q.matches(&entry("my foo bar.txt", 0), None, now));
+        assert!(!q.matches(&entry("foo.txt", 0), None, now));
+
+        // Operators and parentheses alone don't make a query.
+        for name in ["photo (2)", "rock and roll", "this or that", "!important"] {
+            let q = FilterQuery::parse(name)?;
+            assert_eq!(q, FilterQuery::name(name));
+            assert!(q.matches(&entry(&format!("{}.jpg", name), 0), None, now));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn query_name_patterns() -> Result<(), String> {
+        let now = SystemTime::now();
+
+        let q = FilterQuery::parse("*.ORIG")?;
+        assert!(q.matches(&entry("main.rs.orig", 0), None, now));
+        assert!(!q.matches(&entry("main.orig.rs", 0), None, now));
+
+        let q = FilterQuery::parse("not name:file?.* and type:file")?;
+        assert!(!q.matches(&entry("file1.txt", 0), None, now));
+        assert!(q.matches(&entry("file12.txt", 0), None, now));
+        assert!(glob_matches(&['*', 'a', '*', 'b'], "xaxxb"));
+        assert!(!glob_matches(&['a', '?'], "a"));
+        assert!(glob_matches(&['*', 'ä', '?', 'ß'], "xxäöß"));
+        assert!(!glob_matches(&['?', 'ß'], "äöß"));
+        Ok(())
+    }
+
+    #[test]
+    fn query_type_and_ext() -> Result<(), String> {
+        let now = SystemTime::now();
+        let q = FilterQuery::parse("type:file and ext:rs,toml")?;
+
+        assert!(!q.needs_meta());
+        assert!(q.matches(&entry("main.rs", 0), None, now));
+        assert!(q.matches(&entry("Cargo.TOML", 0), None, now));
+        assert!(!q.matches(&entry("notes.md", 0), None, now));
+        assert!(!q.matches(&entry("src.rs", FileEntry::IS_DIR), None, now));
+        Ok(())
+    }
+
+    #[test]
+    fn query_metadata_terms() -> Result<(), String> {
+        let now = SystemTime::now();
+        let day = 24 * 60 * 60;
+        let big_old = meta(Some(200 << 20), 30 * day, 0o644, now);
+        let small_new = meta(Some(10), day, 0o755, now);
+        let file = entry("a", 0);
+
+        let q = FilterQuery::parse("size>100M")?;
+        assert!(q.needs_meta());
+        assert!(q.matches(&file, Some(&big_old), now));
+        assert!(!q.matches(&file, Some(&small_new), now));
+        assert!(!q.matches(&file, None, now));
+
+        let q = FilterQuery::parse("mtime<7d")?;
+        assert!(q.matches(&file, Some(&small_new), now));
+        assert!(!q.matches(&file, Some(&big_old), now));
+
+        let q = FilterQuery::parse("perm:x")?;
+        assert!(q.matches(&file, Some(&small_new), now));
+        assert!(!q.matches(&file, Some(&big_old), now));
+
+        let q = FilterQuery::parse("perm:644 owner:root")?;
+        assert!(q.matches(&file, Some(&big_old), now));
+        assert!(!q.matches(&file, Some(&small_new), now));
+        Ok(())
+    }
+
+    #[test]
+    fn query_boolean_operators() -> Result<(), String> {
+        let now = SystemTime::now();
+        let dir = entry("src", FileEntry::IS_DIR);
+        let rs = entry("lib.rs", 0);
+        let md = entry("README.md", 0);
+
+        let q = FilterQuery::parse("type:dir or ext:rs")?;
+        assert!(q.matches(&dir, None, now));
+        assert!(q.matches(&rs, None, now));
+        assert!(!q.matches(&md, None, now));
+
+        let q = FilterQuery::parse("not (type:dir or ext:rs)")?;
+        assert!(q.matches(&md, None, now));
+        assert!(!q.matches(&rs, None, now));
+
+        let q = FilterQuery::parse("!type:dir readme")?;
+        assert!(q.matches(&md, None, now));
+        assert!(!q.matches(&rs, None, now));
+
+        // Parentheses inside a word are part of the name.
+        let q = FilterQuery::parse("(name:foo(1) or name:*(copy)*) and type:file")?;
+        assert!(q.matches(&entry("foo(1)", 0), None, now));
+        assert!(q.matches(&entry("a (copy).txt", 0), None, now));
+        assert!(!q.matches(&entry("foo", 0), None, now));
+        assert!(!q.matches(&entry("foo(1)", FileEntry::IS_DIR), None, now));
+        Ok(())
+    }
+
+    #[test]
+    fn query_malformed() {
+        assert!(FilterQuery::parse("size>abc").is_err());
+        assert!(FilterQuery::parse("(type:dir").is_err());
+        assert!(FilterQuery::parse("type:dir or").is_err());
+        assert!(FilterQuery::parse("type>dir").is_err());
+
+        let q = FilterQuery::parse_lenient("size>");
+        assert_eq!(q, FilterQuery::name("size>"));
+    }
+
+    #[test]
+    fn query_units() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("4k"), Some(4096));
+        assert_eq!(parse_size("1.5KiB"), Some(1536));
+        assert_eq!(parse_size("2MB"), Some(2 << 20));
+        assert_eq!(parse_age("2h"), Some(7200));
+        assert_eq!(parse_age("7"), Some(7 * 86400));
+        assert_eq!(parse_age("3x"), None);
+    }
+}
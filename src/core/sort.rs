@@ -1,6 +1,12 @@
 //! Sort config module
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub(crate) enum SortMode {
     Name,
     Modified,
@@ -11,7 +17,8 @@ pub(crate) enum SortMode {
     Natural,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub(crate) enum SortOrder {
     Ascending,
     Descending,
@@ -26,7 +33,7 @@ impl SortOrder {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(crate) struct SortConfig {
     mode: SortMode,
     order: SortOrder,
@@ -61,3 +68,52 @@ impl SortConfig {
         self.order = order
     }
 }
+
+/// Parses a sort config such as `modified desc`, `size` or `name ascending`.
+/// The order defaults to ascending.
+impl FromStr for SortConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let mode = match parts.next().map(str::to_lowercase).as_deref() {
+            Some("name") => SortMode::Name,
+            Some("natural") => SortMode::Natural,
+            Some("modified") => SortMode::Modified,
+            Some("created") => SortMode::Created,
+            Some("accessed") => SortMode::Accessed,
+            Some("size") => SortMode::Size,
+            Some("extension") => SortMode::Extension,
+            _ => return Err(format!("invalid sort mode in '{s}'")),
+        };
+        let order = match parts.next().map(str::to_lowercase).as_deref() {
+            None | Some("asc" | "ascending") => SortOrder::Ascending,
+            Some("desc" | "descending") => SortOrder::Descending,
+            Some(_) => return Err(format!("invalid sort order in '{s}'")),
+        };
+        if parts.next().is_some() {
+            return Err(format!("invalid sort '{s}'"));
+        }
+        Ok(Self { mode, order })
+    }
+}
+
+/// Writes the sort config the way it is parsed, e.g. `modified desc`.
+impl fmt::Display for SortConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            SortMode::Name => "name",
+            SortMode::Natural => "natural",
+            SortMode::Modified => "modified",
+            SortMode::Created => "created",
+            SortMode::Accessed => "accessed",
+            SortMode::Size => "size",
+            SortMode::Extension => "extension",
+        };
+        let order = match self.order {
+            SortOrder::Ascending => "asc",
+            SortOrder::Descending => "desc",
+        };
+        write!(f, "{mode} {order}")
+    }
+}
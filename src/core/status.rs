@@ -0,0 +1,343 @@
+//! Status segments which are computed on the status worker thread.
+//!
+//! The git branch, the free space of the mount, the size of the marked entries and the output
+//! of the user commands are too slow to compute while drawing. The [StatusRequest] of the
+//! focused tab is sent to the status worker, which fills a [StatusInfo] with them and refreshes
+//! the git, disk and command segments on the interval of the request.
+//!
+//! Every command gets the interval to finish and is killed after it, so a hung command does
+//! not stop the other segments from updating.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::core::dir_size::DirSizeCache;
+
+/// The status segments the focused tab wants to show.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct StatusRequest {
+    pub(crate) dir: PathBuf,
+    pub(crate) git: bool,
+    pub(crate) disk: bool,
+    pub(crate) commands: Arc<[String]>,
+    /// The marked entries, only set if the marked size is shown.
+    pub(crate) marked: Option<Arc<[PathBuf]>>,
+    pub(crate) refresh: Duration,
+    pub(crate) tab_id: Option<usize>,
+}
+
+impl StatusRequest {
+    /// Returns true if the request has segments which are refreshed on the interval.
+    pub(crate) fn has_refresh(&self) -> bool {
+        self.git || self.disk || !self.commands.is_empty()
+    }
+
+    /// Returns true if the request shows no worker segment at all.
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.has_refresh() && self.marked.is_none()
+    }
+
+    /// Returns true if the refreshed segments of both requests are the same.
+    pub(crate) fn same_refresh(&self, other: &StatusRequest) -> bool {
+        self.dir == other.dir
+            && self.git == other.git
+            && self.disk == other.disk
+            && self.commands == other.commands
+    }
+}
+
+/// The git branch of a directory and whether its work tree has changes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GitStatus {
+    branch: String,
+    dirty: bool,
+}
+
+impl GitStatus {
+    crate::getters! {
+        branch: &str,
+        dirty: bool,
+    }
+}
+
+/// The free and total space of a mount in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DiskSpace {
+    free: u64,
+    total: u64,
+}
+
+impl DiskSpace {
+    crate::getters! {
+        free: u64,
+        total: u64,
+    }
+}
+
+/// The computed worker segments of a directory.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct StatusInfo {
+    dir: PathBuf,
+    git: Option<GitStatus>,
+    disk: Option<DiskSpace>,
+    marked_size: Option<u64>,
+    /// The first line of output of each command, in the order of the commands.
+    commands: Vec<String>,
+}
+
+impl StatusInfo {
+    crate::getters! {
+        dir: &Path,
+        disk: Option<DiskSpace>,
+        marked_size: Option<u64>,
+        commands: &[String],
+    }
+
+    pub(crate) fn git(&self) -> Option<&GitStatus> {
+        self.git.as_ref()
+    }
+
+    /// Computes the git, disk and command segments of the request.
+    pub(crate) fn refresh(&mut self, request: &StatusRequest) {
+        let (dir, timeout) = (&request.dir, request.refresh);
+        self.dir = dir.clone();
+        self.git = request.git.then(|| git_status(dir, timeout)).flatten();
+        self.disk = request.disk.then(|| disk_space(dir, timeout)).flatten();
+        self.commands = request
+            .commands
+            .iter()
+            .map(|cmd| command_output(cmd, dir, timeout))
+            .collect();
+    }
+
+    /// Sums up the sizes of the marked entries of the request.
+    pub(crate) fn refresh_marked(&mut self, request: &StatusRequest, dir_sizes: &DirSizeCache) {
+        self.marked_size = request
+            .marked
+            .as_deref()
+            .map(|paths| marked_size(paths, dir_sizes));
+    }
+}
+
+/// Returns the branch and the dirty state of the git work tree of the directory.
+///
+/// Runs without the optional locks, so the refresh of the index never takes `index.lock`
+/// from a `git commit` of the user.
+fn git_status(dir: &Path, timeout: Duration) -> Option<GitStatus> {
+    let mut command = Command::new("git");
+    command
+        .arg("--no-optional-locks")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain", "--branch", "--no-ahead-behind"])
+        .env("GIT_OPTIONAL_LOCKS", "0");
+    let (success, output) = run_with_timeout(&mut command, timeout)?;
+    if !success {
+        return None;
+    }
+    parse_git_status(&output)
+}
+
+/// Reads the output of `git status --porcelain --branch`.
+///
+/// The first line holds the branch, e.g. `## main...origin/main`, `## No commits yet on main`
+/// or `## HEAD (no branch)`. Every other line is a changed or untracked file.
+fn parse_git_status(output: &str) -> Option<GitStatus> {
+    let mut lines = output.lines();
+    let header = lines.next()?.strip_prefix("## ")?;
+    let header = header
+        .strip_prefix("No commits yet on ")
+        .or_else(|| header.strip_prefix("Initial commit on "))
+        .unwrap_or(header);
+    let branch = match header.split_once("...") {
+        Some((branch, _)) => branch,
+        None => header.split(' ').next().unwrap_or(header),
+    };
+
+    Some(GitStatus {
+        branch: branch.to_string(),
+        dirty: lines.any(|line| !line.is_empty()),
+    })
+}
+
+/// Returns the free and total space of the mount of the directory.
+#[cfg(unix)]
+fn disk_space(dir: &Path, timeout: Duration) -> Option<DiskSpace> {
+    let mut command = Command::new("df");
+    command.arg("-Pk").arg(dir);
+    let (_, output) = run_with_timeout(&mut command, timeout)?;
+    parse_df(&output)
+}
+
+#[cfg(not(unix))]
+fn disk_space(_dir: &Path, _timeout: Duration) -> Option<DiskSpace> {
+    None
+}
+
+/// Reads the output of `df -Pk`.
+///
+/// The second line holds the filesystem, the total, used and available 1024 byte blocks, the
+/// capacity and the mount point. Both names may contain spaces, so the numbers are read back
+/// from the capacity, which ends with '%'.
+#[cfg(unix)]
+fn parse_df(output: &str) -> Option<DiskSpace> {
+    let line = output.lines().nth(1)?;
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let capacity = fields.iter().rposition(|field| field.ends_with('%'))?;
+    let block = |back: usize| -> Option<u64> {
+        let field = fields.get(capacity.checked_sub(back)?)?;
+        Some(field.parse::<u64>().ok()? * 1024)
+    };
+
+    Some(DiskSpace {
+        free: block(1)?,
+        total: block(3)?,
+    })
+}
+
+/// Returns the first line of output of the shell command, run in the directory.
+fn command_output(cmd: &str, dir: &Path, timeout: Duration) -> String {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
+    };
+
+    let Some((_, output)) = run_with_timeout(command.current_dir(dir), timeout) else {
+        return String::new();
+    };
+    output
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Runs the command and returns whether it succeeded and its output.
+///
+/// Returns None if the command could not be started or did not finish within the timeout,
+/// in which case it is killed. The output is read on its own thread, so a command with a lot
+/// of output does not block on a full pipe.
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Option<(bool, String)> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut stdout = child.stdout.take()?;
+    let (out_tx, out_rx) = crossbeam_channel::bounded(1);
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = out_tx.send(output);
+    });
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+    // A process started in the background by the command may keep the output open.
+    let output = out_rx.recv_deadline(deadline).ok()?;
+    Some((
+        status.success(),
+        String::from_utf8_lossy(&output).into_owned(),
+    ))
+}
+
+/// Sums up the sizes of the files.
+///
+/// Directories count with their recursive size once it is in the cache.
+fn marked_size(paths: &[PathBuf], dir_sizes: &DirSizeCache) -> u64 {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let md = std::fs::symlink_metadata(path).ok()?;
+            if md.is_dir() {
+                dir_sizes.get(path)
+            } else {
+                Some(md.len())
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_segments_are_read_from_git_and_df() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            parse_git_status("## main...origin/main [ahead 1]\n"),
+            Some(GitStatus {
+                branch: "main".into(),
+                dirty: false,
+            })
+        );
+        assert_eq!(
+            parse_git_status("## No commits yet on feature/x\n?? new.rs\n"),
+            Some(GitStatus {
+                branch: "feature/x".into(),
+                dirty: true,
+            })
+        );
+        assert_eq!(parse_git_status(""), None);
+
+        #[cfg(unix)]
+        {
+            let df = "Filesystem 1024-blocks Used Available Capacity Mounted on\n\
+                      /dev/sda1 1000 400 600 40% /mnt/my disk\n";
+            assert_eq!(
+                parse_df(df),
+                Some(DiskSpace {
+                    free: 600 * 1024,
+                    total: 1000 * 1024,
+                })
+            );
+        }
+
+        let dir = tempfile::tempdir()?;
+        std::fs::write(dir.path().join("a"), [0; 10])?;
+        std::fs::write(dir.path().join("b"), [0; 5])?;
+        std::fs::create_dir(dir.path().join("sub"))?;
+        let dir_sizes = DirSizeCache::new();
+        let paths = ["a", "b", "sub"].map(|name| dir.path().join(name));
+        assert_eq!(marked_size(&paths, &dir_sizes), 15);
+        dir_sizes.insert(dir.path().join("sub"), false, 100);
+        assert_eq!(marked_size(&paths, &dir_sizes), 115);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hung_commands_are_killed_after_the_timeout() {
+        let dir = std::env::temp_dir();
+        let timeout = Duration::from_millis(200);
+        assert_eq!(command_output("echo one; echo two", &dir, timeout), "one");
+
+        let start = Instant::now();
+        assert_eq!(command_output("sleep 10", &dir, timeout), "");
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
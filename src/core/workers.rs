@@ -31,10 +31,14 @@ use notify::{
 use crate::core::{
     FileEntry, FindResult, Formatter,
     cache::{DirCache, DirListOptions},
+    details::{self, DetailColumn, DetailRow},
+    dir_size::{self, DirSizeCache},
     fm, formatter, fs,
     metadata::{FileMetadata, FileMetadataCache, MetadataNeeds},
     proc,
+    query::{self, FilterMetaMap},
     sort::SortConfig,
+    status::{StatusInfo, StatusRequest},
 };
 use crate::utils::{os, text::StrBuffer, timings::Timings};
 
@@ -44,19 +48,26 @@ pub(crate) struct Workers {
     parent_io_tx: Sender<WorkerTask>,
     preview_io_tx: Sender<WorkerTask>,
     sort_io_tx: Sender<WorkerTask>,
+    tree_io_tx: Sender<WorkerTask>,
     preview_file_tx: Sender<WorkerTask>,
     metadata_tx: Sender<WorkerTask>,
+    filter_meta_tx: Sender<WorkerTask>,
+    details_tx: Sender<WorkerTask>,
+    dir_size_tx: Sender<WorkerTask>,
+    flatten_tx: Sender<WorkerTask>,
     find_tx: Sender<WorkerTask>,
     fileop_tx: Sender<WorkerTask>,
     watch_cmd_tx: Sender<WatchCommand>,
+    status_tx: Sender<StatusRequest>,
     response_rx: Receiver<WorkerResponse>,
     active: Arc<AtomicUsize>,
     cache: Arc<DirCache>,
+    dir_sizes: Arc<DirSizeCache>,
 }
 
 /// Manages worker thread channels for different task types.
 ///
-/// Each major operation (I/O (nav, preview, parent), preview, file metadata, find, file-ops) has its own dedicated worker thread.
+/// Each major operation (I/O (nav, preview, parent, tree), preview, file metadata, find, file-ops) has its own dedicated worker thread.
 ///
 /// The find worker uses a bounded channel of size 1: this design ensures that only the
 /// latest find request will be processed, automatically skipping obsolete queued requests
@@ -68,46 +79,91 @@ impl Workers {
     /// Spawns dedicated threads for I/O, preview, find and file operations.
     pub(crate) fn spawn() -> Self {
         let cache = Arc::new(DirCache::new());
+        let dir_sizes = Arc::new(DirSizeCache::new());
 
         let (nav_io_tx, nav_io_rx) = bounded::<WorkerTask>(1);
         let (parent_io_tx, parent_io_rx) = bounded::<WorkerTask>(1);
         let (preview_io_tx, preview_io_rx) = bounded::<WorkerTask>(1);
         let (sort_io_tx, sort_io_rx) = bounded::<WorkerTask>(1);
+        let (tree_io_tx, tree_io_rx) = unbounded::<WorkerTask>();
 
         let (preview_file_tx, preview_file_rx) = bounded::<WorkerTask>(1);
         let (metadata_tx, metadata_rx) = bounded::<WorkerTask>(1);
+        let (filter_meta_tx, filter_meta_rx) = unbounded::<WorkerTask>();
+        let (details_tx, details_rx) = unbounded::<WorkerTask>();
+        let (dir_size_tx, dir_size_rx) = unbounded::<WorkerTask>();
+        let (flatten_tx, flatten_rx) = unbounded::<WorkerTask>();
         let (find_tx, find_rx) = bounded::<WorkerTask>(1);
         let (fileop_tx, fileop_rx) = unbounded::<WorkerTask>();
         let (watch_cmd_tx, watch_cmd_rx) = unbounded::<WatchCommand>();
+        let (status_tx, status_rx) = unbounded::<StatusRequest>();
         let (res_tx, response_rx) = unbounded::<WorkerResponse>();
 
         let active = Arc::new(AtomicUsize::new(0));
         let fileop_active_for_worker = Arc::clone(&active);
 
-        start_io_worker(nav_io_rx, res_tx.clone(), Arc::clone(&cache));
-        start_io_worker(parent_io_rx, res_tx.clone(), Arc::clone(&cache));
-        start_io_worker(preview_io_rx, res_tx.clone(), Arc::clone(&cache));
+        start_io_worker(
+            nav_io_rx,
+            res_tx.clone(),
+            Arc::clone(&cache),
+            Arc::clone(&dir_sizes),
+        );
+        start_io_worker(
+            parent_io_rx,
+            res_tx.clone(),
+            Arc::clone(&cache),
+            Arc::clone(&dir_sizes),
+        );
+        start_io_worker(
+            preview_io_rx,
+            res_tx.clone(),
+            Arc::clone(&cache),
+            Arc::clone(&dir_sizes),
+        );
+        start_io_worker(
+            tree_io_rx,
+            res_tx.clone(),
+            Arc::clone(&cache),
+            Arc::clone(&dir_sizes),
+        );
 
-        start_sort_worker(sort_io_rx, res_tx.clone(), Arc::clone(&cache));
+        start_sort_worker(
+            sort_io_rx,
+            res_tx.clone(),
+            Arc::clone(&cache),
+            Arc::clone(&dir_sizes),
+        );
         start_preview_worker(preview_file_rx, res_tx.clone());
         start_metadata_worker(metadata_rx, res_tx.clone());
+        start_filter_meta_worker(filter_meta_rx, res_tx.clone());
+        start_details_worker(details_rx, res_tx.clone());
+        start_dir_size_worker(dir_size_rx, res_tx.clone(), Arc::clone(&dir_sizes));
+        start_flatten_worker(flatten_rx, res_tx.clone());
         start_find_worker(find_rx, res_tx.clone());
         start_fileop_worker(fileop_rx, res_tx.clone(), fileop_active_for_worker);
         start_fs_watch_worker(watch_cmd_rx, res_tx.clone());
+        start_status_worker(status_rx, res_tx.clone(), Arc::clone(&dir_sizes));
 
         Self {
             nav_io_tx,
             parent_io_tx,
             preview_io_tx,
             sort_io_tx,
+            tree_io_tx,
             preview_file_tx,
             metadata_tx,
+            filter_meta_tx,
+            details_tx,
+            dir_size_tx,
+            flatten_tx,
             find_tx,
             fileop_tx,
             watch_cmd_tx,
+            status_tx,
             response_rx,
             active,
             cache,
+            dir_sizes,
         }
     }
 
@@ -116,8 +172,13 @@ impl Workers {
         parent_io_tx: &Sender<WorkerTask>,
         preview_io_tx: &Sender<WorkerTask>,
         sort_io_tx: &Sender<WorkerTask>,
+        tree_io_tx: &Sender<WorkerTask>,
         preview_file_tx: &Sender<WorkerTask>,
         metadata_tx: &Sender<WorkerTask>,
+        filter_meta_tx: &Sender<WorkerTask>,
+        details_tx: &Sender<WorkerTask>,
+        dir_size_tx: &Sender<WorkerTask>,
+        flatten_tx: &Sender<WorkerTask>,
         find_tx: &Sender<WorkerTask>,
         fileop_tx: &Sender<WorkerTask>,
         response_rx: &Receiver<WorkerResponse>,
@@ -128,9 +189,18 @@ impl Workers {
         Arc::clone(&self.cache)
     }
 
+    pub(crate) fn dir_sizes(&self) -> &DirSizeCache {
+        &self.dir_sizes
+    }
+
     pub(crate) fn retarget_watch(&self, dirs: Vec<PathBuf>) {
         let _ = self.watch_cmd_tx.send(WatchCommand::Retarget(dirs));
     }
+
+    /// Sends the status segments to show to the status worker.
+    pub(crate) fn request_status(&self, request: StatusRequest) {
+        let _ = self.status_tx.send(request);
+    }
 }
 
 struct ActiveOpGuard(Arc<AtomicUsize>);
@@ -206,6 +276,39 @@ pub(crate) enum WorkerTask {
         request_id: u64,
         tab_id: Option<usize>,
     },
+    LoadFilterMeta {
+        path: PathBuf,
+        entries: Arc<[FileEntry]>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    LoadDetails {
+        path: PathBuf,
+        names: Vec<OsString>,
+        columns: Arc<[DetailColumn]>,
+        date_format: Arc<str>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    CalculateDirSizes {
+        path: PathBuf,
+        names: Vec<OsString>,
+        cross_fs: bool,
+        cancel: Arc<AtomicBool>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    FlattenDirectory {
+        path: PathBuf,
+        list: DirListOptions,
+        sort_config: SortConfig,
+        sort_date_format: Arc<str>,
+        always_show: Arc<HashSet<OsString>>,
+        max_entries: usize,
+        cancel: Arc<AtomicBool>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
 }
 
 /// Supported file system operations the worker can perform.
@@ -265,6 +368,43 @@ pub(crate) enum WorkerResponse {
         request_id: u64,
         tab_id: Option<usize>,
     },
+    FilterMetaLoaded {
+        path: PathBuf,
+        meta: Arc<FilterMetaMap>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    DetailsLoaded {
+        path: PathBuf,
+        rows: Vec<(OsString, DetailRow)>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    DirSizeLoaded {
+        name: OsString,
+        size: u64,
+        done: bool,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    FlattenBatch {
+        path: PathBuf,
+        entries: Vec<FileEntry>,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    FlattenLoaded {
+        path: PathBuf,
+        entries: Arc<[FileEntry]>,
+        sort_column: Option<Arc<StrBuffer>>,
+        truncated: bool,
+        request_id: u64,
+        tab_id: Option<usize>,
+    },
+    StatusLoaded {
+        info: Arc<StatusInfo>,
+        tab_id: Option<usize>,
+    },
     ConfigChanged,
     DirsChanged {
         dirs: Vec<PathBuf>,
@@ -279,6 +419,12 @@ impl WorkerResponse {
             WorkerResponse::PreviewLoaded { tab_id, .. } => *tab_id,
             WorkerResponse::FindResults { tab_id, .. } => *tab_id,
             WorkerResponse::FileMetadataLoaded { tab_id, .. } => *tab_id,
+            WorkerResponse::FilterMetaLoaded { tab_id, .. } => *tab_id,
+            WorkerResponse::DetailsLoaded { tab_id, .. } => *tab_id,
+            WorkerResponse::DirSizeLoaded { tab_id, .. } => *tab_id,
+            WorkerResponse::FlattenBatch { tab_id, .. } => *tab_id,
+            WorkerResponse::FlattenLoaded { tab_id, .. } => *tab_id,
+            WorkerResponse::StatusLoaded { tab_id, .. } => *tab_id,
             _ => None,
         }
     }
@@ -293,6 +439,7 @@ fn start_io_worker(
     task_rx: Receiver<WorkerTask>,
     res_tx: Sender<WorkerResponse>,
     cache: Arc<DirCache>,
+    dir_sizes: Arc<DirSizeCache>,
 ) {
     thread::spawn(move || {
         while let Ok(task) = task_rx.recv() {
@@ -312,7 +459,8 @@ fn start_io_worker(
             match fm::browse_dir(&path) {
                 Ok(mut entries) => {
                     let meta_cache = DashMap::with_capacity(entries.len());
-                    let formatter = Formatter::new(list.clone(), sort_config, always_show);
+                    let formatter = Formatter::new(list.clone(), sort_config, always_show)
+                        .with_dir_sizes(Arc::clone(&dir_sizes));
                     formatter.filter_entries(&mut entries);
                     let sort_column =
                         formatter.sort_entries(&path, &mut entries, &sort_date_format, &meta_cache);
@@ -354,6 +502,7 @@ fn start_sort_worker(
     task_rx: Receiver<WorkerTask>,
     res_tx: Sender<WorkerResponse>,
     cache: Arc<DirCache>,
+    dir_sizes: Arc<DirSizeCache>,
 ) {
     thread::spawn(move || {
         while let Ok(task) = task_rx.recv() {
@@ -375,7 +524,8 @@ fn start_sort_worker(
             let mut entries_vec = entries.to_vec();
             let meta_cache = DashMap::with_capacity(entries.len());
 
-            let formatter = Formatter::new(list.clone(), sort_config, always_show);
+            let formatter = Formatter::new(list.clone(), sort_config, always_show)
+                .with_dir_sizes(Arc::clone(&dir_sizes));
             formatter.filter_entries(&mut entries_vec);
             let sort_column =
                 formatter.sort_entries(&path, &mut entries_vec, &sort_date_format, &meta_cache);
@@ -784,6 +934,251 @@ fn start_metadata_worker(task_rx: Receiver<WorkerTask>, res_tx: Sender<WorkerRes
     });
 }
 
+/// Starts the filter metadata worker thread.
+///
+/// Collects the metadata needed by metadata filter queries for a whole directory.
+/// Queued requests are skipped in favour of the newest one.
+fn start_filter_meta_worker(task_rx: Receiver<WorkerTask>, res_tx: Sender<WorkerResponse>) {
+    thread::spawn(move || {
+        #[cfg(unix)]
+        let mut ug_cache = crate::core::metadata::unix_meta::UserGroupCache::new();
+        while let Ok(mut task) = task_rx.recv() {
+            while let Ok(newer) = task_rx.try_recv() {
+                task = newer;
+            }
+
+            let WorkerTask::LoadFilterMeta {
+                path,
+                entries,
+                request_id,
+                tab_id,
+            } = task
+            else {
+                continue;
+            };
+
+            let meta = query::collect_filter_meta(
+                &path,
+                &entries,
+                #[cfg(unix)]
+                &mut ug_cache,
+            );
+
+            let _ = res_tx.send(WorkerResponse::FilterMetaLoaded {
+                path,
+                meta: Arc::new(meta),
+                request_id,
+                tab_id,
+            });
+        }
+    });
+}
+
+/// Starts the details worker thread.
+///
+/// Collects the detail columns of the rows visible in the details view.
+/// Requests are small and each pane requests only missing rows, so none are skipped.
+fn start_details_worker(task_rx: Receiver<WorkerTask>, res_tx: Sender<WorkerResponse>) {
+    thread::spawn(move || {
+        #[cfg(unix)]
+        let mut ug_cache = crate::core::metadata::unix_meta::UserGroupCache::new();
+        while let Ok(task) = task_rx.recv() {
+            let WorkerTask::LoadDetails {
+                path,
+                names,
+                columns,
+                date_format,
+                request_id,
+                tab_id,
+            } = task
+            else {
+                continue;
+            };
+
+            let rows = details::collect_details(
+                &path,
+                &names,
+                &columns,
+                &date_format,
+                #[cfg(unix)]
+                &mut ug_cache,
+            );
+
+            let _ = res_tx.send(WorkerResponse::DetailsLoaded {
+                path,
+                rows,
+                request_id,
+                tab_id,
+            });
+        }
+    });
+}
+
+/// Starts the directory size worker thread.
+///
+/// Calculates the recursive sizes of the requested subdirectories one by one, stores them in
+/// the [DirSizeCache] and sends each back as soon as it is known.
+/// A cancelled request, for example after leaving the directory, stops the walk.
+fn start_dir_size_worker(
+    task_rx: Receiver<WorkerTask>,
+    res_tx: Sender<WorkerResponse>,
+    cache: Arc<DirSizeCache>,
+) {
+    thread::spawn(move || {
+        while let Ok(task) = task_rx.recv() {
+            let WorkerTask::CalculateDirSizes {
+                path,
+                names,
+                cross_fs,
+                cancel,
+                request_id,
+                tab_id,
+            } = task
+            else {
+                continue;
+            };
+
+            // Subdirectories are measured against the filesystem of the listed directory.
+            let device = if cross_fs {
+                None
+            } else {
+                dir_size::device(&path)
+            };
+            let count = names.len();
+            for (idx, name) in names.into_iter().enumerate() {
+                let dir = path.join(&name);
+                let Some(size) = dir_size::dir_size(&dir, device, &cancel, &cache) else {
+                    break;
+                };
+                cache.insert(dir, cross_fs, size);
+
+                let _ = res_tx.send(WorkerResponse::DirSizeLoaded {
+                    name,
+                    size,
+                    done: idx + 1 == count,
+                    request_id,
+                    tab_id,
+                });
+            }
+        }
+    });
+}
+
+/// Starts the flatten worker thread, which lists every file below a directory.
+///
+/// Found files are streamed back in batches while walking and sent again
+/// as a sorted list once the walk is done.
+fn start_flatten_worker(task_rx: Receiver<WorkerTask>, res_tx: Sender<WorkerResponse>) {
+    thread::spawn(move || {
+        while let Ok(mut task) = task_rx.recv() {
+            while let Ok(newer) = task_rx.try_recv() {
+                task = newer;
+            }
+
+            let WorkerTask::FlattenDirectory {
+                path,
+                list,
+                sort_config,
+                sort_date_format,
+                always_show,
+                max_entries,
+                cancel,
+                request_id,
+                tab_id,
+            } = task
+            else {
+                continue;
+            };
+
+            let formatter = Formatter::new(list, sort_config, always_show);
+            let mut sent = 0;
+            let mut last_batch = Instant::now();
+
+            let (mut entries, truncated) =
+                walk_flatten(&path, &formatter, max_entries, &cancel, |found| {
+                    if found.len() > sent
+                        && last_batch.elapsed() >= Duration::from_millis(Timings::FLATTEN_BATCH_MS)
+                    {
+                        let _ = res_tx.send(WorkerResponse::FlattenBatch {
+                            path: path.clone(),
+                            entries: found[sent..].to_vec(),
+                            request_id,
+                            tab_id,
+                        });
+                        sent = found.len();
+                        last_batch = Instant::now();
+                    }
+                });
+
+            if cancel.load(Ordering::Acquire) {
+                continue;
+            }
+
+            let meta_cache = DashMap::with_capacity(entries.len());
+            let sort_column =
+                formatter.sort_entries(&path, &mut entries, &sort_date_format, &meta_cache);
+
+            let _ = res_tx.send(WorkerResponse::FlattenLoaded {
+                path,
+                entries: Arc::from(entries),
+                sort_column: sort_column.map(|v| Arc::new(StrBuffer::from_iter(v))),
+                truncated,
+                request_id,
+                tab_id,
+            });
+        }
+    });
+}
+
+/// Walks `root` recursively and collects every file, named by its path relative to `root`.
+///
+/// Directories are filtered like a normal listing and the find excludes are skipped.
+/// Symlinked directories are not followed. Returns the files and whether `max_entries` was hit.
+fn walk_flatten(
+    root: &Path,
+    formatter: &Formatter,
+    max_entries: usize,
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(&[FileEntry]),
+) -> (Vec<FileEntry>, bool) {
+    let mut out = Vec::new();
+    let mut stack = vec![PathBuf::new()];
+
+    while let Some(rel_dir) = stack.pop() {
+        if cancel.load(Ordering::Acquire) {
+            break;
+        }
+
+        let Ok(mut entries) = fm::browse_dir(&root.join(&rel_dir)) else {
+            continue;
+        };
+        formatter.filter_entries(&mut entries);
+
+        for entry in entries {
+            if proc::is_excluded(entry.name()) {
+                continue;
+            }
+
+            let rel = rel_dir.join(entry.name());
+            if entry.is_dir() {
+                if !entry.is_symlink() {
+                    stack.push(rel);
+                }
+                continue;
+            }
+
+            if out.len() >= max_entries {
+                return (out, true);
+            }
+            out.push(entry.renamed(rel.into_os_string()));
+        }
+
+        on_progress(&out);
+    }
+
+    (out, false)
+}
+
 /// Starts the filesystem watcher thread.
 fn start_fs_watch_worker(cmd_rx: Receiver<WatchCommand>, res_tx: Sender<WorkerResponse>) {
     thread::spawn(move || {
@@ -882,6 +1277,66 @@ fn start_fs_watch_worker(cmd_rx: Receiver<WatchCommand>, res_tx: Sender<WorkerRe
     });
 }
 
+/// Starts the status worker thread.
+///
+/// Computes the status segments of the latest [StatusRequest] and refreshes the git, disk
+/// and command segments on its interval. A request which only changes the marked entries
+/// only sums up their sizes again.
+fn start_status_worker(
+    request_rx: Receiver<StatusRequest>,
+    res_tx: Sender<WorkerResponse>,
+    dir_sizes: Arc<DirSizeCache>,
+) {
+    thread::spawn(move || {
+        let mut request = StatusRequest::default();
+        let mut info = StatusInfo::default();
+        let mut deadline: Option<Instant> = None;
+
+        loop {
+            let timer = match deadline {
+                Some(d) => crossbeam_channel::after(d.saturating_duration_since(Instant::now())),
+                None => crossbeam_channel::never(),
+            };
+
+            crossbeam_channel::select! {
+                recv(request_rx) -> msg => {
+                    // All senders dropped: runa is shutting down.
+                    let Ok(mut newer) = msg else {
+                        break;
+                    };
+                    while let Ok(next) = request_rx.try_recv() {
+                        newer = next;
+                    }
+                    if !newer.same_refresh(&request) {
+                        info.refresh(&newer);
+                        deadline = newer
+                            .has_refresh()
+                            .then(|| Instant::now() + newer.refresh);
+                    }
+                    info.refresh_marked(&newer, &dir_sizes);
+                    request = newer;
+                    if request.is_empty() {
+                        continue;
+                    }
+                },
+                recv(timer) -> _ => {
+                    let before = info.clone();
+                    info.refresh(&request);
+                    deadline = Some(Instant::now() + request.refresh);
+                    if info == before {
+                        continue;
+                    }
+                },
+            }
+
+            let _ = res_tx.send(WorkerResponse::StatusLoaded {
+                info: Arc::new(info.clone()),
+                tab_id: request.tab_id,
+            });
+        }
+    });
+}
+
 fn retarget_listing_watch(
     watcher: &mut RecommendedWatcher,
     watched: &mut HashSet<PathBuf>,
@@ -1158,6 +1613,67 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn worker_flatten_dir_lists_nested_files() -> Result<(), Box<dyn std::error::Error>> {
+        let workers = Workers::spawn();
+        let temp = tempdir()?;
+        let root = temp.path();
+
+        fs::create_dir_all(root.join("logs").join("old"))?;
+        fs::create_dir_all(root.join("node_modules"))?;
+        fs::create_dir_all(root.join(".hidden"))?;
+        fs::write(root.join("top.log"), "a")?;
+        fs::write(root.join("logs").join("app.log"), "b")?;
+        fs::write(root.join("logs").join("old").join("app.1.log"), "c")?;
+        fs::write(root.join("node_modules").join("dep.js"), "d")?;
+        fs::write(root.join(".hidden").join("secret.log"), "e")?;
+
+        workers.flatten_tx().send(WorkerTask::FlattenDirectory {
+            path: root.to_path_buf(),
+            list: test_list_opts(),
+            sort_config: SortConfig::default(),
+            sort_date_format: Arc::<str>::from(""),
+            always_show: Arc::new(HashSet::new()),
+            max_entries: 100,
+            cancel: Arc::new(AtomicBool::new(false)),
+            request_id: 7,
+            tab_id: TEST_TAB_ID,
+        })?;
+
+        let timeout = Instant::now() + TEST_TIMEOUT;
+        while Instant::now() < timeout {
+            let Ok(resp) = workers
+                .response_rx()
+                .recv_timeout(Duration::from_millis(500))
+            else {
+                continue;
+            };
+            if let WorkerResponse::FlattenLoaded {
+                entries,
+                truncated,
+                request_id,
+                ..
+            } = resp
+            {
+                assert_eq!(request_id, 7);
+                assert!(!truncated);
+
+                let mut names: Vec<PathBuf> =
+                    entries.iter().map(|e| PathBuf::from(e.name())).collect();
+                names.sort();
+                let expected = vec![
+                    PathBuf::from("logs").join("app.log"),
+                    PathBuf::from("logs").join("old").join("app.1.log"),
+                    PathBuf::from("top.log"),
+                ];
+                assert_eq!(names, expected);
+                assert!(entries.iter().all(|e| e.ext() == Some("log")));
+                return Ok(());
+            }
+        }
+        panic!("Flatten worker did not respond");
+    }
+
     #[test]
     fn worker_dir_load_requests_multithreaded() -> Result<(), Box<dyn std::error::Error>> {
         let temp_root = tempdir()?;
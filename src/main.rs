@@ -12,9 +12,11 @@ use std::io;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use crate::app::session::Session;
 use crate::cli::{CliAction, handle_args};
 use crate::config::Config;
 use crate::core::workers::Workers;
+use crate::utils::os;
 use crate::utils::path::{resolve_initial_dir, validate_path};
 
 fn set_panic_hook() {
@@ -26,6 +28,8 @@ fn handle_panic(info: &std::panic::PanicHookInfo<'_>) {
     let mut stdout = io::stdout();
     let _ = crossterm::execute!(
         stdout,
+        crossterm::event::DisableMouseCapture,
+        crossterm::event::DisableBracketedPaste,
         crossterm::terminal::LeaveAlternateScreen,
         crossterm::cursor::Show
     );
@@ -123,22 +127,57 @@ fn main() -> io::Result<()> {
         return Ok(());
     }
 
+    // The TUI is starting, so the theme may ask the terminal for its background.
+    ui::terminal::enable_background_query();
     let config = load_config_or_default();
 
-    let cli_paths = match action {
-        CliAction::RunApp => None,
-        CliAction::RunAppAtPath(paths) => Some(paths),
+    let (cli_paths, restore) = match action {
+        CliAction::RunApp => (None, config.general().restore_session()),
+        CliAction::RunAppAtPath(paths) => (Some(paths), false),
+        CliAction::RunAppRestore => (None, true),
         _ => unreachable!(),
     };
+    // The session is only written for users who restore it.
+    let save_session = restore || config.general().restore_session();
 
+    let config = Arc::new(config);
     let workers = Workers::spawn();
-
-    let container = match startup_container(Arc::new(config), &workers, cli_paths) {
-        Ok(cont) => cont,
-        Err(e) => exit_with_startup_error(e),
+    let session_path = os::default_session_path();
+
+    let restored = session_path
+        .as_deref()
+        .filter(|_| restore)
+        .and_then(Session::load)
+        .and_then(|session| session.restore(&config, &workers));
+
+    let (container, clipboard) = match restored {
+        Some(restored) => restored,
+        None => match startup_container(config, &workers, cli_paths) {
+            Ok(cont) => (cont, app::Clipboard::default()),
+            Err(e) => exit_with_startup_error(e),
+        },
     };
 
     let mut runa = app::RunaRoot::new(container, workers);
+    runa.clipboard = clipboard;
+    let history_path = os::default_history_path();
+    if let Some(path) = &history_path {
+        runa.load_history(path);
+    }
+
+    let result = ui::run_terminal(&mut runa);
+
+    if let Some(path) = session_path.filter(|_| save_session)
+        && let Err(e) = Session::capture(&runa.container, &runa.clipboard).save(&path)
+    {
+        eprintln!("[runa] Failed to save session: {}", e);
+    }
+
+    if let Some(path) = history_path
+        && let Err(e) = runa.history.save(&path)
+    {
+        eprintln!("[runa] Failed to save prompt history: {}", e);
+    }
 
-    ui::run_terminal(&mut runa)
+    result
 }
@@ -11,6 +11,7 @@
 //!
 //! See submodules [panes] and [widgets] for detailed drawing functions.
 
+pub(crate) mod colors;
 pub(crate) mod icons;
 pub(crate) mod overlays;
 pub(crate) mod panes;
@@ -18,6 +19,6 @@ pub(crate) mod render;
 pub(crate) mod terminal;
 pub(crate) mod widgets;
 
-pub(crate) use render::render;
+pub(crate) use render::render_container;
 
 pub(crate) use terminal::run_terminal;
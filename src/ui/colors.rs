@@ -0,0 +1,145 @@
+//! Color downsampling for terminals without true color support.
+//!
+//! The themes are made of RGB colors. On terminals which show only 256 or 16 colors, each
+//! color of the rendered frame is replaced by the nearest color the terminal can show.
+
+use ratatui::{buffer::Buffer, style::Color};
+
+use crate::config::display::ColorDepth;
+
+/// The 16 ANSI colors with the RGB values of xterm, in index order.
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// The channel values of the 6x6x6 color cube of the 256 colors.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Replaces the colors of the buffer which the terminal can not show.
+pub(crate) fn downsample(buffer: &mut Buffer, depth: ColorDepth) {
+    let convert: fn(Color) -> Color = match depth {
+        ColorDepth::Ansi256 => to_256,
+        ColorDepth::Ansi16 => to_16,
+        ColorDepth::TrueColor | ColorDepth::Auto => return,
+    };
+    for cell in &mut buffer.content {
+        cell.fg = convert(cell.fg);
+        cell.bg = convert(cell.bg);
+    }
+}
+
+fn to_256(color: Color) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => Color::Indexed(nearest_256((r, g, b))),
+        color => color,
+    }
+}
+
+fn to_16(color: Color) -> Color {
+    match color {
+        Color::Rgb(r, g, b) => nearest_16((r, g, b)),
+        Color::Indexed(index) if index < 16 => ANSI[usize::from(index)].0,
+        Color::Indexed(index) => nearest_16(indexed_rgb(index)),
+        color => color,
+    }
+}
+
+/// Returns the index of the nearest color of the color cube or the gray ramp.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        v => (v - 35) / 40,
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (
+        CUBE_LEVELS[usize::from(r)],
+        CUBE_LEVELS[usize::from(g)],
+        CUBE_LEVELS[usize::from(b)],
+    );
+
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let gray = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray;
+
+    if distance(rgb, (gray_level, gray_level, gray_level)) < distance(rgb, cube) {
+        232 + gray
+    } else {
+        16 + 36 * r + 6 * g + b
+    }
+}
+
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI.iter()
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Returns the RGB value of a color of the 256 colors.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI[usize::from(index)].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[usize::from(i / 36)],
+                CUBE_LEVELS[usize::from(i / 6 % 6)],
+                CUBE_LEVELS[usize::from(i % 6)],
+            )
+        }
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |a: u8, b: u8| u32::from(a.abs_diff(b)).pow(2);
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{layout::Rect, style::Style};
+
+    #[test]
+    fn downsample_maps_rgb_to_the_terminal_colors() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+        buffer.set_style(
+            Rect::new(0, 0, 1, 1),
+            Style::new()
+                .fg(Color::Rgb(0xc0, 0xca, 0xf5))
+                .bg(Color::Rgb(0x1a, 0x1b, 0x26)),
+        );
+        buffer.set_style(Rect::new(1, 0, 1, 1), Style::new().fg(Color::Indexed(196)));
+
+        let mut ansi256 = buffer.clone();
+        downsample(&mut ansi256, ColorDepth::Ansi256);
+        assert_eq!(ansi256[(0, 0)].fg, Color::Indexed(153));
+        assert_eq!(ansi256[(0, 0)].bg, Color::Indexed(234));
+        assert_eq!(ansi256[(1, 0)].fg, Color::Indexed(196));
+
+        downsample(&mut buffer, ColorDepth::Ansi16);
+        assert_eq!(buffer[(0, 0)].fg, Color::Gray);
+        assert_eq!(buffer[(0, 0)].bg, Color::Black);
+        assert_eq!(buffer[(1, 0)].fg, Color::LightRed);
+        assert_eq!(buffer[(1, 0)].bg, Color::Reset);
+    }
+}
@@ -1,14 +1,17 @@
-//! Module for mapping file types and names to Nerd Font icons.
+//! Module for mapping file types and names to icons.
 //! This module provides functions to retrieve appropriate icons
 //! based on file extensions, special filenames, and directory names.
 //!
-//! The main function `nerd_font_icon` takes a `FileEntry` and returns
-//! the corresponding Nerd Font icon.
+//! The main function `entry_icon` takes a `FileEntry` and returns
+//! the corresponding icon of the icon set, or the icon mapped in the `[icons]` table.
 
 use phf::phf_map;
 use ratatui::style::Color;
 
-use crate::config::Theme;
+use crate::config::{
+    Theme,
+    icons::{IconRule, IconSet, Icons},
+};
 use crate::core::FileEntry;
 use crate::utils::text::parse_color;
 
@@ -157,20 +160,36 @@ pub(crate) static SPECIAL_DIR_ICON_MAP: phf::Map<
     "nvim"         => ("", Some("#50a044")),
 };
 
-/// Get the Nerd Font icon for a given file entry.
-pub(crate) fn nerd_font_icon(entry: &FileEntry, theme: &Theme) -> (&'static str, Option<Color>) {
-    let name_str = entry.name_str();
+/// Returns the icon of the entry and its color.
+///
+/// The mapping of the `[icons]` table comes first, then the builtin icons of the icon set.
+/// The color is taken from the mapping, then from `[theme.icon_color]`, then from
+/// `LS_COLORS` and then from the builtin Nerd Font icons.
+pub(crate) fn entry_icon<'a>(
+    entry: &FileEntry,
+    theme: &Theme,
+    icons: &'a Icons,
+) -> (&'a str, Option<Color>) {
+    let set = icons.set();
     let is_dir = entry.is_dir();
 
     if entry.is_symlink() {
-        return if is_dir { ("", None) } else { ("", None) };
+        return (link_icon(set, is_dir), None);
     }
 
+    let rule = icons.rule(entry);
+    let rule_icon = rule.and_then(IconRule::icon);
+    let rule_color = rule.and_then(IconRule::color);
+
     #[cfg(unix)]
-    if entry.is_executable() && !is_dir {
-        return ("", Some(theme.exe_color()));
+    if entry.is_executable() && !is_dir && rule_icon.is_none() {
+        return (
+            executable_icon(set),
+            Some(rule_color.unwrap_or(theme.exe_color())),
+        );
     }
 
+    let name_str = entry.name_str();
     let ext = entry.ext();
     let lookup = if is_dir {
         SPECIAL_DIR_ICON_MAP.get(name_str)
@@ -180,16 +199,69 @@ pub(crate) fn nerd_font_icon(entry: &FileEntry, theme: &Theme) -> (&'static str,
             .or_else(|| ext.and_then(|e| EXT_ICON_MAP.get(e)))
     };
 
-    let icon = lookup
-        .map(|(i, _)| *i)
-        .unwrap_or(if is_dir { "" } else { "" });
+    let icon = rule_icon.unwrap_or_else(|| match set {
+        IconSet::Nerd => lookup
+            .map(|(i, _)| *i)
+            .unwrap_or(if is_dir { "" } else { "" }),
+        IconSet::Emoji => emoji_icon(is_dir, ext),
+        IconSet::Ascii => {
+            if is_dir {
+                "+"
+            } else {
+                "-"
+            }
+        }
+    });
 
-    let color = theme
-        .icon_color_cache()
-        .get(name_str)
-        .or_else(|| ext.and_then(|e| theme.icon_color_cache().get(e)))
-        .copied()
-        .or_else(|| lookup.and_then(|(_, hex)| hex.map(parse_color)));
+    let color = rule_color.or_else(|| {
+        theme
+            .icon_color_cache()
+            .get(name_str)
+            .or_else(|| ext.and_then(|e| theme.icon_color_cache().get(e)))
+            .copied()
+            .or_else(|| theme.ls_icon_color(entry))
+            .or_else(|| lookup.and_then(|(_, hex)| hex.map(parse_color)))
+    });
 
     (icon, color)
 }
+
+fn link_icon(set: IconSet, is_dir: bool) -> &'static str {
+    match (set, is_dir) {
+        (IconSet::Nerd, true) => "",
+        (IconSet::Nerd, false) => "",
+        (IconSet::Emoji, _) => "🔗",
+        (IconSet::Ascii, _) => "@",
+    }
+}
+
+#[cfg(unix)]
+fn executable_icon(set: IconSet) -> &'static str {
+    match set {
+        IconSet::Nerd => "",
+        IconSet::Emoji => "🚀",
+        IconSet::Ascii => "*",
+    }
+}
+
+/// Returns the emoji of the kind of file.
+fn emoji_icon(is_dir: bool, ext: Option<&str>) -> &'static str {
+    if is_dir {
+        return "📁";
+    }
+    match ext.unwrap_or_default() {
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "bmp" | "ico" | "psd" => "📷",
+        "mp4" | "mkv" | "webm" | "mov" | "avi" => "🎬",
+        "mp3" | "flac" | "wav" | "ogg" | "m4a" | "opus" => "🎵",
+        "zip" | "tar" | "gz" | "xz" | "bz2" | "zst" | "7z" | "rar" | "deb" | "rpm" | "dmg"
+        | "iso" | "img" | "msi" | "appimage" | "snap" | "flatpak" | "cab" => "📦",
+        "pdf" | "epub" => "📕",
+        "md" | "txt" | "rst" | "org" => "📝",
+        "toml" | "yaml" | "yml" | "json" | "ini" | "conf" | "cfg" | "xml" => "🔧",
+        "lock" => "🔒",
+        "rs" | "py" | "js" | "ts" | "tsx" | "jsx" | "go" | "java" | "lua" | "php" | "rb"
+        | "html" | "css" | "swift" | "kt" | "c" | "cpp" | "h" | "hpp" | "sh" | "bash" | "zsh"
+        | "fish" | "sql" => "💻",
+        _ => "📄",
+    }
+}
@@ -10,13 +10,16 @@ use std::sync::Arc;
 
 use ratatui::layout::Alignment;
 
+use crate::app::help::HelpPalette;
+use crate::app::theme_picker::ThemePicker;
 use crate::core::metadata::FileMetadataCache;
 
 pub(crate) enum Overlay {
     ShowInfo { info: Arc<FileMetadataCache> },
     Message { text: String, alignment: Alignment },
     PrefixHelp,
-    KeybindHelp,
+    KeybindHelp { help: Box<HelpPalette> },
+    ThemePicker { picker: ThemePicker },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,6 +28,7 @@ pub(crate) enum OverlayKind {
     Message,
     PrefixHelp,
     KeybindHelp,
+    ThemePicker,
 }
 
 pub(crate) struct OverlayStack {
@@ -37,7 +41,8 @@ impl Overlay {
             Overlay::ShowInfo { .. } => OverlayKind::ShowInfo,
             Overlay::Message { .. } => OverlayKind::Message,
             Overlay::PrefixHelp => OverlayKind::PrefixHelp,
-            Overlay::KeybindHelp => OverlayKind::KeybindHelp,
+            Overlay::KeybindHelp { .. } => OverlayKind::KeybindHelp,
+            Overlay::ThemePicker { .. } => OverlayKind::ThemePicker,
         }
     }
 }
@@ -91,11 +96,22 @@ impl OverlayStack {
         })
     }
 
+    pub(crate) fn find_keybind_help_mut(&mut self) -> Option<&mut HelpPalette> {
+        self.overlays.iter_mut().find_map(|o| match o {
+            Overlay::KeybindHelp { help } => Some(help.as_mut()),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn find_theme_picker_mut(&mut self) -> Option<&mut ThemePicker> {
+        self.overlays.iter_mut().find_map(|o| match o {
+            Overlay::ThemePicker { picker } => Some(picker),
+            _ => None,
+        })
+    }
+
     pub(crate) fn needs_scroll(&self) -> bool {
-        matches!(
-            self.top(),
-            Some(Overlay::ShowInfo { .. }) | Some(Overlay::KeybindHelp)
-        )
+        matches!(self.top(), Some(Overlay::ShowInfo { .. }))
     }
 }
 
@@ -7,7 +7,7 @@
 
 use std::borrow::Cow;
 use std::collections::HashSet;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
 
 use ratatui::widgets::BorderType;
@@ -20,14 +20,19 @@ use ratatui::{
 };
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use crate::app::{AppState, Clipboard, PreviewData};
-use crate::config::{Display, Theme};
-use crate::core::FileEntry;
+use crate::app::{AppState, Clipboard, PreviewData, mouse::ListRegion};
+use crate::config::{Config, Theme, icons::Icons, theme::EntryColor};
+use crate::core::{
+    FileEntry,
+    details::{DetailColumn, DetailRow},
+    sort::{SortMode, SortOrder},
+};
 use crate::ui::icons;
 use crate::utils::text::StrBuffer;
 
 const MAX_RIGHT_COLUMN_WIDTH: u16 = 24;
 const MIN_LEFT_WIDTH: u16 = 12;
+const DETAILS_SEPARATOR: &str = "  ";
 
 /// Styles used for rendering items in a pane
 /// Includes styles for regular items, directories and selected items
@@ -95,6 +100,7 @@ pub(super) struct PaneContext<'a> {
     block: Block<'a>,
     border_type: BorderType,
     theme: &'a Theme,
+    icons: &'a Icons,
     accent_style: Style,
     styles: PaneStyles,
     highlight_symbol: &'a str,
@@ -108,16 +114,17 @@ impl<'a> PaneContext<'a> {
         area: Rect,
         block: Block<'a>,
         border_type: BorderType,
-        theme: &'a Theme,
-        display: &'a Display,
+        config: &'a Config,
         styles: PaneStyles,
         highlight_symbol: &'a str,
     ) -> Self {
+        let (theme, display) = (config.theme(), config.display());
         Self {
             area,
             block,
             border_type,
             theme,
+            icons: config.icons(),
             accent_style: theme.accent_style(),
             padding_str: display.padding_str(),
             show_icons: display.icons(),
@@ -150,6 +157,25 @@ struct RightCol<'a> {
     width: u16,
 }
 
+/// Per-row columns around the entry name: the tree guide and the right column.
+#[derive(Clone, Copy)]
+struct RowCols<'a, 'k> {
+    guide: &'a str,
+    key: Option<&'k OsStr>,
+    right: RightCol<'k>,
+}
+
+impl<'k> RowCols<'_, 'k> {
+    #[inline]
+    fn right(right: RightCol<'k>) -> Self {
+        Self {
+            guide: "",
+            key: None,
+            right,
+        }
+    }
+}
+
 impl<'a> RightCol<'a> {
     #[inline]
     fn none() -> Self {
@@ -172,14 +198,16 @@ impl<'a> RightCol<'a> {
 /// Draws the main file list pane in the UI
 ///
 /// Highlights selection, markers and directories and handles styling for items.
+/// Returns the drawn list region, used to map mouse clicks onto entries.
 pub(super) fn draw_main(
     frame: &mut Frame,
     app: &AppState,
     context: PaneContext,
     clipboard: &Clipboard,
-) {
+) -> ListRegion {
     let selected_idx = app.visible_selected();
     let current_dir = app.nav().current_dir();
+    let inner = context.block.inner(context.area);
 
     if !app.is_loading()
         && app.nav().shown_entries_len() == 0
@@ -191,7 +219,7 @@ pub(super) fn draw_main(
             Span::styled("[No results for this filter]", style),
         ]);
         frame.render_widget(Paragraph::new(line).block(context.block), context.area);
-        return;
+        return ListRegion::new(inner, 0);
     }
 
     if !app.has_visible_entries() {
@@ -205,7 +233,7 @@ pub(super) fn draw_main(
             Paragraph::new(line).block(context.block.border_style(context.accent_style)),
             context.area,
         );
-        return;
+        return ListRegion::new(inner, 0);
     }
 
     let markers = make_main_pane_markers(app, current_dir, clipboard);
@@ -214,14 +242,51 @@ pub(super) fn draw_main(
 
     let sort_column = app.nav().sort_column();
     let inner_w = pane_inner_width(&context);
-    let (show_col, right_w) = right_col_config(inner_w, sort_column.as_deref());
+    // Sorted by size, directories show their recursive size once it is known.
+    let size_sorted = app.nav().sort_for(current_dir).mode() == SortMode::Size;
+    let dir_size_w = if size_sorted {
+        app.dir_sizes().max_width()
+    } else {
+        0
+    };
+    let right_w = right_col_width(sort_column.as_deref())
+        .max(dir_size_w)
+        .min(MAX_RIGHT_COLUMN_WIDTH);
+    let show_col = pane_show_col(inner_w, right_w);
+    let details = app
+        .details()
+        .enabled()
+        .then(|| DetailsView::new(app, inner_w, sort_column.as_deref()));
 
     let mut items = Vec::with_capacity(shown_len);
     for (vis_idx, &abs_idx) in app.nav().shown_indices().iter().enumerate() {
         let entry = &app.nav().entries()[abs_idx];
         let is_selected = Some(vis_idx) == selected_idx;
         let entry_style = context.styles.get_style(entry.is_dir(), is_selected);
-        let right = right_col_for(show_col, right_w, sort_column.as_deref(), abs_idx);
+
+        // Nested tree rows are looked up in the markers and details by their path relative to the current dir.
+        let node = app.nav().tree_node(abs_idx).filter(|n| n.depth() > 0);
+        let key = app.nav().row_key(abs_idx);
+        let dir_size = if entry.is_dir() && node.is_none() {
+            app.dir_sizes().size_of(entry.name())
+        } else {
+            None
+        };
+
+        let details_text = details.as_ref().map(|view| {
+            let row = key.as_deref().and_then(|k| app.details().row(k));
+            view.row_text(row, sort_column.as_deref(), abs_idx, dir_size)
+        });
+        let right = match (&details, &details_text) {
+            (Some(view), Some(text)) => view.right_col(text),
+            _ => {
+                let mut right = right_col_for(show_col, right_w, sort_column.as_deref(), abs_idx);
+                if show_col && size_sorted && dir_size.is_some() {
+                    right.text = dir_size;
+                }
+                right
+            }
+        };
 
         items.push(make_entry_row(
             entry,
@@ -230,7 +295,11 @@ pub(super) fn draw_main(
             &context,
             &markers,
             None,
-            right,
+            RowCols {
+                guide: node.map_or("", |n| n.guide()),
+                key: node.and(key.as_deref()),
+                right,
+            },
         ));
     }
 
@@ -239,33 +308,266 @@ pub(super) fn draw_main(
         state.select(selected_idx);
     }
 
-    frame.render_stateful_widget(
-        List::new(items)
-            .block(
-                context
-                    .block
-                    .border_style(context.accent_style)
-                    .border_type(context.border_type),
-            )
-            .highlight_style(Style::default())
-            .highlight_symbol(context.highlight_symbol)
-            .scroll_padding(app.config().display().scroll_padding()),
-        context.area,
-        &mut state,
-    );
+    let block = context
+        .block
+        .clone()
+        .border_style(context.accent_style)
+        .border_type(context.border_type);
+    let list = List::new(items)
+        .highlight_style(Style::default())
+        .highlight_symbol(context.highlight_symbol)
+        .scroll_padding(app.config().display().scroll_padding());
+
+    let Some(view) = details else {
+        frame.render_stateful_widget(list.block(block), context.area, &mut state);
+        return ListRegion::new(inner, state.offset());
+    };
+
+    // The details view draws its header on the first row of the pane.
+    let header_area = Rect { height: 1, ..inner };
+    let list_area = Rect {
+        y: inner.y.saturating_add(1),
+        height: inner.height.saturating_sub(1),
+        ..inner
+    };
+    frame.render_widget(block, context.area);
+    frame.render_widget(Paragraph::new(view.header(&context)), header_area);
+    frame.render_stateful_widget(list, list_area, &mut state);
+    ListRegion::new(list_area, state.offset())
+}
+
+/// Where a column of the details view takes its values from.
+#[derive(Clone, Copy)]
+enum DetailSource {
+    /// The value at this index of the loaded detail row.
+    Row(usize),
+    /// The sort column of the directory listing.
+    Sort,
+}
+
+/// A shown column of the details view.
+struct DetailsCol {
+    title: String,
+    source: DetailSource,
+    width: usize,
+    /// Shows the recursive size of directories once it is known.
+    dir_sizes: bool,
+}
+
+/// The columns of the details view which fit into the main pane.
+struct DetailsView {
+    name_title: String,
+    cols: Vec<DetailsCol>,
+    width: u16,
+}
+
+impl DetailsView {
+    /// Resolves the shown columns of the details view.
+    ///
+    /// The column matching the sort mode shows the sort column of the listing and gets
+    /// an indicator of the sort order in its title. A metadata sort column which is not part
+    /// of the configured columns is appended. Columns which don't fit are dropped from the right.
+    /// Size columns show the recursive size of directories once it is known.
+    fn new(app: &AppState, inner_w: u16, sort_column: Option<&StrBuffer>) -> Self {
+        let sort = app.nav().sort_for(app.nav().current_dir());
+        let arrow = match sort.order() {
+            SortOrder::Ascending => "↑",
+            SortOrder::Descending => "↓",
+        };
+        let titled = |title: &str, sorted: bool| {
+            if sorted {
+                format!("{title} {arrow}")
+            } else {
+                title.to_string()
+            }
+        };
+        let row_width = |idx: usize| {
+            app.details()
+                .rows()
+                .filter_map(|row| row.get(idx))
+                .map(|v| UnicodeWidthStr::width(&**v))
+                .max()
+                .unwrap_or(0)
+        };
+        let dir_size_width = |dir_sizes: bool| {
+            if dir_sizes {
+                app.dir_sizes().max_width() as usize
+            } else {
+                0
+            }
+        };
+
+        let mut cols = Vec::new();
+        let mut sort_shown = false;
+        for (idx, column) in app
+            .config()
+            .display()
+            .details()
+            .columns()
+            .iter()
+            .enumerate()
+        {
+            let sorted = column.sort_mode() == Some(sort.mode());
+            let (source, width) = if sorted && sort_column.is_some() {
+                sort_shown = true;
+                (DetailSource::Sort, right_col_width(sort_column) as usize)
+            } else {
+                (DetailSource::Row(idx), row_width(idx))
+            };
+            let title = titled(column.title(), sorted);
+            let dir_sizes = *column == DetailColumn::Size;
+            cols.push(DetailsCol {
+                width: width
+                    .max(UnicodeWidthStr::width(title.as_str()))
+                    .max(dir_size_width(dir_sizes)),
+                title,
+                source,
+                dir_sizes,
+            });
+        }
+        if !sort_shown && sort_column.is_some() {
+            let title = titled(sort_mode_title(sort.mode()), true);
+            let dir_sizes = sort.mode() == SortMode::Size;
+            cols.push(DetailsCol {
+                width: (right_col_width(sort_column) as usize)
+                    .max(UnicodeWidthStr::width(title.as_str()))
+                    .max(dir_size_width(dir_sizes)),
+                title,
+                source: DetailSource::Sort,
+                dir_sizes,
+            });
+        }
+
+        for col in &mut cols {
+            col.width = col.width.min(MAX_RIGHT_COLUMN_WIDTH as usize);
+        }
+        let available = (inner_w as usize).saturating_sub(MIN_LEFT_WIDTH as usize + 1);
+        while !cols.is_empty() && Self::total_width(&cols) > available {
+            cols.pop();
+        }
+
+        let name_sorted = matches!(sort.mode(), SortMode::Name | SortMode::Natural);
+        Self {
+            name_title: titled("Name", name_sorted),
+            width: Self::total_width(&cols) as u16,
+            cols,
+        }
+    }
+
+    fn total_width(cols: &[DetailsCol]) -> usize {
+        let seps = cols.len().saturating_sub(1) * DETAILS_SEPARATOR.len();
+        cols.iter().map(|c| c.width).sum::<usize>() + seps
+    }
+
+    /// Builds the right-aligned columns of an entry.
+    /// Columns of rows which are not loaded yet are left empty.
+    fn row_text(
+        &self,
+        row: Option<&DetailRow>,
+        sort_column: Option<&StrBuffer>,
+        idx: usize,
+        dir_size: Option<&str>,
+    ) -> String {
+        self.join(|col| match (col.source, dir_size) {
+            (_, Some(size)) if col.dir_sizes => size,
+            (DetailSource::Row(i), _) => row.and_then(|r| r.get(i)).map_or("", |v| &**v),
+            (DetailSource::Sort, _) => right_col_at(sort_column, idx).unwrap_or_default(),
+        })
+    }
+
+    fn join<'s>(&'s self, value: impl Fn(&'s DetailsCol) -> &'s str) -> String {
+        let mut out = String::with_capacity(self.width as usize);
+        for (i, col) in self.cols.iter().enumerate() {
+            if i > 0 {
+                out.push_str(DETAILS_SEPARATOR);
+            }
+            let text = truncate_owned(value(col), col.width);
+            let pad = col.width.saturating_sub(UnicodeWidthStr::width(&*text));
+            out.extend(std::iter::repeat_n(' ', pad));
+            out.push_str(&text);
+        }
+        out
+    }
+
+    fn right_col<'t>(&self, text: &'t str) -> RightCol<'t> {
+        if self.cols.is_empty() {
+            return RightCol::none();
+        }
+        RightCol {
+            text: Some(text),
+            width: self.width,
+        }
+    }
+
+    /// Builds the header line, aligned with the names and columns of the entry rows.
+    fn header(&self, context: &PaneContext) -> Line<'static> {
+        let style = context.styles.item.add_modifier(Modifier::BOLD);
+        let mut lead = " ".repeat(
+            UnicodeWidthStr::width(context.highlight_symbol)
+                + UnicodeWidthStr::width(context.padding_str),
+        );
+        let icon_w = if context.show_icons {
+            context.icons.set().width() + 1
+        } else {
+            0
+        };
+        lead.push_str(&" ".repeat(icon_w));
+
+        let total_w = pane_inner_width(context) as usize;
+        let name_budget = total_w
+            .saturating_sub(lead.len())
+            .saturating_sub(self.right_reserve())
+            .max(1);
+        let name = truncate_owned(&self.name_title, name_budget).into_owned();
+        let used_w = UnicodeWidthStr::width(context.padding_str)
+            + icon_w
+            + UnicodeWidthStr::width(name.as_str());
+
+        let mut spans = vec![Span::raw(lead), Span::styled(name, style)];
+        if !self.cols.is_empty() {
+            let titles = self.join(|col| col.title.as_str());
+            spans.push(Span::styled(
+                build_right_field(&titles, total_w, used_w, self.width as usize),
+                style,
+            ));
+        }
+        Line::from(spans)
+    }
+
+    fn right_reserve(&self) -> usize {
+        if self.cols.is_empty() {
+            0
+        } else {
+            self.width as usize + 1
+        }
+    }
+}
+
+/// Title of the sort column appended to the details view for a metadata sort mode.
+fn sort_mode_title(mode: SortMode) -> &'static str {
+    match mode {
+        SortMode::Size => "Size",
+        SortMode::Modified => "Modified",
+        SortMode::Created => "Created",
+        SortMode::Accessed => "Accessed",
+        SortMode::Name | SortMode::Natural => "Name",
+        SortMode::Extension => "Ext",
+    }
 }
 
 /// Draws the preview pane, showing either the file content or directory listing
 ///
 /// Also applies underline/selection styles and manages cursor position
+/// Returns the drawn list region, used to map mouse clicks onto entries.
 pub(super) fn draw_preview(
     frame: &mut Frame,
     app: &AppState,
     context: PaneContext,
     markers: &PaneMarkers,
-) {
+) -> ListRegion {
     let preview = app.preview().data();
     let selected_idx = Some(app.preview().selected_idx());
+    let inner = context.block.inner(context.area);
 
     let opts = PreviewOptions {
         use_underline: app.config().display().preview_underline(),
@@ -284,6 +586,7 @@ pub(super) fn draw_preview(
                 ),
                 context.area,
             );
+            ListRegion::new(inner, 0)
         }
 
         PreviewData::File(text) => {
@@ -296,6 +599,7 @@ pub(super) fn draw_preview(
                 ),
                 context.area,
             );
+            ListRegion::new(inner, 0)
         }
 
         PreviewData::Directory {
@@ -316,7 +620,7 @@ pub(super) fn draw_preview(
                     context.area,
                     &mut state,
                 );
-                return;
+                return ListRegion::new(inner, 0);
             }
 
             let inner_w = pane_inner_width(&context);
@@ -335,7 +639,7 @@ pub(super) fn draw_preview(
                     &context,
                     markers,
                     Some(&opts),
-                    right,
+                    RowCols::right(right),
                 ));
             }
 
@@ -355,19 +659,22 @@ pub(super) fn draw_preview(
                 context.area,
                 &mut state,
             );
+            ListRegion::new(inner, state.offset())
         }
     }
 }
 
 /// Draws the parent directory of the current working directory.
+/// Returns the drawn list region, used to map mouse clicks onto entries.
 pub(super) fn draw_parent(
     frame: &mut Frame,
     app: &AppState,
     context: PaneContext,
     markers: &PaneMarkers,
-) {
+) -> ListRegion {
     let entries = app.parent().entries();
     let selected_idx = app.parent().selected_idx();
+    let inner = context.block.inner(context.area);
     if entries.is_empty() {
         frame.render_widget(
             Paragraph::new("").block(
@@ -378,7 +685,7 @@ pub(super) fn draw_parent(
             ),
             context.area,
         );
-        return;
+        return ListRegion::new(inner, 0);
     }
 
     let sort_column = app.parent().sort_column();
@@ -398,7 +705,7 @@ pub(super) fn draw_parent(
             &context,
             markers,
             None,
-            right,
+            RowCols::right(right),
         ));
     }
 
@@ -418,6 +725,7 @@ pub(super) fn draw_parent(
         context.area,
         &mut state,
     );
+    ListRegion::new(inner, state.offset())
 }
 
 /// Helper: Create a PaneMarkers struct for use in pane drawing functions.
@@ -468,6 +776,7 @@ fn pane_marker_sets(
 
 /// Helper: Create a PaneMarkers struct for the main pane.
 /// Builds marker and clipboard sets for the current directory.
+/// In tree and flatten mode the sets also hold nested entries, keyed by their path relative to the current directory.
 /// Used in main pane drawing function.
 fn make_main_pane_markers<'a>(
     app: &'a AppState,
@@ -478,23 +787,31 @@ fn make_main_pane_markers<'a>(
     let marker_icon = marker_theme.icon();
 
     let nav = app.nav();
+    let nested = nav.shows_nested();
+    let local_key = |p: &PathBuf| -> Option<OsString> {
+        if p.parent() == Some(current_dir) {
+            return p.file_name().map(|n| n.to_os_string());
+        }
+        if !nested {
+            return None;
+        }
+        p.strip_prefix(current_dir)
+            .ok()
+            .map(|rel| rel.as_os_str().to_os_string())
+    };
+
     let markers = nav.markers();
     let local_markers = if markers.is_empty() {
         None
     } else {
-        let set: HashSet<OsString> = markers
-            .iter()
-            .filter(|p| p.parent() == Some(current_dir))
-            .filter_map(|p| p.file_name().map(|n| n.to_os_string()))
-            .collect();
+        let set: HashSet<OsString> = markers.iter().filter_map(local_key).collect();
 
         if set.is_empty() { None } else { Some(set) }
     };
 
     let clipboard = clipboard.entries.as_ref().map(|set| {
         set.iter()
-            .filter(|p| p.parent() == Some(current_dir))
-            .filter_map(|p| p.file_name().map(|n| n.to_os_string()))
+            .filter_map(local_key)
             .collect::<HashSet<OsString>>()
     });
 
@@ -518,19 +835,21 @@ fn make_entry_row<'a>(
     context: &PaneContext,
     markers: &PaneMarkers,
     opts: Option<&PreviewOptions>,
-    right: RightCol<'a>,
+    cols: RowCols<'a, '_>,
 ) -> ListItem<'a> {
     let mut used_w: usize = 0;
+    let right = cols.right;
+    let key = cols.key.unwrap_or(entry.name());
 
     let is_marked = markers
         .markers
         .as_ref()
-        .is_some_and(|set| set.contains(entry.name()));
+        .is_some_and(|set| set.contains(key));
 
     let is_copied = markers
         .clipboard
         .as_ref()
-        .is_some_and(|set| set.contains(entry.name()));
+        .is_some_and(|set| set.contains(key));
 
     let mut icon_style = if is_copied {
         markers.clipboard_style
@@ -547,11 +866,14 @@ fn make_entry_row<'a>(
 
     let mut row_style = style;
 
-    if let Some(override_style) =
-        context
-            .theme
-            .entry_color_override(entry.name_str(), entry.is_dir(), entry.ext())
-    {
+    let entry_color = context.theme.entry_color(entry);
+    // LS_COLORS which take precedence also replace the executable and symlink colors.
+    let ls_style = match entry_color {
+        Some(EntryColor::Entry(style)) => Some(style),
+        _ => None,
+    };
+
+    if let Some(override_style) = entry_color.as_ref().map(EntryColor::style) {
         row_style = row_style.patch(override_style);
 
         if is_selected {
@@ -599,7 +921,15 @@ fn make_entry_row<'a>(
         spans.push(Span::raw(context.padding_str));
     }
 
-    let symlink_fg = if entry.is_broken_sym() {
+    if !cols.guide.is_empty() {
+        used_w += UnicodeWidthStr::width(cols.guide);
+        spans.push(Span::styled(cols.guide, style.add_modifier(Modifier::DIM)));
+    }
+
+    let ls_fg = ls_style.and_then(|style| style.fg);
+    let symlink_fg = if let Some(fg) = ls_fg {
+        fg
+    } else if entry.is_broken_sym() {
         Color::Red
     } else if entry.is_dir() {
         context.styles.symlink_dir
@@ -607,12 +937,12 @@ fn make_entry_row<'a>(
         context.styles.symlink_file
     };
 
-    if entry.is_executable() && !entry.is_dir() && !entry.is_symlink() {
+    if entry.is_executable() && !entry.is_dir() && !entry.is_symlink() && ls_style.is_none() {
         row_style = row_style.fg(context.styles.executable_fg);
     }
 
     if context.show_icons {
-        let (icon, icon_custom_color) = icons::nerd_font_icon(entry, context.theme);
+        let (icon, icon_custom_color) = icons::entry_icon(entry, context.theme, context.icons);
 
         let mut icon_col = String::with_capacity(icon.len() + 1);
         icon_col.push_str(icon);
@@ -622,7 +952,9 @@ fn make_entry_row<'a>(
 
         let mut icon_render_style = row_style.add_modifier(Modifier::BOLD);
 
-        if entry.is_symlink() {
+        if let Some(fg) = ls_fg.filter(|_| context.theme.ls_colors().icons()) {
+            icon_render_style = icon_render_style.fg(fg);
+        } else if entry.is_symlink() {
             icon_render_style = icon_render_style.fg(symlink_fg);
         } else if let Some(custom_color) = icon_custom_color {
             icon_render_style = icon_render_style.fg(custom_color);
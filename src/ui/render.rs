@@ -16,9 +16,12 @@ use ratatui::{
 };
 
 use crate::app::{
-    AppState, Clipboard, LayoutMetrics,
+    AppContainer, AppState, Clipboard, LayoutMetrics,
     actions::{ActionMode, InputMode},
+    layout::PaneLayout,
+    mouse::{ListRegion, MouseRegions},
 };
+use crate::config::display::Display;
 use crate::core::workers::Workers;
 use crate::ui::{
     overlays::Overlay,
@@ -26,6 +29,9 @@ use crate::ui::{
     widgets,
 };
 
+/// Regions of the parent, main and preview panes of a rendered view.
+type PaneRegions = (Option<ListRegion>, Option<ListRegion>, Option<ListRegion>);
+
 /// Render function which renders the entire terminal UI for runa on each frame.
 /// Handles layout, pane rendering, borders, headers and coordinates all widgets.
 pub(crate) fn render(
@@ -34,18 +40,103 @@ pub(crate) fn render(
     workers: &Workers,
     clipboard: &mut Clipboard,
 ) {
-    let mut root_area = frame.area();
+    let regions = render_view(frame, app, frame.area(), workers, clipboard, true);
+    render_status_and_overlays(frame, app, workers, clipboard, regions);
+}
+
+/// Renders the app container.
+///
+/// In the dual pane layout the current and the opposite tab are rendered side by side,
+/// otherwise only the current tab is rendered.
+pub(crate) fn render_container(
+    frame: &mut Frame,
+    container: &mut AppContainer,
+    workers: &Workers,
+    clipboard: &mut Clipboard,
+) {
+    match container {
+        AppContainer::Single(app) => render(frame, app, workers, clipboard),
+        AppContainer::Tabs(tabs) => {
+            let current_is_left = tabs.current_is_left();
+            match tabs.dual_panes_mut() {
+                // A zoomed preview fills the whole frame.
+                Some((current, opposite)) if !current.layout().zoomed() => {
+                    render_dual(
+                        frame,
+                        current,
+                        opposite,
+                        current_is_left,
+                        workers,
+                        clipboard,
+                    );
+                }
+                _ => render(frame, tabs.current_tab_mut(), workers, clipboard),
+            }
+        }
+    }
+}
+
+/// Renders the current and the opposite pane of the dual pane layout.
+/// Only the current pane receives the status bars, overlays and mouse regions.
+fn render_dual(
+    frame: &mut Frame,
+    current: &mut AppState,
+    opposite: &mut AppState,
+    current_is_left: bool,
+    workers: &Workers,
+    clipboard: &mut Clipboard,
+) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(frame.area());
+    let (current_area, opposite_area) = if current_is_left {
+        (halves[0], halves[1])
+    } else {
+        (halves[1], halves[0])
+    };
+
+    render_view(frame, opposite, opposite_area, workers, clipboard, false);
+    let regions = render_view(frame, current, current_area, workers, clipboard, true);
+    render_status_and_overlays(frame, current, workers, clipboard, regions);
+}
+
+/// Renders the root block, header and panes of the app into the given area.
+///
+/// Unfocused views are drawn with the separator style instead of the accent style.
+/// Returns the parent, main and preview pane regions for mouse handling.
+fn render_view(
+    frame: &mut Frame,
+    app: &mut AppState,
+    area: Rect,
+    workers: &Workers,
+    clipboard: &mut Clipboard,
+    focused: bool,
+) -> PaneRegions {
+    let mut root_area = area;
     let outer_area = root_area;
-    let metrics = calculate_layout_metrics(frame.area(), app);
+    let panes = app.pane_layout(area.width, area.height);
+    let metrics = calculate_layout_metrics(area, app.config().display(), panes);
     app.update_layout_metrics(workers, metrics);
 
     let cfg = app.config();
     let display_cfg = cfg.display();
     let theme_cfg = cfg.theme();
 
-    let accent_style = theme_cfg.accent_style();
-
     let border_type = display_cfg.border_shape().as_border_type();
+    let border_style = if focused {
+        theme_cfg.accent_style()
+    } else {
+        theme_cfg.separator_style()
+    };
+    let pane_block = |title: &str| {
+        let block = widgets::get_pane_block(title, app);
+        if focused {
+            block
+        } else {
+            block.border_style(border_style)
+        }
+    };
 
     let markers = app.nav().markers();
     let marker_theme = theme_cfg.marker();
@@ -54,18 +145,19 @@ pub(crate) fn render(
 
     let clipboard_style = marker_theme.clipboard_style_or_theme();
 
-    root_area = render_root_and_header(frame, app, root_area);
+    root_area = render_root_and_header(frame, app, root_area, border_style);
     if display_cfg.is_no_borders() {
         root_area.height = root_area.height.saturating_sub(1);
     }
 
     // Render Panes
-    let chunks = layout_chunks(root_area, app);
+    let chunks = layout_chunks(root_area, display_cfg, panes);
     let mut pane_idx = 0;
-    let show_separators = display_cfg.separators() && !display_cfg.is_split();
+    let show_separators = has_separators(display_cfg, panes);
+    let (mut parent_region, mut main_region, mut preview_region) = (None, None, None);
 
     // PARENT PANE
-    if display_cfg.parent() && pane_idx < chunks.len() {
+    if panes.parent && pane_idx < chunks.len() {
         let parent_dir = app
             .parent()
             .last_path()
@@ -83,20 +175,19 @@ pub(crate) fn render(
             .with_entry(theme_cfg.parent().entry_style_or_theme())
             .with_selection(theme_cfg.parent_selection_style());
 
-        panes::draw_parent(
+        parent_region = Some(panes::draw_parent(
             frame,
             app,
             PaneContext::new(
                 chunks[pane_idx],
-                widgets::get_pane_block("Parent", app),
+                pane_block("Parent"),
                 border_type,
-                theme_cfg,
-                display_cfg,
+                cfg,
                 parent_pane_style,
                 "",
             ),
             &parent_markers,
-        );
+        ));
         pane_idx += 1;
         if show_separators && pane_idx < chunks.len() {
             render_separator(
@@ -111,29 +202,28 @@ pub(crate) fn render(
     }
 
     // MAIN PANE
-    if pane_idx < chunks.len() {
+    if panes.main && pane_idx < chunks.len() {
         let symbol = if display_cfg.selection_marker() {
             theme_cfg.selection_icon()
         } else {
             ""
         };
 
-        panes::draw_main(
+        main_region = Some(panes::draw_main(
             frame,
             app,
             PaneContext::new(
                 chunks[pane_idx],
-                widgets::get_pane_block("Files", app),
+                pane_block("Files"),
                 border_type,
-                theme_cfg,
-                display_cfg,
+                cfg,
                 PaneStyles::new(theme_cfg),
                 symbol,
             ),
             clipboard,
-        );
+        ));
         pane_idx += 1;
-        if show_separators && display_cfg.preview() && pane_idx < chunks.len() {
+        if show_separators && panes.preview && pane_idx < chunks.len() {
             render_separator(
                 frame,
                 chunks[pane_idx].x,
@@ -146,7 +236,7 @@ pub(crate) fn render(
     }
 
     // PREVIEW PANE
-    if display_cfg.preview() && pane_idx < chunks.len() {
+    if panes.preview && pane_idx < chunks.len() {
         let area = chunks[pane_idx];
         let bg_filler = Block::default().style(theme_cfg.preview().entry_style_or_theme());
         frame.render_widget(bg_filler, area);
@@ -165,64 +255,72 @@ pub(crate) fn render(
             .with_entry(theme_cfg.preview().entry_style_or_theme())
             .with_selection(theme_cfg.preview_selection_style());
 
-        panes::draw_preview(
+        preview_region = Some(panes::draw_preview(
             frame,
             app,
             PaneContext::new(
                 chunks[pane_idx],
-                widgets::get_pane_block("Preview", app),
+                pane_block("Preview"),
                 border_type,
-                theme_cfg,
-                display_cfg,
+                cfg,
                 preview_pane_style,
                 "",
             ),
             &preview_markers,
-        );
+        ));
+    }
+
+    if let Some(region) = &main_region {
+        app.request_visible_details(workers, region);
     }
 
-    widgets::draw_status_bar(
+    (parent_region, main_region, preview_region)
+}
+
+/// Renders the status bars and overlays of the focused app and stores its mouse regions.
+fn render_status_and_overlays(
+    frame: &mut Frame,
+    app: &mut AppState,
+    workers: &Workers,
+    clipboard: &mut Clipboard,
+    (parent_region, main_region, preview_region): PaneRegions,
+) {
+    let mut tab_regions = widgets::draw_status_bar(
         frame,
         app,
         widgets::StatusPosition::Header,
         workers,
         clipboard,
     );
-    widgets::draw_status_bar(
+    tab_regions.extend(widgets::draw_status_bar(
         frame,
         app,
         widgets::StatusPosition::Footer,
         workers,
         clipboard,
-    );
-    render_overlays(frame, app, accent_style);
+    ));
+    render_overlays(frame, app, app.config().theme().accent_style());
+
+    app.set_mouse_regions(MouseRegions::new(
+        parent_region,
+        main_region,
+        preview_region,
+        tab_regions,
+    ));
 }
 
-/// Returns the rectangular areas for all active panes, given the current configuration
+/// Returns the rectangular areas for all shown panes of the resolved [PaneLayout].
 ///
 /// The result is used for positioning file navigation, parent and preview panes in the layout.
-/// Handles separators and dynamic ratios.
-pub(crate) fn layout_chunks(size: Rect, app: &AppState) -> Vec<Rect> {
-    let cfg = app.config().display();
+/// Handles separators, dynamic ratios and the stacked layout.
+pub(crate) fn layout_chunks(size: Rect, cfg: &Display, panes: PaneLayout) -> Vec<Rect> {
     let mut constraints = Vec::new();
-    let show_sep = cfg.separators() && !cfg.is_split();
-
-    let parent = if cfg.parent() {
-        cfg.parent_ratio() as u32
-    } else {
-        0
-    };
-    let main = cfg.main_ratio() as u32;
-    let preview = if cfg.preview() {
-        cfg.preview_ratio() as u32
-    } else {
-        0
-    };
+    let show_sep = has_separators(cfg, panes);
 
     let enabled = [
-        (parent, cfg.parent()),
-        (main, true),
-        (preview, cfg.preview()),
+        (panes.parent_ratio as u32, panes.parent),
+        (panes.main_ratio as u32, panes.main),
+        (panes.preview_ratio as u32, panes.preview),
     ];
 
     let total: u32 = enabled
@@ -233,7 +331,7 @@ pub(crate) fn layout_chunks(size: Rect, app: &AppState) -> Vec<Rect> {
         .max(1);
 
     let mut sum_pct: u16 = 0;
-    let pane_count = enabled.iter().filter(|e| e.1).count();
+    let pane_count = panes.pane_count();
     let mut pane_added = 0;
 
     for &(val, enabled) in &enabled {
@@ -253,15 +351,31 @@ pub(crate) fn layout_chunks(size: Rect, app: &AppState) -> Vec<Rect> {
         }
     }
 
+    let direction = if panes.stacked {
+        Direction::Vertical
+    } else {
+        Direction::Horizontal
+    };
+
     Layout::default()
-        .direction(Direction::Horizontal)
+        .direction(direction)
         .constraints(constraints)
         .split(size)
         .to_vec()
 }
 
+/// Returns true if vertical separators are drawn between the panes.
+fn has_separators(cfg: &Display, panes: PaneLayout) -> bool {
+    cfg.separators() && !cfg.is_split() && !panes.stacked
+}
+
 /// Renders the root block and header (if applicable) around the main content area.
-fn render_root_and_header(frame: &mut Frame, app: &AppState, area: Rect) -> Rect {
+fn render_root_and_header(
+    frame: &mut Frame,
+    app: &AppState,
+    area: Rect,
+    border_style: Style,
+) -> Rect {
     let cfg = app.config();
     let display_cfg = cfg.display();
     let theme_cfg = cfg.theme();
@@ -271,7 +385,7 @@ fn render_root_and_header(frame: &mut Frame, app: &AppState, area: Rect) -> Rect
     if display_cfg.is_unified() {
         let mut outer_block = Block::default()
             .borders(Borders::ALL)
-            .border_style(theme_cfg.accent_style())
+            .border_style(border_style)
             .border_type(border_type);
 
         if display_cfg.titles() {
@@ -362,8 +476,11 @@ fn render_single_overlay(
         Overlay::PrefixHelp => {
             widgets::draw_prefix_help_overlay(frame, app, accent_style);
         }
-        Overlay::KeybindHelp => {
-            widgets::draw_keybind_help(frame, app, accent_style);
+        Overlay::KeybindHelp { help } => {
+            widgets::draw_keybind_help(frame, app, accent_style, help);
+        }
+        Overlay::ThemePicker { picker } => {
+            widgets::draw_theme_picker(frame, app, accent_style, picker);
         }
     }
 }
@@ -371,13 +488,12 @@ fn render_single_overlay(
 /// Helper function to calculate and return layout metrics
 ///
 /// Used to store pane widths and heights in the AppState for later use.
-fn calculate_layout_metrics(area: Rect, app: &AppState) -> LayoutMetrics {
-    let chunks = layout_chunks(area, app);
+fn calculate_layout_metrics(area: Rect, display_cfg: &Display, panes: PaneLayout) -> LayoutMetrics {
+    let chunks = layout_chunks(area, display_cfg, panes);
     let mut metrics = LayoutMetrics::default();
-    let display_cfg = app.config().display();
 
     let mut idx = 0;
-    let has_sep = display_cfg.separators() && !display_cfg.is_split();
+    let has_sep = has_separators(display_cfg, panes);
 
     let get_inner = |rect: Rect| {
         let width = if display_cfg.is_split() || display_cfg.is_unified() {
@@ -390,21 +506,17 @@ fn calculate_layout_metrics(area: Rect, app: &AppState) -> LayoutMetrics {
         (width as usize, height as usize)
     };
 
-    if display_cfg.parent() && idx < chunks.len() {
+    if panes.parent && idx < chunks.len() {
         metrics.parent_width = get_inner(chunks[idx]).0;
         idx += if has_sep { 2 } else { 1 };
     }
 
-    if idx < chunks.len() {
+    if panes.main && idx < chunks.len() {
         metrics.main_width = get_inner(chunks[idx]).0;
-        idx += if has_sep && display_cfg.preview() {
-            2
-        } else {
-            1
-        };
+        idx += if has_sep && panes.preview { 2 } else { 1 };
     }
 
-    if display_cfg.preview() && idx < chunks.len() {
+    if panes.preview && idx < chunks.len() {
         let (width, height) = get_inner(chunks[idx]);
         metrics.preview_width = width;
         metrics.preview_height = height;
@@ -471,7 +583,11 @@ mod tests {
 
         let app = AppState::new(Arc::new(config)).expect("Failed to create AppState");
 
-        let chunks = layout_chunks(size, &app);
+        let chunks = layout_chunks(
+            size,
+            app.config().display(),
+            app.pane_layout(size.width, size.height),
+        );
 
         assert_eq!(chunks.len(), 3);
         let total_width: u16 = chunks.iter().map(|c| c.width).sum();
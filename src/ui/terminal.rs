@@ -1,13 +1,24 @@
 //! Terminal rendering and event loop for runa.
 //!
 //! Handles setup/teardown of raw mode, alternate screen, redraws,
-//! and events (keypress, resize) to app logic.
+//! and events (keypress, mouse, resize) to app logic.
 
-use std::{io, time::Duration};
+use std::collections::VecDeque;
+use std::{
+    io,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
 
 use crossterm::{
     cursor::{Hide, Show},
-    event::{self, Event, KeyEventKind},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -28,16 +39,40 @@ use crate::ui;
 pub(crate) fn run_terminal(root: &mut RunaRoot) -> io::Result<()> {
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, Hide)?;
+    execute!(stdout, EnterAlternateScreen, EnableBracketedPaste, Hide)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
 
     let result = event_loop(&mut terminal, root);
 
     terminal::disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, Show)?;
+    execute!(
+        terminal.backend_mut(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        LeaveAlternateScreen,
+        Show
+    )?;
     result
 }
 
+/// Enables or disables mouse capture to match the `display.mouse` option.
+fn sync_mouse_capture(
+    writer: &mut impl io::Write,
+    root: &RunaRoot,
+    captured: &mut bool,
+) -> io::Result<()> {
+    let enabled = root.mouse_enabled();
+    if enabled != *captured {
+        if enabled {
+            execute!(writer, EnableMouseCapture)?;
+        } else {
+            execute!(writer, DisableMouseCapture)?;
+        }
+        *captured = enabled;
+    }
+    Ok(())
+}
+
 /// Main event loop of runa: draws UI, polls for events and dispatches them to the app.
 /// Returns on quit
 fn event_loop<B: Backend + io::Write>(
@@ -47,106 +82,395 @@ fn event_loop<B: Backend + io::Write>(
 where
     io::Error: From<<B as Backend>::Error>,
 {
+    let mut mouse_captured = false;
+    let mut typed = VecDeque::new();
+
     loop {
+        sync_mouse_capture(terminal.backend_mut(), root, &mut mouse_captured)?;
         let mut changed = root.update();
+        changed |= root.sync_macro_status();
 
-        changed |= match &mut root.container {
-            AppContainer::Single(app) => app.tick(&root.workers),
-            AppContainer::Tabs(tabs) => tabs.current_tab_mut().tick(&root.workers),
-        };
+        changed |= root.tick();
 
         if changed {
             root.sync_watch();
+            root.sync_status();
 
             if let AppContainer::Tabs(tabs) = &mut root.container {
                 tabs.sync_tab_line();
             }
 
-            terminal.draw(|f| match &mut root.container {
-                AppContainer::Single(app) => ui::render(f, app, &root.workers, &mut root.clipboard),
-                AppContainer::Tabs(tabs) => ui::render(
-                    f,
-                    tabs.current_tab_mut(),
-                    &root.workers,
-                    &mut root.clipboard,
-                ),
-            })?;
+            draw(terminal, root)?;
         }
 
         // Event Polling
-        if event::poll(Duration::from_millis(16))? {
+        // While a macro plays, events are only peeked at so the replayed keys are not delayed.
+        let timeout = if root.macro_playing() {
+            Duration::from_millis(1)
+        } else {
+            Duration::from_millis(16)
+        };
+
+        // Keys typed while the terminal was asked for its background come first.
+        typed.extend(take_typed_keys());
+        let result = if let Some(key) = typed.pop_front() {
+            root.handle_key(key)
+        } else if event::poll(timeout)? {
             match event::read()? {
                 // handle keypress
-                Event::Key(key) if key.kind == KeyEventKind::Press => {
-                    let result = match &mut root.container {
-                        AppContainer::Single(app) => {
-                            app.handle_keypress(key, &root.workers, &mut root.clipboard)
-                        }
-                        AppContainer::Tabs(tabs) => tabs.current_tab_mut().handle_keypress(
-                            key,
-                            &root.workers,
-                            &mut root.clipboard,
-                        ),
-                    };
-
-                    match result {
-                        KeypressResult::Quit => break,
-                        KeypressResult::OpenedEditor | KeypressResult::Recovered => {
-                            execute!(
-                                terminal.backend_mut(),
-                                LeaveAlternateScreen,
-                                EnterAlternateScreen,
-                                Hide,
-                            )?;
-                            terminal.clear()?;
-                        }
-                        KeypressResult::UiReload if root.reload_ui(terminal.backend_mut())? => {
-                            terminal.clear()?;
-                        }
-                        KeypressResult::Tab(tab_act) => {
-                            if let KeypressResult::Quit =
-                                app::handle_tab_action(&root.workers, &mut root.container, tab_act)
-                            {
-                                break;
-                            }
-                        }
-                        KeypressResult::Sort(config) => {
-                            app::handle_sort_action(&mut root.container, config);
-                        }
-                        _ => {}
+                Event::Key(key) if key.kind == KeyEventKind::Press => root.handle_key(key),
+
+                // handle mouse
+                Event::Mouse(mouse) => match &mut root.container {
+                    AppContainer::Single(app) => {
+                        app.handle_mouse(mouse, &root.workers, &mut root.clipboard)
                     }
-                    // Redraw after state change
-                    terminal.draw(|f| match &mut root.container {
-                        AppContainer::Single(app) => {
-                            ui::render(f, app, &root.workers, &mut root.clipboard)
-                        }
-                        AppContainer::Tabs(tabs) => ui::render(
-                            f,
-                            tabs.current_tab_mut(),
-                            &root.workers,
-                            &mut root.clipboard,
-                        ),
-                    })?;
-                }
+                    AppContainer::Tabs(tabs) => tabs.current_tab_mut().handle_mouse(
+                        mouse,
+                        &root.workers,
+                        &mut root.clipboard,
+                    ),
+                },
+
+                // handle pasted text
+                Event::Paste(text) => root.handle_paste(&text),
 
                 // handle resize
-                Event::Resize(_, _) => {
-                    terminal.draw(|f| match &mut root.container {
-                        AppContainer::Single(app) => {
-                            ui::render(f, app, &root.workers, &mut root.clipboard)
-                        }
-                        AppContainer::Tabs(tabs) => ui::render(
-                            f,
-                            tabs.current_tab_mut(),
-                            &root.workers,
-                            &mut root.clipboard,
-                        ),
-                    })?;
-                }
+                Event::Resize(_, _) => KeypressResult::Continue,
+
+                _ => continue,
+            }
+        } else if let Some(key) = root.next_macro_key() {
+            root.replay_key(key)
+        } else if let Some(result) = root.resolve_expired_keys() {
+            result
+        } else {
+            continue;
+        };
 
-                _ => {}
+        match result {
+            KeypressResult::Quit => break,
+            KeypressResult::OpenedEditor | KeypressResult::Recovered => {
+                execute!(
+                    terminal.backend_mut(),
+                    LeaveAlternateScreen,
+                    EnterAlternateScreen,
+                    EnableBracketedPaste,
+                    Hide,
+                )?;
+                terminal.clear()?;
+                // The editor may have reset the mouse mode of the terminal.
+                mouse_captured = false;
+                sync_mouse_capture(terminal.backend_mut(), root, &mut mouse_captured)?;
+            }
+            KeypressResult::UiReload if root.reload_ui(terminal.backend_mut())? => {
+                terminal.clear()?;
+            }
+            KeypressResult::Tab(tab_act) => {
+                if let KeypressResult::Quit =
+                    app::handle_tab_action(&root.workers, &mut root.container, tab_act)
+                {
+                    break;
+                }
+            }
+            KeypressResult::Theme(name) => root.preview_theme(name.as_deref()),
+            KeypressResult::Sort(config) => {
+                app::handle_sort_action(&mut root.container, config);
+            }
+            KeypressResult::Pane(pane_act) => {
+                app::handle_pane_action(&root.workers, &mut root.container, pane_act);
             }
+            _ => {}
         }
+        // Redraw after state change
+        root.sync_status();
+        draw(terminal, root)?;
     }
     Ok(())
 }
+
+/// Draws a frame, with the colors downsampled to the color depth of the terminal.
+fn draw<B: Backend>(terminal: &mut Terminal<B>, root: &mut RunaRoot) -> Result<(), B::Error> {
+    let depth = root.color_depth();
+    terminal.draw(|f| {
+        ui::render_container(f, &mut root.container, &root.workers, &mut root.clipboard);
+        ui::colors::downsample(f.buffer_mut(), depth);
+    })?;
+    Ok(())
+}
+
+/// Returns true if the background of the terminal is light, or None if it is not known.
+///
+/// The terminal is asked once with OSC 11, before the event loop reads the input. Until
+/// [enable_background_query] was called, e.g. for the CLI commands, it is never asked.
+pub(crate) fn light_background() -> Option<bool> {
+    static LIGHT: OnceLock<Option<bool>> = OnceLock::new();
+    if !BACKGROUND_QUERY.load(Ordering::Relaxed) {
+        return LIGHT.get().copied().flatten();
+    }
+    *LIGHT.get_or_init(|| {
+        let (r, g, b) = query_background()?;
+        // Relative luminance of the sRGB color, without gamma correction.
+        let luma = 0.2126 * f32::from(r) + 0.7152 * f32::from(g) + 0.0722 * f32::from(b);
+        Some(luma > 127.5)
+    })
+}
+
+/// Set once the TUI starts, the terminal is only asked for its background after that.
+static BACKGROUND_QUERY: AtomicBool = AtomicBool::new(false);
+
+/// Lets [light_background] ask the terminal for its background.
+/// Called when the TUI is starting, before the config is loaded.
+pub(crate) fn enable_background_query() {
+    BACKGROUND_QUERY.store(true, Ordering::Relaxed);
+}
+
+/// How long to wait for the replies of the terminal to the background query.
+///
+/// The DA1 reply ends the wait, this is only a fallback for terminals which answer neither.
+#[cfg(unix)]
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Keys typed while the terminal was asked for its background, handed to the event loop.
+static TYPED_INPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Asks the terminal for its background color.
+///
+/// The OSC 11 query is followed by a DA1 query, which every terminal answers, and the replies
+/// are read as raw bytes from the tty until the DA1 reply. So a terminal without OSC 11
+/// support ends the wait as well and no late reply is read as keys by the event loop.
+#[cfg(unix)]
+fn query_background() -> Option<(u8, u8, u8)> {
+    use std::io::IsTerminal;
+
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    let raw = terminal::is_raw_mode_enabled().unwrap_or(false);
+    if !raw {
+        terminal::enable_raw_mode().ok()?;
+    }
+    let background = read_query_replies();
+    if !raw {
+        let _ = terminal::disable_raw_mode();
+    }
+    parse_background_reply(&background?)
+}
+
+#[cfg(not(unix))]
+fn query_background() -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// Sends the queries and reads the tty until the DA1 reply.
+/// Returns the text of the OSC 11 reply.
+///
+/// The tty is only read once `poll` reports input, so nothing is left reading it after the
+/// timeout. The keys typed in the meantime are handed to the event loop.
+#[cfg(unix)]
+fn read_query_replies() -> Option<String> {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::time::Instant;
+
+    use rustix::event::{PollFd, PollFlags, Timespec, poll};
+    use rustix::io::Errno;
+
+    let mut tty = File::open("/dev/tty").ok()?;
+    let mut stdout = io::stdout();
+    stdout.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    stdout.flush().ok()?;
+
+    let deadline = Instant::now() + BACKGROUND_QUERY_TIMEOUT;
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 256];
+    let input = loop {
+        let input = scan_query_input(&buf);
+        let left = deadline.saturating_duration_since(Instant::now());
+        if input.done || left.is_zero() {
+            break input;
+        }
+        let timeout = Timespec::try_from(left).ok()?;
+        let mut fds = [PollFd::new(&tty, PollFlags::IN)];
+        match poll(&mut fds, Some(&timeout)) {
+            Ok(0) => break input,
+            Ok(_) => {}
+            Err(Errno::INTR) => continue,
+            Err(_) => break input,
+        }
+        match tty.read(&mut chunk) {
+            Ok(n @ 1..) => buf.extend_from_slice(&chunk[..n]),
+            _ => break input,
+        }
+    };
+
+    if let Ok(mut typed) = TYPED_INPUT.lock() {
+        typed.extend_from_slice(&input.typed);
+    }
+    input.background
+}
+
+/// The input read while waiting for the replies to the queries.
+#[cfg(unix)]
+#[derive(Debug, Default, PartialEq)]
+struct QueryInput {
+    /// The text of the OSC 11 reply, e.g. `11;rgb:1a1a/1b1b/2626`.
+    background: Option<String>,
+    /// True once the DA1 reply `ESC [ ? ... c` was read.
+    done: bool,
+    /// Everything else, typed by the user.
+    typed: Vec<u8>,
+}
+
+/// Splits the input into the replies of the terminal and the typed keys.
+///
+/// An OSC reply ends with `ESC \` or BEL. Anything after the DA1 reply is typed.
+#[cfg(unix)]
+fn scan_query_input(input: &[u8]) -> QueryInput {
+    let mut out = QueryInput::default();
+    let mut i = 0;
+    while i < input.len() {
+        let rest = &input[i..];
+        if let Some(body) = rest.strip_prefix(b"\x1b]") {
+            let bel = body.iter().position(|&b| b == 0x07).map(|end| (end, 1));
+            let st = body
+                .windows(2)
+                .position(|w| w == b"\x1b\\")
+                .map(|end| (end, 2));
+            let Some((end, len)) = bel.into_iter().chain(st).min() else {
+                // The rest of the reply is still on its way.
+                break;
+            };
+            out.background = Some(String::from_utf8_lossy(&body[..end]).into_owned());
+            i += 2 + end + len;
+        } else if let Some(params) = rest.strip_prefix(b"\x1b[?") {
+            let Some(end) = params
+                .iter()
+                .position(|b| !b.is_ascii_digit() && *b != b';')
+            else {
+                break;
+            };
+            if params[end] == b'c' {
+                out.done = true;
+                out.typed.extend_from_slice(&params[end + 1..]);
+                break;
+            }
+            out.typed.push(rest[0]);
+            i += 1;
+        } else {
+            out.typed.push(rest[0]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Returns the keys typed while the terminal was asked for its background.
+///
+/// Escape sequences such as the arrow keys are dropped, only text, enter, tab, backspace,
+/// escape and control keys are kept.
+fn take_typed_keys() -> Vec<KeyEvent> {
+    let typed = TYPED_INPUT
+        .lock()
+        .map(|mut typed| std::mem::take(&mut *typed))
+        .unwrap_or_default();
+    typed_keys(&String::from_utf8_lossy(&typed))
+}
+
+fn typed_keys(typed: &str) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut chars = typed.chars().peekable();
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.peek() {
+                Some('[' | 'O') => {
+                    chars.next();
+                    // Skip the parameters up to the final byte of the sequence.
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                _ => KeyEvent::from(KeyCode::Esc),
+            },
+            '\r' | '\n' => KeyEvent::from(KeyCode::Enter),
+            '\t' => KeyEvent::from(KeyCode::Tab),
+            '\x7f' | '\x08' => KeyEvent::from(KeyCode::Backspace),
+            '\x01'..='\x1a' => KeyEvent::new(
+                KeyCode::Char(char::from(b'a' + c as u8 - 1)),
+                KeyModifiers::CONTROL,
+            ),
+            c if c.is_control() => continue,
+            c => KeyEvent::from(KeyCode::Char(c)),
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// Reads the color of a `11;rgb:RRRR/GGGG/BBBB` reply, with 1 to 4 hex digits per channel.
+#[cfg(unix)]
+fn parse_background_reply(reply: &str) -> Option<(u8, u8, u8)> {
+    let color = reply.strip_prefix("11;")?.strip_prefix("rgb:")?;
+    let mut channels = color.split('/').map(|channel| {
+        let digits = u32::try_from(channel.len())
+            .ok()
+            .filter(|n| (1..=4).contains(n))?;
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        let max = 16u32.pow(digits) - 1;
+        u8::try_from(value * 255 / max).ok()
+    });
+    let rgb = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn background_reply_is_parsed() {
+        assert_eq!(
+            parse_background_reply("11;rgb:1a1a/1b1b/2626"),
+            Some((0x1a, 0x1b, 0x26))
+        );
+        assert_eq!(
+            parse_background_reply("11;rgb:f/f/f"),
+            Some((255, 255, 255))
+        );
+        assert_eq!(parse_background_reply("11;rgb:ffff/ffff"), None);
+        assert_eq!(parse_background_reply("10;rgb:0/0/0"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn query_replies_are_split_from_typed_keys() {
+        let input = scan_query_input(b"j\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22ck");
+        assert_eq!(
+            input,
+            QueryInput {
+                background: Some("11;rgb:ffff/ffff/ffff".into()),
+                done: true,
+                typed: b"jk".to_vec(),
+            }
+        );
+
+        // A terminal without OSC 11 only answers the DA1 query.
+        let input = scan_query_input(b"\x1b[?1;2c");
+        assert!(input.done && input.background.is_none());
+        // The DA1 reply is not complete yet.
+        assert!(!scan_query_input(b"\x1b]11;rgb:0/0/0\x07\x1b[?6").done);
+
+        assert_eq!(
+            typed_keys("g\x1b[A\r\x04"),
+            vec![
+                KeyEvent::from(KeyCode::Char('g')),
+                KeyEvent::from(KeyCode::Enter),
+                KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL),
+            ]
+        );
+    }
+}
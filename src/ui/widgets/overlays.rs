@@ -6,9 +6,13 @@ use ratatui::{
     style::{Modifier, Style},
     text::{Line, Span, Text},
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::app::AppState;
-use crate::config::input::InputKeys;
+use crate::app::{
+    AppState,
+    help::{self, HelpPalette},
+    theme_picker::ThemePicker,
+};
 use crate::core::metadata::FileMetadataCache;
 use crate::ui::widgets::{self, DialogLayout, DialogPosition, DialogSize};
 
@@ -104,116 +108,97 @@ pub(crate) fn draw_show_info_dialog(
     );
 }
 
+/// Draws the which-key style help of the pending key sequence.
+///
+/// Lists the keys which continue the sequence with their action, or the name of their
+/// prefix group for keys which start a longer sequence.
 pub(crate) fn draw_prefix_help_overlay(frame: &mut Frame, app: &AppState, accent_style: Style) {
     let widget = app.config().theme().widget();
     let area = frame.area();
     let border_type = app.config().display().border_shape().as_border_type();
-    let keys = app.config().keys();
-    let go_to_top_keys = keys.go_to_top();
-    let go_to_home_keys = keys.go_to_home();
-    let go_to_path_keys = keys.go_to_path();
-
-    let is_sort_prefix = app.actions().prefix_recognizer().is_sort_state();
-    if is_sort_prefix {
-        fn mk_line(items: &[(&[String], &str)], accent_style: Style) -> Line<'static> {
-            let mut spans: Vec<Span<'static>> = Vec::with_capacity(items.len() * 4);
-            for (i, (keys, desc)) in items.iter().enumerate() {
-                if i > 0 {
-                    spans.push(Span::raw("    "));
-                }
-                let primary_key = keys.first().map(|s| s.as_str()).unwrap_or("?");
-                spans.push(Span::styled(format!("[{}]", primary_key), accent_style));
-                spans.push(Span::raw(" "));
-                spans.push(Span::raw((*desc).to_string()));
-            }
-            Line::from(spans)
-        }
 
-        let row1: [(&[String], &str); 4] = [
-            (keys.sort_by_name(), "Name"),
-            (keys.sort_by_modified(), "Modified"),
-            (keys.sort_by_created(), "Created"),
-            (keys.sort_by_accessed(), "Accessed"),
-        ];
-        let row2: [(&[String], &str); 3] = [
-            (keys.sort_by_size(), "Size"),
-            (keys.sort_by_extension(), "Ext"),
-            (keys.sort_by_natural(), "Natural"),
-        ];
-
-        let lines: Vec<Line<'static>> =
-            vec![mk_line(&row1, accent_style), mk_line(&row2, accent_style)];
-
-        let area = frame.area();
-        let border_pad = 2usize;
-        let inner_pad = 2usize;
-
-        let max_line_w = lines.iter().map(|l| l.width()).max().unwrap_or(0);
-
-        let min_width = 24usize;
-        let max_width = (area.width as usize).saturating_sub(2).max(min_width);
-
-        let width = (max_line_w + inner_pad + border_pad)
-            .max(min_width)
-            .min(max_width) as u16;
-
-        let height = (lines.len() + border_pad).min(area.height as usize) as u16;
-
-        widgets::draw_dialog(
-            frame,
-            DialogLayout {
-                area,
-                position: widget.go_to_help_position(),
-                size: DialogSize::Custom(width, height),
-            },
-            border_type,
-            &widgets::get_dialog_style(app, accent_style, "Sort", None),
-            Text::from(lines),
-            Some(Alignment::Center),
-            None,
-        );
+    let pending = app.actions().prefix_recognizer().pending();
+    let Some(node) = app.keymap().root().find(pending) else {
         return;
-    }
+    };
 
-    let mut g_prefixes: Vec<(String, &'static str)> =
-        Vec::with_capacity(go_to_top_keys.len() + go_to_path_keys.len());
-    if let Some(k) = go_to_top_keys.first() {
-        g_prefixes.push((k.clone(), "Go to top"));
-    }
-    if let Some(k) = go_to_home_keys.first() {
-        g_prefixes.push((k.clone(), "Go to home"));
-    }
-    if let Some(k) = go_to_path_keys.first() {
-        g_prefixes.push((k.clone(), "Go to path"));
-    }
+    let items: Vec<(String, String)> = node
+        .children()
+        .into_iter()
+        .map(|(key, child)| {
+            let desc = match (child.is_prefix(), child.group(), child.binding()) {
+                (true, Some(group), _) => format!("+{group}"),
+                (_, _, Some(binding)) => help::describe(binding.label()),
+                _ => "+prefix".to_string(),
+            };
+            (format!("[{key}]"), desc)
+        })
+        .collect();
 
-    let mut spans = Vec::with_capacity(4 * g_prefixes.len() + 2);
-    for (i, (ch, desc)) in g_prefixes.iter().enumerate() {
-        if i > 0 {
-            spans.push(Span::raw("    "));
-        }
-        spans.push(Span::styled(format!("[{}]", ch), accent_style));
-        spans.push(Span::raw(" "));
-        spans.push(Span::raw(*desc));
-    }
+    let title = node.group().map_or_else(
+        || {
+            pending
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        },
+        str::to_string,
+    );
 
-    spans.push(Span::raw(" "));
-    let line = Line::from(spans);
+    let border_pad = 2usize;
+    let inner_pad = 2usize;
+    let gap = 4usize;
+    let item_w = items
+        .iter()
+        .map(|(key, desc)| key.width() + 1 + desc.width())
+        .max()
+        .unwrap_or(0);
+
+    // Columns of items, as many as fit next to each other.
+    let max_width = (area.width as usize).saturating_sub(2);
+    let usable = max_width.saturating_sub(border_pad + inner_pad);
+    let columns = ((usable + gap) / (item_w + gap)).clamp(1, 4);
+
+    let lines: Vec<Line<'static>> = items
+        .chunks(columns)
+        .map(|row| {
+            let mut spans = Vec::with_capacity(row.len() * 4 + 1);
+            spans.push(Span::raw(" "));
+            for (i, (key, desc)) in row.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::raw(" ".repeat(gap)));
+                }
+                let pad = item_w.saturating_sub(key.width() + 1 + desc.width());
+                spans.push(Span::styled(key.clone(), accent_style));
+                spans.push(Span::raw(" "));
+                spans.push(Span::raw(format!("{desc}{}", " ".repeat(pad))));
+            }
+            Line::from(spans)
+        })
+        .collect();
 
-    let size = widget.go_to_help_size();
-    let position = widget.go_to_help_position();
+    let min_width = match widget.go_to_help_size() {
+        DialogSize::Custom(w, _) => w as usize,
+        _ => 24,
+    };
+    let max_line_w = lines.iter().map(|l| l.width()).max().unwrap_or(0);
+    let width = (max_line_w + inner_pad + border_pad)
+        .max(min_width)
+        .min(max_width.max(min_width)) as u16;
+    let height = (lines.len() + border_pad).min(area.height as usize) as u16;
 
     widgets::draw_dialog(
         frame,
         DialogLayout {
             area,
-            position,
-            size,
+            position: widget.go_to_help_position(),
+            size: DialogSize::Custom(width, height),
         },
         border_type,
-        &widgets::get_dialog_style(app, accent_style, "Go to", None),
-        line,
-        Some(Alignment::Center),
+        &widgets::get_dialog_style(app, accent_style, &title, None),
+        Text::from(lines),
+        Some(Alignment::Left),
         None,
     );
 }
@@ -269,273 +254,183 @@ pub(crate) fn draw_message_overlay(
     );
 }
 
-struct HelpEntry {
-    key: InputKeys,
-    desc: &'static str,
-}
-
-struct HelpSection {
-    name: &'static str,
-    entries: &'static [HelpEntry],
-}
-
-#[rustfmt::skip]
-const HELP_DATA: &[HelpSection] = &[
-    HelpSection {
-        name: "Navigation",
-        entries: &[
-            HelpEntry { key: InputKeys::GoUp, desc: "Move selection up" },
-            HelpEntry { key: InputKeys::GoDown, desc: "Move selection down" },
-            HelpEntry { key: InputKeys::GoParent, desc: "Go to parent directory" },
-            HelpEntry { key: InputKeys::GoIntoDir, desc: "Enter directory" },
-            HelpEntry { key: InputKeys::ToggleMarker, desc: "Toggle marker" },
-            HelpEntry { key: InputKeys::ClearMarkers, desc: "Clear markers" },
-            HelpEntry { key: InputKeys::ClearFilter, desc: "Clear filter" },
-            HelpEntry { key: InputKeys::ClearAll, desc: "Clear all markers and filters" },
-            HelpEntry { key: InputKeys::SelectAll, desc: "Select all entries in directory" },
-            HelpEntry { key: InputKeys::GoToBottom, desc: "Go to bottom" },
-            HelpEntry { key: InputKeys::ScrollUp, desc: "Scroll widget up" },
-            HelpEntry { key: InputKeys::ScrollDown, desc: "Scroll widget down" },
-        ],
-    },
-    HelpSection {
-        name: "Tabs",
-        entries: &[
-            HelpEntry { key: InputKeys::TabNew, desc: "Create a new tab" },
-            HelpEntry { key: InputKeys::TabClose, desc: "Close the selected tab" },
-            HelpEntry { key: InputKeys::TabNext, desc: "Switch to next tab" },
-            HelpEntry { key: InputKeys::TabPrev, desc: "Switch to previous tab" },
-        ],
-    },
-    HelpSection {
-        name: "File",
-        entries: &[
-            HelpEntry { key: InputKeys::OpenFile, desc: "Open file in editor" },
-            HelpEntry { key: InputKeys::Copy, desc: "Copy/Yank selection" },
-            HelpEntry { key: InputKeys::Paste, desc: "Paste" },
-            HelpEntry { key: InputKeys::Rename, desc: "Rename" },
-            HelpEntry { key: InputKeys::Create, desc: "Create file" },
-            HelpEntry { key: InputKeys::CreateDirectory, desc: "Create directory" },
-            HelpEntry { key: InputKeys::Delete, desc: "Delete / move to trash" },
-            HelpEntry { key: InputKeys::AlternateDelete, desc: "Alternate delete mode" },
-            HelpEntry { key: InputKeys::Filter, desc: "Filter entries" },
-            HelpEntry { key: InputKeys::Find, desc: "Find (fuzzy)" },
-            HelpEntry { key: InputKeys::MoveFile, desc: "Move file(s)" },
-            HelpEntry { key: InputKeys::ShowInfo, desc: "Toggle file info" },
-            HelpEntry { key: InputKeys::ClearClipboard, desc: "Clear copied entries" },
-        ],
-    },
-    HelpSection {
-        name: "Go To",
-        entries: &[
-            HelpEntry { key: InputKeys::PrefixGoTo, desc: "Go to prefix" },
-            HelpEntry { key: InputKeys::GoToTop, desc: "Go to top" },
-            HelpEntry { key: InputKeys::GoToHome, desc: "Go to home" },
-            HelpEntry { key: InputKeys::GoToPath, desc: "Go to path" },
-        ],
-    },
-    HelpSection {
-        name: "Sort",
-        entries: &[
-            HelpEntry { key: InputKeys::Sort, desc: "Sort prefix" },
-            HelpEntry { key: InputKeys::SortByName, desc: "Sort by name" },
-            HelpEntry { key: InputKeys::SortByNatural, desc: "Sort by natural order" },
-            HelpEntry { key: InputKeys::SortByExtension, desc: "Sort by extension" },
-            HelpEntry { key: InputKeys::SortBySize, desc: "Sort by size" },
-            HelpEntry { key: InputKeys::SortByModified, desc: "Sort by modified time" },
-            HelpEntry { key: InputKeys::SortByCreated, desc: "Sort by created time" },
-            HelpEntry { key: InputKeys::SortByAccessed, desc: "Sort by accessed time" },
-        ],
-    },
-    HelpSection {
-        name: "System",
-        entries: &[
-            HelpEntry { key: InputKeys::Quit, desc: "Quit" },
-            HelpEntry { key: InputKeys::KeybindHelp, desc: "Toggle keybind help" },
-        ],
-    },
-];
-
-pub(crate) fn draw_keybind_help(frame: &mut Frame, app: &AppState, accent_style: Style) {
-    let keys = app.config().keys();
-    let widget = app.config().theme().widget();
+/// Draws the keybind help, a searchable list of every action with its keys.
+///
+/// Rows are grouped by category, under the search line. The list scrolls to keep the
+/// selected row in view on small terminals.
+pub(crate) fn draw_keybind_help(
+    frame: &mut Frame,
+    app: &AppState,
+    accent_style: Style,
+    help: &HelpPalette,
+) {
+    let theme = app.config().theme();
+    let widget = theme.widget();
     let area = frame.area();
 
     let position = widgets::dialog_position_unified(widget.position(), app, DialogPosition::Center);
 
     let border_type = app.config().display().border_shape().as_border_type();
     let dim_style = Style::default().add_modifier(Modifier::DIM);
-    let header_style = app.config().theme().widget().label_style_or_theme();
-    let key_style = app.config().theme().widget().value_style_or_theme();
+    let header_style = widget.label_style_or_theme();
+    let key_style = widget.value_style_or_theme();
+    let selected_style = theme.selection_style();
 
-    let max_keys_to_show: usize = 3;
     let key_pad: usize = 3;
-
-    let fmt_key_token = |k: &str| -> String {
-        if k == " " || k.trim().is_empty() || k.eq_ignore_ascii_case("space") {
-            "Space".to_string()
-        } else {
-            k.to_string()
-        }
-    };
-
-    let fmt_keys = |list: &[String]| -> String {
-        if list.is_empty() {
-            return "-".to_string();
-        }
-        let mut out: Vec<String> = list
-            .iter()
-            .take(max_keys_to_show)
-            .map(|k| fmt_key_token(k))
-            .collect();
-        out.reverse();
-        if list.len() > max_keys_to_show {
-            out.push("...".to_string());
-        }
-        out.join(", ")
-    };
-
-    let go_to_prefix = keys.prefix_go_to();
-    let sort_prefix = keys.sort();
-    let go_leader = go_to_prefix.first().map(|s| s.as_str()).unwrap_or("g");
-    let sort_leader = sort_prefix.first().map(|s| s.as_str()).unwrap_or("o");
-
-    let fmt_prefix =
-        |leader: &str, list: &[String]| -> String { format!("{leader} + {}", fmt_keys(list)) };
-
-    let get_keys = |k: &InputKeys| -> &[String] {
-        match k {
-            InputKeys::GoUp => keys.go_up(),
-            InputKeys::GoDown => keys.go_down(),
-            InputKeys::GoParent => keys.go_parent(),
-            InputKeys::GoIntoDir => keys.go_into_dir(),
-            InputKeys::ToggleMarker => keys.toggle_marker(),
-            InputKeys::ClearMarkers => keys.clear_markers(),
-            InputKeys::ClearFilter => keys.clear_filter(),
-            InputKeys::ClearAll => keys.clear_all(),
-            InputKeys::SelectAll => keys.select_all(),
-            InputKeys::GoToBottom => keys.go_to_bottom(),
-            InputKeys::ScrollUp => keys.scroll_up(),
-            InputKeys::ScrollDown => keys.scroll_down(),
-            InputKeys::TabNew => keys.tab_new(),
-            InputKeys::TabClose => keys.tab_close(),
-            InputKeys::TabNext => keys.tab_next(),
-            InputKeys::TabPrev => keys.tab_prev(),
-            InputKeys::OpenFile => keys.open_file(),
-            InputKeys::Copy => keys.copy(),
-            InputKeys::Paste => keys.paste(),
-            InputKeys::Rename => keys.rename(),
-            InputKeys::Create => keys.create(),
-            InputKeys::CreateDirectory => keys.create_directory(),
-            InputKeys::Delete => keys.delete(),
-            InputKeys::AlternateDelete => keys.alternate_delete(),
-            InputKeys::Filter => keys.filter(),
-            InputKeys::Find => keys.find(),
-            InputKeys::MoveFile => keys.move_file(),
-            InputKeys::ShowInfo => keys.show_info(),
-            InputKeys::ClearClipboard => keys.clear_clipboard(),
-            InputKeys::PrefixGoTo => keys.prefix_go_to(),
-            InputKeys::GoToTop => keys.go_to_top(),
-            InputKeys::GoToHome => keys.go_to_home(),
-            InputKeys::GoToPath => keys.go_to_path(),
-            InputKeys::Sort => keys.sort(),
-            InputKeys::SortByName => keys.sort_by_name(),
-            InputKeys::SortByNatural => keys.sort_by_natural(),
-            InputKeys::SortByExtension => keys.sort_by_extension(),
-            InputKeys::SortBySize => keys.sort_by_size(),
-            InputKeys::SortByModified => keys.sort_by_modified(),
-            InputKeys::SortByCreated => keys.sort_by_created(),
-            InputKeys::SortByAccessed => keys.sort_by_accessed(),
-            InputKeys::Quit => keys.quit(),
-            InputKeys::KeybindHelp => keys.keybind_help(),
-            InputKeys::Reload => keys.reload(),
-        }
-    };
-
-    let sections: Vec<(&str, Vec<(String, &'static str)>)> = HELP_DATA
-        .iter()
-        .map(|section| {
-            let mut rows: Vec<(String, &'static str)> = section
-                .entries
-                .iter()
-                .map(|entry| {
-                    let key_text = match section.name {
-                        "Go To" if entry.key != InputKeys::PrefixGoTo => {
-                            fmt_prefix(go_leader, get_keys(&entry.key))
-                        }
-                        "Sort" if entry.key != InputKeys::Sort => {
-                            fmt_prefix(sort_leader, get_keys(&entry.key))
-                        }
-                        _ => fmt_keys(get_keys(&entry.key)),
-                    };
-                    (key_text, entry.desc)
-                })
-                .collect();
-
-            if section.name == "Tabs" {
-                rows.push(("[0-9]".into(), "Switch to tab by index"));
-            }
-            (section.name, rows)
-        })
-        .collect();
-
-    let key_w: usize = sections
-        .iter()
-        .flat_map(|(_, rows)| rows.iter().map(|(k, _)| k.len()))
+    let margin = " ".repeat(2);
+    let key_w = help
+        .shown()
+        .map(|row| row.keys().width())
         .max()
         .unwrap_or(10)
         .clamp(10, 28);
 
-    let margin = 6;
-    let margin_spacer = " ".repeat(margin);
-
-    let mk_line = |key_text: &str, desc: &str| -> Line<'static> {
-        let key_string = format!("{:>width$}", key_text, width = key_w);
-        Line::from(vec![
-            Span::raw(margin_spacer.to_string()),
-            Span::styled(key_string, key_style),
-            Span::styled(" ".repeat(key_pad), dim_style),
-            Span::raw(desc.to_string()),
-        ])
-    };
+    let width = (area.width * 60 / 100).clamp(45, 80).min(area.width);
+    let height = (area.height * 80 / 100).max(12).min(area.height);
+    // The borders and the search line with its spacer take four lines.
+    let visible = (height as usize).saturating_sub(4).max(1);
 
     let mut lines: Vec<Line> = Vec::new();
-    let section_title_indent = " ".repeat(key_w + key_pad);
-
-    for (section_name, rows) in sections {
-        lines.push(Line::from(vec![
-            Span::raw(margin_spacer.to_string()),
-            Span::raw(&section_title_indent),
-            Span::styled(format!("{section_name}:"), header_style),
-        ]));
+    let mut section = "";
+    // The selected line and the first line to keep in view with it.
+    let mut selected = (0, 0);
+
+    for (i, row) in help.shown().enumerate() {
+        let mut top = lines.len();
+        if row.section() != section {
+            if !section.is_empty() {
+                lines.push(Line::raw(""));
+            }
+            section = row.section();
+            top = lines.len();
+            lines.push(Line::from(vec![
+                Span::raw(margin.clone()),
+                Span::raw(" ".repeat(key_w + key_pad)),
+                Span::styled(format!("{section}:"), header_style),
+            ]));
+        }
 
-        for (k, desc) in rows {
-            lines.push(mk_line(&k, desc));
+        let mut line = Line::from(vec![
+            Span::raw(margin.clone()),
+            Span::styled(format!("{:>key_w$}", row.keys()), key_style),
+            Span::styled(" ".repeat(key_pad), dim_style),
+            Span::raw(row.desc().to_string()),
+        ]);
+        if i == help.selected() {
+            selected = (lines.len(), top);
+            line = line.style(selected_style);
         }
-        lines.push(Line::raw(""));
+        lines.push(line);
     }
 
-    while matches!(lines.last(), Some(l) if l.width() == 0) {
-        lines.pop();
+    if lines.is_empty() {
+        lines.push(Line::styled(
+            format!("{margin}No matching keybinds"),
+            dim_style,
+        ));
     }
-    lines.push(Line::raw(""));
 
-    let content_height = lines.len() as u16;
-    let dynamic_width = (area.width * 60 / 100).clamp(45, 80);
-    let max_allowed_height = (area.height * 80 / 100).max(12);
-    let dynamic_height = (content_height + 1).min(max_allowed_height);
+    let scroll = app.actions().scroll();
+    let (selected_line, selected_top) = selected;
+    let offset = (scroll.offset() as usize)
+        .min(selected_top)
+        .max((selected_line + 1).saturating_sub(visible))
+        .min(lines.len().saturating_sub(visible));
+    scroll.set_offset(offset as u16);
+
+    let mut search = vec![
+        Span::styled(format!("{margin}/ "), accent_style),
+        Span::raw(help.query().to_string()),
+        Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+    ];
+    if help.query().is_empty() {
+        search.push(Span::styled(" type to filter, Enter runs", dim_style));
+    }
+
+    let mut content = vec![Line::from(search), Line::raw("")];
+    content.extend(lines.into_iter().skip(offset).take(visible));
 
+    let title = format!("Keybinds {}/{}", help.shown_len(), help.len());
     widgets::draw_dialog(
         frame,
         DialogLayout {
             area,
             position,
-            size: DialogSize::Custom(dynamic_width, dynamic_height),
+            size: DialogSize::Custom(width, height),
         },
         border_type,
-        &widgets::get_dialog_style(app, accent_style, "Keybinds", None),
-        Text::from(lines),
+        &widgets::get_dialog_style(app, accent_style, &title, None),
+        Text::from(content),
         Some(Alignment::Left),
-        Some(app.actions().scroll()),
+        None,
+    );
+}
+
+/// Draws the theme picker at the top right, leaving the panes visible for the preview.
+pub(crate) fn draw_theme_picker(
+    frame: &mut Frame,
+    app: &AppState,
+    accent_style: Style,
+    picker: &ThemePicker,
+) {
+    let area = frame.area();
+    let border_type = app.config().display().border_shape().as_border_type();
+    let dim_style = Style::default().add_modifier(Modifier::DIM);
+    let selected_style = app.config().theme().selection_style();
+
+    let current_tag = " (config)";
+    let lines: Vec<Line> = picker
+        .names()
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut spans = vec![Span::raw(format!(" {name}"))];
+            if picker.current() == Some(name.as_str()) {
+                spans.push(Span::styled(current_tag, dim_style));
+            }
+            let line = Line::from(spans);
+            if i == picker.selected() {
+                line.style(selected_style)
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    let border_pad = 2;
+    let name_w = picker.names().iter().map(|n| n.width()).max().unwrap_or(0);
+    let width = (name_w + current_tag.len() + 2 + border_pad)
+        .max(24)
+        .min(area.width as usize) as u16;
+    let height = (lines.len() + border_pad)
+        .min((area.height as usize * 80 / 100).max(6))
+        .min(area.height as usize) as u16;
+    let visible = (height as usize).saturating_sub(border_pad).max(1);
+
+    // Keep the selected theme in view.
+    let scroll = app.actions().scroll();
+    let offset = (scroll.offset() as usize)
+        .min(picker.selected())
+        .max((picker.selected() + 1).saturating_sub(visible));
+    scroll.set_offset(offset as u16);
+
+    widgets::draw_dialog(
+        frame,
+        DialogLayout {
+            area,
+            position: DialogPosition::TopRight,
+            size: DialogSize::Custom(width, height),
+        },
+        border_type,
+        &widgets::get_dialog_style(app, accent_style, "Theme", None),
+        Text::from(
+            lines
+                .into_iter()
+                .skip(offset)
+                .take(visible)
+                .collect::<Vec<_>>(),
+        ),
+        Some(Alignment::Left),
+        None,
     );
 }
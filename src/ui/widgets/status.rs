@@ -13,7 +13,7 @@ use ratatui::{
 
 use crate::app::{AppState, Clipboard};
 use crate::config::display::{StatusSegment, StatusTag};
-use crate::core::workers::Workers;
+use crate::core::{formatter, workers::Workers};
 use crate::ui::widgets::StatusPosition;
 
 pub(crate) fn draw_separator(frame: &mut Frame, area: Rect, style: Style, border_type: BorderType) {
@@ -26,15 +26,17 @@ pub(crate) fn draw_separator(frame: &mut Frame, area: Rect, style: Style, border
     );
 }
 
+/// Draws the status bar at the given position.
+/// Returns the click areas of the tabs drawn in it.
 pub(crate) fn draw_status_bar(
     frame: &mut Frame,
     app: &AppState,
     position: StatusPosition,
     workers: &Workers,
     clipboard: &Clipboard,
-) {
+) -> Vec<(Rect, usize)> {
     if position == StatusPosition::None {
-        return;
+        return Vec::new();
     }
 
     let area = frame.area();
@@ -76,7 +78,8 @@ pub(crate) fn draw_status_bar(
                         ));
                     }
                     StatusTag::Size => {
-                        let padded_size = format!("{:>8}", file_meta.size());
+                        let size = app.selected_dir_size().unwrap_or(file_meta.size());
+                        let padded_size = format!("{size:>8}");
                         left_spans.push(Span::styled(
                             padded_size,
                             patch_style(info_theme.size_style()),
@@ -133,6 +136,19 @@ pub(crate) fn draw_status_bar(
         }
     };
 
+    if status_cfg.count() == position
+        && let Some(count) = app.actions().count()
+    {
+        spans.push(Span::styled(count.to_string(), base_style));
+    }
+
+    if status_cfg.recording() == position
+        && let Some(register) = app.macro_recording()
+    {
+        add_sep(&mut spans);
+        spans.push(Span::styled(format!("recording @{register}"), base_style));
+    }
+
     if status_cfg.tasks() == position {
         let queued_ops = workers.fileop_tx().len();
         let active_ops = workers.active().load(Ordering::Relaxed);
@@ -142,6 +158,7 @@ pub(crate) fn draw_status_bar(
             && let Some(start) = app.worker_time()
             && start.elapsed() >= Duration::from_millis(200)
         {
+            add_sep(&mut spans);
             if active_ops > 0 {
                 let symbols: &[&str] = if use_icons {
                     &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
@@ -179,6 +196,12 @@ pub(crate) fn draw_status_bar(
         }
     }
 
+    if status_cfg.markers() == position && app.nav().visual_active() {
+        add_sep(&mut spans);
+        let style = patch_style(marker_theme.style_or_theme());
+        spans.push(Span::styled("VISUAL", style));
+    }
+
     if status_cfg.markers() == position {
         let markers = app.nav().markers();
         let marker_count = markers.len();
@@ -210,11 +233,78 @@ pub(crate) fn draw_status_bar(
         }
     }
 
+    if status_cfg.sort() == position {
+        let (sort, _) = app.view_for(app.nav().current_dir());
+        add_sep(&mut spans);
+        spans.push(Span::styled(format!("Sort: {sort}"), base_style));
+    }
+
+    let info = app.status().info();
+    // Segments of the previous directory are not shown until the worker caught up.
+    let info_current = info.dir() == app.nav().current_dir();
+
+    if status_cfg.marked_size() == position
+        && !app.nav().markers().is_empty()
+        && let Some(size) = info.marked_size()
+    {
+        add_sep(&mut spans);
+        let style = patch_style(marker_theme.style_or_theme());
+        let size = formatter::format_file_size(Some(size), false);
+        spans.push(Span::styled(format!("{size} marked"), style));
+    }
+
+    if status_cfg.git() == position
+        && info_current
+        && let Some(git) = info.git()
+    {
+        add_sep(&mut spans);
+        let icon = if use_icons { "\u{e0a0} " } else { "git:" };
+        let dirty = if git.dirty() { "*" } else { "" };
+        spans.push(Span::styled(
+            format!("{icon}{}{dirty}", git.branch()),
+            base_style,
+        ));
+    }
+
+    if status_cfg.disk() == position
+        && info_current
+        && let Some(disk) = info.disk()
+    {
+        add_sep(&mut spans);
+        let free = formatter::format_file_size(Some(disk.free()), false);
+        let total = formatter::format_file_size(Some(disk.total()), false);
+        spans.push(Span::styled(format!("{free} free of {total}"), base_style));
+    }
+
+    // The worker runs the shown commands only, in their order.
+    let commands = status_cfg
+        .command()
+        .iter()
+        .filter(|cmd| cmd.position() != StatusPosition::None);
+    for (cmd, output) in commands.zip(info.commands()) {
+        if cmd.position() == position && info_current && !output.is_empty() {
+            add_sep(&mut spans);
+            spans.push(Span::styled(output.as_str(), base_style));
+        }
+    }
+
+    if status_cfg.clock() == position && !app.status().clock().is_empty() {
+        add_sep(&mut spans);
+        spans.push(Span::styled(app.status().clock(), base_style));
+    }
+
+    let mut tab_start = None;
     if status_cfg.tabs() == position && !app.tab_line().is_empty() {
         add_sep(&mut spans);
+        tab_start = Some(spans.len());
         spans.extend(app.tab_line().iter().cloned());
     }
 
+    if status_cfg.listing() == position {
+        add_sep(&mut spans);
+        spans.push(Span::styled(listing_flags(app), base_style));
+    }
+
     if status_cfg.entry_count() == position {
         let total = app.nav().shown_entries_len();
         add_sep(&mut spans);
@@ -225,19 +315,25 @@ pub(crate) fn draw_status_bar(
                 .visible_selected()
                 .map(|idx| (idx + 1).to_string())
                 .unwrap_or_else(|| "0".to_string());
-            format!("{}/{}", idx_text, total)
+            // A capped flatten view has more entries than shown.
+            let capped = if app.nav().flatten_truncated() {
+                "+"
+            } else {
+                ""
+            };
+            format!("{}/{}{}", idx_text, total, capped)
         };
         spans.push(Span::styled(count_str, base_style));
     }
 
     if left_spans.is_empty() && spans.is_empty() {
-        return;
+        return Vec::new();
     }
 
     let y = match position {
         StatusPosition::Header => area.y,
         StatusPosition::Footer => area.y + area.height - 1,
-        _ => return,
+        _ => return Vec::new(),
     };
 
     let rect = Rect::new(area.x, y, area.width, 1);
@@ -256,18 +352,60 @@ pub(crate) fn draw_status_bar(
 
         frame.render_widget(Paragraph::new(Line::from(left_spans)), chunks[0]);
 
+        let tabs = tab_regions(&spans, tab_start, chunks[2]);
         frame.render_widget(
             Paragraph::new(Line::from(spans)).alignment(Alignment::Right),
             chunks[2],
         );
 
         frame.render_widget(block, rect);
+        tabs
     } else {
+        let tabs = tab_regions(&spans, tab_start, block.inner(rect));
         frame.render_widget(
             Paragraph::new(Line::from(spans))
                 .alignment(Alignment::Right)
                 .block(block),
             rect,
         );
+        tabs
+    }
+}
+
+/// Returns the listing options of the current directory as flags,
+/// e.g. `H-LDI` for hidden files, no system files, symlinks, directories first and case insensitive sort.
+fn listing_flags(app: &AppState) -> String {
+    let (_, list) = app.view_for(app.nav().current_dir());
+    [
+        (list.show_hidden, 'H'),
+        (list.show_system, 'S'),
+        (list.show_symlink, 'L'),
+        (list.dirs_first, 'D'),
+        (list.case_insensitive, 'I'),
+    ]
+    .iter()
+    .map(|&(on, flag)| if on { flag } else { '-' })
+    .collect()
+}
+
+/// Returns the area and index of each tab in a right aligned line of spans.
+///
+/// The tab line starts at `tab_start` and alternates between tabs and separators.
+fn tab_regions(spans: &[Span], tab_start: Option<usize>, area: Rect) -> Vec<(Rect, usize)> {
+    let Some(start) = tab_start else {
+        return Vec::new();
+    };
+
+    let total: u16 = spans.iter().map(|s| s.width() as u16).sum();
+    let mut x = area.x + area.width.saturating_sub(total);
+    let mut regions = Vec::new();
+
+    for (i, span) in spans.iter().enumerate() {
+        let width = span.width() as u16;
+        if i >= start && (i - start) % 2 == 0 {
+            regions.push((Rect::new(x, area.y, width, 1), (i - start) / 2));
+        }
+        x = x.saturating_add(width);
     }
+    regions
 }
@@ -10,6 +10,7 @@
 
 pub(crate) mod os;
 pub(crate) mod path;
+pub(crate) mod term;
 pub(crate) mod text;
 pub(crate) mod timings;
 
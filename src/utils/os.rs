@@ -3,6 +3,7 @@
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Shared cache for the home_dir dirs call
 static HOME_DIR_CACHE: OnceLock<Option<PathBuf>> = OnceLock::new();
@@ -16,6 +17,9 @@ static FD_BIN: OnceLock<Option<&'static str>> = OnceLock::new();
 /// If bat is not found, the value will be None.
 static BAT_BIN: OnceLock<Option<&'static str>> = OnceLock::new();
 
+/// Counter to give every temporary file of [write_atomic] its own name.
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 /// Thread safe for getting home_dir once.
 #[inline]
 pub(crate) fn get_home() -> Option<&'static PathBuf> {
@@ -42,6 +46,53 @@ pub(crate) fn default_config_path() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("runa.toml"))
 }
 
+/// Returns the themes directory next to the config file.
+pub(crate) fn default_themes_dir() -> Option<PathBuf> {
+    default_config_path().parent().map(|dir| dir.join("themes"))
+}
+
+/// Returns the path of the session file.
+/// Uses `$XDG_STATE_HOME/runa/session.toml` or `~/.local/state/runa/session.toml`.
+pub(crate) fn default_session_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(|s| PathBuf::from(s).join("runa/session.toml"))
+        .or_else(|| get_home().map(|h| h.join(".local/state/runa/session.toml")))
+}
+
+/// Returns the path of the prompt history file.
+/// Uses `$XDG_STATE_HOME/runa/history.toml` or `~/.local/state/runa/history.toml`.
+pub(crate) fn default_history_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(|s| PathBuf::from(s).join("runa/history.toml"))
+        .or_else(|| get_home().map(|h| h.join(".local/state/runa/history.toml")))
+}
+
+/// Writes the file through a temporary file which is renamed over it,
+/// so a crash while writing never leaves a half written file behind.
+///
+/// The temporary file is named `<path>.<pid>.<n>.tmp`, so several runa instances
+/// or threads writing the same file never write into each other's temporary file.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp_name = path.as_os_str().to_os_string();
+    let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    tmp_name.push(format!(".{}.{n}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp_name);
+
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        io::Write::write_all(&mut file, content.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    });
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
 pub(crate) fn is_regular_file(path: &Path) -> bool {
     #[cfg(unix)]
     {
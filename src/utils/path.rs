@@ -188,6 +188,39 @@ pub(crate) fn format_display_path(path: &Path) -> String {
     }
 }
 
+/// Increments the number at the end of a file name, keeping the extension of files.
+///
+/// `notes.txt` becomes `notes_1.txt`, `notes_1.txt` becomes `notes_2.txt`
+/// and `v09` becomes `v10`.
+pub(crate) fn increment_name(name: &str, is_dir: bool) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(pos) if !is_dir && pos > 0 => name.split_at(pos),
+        _ => (name, ""),
+    };
+
+    let base = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &stem[base.len()..];
+    match digits.parse::<u64>() {
+        Ok(n) => format!(
+            "{}{:0width$}{}",
+            base,
+            n.saturating_add(1),
+            ext,
+            width = digits.len()
+        ),
+        Err(_) => format!("{}_1{}", stem, ext),
+    }
+}
+
+/// Returns the next incremented name of `name` that does not exist in `dir` yet.
+pub(crate) fn next_free_name(dir: &Path, name: &str, is_dir: bool) -> String {
+    let mut next = increment_name(name, is_dir);
+    while dir.join(&next).exists() {
+        next = increment_name(&next, is_dir);
+    }
+    next
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,4 +276,19 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn increment_and_free_names() -> Result<(), Box<dyn error::Error>> {
+        assert_eq!(increment_name("notes.txt", false), "notes_1.txt");
+        assert_eq!(increment_name("notes_1.txt", false), "notes_2.txt");
+        assert_eq!(increment_name("v09", false), "v10");
+        assert_eq!(increment_name(".bashrc", false), ".bashrc_1");
+        assert_eq!(increment_name("release.d", true), "release.d_1");
+
+        let sandbox = tempdir()?;
+        fs::write(sandbox.path().join("a_1.txt"), "")?;
+        assert_eq!(next_free_name(sandbox.path(), "a.txt", false), "a_2.txt");
+
+        Ok(())
+    }
 }
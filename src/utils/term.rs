@@ -0,0 +1,174 @@
+//! Terminal capability detection.
+//!
+//! Detects how many colors the terminal can show from `COLORTERM`, the `colors` capability of
+//! the terminfo entry of `TERM` and the name in `TERM`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::display::ColorDepth;
+use crate::utils::os;
+
+/// Index of the `colors` number in the numbers section of a terminfo entry.
+const TERMINFO_COLORS: usize = 13;
+
+/// Returns the color depth of the terminal, detected once.
+pub(crate) fn color_depth() -> ColorDepth {
+    static DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+    *DEPTH.get_or_init(|| {
+        detect_color_depth(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+            terminfo_colors,
+        )
+    })
+}
+
+/// Detects the color depth from the values of `COLORTERM` and `TERM`.
+///
+/// Without a hint of either the terminal is expected to show true colors, so only terminals
+/// which are known to show less are downsampled.
+fn detect_color_depth(
+    colorterm: Option<&str>,
+    term: Option<&str>,
+    terminfo: impl Fn(&str) -> Option<i32>,
+) -> ColorDepth {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return ColorDepth::TrueColor;
+    }
+    let Some(term) = term.filter(|term| !term.is_empty()) else {
+        return ColorDepth::TrueColor;
+    };
+
+    match terminfo(term) {
+        Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+        Some(colors) if colors >= 256 => ColorDepth::Ansi256,
+        Some(_) => ColorDepth::Ansi16,
+        None if term.contains("direct") || term.contains("truecolor") => ColorDepth::TrueColor,
+        None if term.contains("256color") => ColorDepth::Ansi256,
+        None if term == "linux" || term == "dumb" || term.ends_with("-color") => ColorDepth::Ansi16,
+        None => ColorDepth::TrueColor,
+    }
+}
+
+/// Reads the `colors` capability of the terminfo entry of the terminal.
+fn terminfo_colors(term: &str) -> Option<i32> {
+    if term.contains(['/', '\\']) || term.starts_with('.') {
+        return None;
+    }
+    let first = term.chars().next()?;
+    // Entries are kept below the first letter, or below its hex code on macOS.
+    let subdirs = [first.to_string(), format!("{:x}", first as u32)];
+
+    terminfo_dirs().into_iter().find_map(|dir| {
+        subdirs
+            .iter()
+            .find_map(|sub| fs::read(dir.join(sub).join(term)).ok())
+            .and_then(|entry| parse_terminfo_colors(&entry))
+    })
+}
+
+/// Returns the terminfo directories in the order ncurses searches them.
+fn terminfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = os::get_home() {
+        dirs.push(home.join(".terminfo"));
+    }
+    if let Ok(list) = env::var("TERMINFO_DIRS") {
+        dirs.extend(
+            list.split(':')
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        );
+    }
+    dirs.extend(
+        [
+            "/etc/terminfo",
+            "/lib/terminfo",
+            "/usr/share/terminfo",
+            "/usr/lib/terminfo",
+        ]
+        .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// Reads the `colors` number of a compiled terminfo entry.
+///
+/// The header holds six little endian words: the magic number, which tells if the numbers
+/// are 16 or 32 bit wide, and the sizes of the names, booleans, numbers and strings.
+fn parse_terminfo_colors(entry: &[u8]) -> Option<i32> {
+    let word = |index: usize| {
+        let bytes = entry.get(index * 2..index * 2 + 2)?;
+        Some(i16::from_le_bytes([bytes[0], bytes[1]]))
+    };
+    let width = match word(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let names = usize::try_from(word(1)?).ok()?;
+    let booleans = usize::try_from(word(2)?).ok()?;
+    let numbers = usize::try_from(word(3)?).ok()?;
+    if numbers <= TERMINFO_COLORS {
+        return None;
+    }
+
+    // The numbers start on an even offset.
+    let start = (12 + names + booleans).next_multiple_of(2) + TERMINFO_COLORS * width;
+    let bytes = entry.get(start..start + width)?;
+    let colors = if width == 2 {
+        i32::from(i16::from_le_bytes([bytes[0], bytes[1]]))
+    } else {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    };
+    (colors >= 0).then_some(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_depth_follows_colorterm_and_terminfo() {
+        let none = |_: &str| None;
+        assert_eq!(
+            detect_color_depth(Some("truecolor"), Some("xterm-256color"), |_| Some(256)),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect_color_depth(None, Some("tmux-256color"), |_| Some(256)),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            detect_color_depth(None, Some("xterm-direct"), |_| Some(1 << 24)),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            detect_color_depth(None, Some("screen-256color"), none),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            detect_color_depth(None, Some("linux"), none),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(detect_color_depth(None, None, none), ColorDepth::TrueColor);
+
+        // A legacy entry with 16 bit numbers: 2 names bytes, 1 boolean and 14 numbers.
+        let mut entry = Vec::new();
+        for word in [0o432i16, 2, 1, 14, 0, 0] {
+            entry.extend(word.to_le_bytes());
+        }
+        entry.extend(b"x\0\x01\0");
+        for n in 0..14i16 {
+            entry.extend(if n == 13 { 256i16 } else { -1 }.to_le_bytes());
+        }
+        assert_eq!(parse_terminfo_colors(&entry), Some(256));
+        assert_eq!(parse_terminfo_colors(&entry[..20]), None);
+    }
+}
@@ -15,6 +15,9 @@ impl Timings {
     pub(crate) const CONFIG_RELOAD_MS: u64 = 1000;
     pub(crate) const UI_RELOAD_MS: u64 = 200;
     pub(crate) const FS_WATCH_DEBOUNCE_MS: u64 = 150;
+    pub(crate) const FLATTEN_BATCH_MS: u64 = 100;
+    pub(crate) const DOUBLE_CLICK_MS: u64 = 400;
+    pub(crate) const MACRO_WAIT_MS: u64 = 2000;
 }
 
 #[derive(Default, Debug, Clone)]